@@ -0,0 +1,56 @@
+//! Measures the raw throughput of the interpreter on synthetic ROMs, without any
+//! frontend: every iteration loads a ROM in a fresh VM and runs it for `CYCLES` cycles.
+//!
+//! ```shell script
+//! $ cargo bench --no-default-features
+//! ```
+use chip8::chip_8::{Chip8, ExecutionLimits};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+/// Cycles run by every iteration
+const CYCLES: u64 = 1_000_000;
+
+/// Tight loop of ALU instructions: additions, subtractions and logic operations
+const ALU_ROM: [u8; 14] = [
+    0x70, 0x01, // 200: ADD V0, 0x01
+    0x80, 0x14, // 202: ADD V0, V1
+    0x81, 0x25, // 204: SUB V1, V2
+    0x82, 0x03, // 206: XOR V2, V0
+    0x83, 0x06, // 208: SHR V3
+    0x84, 0x31, // 20A: OR V4, V3
+    0x12, 0x00, // 20C: JP 0x200
+];
+
+/// Draws a 15 rows sprite in a loop, moving it diagonally across the display
+const DRAW_ROM: [u8; 25] = [
+    0xA2, 0x0A, // 200: LD I, sprite
+    0xD0, 0x1F, // 202: DRW V0, V1, 15
+    0x70, 0x03, // 204: ADD V0, 0x03
+    0x71, 0x05, // 206: ADD V1, 0x05
+    0x12, 0x02, // 208: JP 0x202
+    0xFF, 0x81, 0xBD, 0xA5, 0xA5, 0xBD, 0x81, 0xFF, // 20A: sprite
+    0x81, 0xBD, 0xA5, 0xA5, 0xBD, 0x81, 0xFF,
+];
+
+/// Runs `rom` in a fresh VM for `CYCLES` cycles
+fn run(rom: &[u8]) {
+    let mut vm = Chip8::new();
+    vm.load_rom_content(rom.to_vec()).unwrap();
+    vm.run_until_halted(ExecutionLimits {
+        max_cycles: Some(CYCLES),
+        ..ExecutionLimits::default()
+    })
+    .unwrap();
+}
+
+fn interpreter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("interpreter");
+    group.throughput(Throughput::Elements(CYCLES));
+    group.sample_size(20);
+    group.bench_function("alu_loop", |b| b.iter(|| run(&ALU_ROM)));
+    group.bench_function("draw_loop", |b| b.iter(|| run(&DRAW_ROM)));
+    group.finish();
+}
+
+criterion_group!(benches, interpreter);
+criterion_main!(benches);
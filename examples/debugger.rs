@@ -0,0 +1,62 @@
+//! Scripts a `Debugger` without any window: stops at a breakpoint, inspects the VM and
+//! how it got there, then steps out of the subroutine it hit.
+//!
+//! ```shell script
+//! $ cargo run --example debugger -- ./roms/PONG 2D4
+//! ```
+use chip8::chip_8::{Chip8, Debugger, StopReason};
+use std::{env, fs, process};
+
+/// Cycles run before giving up on reaching a stop
+const MAX_CYCLES: u32 = 100_000;
+
+/// Runs `vm` under `debugger` until it stops
+/// # Returns
+/// Why the execution stopped, or `None` if it didn't within `MAX_CYCLES`
+fn run_until_stopped(debugger: &mut Debugger, vm: &mut Chip8) -> Option<StopReason> {
+    (0..MAX_CYCLES).find_map(|_| debugger.cycle(vm))
+}
+
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "roms/PONG".to_string());
+    // PONG draws the score in the subroutine at `0x2D4`
+    let breakpoint = env::args()
+        .nth(2)
+        .and_then(|address| u16::from_str_radix(address.trim_start_matches("0x"), 16).ok())
+        .unwrap_or(0x2D4);
+
+    let mut vm = Chip8::new();
+    let rom = fs::read(&path).unwrap_or_else(|e| {
+        eprintln!("Failed to open the ROM {}: {}", path, e);
+        process::exit(1);
+    });
+    if let Err(msg) = vm.load_rom_content(rom) {
+        eprintln!("Failed to load the ROM {}: {}", path, msg);
+        process::exit(1);
+    }
+
+    let mut debugger = Debugger::new();
+    debugger.set_breakpoints(vec![breakpoint]);
+    debugger.resume();
+
+    for hit in 1..=2 {
+        match run_until_stopped(&mut debugger, &mut vm) {
+            Some(reason) => println!("Hit #{}, stopped by {:?}", hit, reason),
+            None => {
+                println!("Breakpoint 0x{:03X} never reached.", breakpoint);
+                return;
+            }
+        }
+        println!("{}", vm.backtrace());
+        println!("{}", vm.register_dump());
+
+        debugger.step_out(&vm);
+        if let Some(reason) = run_until_stopped(&mut debugger, &mut vm) {
+            println!("Stopped by {:?} after returning", reason);
+            println!("{}", vm.disassembly_line());
+        }
+        debugger.resume();
+    }
+}
@@ -0,0 +1,79 @@
+//! Embeds the VM in a host that owns its own main loop, as a WASI or browser host would:
+//! every frame it forwards the input, runs a frame worth of cycles through a `SharedChip8`
+//! and renders a copy of the display, here as text.
+//! Builds without the `window` feature, ex: for WASI:
+//!
+//! ```shell script
+//! $ cargo build --example embed --no-default-features --target wasm32-wasip1
+//! $ wasmtime --dir . target/wasm32-wasip1/debug/examples/embed.wasm ./roms/PONG
+//! ```
+use chip8::chip_8::{Chip8, DisplaySnapshot, SharedChip8};
+use std::{env, fs, process};
+
+/// Frames run by the host, 5 seconds at 60 frames per second
+const FRAMES: u32 = 300;
+
+/// Frame during which the host holds down a key, along with the key
+const KEY_PRESSES: [(u32, usize); 2] = [(60, 0x1), (120, 0x4)];
+
+/// Renders `display` as text, two pixels per character
+fn render(display: &DisplaySnapshot) -> String {
+    let rows: Vec<&[bool]> = display.pixels.chunks(display.width).collect();
+    rows.chunks(2)
+        .map(|pair| {
+            (0..display.width)
+                .map(
+                    |x| match (pair[0][x], pair.get(1).is_some_and(|row| row[x])) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    },
+                )
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "roms/PONG".to_string());
+
+    let mut vm = Chip8::new();
+    let rom = fs::read(&path).unwrap_or_else(|e| {
+        eprintln!("Failed to open the ROM {}: {}", path, e);
+        process::exit(1);
+    });
+    if let Err(msg) = vm.load_rom_content(rom) {
+        eprintln!("Failed to load the ROM {}: {}", path, msg);
+        process::exit(1);
+    }
+    let cycles_per_frame = vm.cycle_rate() / Chip8::FRAME_RATE;
+    let vm = SharedChip8::new(vm);
+
+    let mut sound_frames = 0;
+    for frame in 0..FRAMES {
+        for &(at, key) in KEY_PRESSES.iter() {
+            if frame == at {
+                vm.press_key(key);
+            } else if frame == at + 10 {
+                vm.release_key(key);
+            }
+        }
+        if let Err(error) = vm.run_cycles(cycles_per_frame) {
+            eprintln!("The ROM faulted: {}", error);
+            break;
+        }
+        if vm.is_sound_active() {
+            sound_frames += 1;
+        }
+    }
+
+    println!("{}", render(&vm.snapshot_display()));
+    println!(
+        "{} frames run, the sound was active during {} of them.",
+        FRAMES, sound_frames
+    );
+}
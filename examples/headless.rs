@@ -0,0 +1,39 @@
+//! Runs a ROM without any window for a fixed amount of cycles, then dumps the final frame
+//! as a PBM image.
+//!
+//! ```shell script
+//! $ cargo run --example headless -- ./roms/PONG 3000 > final.pbm
+//! ```
+use chip8::chip_8::{Chip8, ExecutionLimits};
+use std::{env, fs, process};
+
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "roms/PONG".to_string());
+    let cycles = env::args()
+        .nth(2)
+        .and_then(|cycles| cycles.parse::<u64>().ok())
+        .unwrap_or(3000);
+
+    let mut vm = Chip8::new();
+    let rom = fs::read(&path).unwrap_or_else(|e| {
+        eprintln!("Failed to open the ROM {}: {}", path, e);
+        process::exit(1);
+    });
+    if let Err(msg) = vm.load_rom_content(rom) {
+        eprintln!("Failed to load the ROM {}: {}", path, msg);
+        process::exit(1);
+    }
+
+    let halted = vm.run_until_halted(ExecutionLimits {
+        max_cycles: Some(cycles),
+        ..ExecutionLimits::default()
+    });
+    match halted {
+        Ok(report) => eprintln!("{}", report),
+        Err(error) => eprintln!("The ROM faulted: {}", error),
+    }
+    eprintln!("{:?} elapsed on the emulated hardware", vm.virtual_time());
+    print!("{}", vm.display_pbm());
+}
@@ -0,0 +1,50 @@
+//! Minimal windowed frontend: opens a ROM with the default settings and a custom keypad
+//! layout, where the arrow keys drive the keypad keys most games use to move around.
+//! Requires the `window` feature.
+//!
+//! ```shell script
+//! $ cargo run --example window -- ./roms/BRIX
+//! ```
+use chip8::chip_8::{Chip8, KeyMap, VideoSettings};
+use piston::input::Key;
+use std::{env, fs, process};
+
+/// Instructions executed per frame, 60 frames per second
+const INSTRUCTIONS_PER_FRAME: u32 = 11;
+
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "roms/BRIX".to_string());
+
+    let mut vm = Chip8::new();
+    let rom = fs::read(&path).unwrap_or_else(|e| {
+        eprintln!("Failed to open the ROM {}: {}", path, e);
+        process::exit(1);
+    });
+    if let Err(msg) = vm.load_rom_content(rom) {
+        eprintln!("Failed to load the ROM {}: {}", path, msg);
+        process::exit(1);
+    }
+
+    let mut keymap = KeyMap::new();
+    let arrows = [
+        (0x2, Key::Up),
+        (0x4, Key::Left),
+        (0x6, Key::Right),
+        (0x8, Key::Down),
+    ];
+    for (keypad_key, key) in arrows.iter() {
+        if let Err(msg) = keymap.bind(*keypad_key, *key) {
+            eprintln!("Failed to map the arrow keys: {}", msg);
+            process::exit(1);
+        }
+    }
+    if let Err(msg) = vm.set_keymap(keymap) {
+        eprintln!("Failed to map the arrow keys: {}", msg);
+        process::exit(1);
+    }
+
+    vm.set_instructions_per_frame(INSTRUCTIONS_PER_FRAME);
+    vm.start(VideoSettings::default(), None);
+}
@@ -0,0 +1,47 @@
+//! Runs arbitrary bytes as a ROM, asserting the VM never panics whatever the instructions,
+//! the profile, the quirks or the keys pressed. Faults reported by `Chip8::step` are expected.
+//!
+//! The first 3 bytes select the settings, the rest is the ROM:
+//! * The `Profile`, as an index in `Profile::ALL`.
+//! * The quirks enabled, one bit per quirk of `Quirk::ALL`.
+//! * The key toggled (lowest digit), and how often (highest digit, in steps of 16 cycles).
+#![no_main]
+use chip8::chip_8::{Chip8, Profile, Quirk};
+use libfuzzer_sys::fuzz_target;
+
+/// Maximum amount of cycles executed for each input
+const MAX_CYCLES: u32 = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 3 {
+        return;
+    }
+    let (settings, rom) = data.split_at(3);
+
+    let mut vm = Chip8::new();
+    vm.apply_profile(Profile::ALL[settings[0] as usize % Profile::ALL.len()]);
+    let mut quirks = vm.quirks();
+    for (bit, &quirk) in Quirk::ALL.iter().enumerate() {
+        quirks.set(quirk, settings[1] & (1 << bit) != 0);
+    }
+    vm.set_quirks(quirks);
+    if vm.load_rom_content(rom.to_vec()).is_err() {
+        return;
+    }
+
+    let key = (settings[2] & 0xF) as usize;
+    let key_period = ((settings[2] >> 4) as u32 + 1) * 16;
+    for cycle in 0..MAX_CYCLES {
+        if cycle % key_period == 0 {
+            match (cycle / key_period) % 2 {
+                0 => vm.press_key(key),
+                _ => vm.release_key(key),
+            }
+        }
+        match vm.step() {
+            Ok(Some(_)) => {}
+            Ok(None) | Err(_) => break,
+        }
+    }
+    vm.display_pbm();
+});
@@ -0,0 +1,334 @@
+//! Assembler for the mnemonics printed by the disassembler, ex: for homebrew ROMs.
+//!
+//! Each line holds an optional label, an optional instruction or directive and an optional
+//! comment, starting with `;`:
+//! ```text
+//! start:  LD V0, 10       ; labels end with ':'
+//!         LD I, sprite
+//!         DRW V0, V0, 5
+//! loop:   JP loop
+//! sprite: DB 0xF0, 0x90, 0x90, 0x90, 0xF0
+//! ```
+//! * Instructions use the syntax of `Instruction::info`, ex: `SE Vx, kk`. Mnemonics and
+//!   registers are case insensitive.
+//! * Numbers are decimal, hexadecimal (`0x`) or binary (`0b`). Labels can be used anywhere
+//!   an address or a byte is expected.
+//! * `DB` emits bytes and `DW` emits big-endian words, ex: for sprites and tables.
+//! * `SHR Vx` and `SHL Vx` can leave out `Vy`, which is then `Vx`.
+//! * `LD I, LONG nnnn` assembles the XO-CHIP `F000 NNNN`.
+//!
+//! ROMs are assembled to run from `0x200`, where they are loaded.
+use super::{Chip8, Instruction};
+use std::collections::HashMap;
+
+/// Assembles `source` into a ROM
+/// # Errors
+/// On the first invalid line, with its line number. Ex: `Line 3: Unknown label 'lop'`
+pub fn assemble(source: &str) -> Result<Vec<u8>, String> {
+    let lines: Vec<Line> = source
+        .lines()
+        .enumerate()
+        .map(|(index, text)| Line::parse(text).map_err(|msg| at_line(index, msg)))
+        .collect::<Result<_, _>>()?;
+
+    // First pass: address of every label
+    let mut labels: HashMap<String, u16> = HashMap::new();
+    let mut address = Chip8::INITIAL_MEMORY_ADDRESS;
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(label) = &line.label {
+            if labels
+                .insert(label.to_lowercase(), address as u16)
+                .is_some()
+            {
+                return Err(at_line(index, format!("Duplicated label '{}'", label)));
+            }
+        }
+        address += line.size();
+        if address > 0x10000 {
+            return Err(at_line(index, "The ROM exceeds the memory".to_string()));
+        }
+    }
+
+    // Second pass: encoding, now that labels can be resolved
+    let mut rom = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        line.encode(&labels, &mut rom)
+            .map_err(|msg| at_line(index, msg))?;
+    }
+    Ok(rom)
+}
+
+/// Prefixes an error message with its line number, counted from `1`
+fn at_line(index: usize, msg: String) -> String {
+    format!("Line {}: {}", index + 1, msg)
+}
+
+/// A line of source, split into its parts
+struct Line {
+    label: Option<String>,
+    /// Mnemonic or directive, in uppercase
+    mnemonic: Option<String>,
+    operands: Vec<String>,
+}
+
+impl Line {
+    fn parse(text: &str) -> Result<Line, String> {
+        let mut text = text.split(';').next().unwrap_or_default().trim();
+
+        let mut label = None;
+        if let Some((name, rest)) = text.split_once(':') {
+            let name = name.trim();
+            if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(format!("Invalid label '{}'", name));
+            }
+            if name.starts_with(|c: char| c.is_ascii_digit()) {
+                return Err(format!("Labels can't start with a digit: '{}'", name));
+            }
+            label = Some(name.to_string());
+            text = rest.trim();
+        }
+
+        if text.is_empty() {
+            return Ok(Line {
+                label,
+                mnemonic: None,
+                operands: Vec::new(),
+            });
+        }
+        let (mnemonic, operands) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let operands = match operands.trim() {
+            "" => Vec::new(),
+            operands => operands
+                .split(',')
+                .map(|operand| operand.trim().to_string())
+                .collect(),
+        };
+        Ok(Line {
+            label,
+            mnemonic: Some(mnemonic.to_uppercase()),
+            operands,
+        })
+    }
+
+    /// Returns the number of bytes the line assembles to
+    fn size(&self) -> usize {
+        match self.mnemonic.as_deref() {
+            None => 0,
+            Some("DB") => self.operands.len(),
+            Some("DW") => self.operands.len() * 2,
+            Some("LD") if self.long_address().is_some() => 4,
+            Some(_) => 2,
+        }
+    }
+
+    /// Returns the operand of `LD I, LONG nnnn`, if the line is one
+    fn long_address(&self) -> Option<&str> {
+        match self.operands.as_slice() {
+            [target, source] if target.eq_ignore_ascii_case("I") => {
+                let (keyword, address) = source.split_once(char::is_whitespace)?;
+                keyword.eq_ignore_ascii_case("LONG").then(|| address.trim())
+            }
+            _ => None,
+        }
+    }
+
+    /// Appends the bytes of the line to `rom`
+    fn encode(&self, labels: &HashMap<String, u16>, rom: &mut Vec<u8>) -> Result<(), String> {
+        let mnemonic = match &self.mnemonic {
+            None => return Ok(()),
+            Some(mnemonic) => mnemonic.as_str(),
+        };
+        let value = |text: &str, max: u32| -> Result<u32, String> {
+            let value = match labels.get(&text.to_lowercase()) {
+                Some(&address) => address as u32,
+                None => number(text)?,
+            };
+            if value > max {
+                return Err(format!("'{}' exceeds the maximum of 0x{:X}", text, max));
+            }
+            Ok(value)
+        };
+
+        match mnemonic {
+            "DB" => {
+                for operand in self.operands.iter() {
+                    rom.push(value(operand, 0xFF)? as u8);
+                }
+                return Ok(());
+            }
+            "DW" => {
+                for operand in self.operands.iter() {
+                    rom.extend_from_slice(&(value(operand, 0xFFFF)? as u16).to_be_bytes());
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+        if let Some(address) = self.long_address() {
+            rom.extend_from_slice(&Instruction::LdILong.opcode().to_be_bytes());
+            rom.extend_from_slice(&(value(address, 0xFFFF)? as u16).to_be_bytes());
+            return Ok(());
+        }
+
+        let operands: Vec<Operand> = self.operands.iter().map(|o| Operand::parse(o)).collect();
+        let addr = |text: &str| value(text, 0xFFF).map(|nnn| nnn as u16);
+        let byte = |text: &str| value(text, 0xFF).map(|kk| kk as u8);
+        use Operand::*;
+        let instruction = match (mnemonic, operands.as_slice()) {
+            ("CLS", []) => Instruction::Cls,
+            ("RET", []) => Instruction::Ret,
+            ("JP", [Value(nnn)]) => Instruction::Jp(addr(nnn)?),
+            ("JP", [V(0), Value(nnn)]) => Instruction::JpV0Addr(addr(nnn)?),
+            ("CALL", [Value(nnn)]) => Instruction::Call(addr(nnn)?),
+            ("SE", [V(x), V(y)]) => Instruction::SeVxVy(*x, *y),
+            ("SE", [V(x), Value(kk)]) => Instruction::SeVxByte(*x, byte(kk)?),
+            ("SNE", [V(x), V(y)]) => Instruction::SneVxVy(*x, *y),
+            ("SNE", [V(x), Value(kk)]) => Instruction::SneVxByte(*x, byte(kk)?),
+            ("LD", [V(x), V(y)]) => Instruction::LdVxVy(*x, *y),
+            ("LD", [V(x), Dt]) => Instruction::LdVxDt(*x),
+            ("LD", [V(x), K]) => Instruction::LdVxK(*x),
+            ("LD", [V(x), IndirectI]) => Instruction::LdVxI(*x),
+            ("LD", [V(x), R]) => Instruction::LdVxR(*x),
+            ("LD", [V(x), Value(kk)]) => Instruction::LdVxValue(*x, byte(kk)?),
+            ("LD", [I, Value(nnn)]) => Instruction::LdIAddr(addr(nnn)?),
+            ("LD", [Dt, V(x)]) => Instruction::LdDtVx(*x),
+            ("LD", [St, V(x)]) => Instruction::LdStVx(*x),
+            ("LD", [F, V(x)]) => Instruction::LdFVx(*x),
+            ("LD", [Hf, V(x)]) => Instruction::LdHfVx(*x),
+            ("LD", [B, V(x)]) => Instruction::LdBVx(*x),
+            ("LD", [IndirectI, V(x)]) => Instruction::LdIVx(*x),
+            ("LD", [R, V(x)]) => Instruction::LdRVx(*x),
+            ("ADD", [V(x), V(y)]) => Instruction::AddVxVy(*x, *y),
+            ("ADD", [V(x), Value(kk)]) => Instruction::AddVxByte(*x, byte(kk)?),
+            ("ADD", [I, V(x)]) => Instruction::AddIVx(*x),
+            ("OR", [V(x), V(y)]) => Instruction::OrVxVy(*x, *y),
+            ("AND", [V(x), V(y)]) => Instruction::AndVxVy(*x, *y),
+            ("XOR", [V(x), V(y)]) => Instruction::XorVxVy(*x, *y),
+            ("SUB", [V(x), V(y)]) => Instruction::SubVxVy(*x, *y),
+            ("SUBN", [V(x), V(y)]) => Instruction::SubnVxVy(*x, *y),
+            ("SHR", [V(x)]) => Instruction::ShrVx(*x, *x),
+            ("SHR", [V(x), V(y)]) => Instruction::ShrVx(*x, *y),
+            ("SHL", [V(x)]) => Instruction::ShlVx(*x, *x),
+            ("SHL", [V(x), V(y)]) => Instruction::ShlVx(*x, *y),
+            ("RND", [V(x), Value(kk)]) => Instruction::RndVxByte(*x, byte(kk)?),
+            ("DRW", [V(x), V(y), Value(n)]) => {
+                Instruction::DrwVxVyN(*x, *y, value(n, 0xF)? as usize)
+            }
+            ("SKP", [V(x)]) => Instruction::SkipVx(*x),
+            ("SKNP", [V(x)]) => Instruction::SkipNVx(*x),
+            ("SAVE", [V(x), V(y)]) => Instruction::SaveVxVy(*x, *y),
+            ("LOAD", [V(x), V(y)]) => Instruction::LoadVxVy(*x, *y),
+            ("PLANE", [Value(n)]) => Instruction::Plane(value(n, 0xF)? as usize),
+            ("AUDIO", []) => Instruction::Audio,
+            ("PITCH", [V(x)]) => Instruction::Pitch(*x),
+            ("SCD", [Value(n)]) => Instruction::ScrollDown(value(n, 0xF)? as usize),
+            ("SCU", [Value(n)]) => Instruction::ScrollUp(value(n, 0xF)? as usize),
+            ("SCR", []) => Instruction::ScrollRight,
+            ("SCL", []) => Instruction::ScrollLeft,
+            ("EXIT", []) => Instruction::Exit,
+            ("LOW", []) => Instruction::Low,
+            ("HIGH", []) => Instruction::High,
+            _ if is_mnemonic(mnemonic) => {
+                return Err(format!(
+                    "Invalid operands for {}: '{}'",
+                    mnemonic,
+                    self.operands.join(", ")
+                ))
+            }
+            _ => return Err(format!("Unknown instruction '{}'", mnemonic)),
+        };
+        rom.extend_from_slice(&instruction.opcode().to_be_bytes());
+        Ok(())
+    }
+}
+
+/// Returns whether `mnemonic` names an instruction, whatever its operands
+fn is_mnemonic(mnemonic: &str) -> bool {
+    const MNEMONICS: [&str; 31] = [
+        "CLS", "RET", "JP", "CALL", "SE", "SNE", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SUBN",
+        "SHR", "SHL", "RND", "DRW", "SKP", "SKNP", "SAVE", "LOAD", "PLANE", "AUDIO", "PITCH",
+        "SCD", "SCU", "SCR", "SCL", "EXIT", "LOW", "HIGH",
+    ];
+    MNEMONICS.contains(&mnemonic)
+}
+
+/// An operand of an instruction
+enum Operand<'a> {
+    /// A `v` register, ex: `VA`
+    V(usize),
+    I,
+    /// `[I]`: the memory at `I`
+    IndirectI,
+    Dt,
+    St,
+    K,
+    F,
+    /// The big font of SUPER-CHIP
+    Hf,
+    B,
+    /// The RPL user flags
+    R,
+    /// A number or a label, resolved on encoding
+    Value(&'a str),
+}
+
+impl<'a> Operand<'a> {
+    fn parse(text: &'a str) -> Operand<'a> {
+        match text.to_uppercase().as_str() {
+            "I" => Operand::I,
+            "[I]" => Operand::IndirectI,
+            "DT" => Operand::Dt,
+            "ST" => Operand::St,
+            "K" => Operand::K,
+            "F" => Operand::F,
+            "HF" => Operand::Hf,
+            "B" => Operand::B,
+            "R" => Operand::R,
+            upper => match upper
+                .strip_prefix('V')
+                .map(|x| usize::from_str_radix(x, 16))
+            {
+                Some(Ok(x)) if upper.len() == 2 => Operand::V(x),
+                _ => Operand::Value(text),
+            },
+        }
+    }
+}
+
+/// Parses a decimal, hexadecimal (`0x`) or binary (`0b`) number
+fn number(text: &str) -> Result<u32, String> {
+    let lower = text.to_lowercase();
+    let parsed = if let Some(digits) = lower.strip_prefix("0x") {
+        u32::from_str_radix(digits, 16)
+    } else if let Some(digits) = lower.strip_prefix("0b") {
+        u32::from_str_radix(digits, 2)
+    } else if lower.starts_with(|c: char| c.is_ascii_digit()) {
+        lower.parse::<u32>()
+    } else {
+        return Err(format!("Unknown label '{}'", text));
+    };
+    parsed.map_err(|_| format!("Invalid number '{}'", text))
+}
+
+#[test]
+fn disassembly_assembles_back() {
+    for opcode in 0..=u16::MAX {
+        // Followed by a word, the address of `F000 NNNN`
+        let mut rom = opcode.to_be_bytes().to_vec();
+        rom.extend_from_slice(&[0x12, 0x34]);
+        let source: Vec<String> = super::disassemble_rom(&rom)
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect();
+        let source = source.join("\n");
+        assert_eq!(assemble(&source).as_ref(), Ok(&rom), "{}", source);
+    }
+    // An odd trailing byte, and `F000` without its address
+    for rom in [vec![0x00, 0xE0, 0xAB], vec![0xF0, 0x00]] {
+        let source: Vec<String> = super::disassemble_rom(&rom)
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect();
+        assert_eq!(assemble(&source.join("\n")), Ok(rom));
+    }
+}
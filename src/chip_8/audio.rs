@@ -0,0 +1,371 @@
+use super::Chip8;
+use std::convert::TryFrom;
+use std::f32::consts::PI;
+use std::fs;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+/// Samples per second of the synthesized audio
+const SAMPLE_RATE: u32 = 44_100;
+
+/// Samples per second of the XO-CHIP audio pattern at `AudioPattern::DEFAULT_PITCH`
+const PATTERN_RATE: f64 = 4000.0;
+
+/// `WAVE` format tags of the samples supported by `WavSample::parse`
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Shape of the beep: a wave synthesized at the frequency of the `Tone`,
+/// or a recorded sample played at its own pitch
+#[derive(Clone, Debug, PartialEq)]
+pub enum Waveform {
+    Square,
+    Triangle,
+    Sine,
+    /// Played from its start on every beep, and looped while the beep lasts
+    Sample(Arc<WavSample>),
+}
+
+impl Waveform {
+    /// Waves that can be selected by name
+    pub const SYNTHESIZED: [Waveform; 3] = [Waveform::Square, Waveform::Triangle, Waveform::Sine];
+
+    /// Returns the name used to select the wave, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Waveform::Square => "square",
+            Waveform::Triangle => "triangle",
+            Waveform::Sine => "sine",
+            Waveform::Sample(_) => "sample",
+        }
+    }
+
+    /// Finds a synthesized wave by its name
+    pub fn from_name(name: &str) -> Option<Waveform> {
+        Waveform::SYNTHESIZED
+            .iter()
+            .find(|waveform| waveform.name() == name)
+            .cloned()
+    }
+}
+
+/// A sound recorded in a WAV file, mixed down to mono
+#[derive(Clone, Debug, PartialEq)]
+pub struct WavSample {
+    /// Samples from `-1.0` to `1.0`
+    samples: Vec<f32>,
+    sample_rate: u32,
+}
+
+impl WavSample {
+    /// Parses a WAV file holding PCM samples of 8, 16, 24 or 32 bits, or 32-bit floats,
+    /// with any number of channels
+    /// # Errors
+    /// If the file isn't a WAV file, or if its format isn't supported
+    pub fn parse(bytes: &[u8]) -> Result<WavSample, String> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err("Not a WAV file.".to_string());
+        }
+        let u16_at =
+            |chunk: &[u8], offset: usize| u16::from_le_bytes([chunk[offset], chunk[offset + 1]]);
+        let u32_at = |chunk: &[u8], offset: usize| {
+            u32::from_le_bytes([
+                chunk[offset],
+                chunk[offset + 1],
+                chunk[offset + 2],
+                chunk[offset + 3],
+            ])
+        };
+
+        // Chunks are padded to an even size
+        let mut format = None;
+        let mut data = None;
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let size = u32_at(bytes, offset + 4) as usize;
+            let chunk = &bytes[offset + 8..(offset + 8).saturating_add(size).min(bytes.len())];
+            match &bytes[offset..offset + 4] {
+                b"fmt " if chunk.len() >= 16 => format = Some(chunk),
+                b"data" => data = Some(chunk),
+                _ => {}
+            }
+            offset = offset.saturating_add(8 + size + size % 2);
+        }
+        let format = format.ok_or("The WAV file has no format.")?;
+        let data = data.ok_or("The WAV file has no samples.")?;
+
+        let tag = match u16_at(format, 0) {
+            WAVE_FORMAT_EXTENSIBLE if format.len() >= 26 => u16_at(format, 24),
+            tag => tag,
+        };
+        let channels = u16_at(format, 2) as usize;
+        let sample_rate = u32_at(format, 4);
+        let bits = u16_at(format, 14);
+        let decode: fn(&[u8]) -> f32 = match (tag, bits) {
+            (WAVE_FORMAT_PCM, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+            (WAVE_FORMAT_PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32_768.0,
+            (WAVE_FORMAT_PCM, 24) => {
+                |b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2_147_483_648.0
+            }
+            (WAVE_FORMAT_PCM, 32) => {
+                |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0
+            }
+            (WAVE_FORMAT_IEEE_FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            _ => {
+                return Err(format!(
+                    "Unsupported WAV format {} with {} bits per sample.",
+                    tag, bits
+                ))
+            }
+        };
+        if channels == 0 || sample_rate == 0 {
+            return Err("Invalid WAV format.".to_string());
+        }
+
+        let width = bits as usize / 8;
+        let samples = data
+            .chunks_exact(width * channels)
+            .map(|frame| {
+                let sum: f32 = frame.chunks_exact(width).map(decode).sum();
+                (sum / channels as f32).clamp(-1.0, 1.0)
+            })
+            .collect();
+        Ok(WavSample {
+            samples,
+            sample_rate,
+        })
+    }
+
+    /// Reads the WAV file at `path`. See `WavSample::parse`
+    pub fn load(path: &str) -> Result<WavSample, String> {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+        WavSample::parse(&bytes).map_err(|e| format!("Invalid sample {}: {}", path, e))
+    }
+
+    /// Returns the sample at `position`, at `sample_rate`, looping over the sound
+    fn value(&self, position: u64, sample_rate: u32) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let index = position * self.sample_rate as u64 / sample_rate as u64;
+        self.samples[(index % self.samples.len() as u64) as usize]
+    }
+}
+
+/// Pitch, shape and loudness of the beep played while the sound timer is active.
+/// Shared by the `AudioTrack` and, with the `sound` feature, the `Beeper`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tone {
+    /// Frequency of the wave, in Hz. Samples play at their own pitch
+    pub frequency: u32,
+    /// Volume, from `0` (muted) to `100` (full scale)
+    pub volume: u8,
+    pub waveform: Waveform,
+}
+
+impl Tone {
+    /// Default tone: a 440 Hz square wave at a quarter of the full scale
+    pub const DEFAULT: Tone = Tone {
+        frequency: 440,
+        volume: 25,
+        waveform: Waveform::Square,
+    };
+
+    /// Returns the sample of the beep at `position`, from `-1.0` to `1.0`.
+    /// `position` counts the samples since the beep started, so beeps split across
+    /// ticks stay continuous, and samples play from their start
+    pub fn sample(&self, position: u64, sample_rate: u32) -> f32 {
+        // Position in the period of the wave, from `0.0` to `1.0`
+        let phase =
+            || (position * self.frequency as u64 % sample_rate as u64) as f32 / sample_rate as f32;
+        let value = match &self.waveform {
+            Waveform::Square if phase() < 0.5 => 1.0,
+            Waveform::Square => -1.0,
+            Waveform::Triangle => 4.0 * (phase() - 0.5).abs() - 1.0,
+            Waveform::Sine => (2.0 * PI * phase()).sin(),
+            Waveform::Sample(sample) => sample.value(position, sample_rate),
+        };
+        self.amplitude() * value
+    }
+
+    /// Returns the peak of the wave, from `0.0` to `1.0`
+    fn amplitude(&self) -> f32 {
+        self.volume.min(100) as f32 / 100.0
+    }
+}
+
+impl Default for Tone {
+    fn default() -> Tone {
+        Tone::DEFAULT
+    }
+}
+
+/// The XO-CHIP sound: 128 samples of 1 bit loaded with `F002`, looped at the rate set
+/// by the pitch with `Fx3A`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AudioPattern {
+    /// Samples, played from the most significant bit of the first byte
+    pub bits: [u8; 16],
+    pub pitch: u8,
+}
+
+impl AudioPattern {
+    /// Pitch of the VM after a reset, playing the pattern at 4000 samples per second
+    pub const DEFAULT_PITCH: u8 = 64;
+
+    /// Returns the samples of the pattern played per second: 4000 at the default pitch,
+    /// doubling every 48 steps above it and halving every 48 below
+    pub fn rate(&self) -> f64 {
+        PATTERN_RATE * 2f64.powf((self.pitch as f64 - AudioPattern::DEFAULT_PITCH as f64) / 48.0)
+    }
+
+    /// Returns the sample at `index`, looping over the pattern: `1.0` for set bits,
+    /// `-1.0` otherwise
+    pub fn sample(&self, index: u64) -> f32 {
+        let index = (index % 128) as usize;
+        if self.bits[index / 8] & (0x80 >> (index % 8)) != 0 {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+}
+
+/// What the VM plays during a tick of the timers. See `Chip8::sound`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sound {
+    Silent,
+    /// The wave of the `Tone`
+    Beep,
+    /// The XO-CHIP audio pattern, at the volume of the `Tone`
+    Pattern(AudioPattern),
+}
+
+/// Renders a `Sound` sample by sample at the rate of the host, keeping the position of the
+/// beep and the phase of the pattern across calls, so the wave stays continuous when the
+/// sound is split across ticks or buffers, and when the pitch changes
+pub(super) struct Voice {
+    /// Samples since the beep started
+    position: u64,
+    /// Position in the pattern, in samples of the pattern
+    phase: f64,
+}
+
+impl Voice {
+    pub(super) fn new() -> Voice {
+        Voice {
+            position: 0,
+            phase: 0.0,
+        }
+    }
+
+    /// Returns the next sample of `sound`, from `-1.0` to `1.0`. Silence restarts the
+    /// beep and the pattern
+    pub(super) fn next(&mut self, sound: &Sound, tone: &Tone, sample_rate: u32) -> f32 {
+        match sound {
+            Sound::Silent => {
+                self.position = 0;
+                self.phase = 0.0;
+                0.0
+            }
+            Sound::Beep => {
+                self.position += 1;
+                tone.sample(self.position - 1, sample_rate)
+            }
+            Sound::Pattern(pattern) => {
+                // Nearest sample: the pattern is a 1-bit wave, filtering adds nothing
+                let value = pattern.sample(self.phase as u64);
+                self.phase = (self.phase + pattern.rate() / sample_rate as f64) % 128.0;
+                tone.amplitude() * value
+            }
+        }
+    }
+}
+
+/// Audio track synthesized offline from the `Sound` of every tick, so runs without an
+/// audio device (ex: `headless` renders) still get a sample-accurate soundtrack: the beep,
+/// or the XO-CHIP audio pattern, plays on every tick the sound timer is active.
+///
+/// Ticks are stored as runs of the same sound, so long silences take no memory.
+///
+/// **Note:** Timers tick once per cycle, so each cycle is rendered as one `1/60s` tick.
+pub struct AudioTrack {
+    /// Runs of ticks with the same sound: `(sound, ticks)`
+    runs: Vec<(Sound, u64)>,
+    tone: Tone,
+}
+
+impl AudioTrack {
+    pub fn new() -> AudioTrack {
+        AudioTrack::with_tone(Tone::DEFAULT)
+    }
+
+    /// Creates an empty track beeping with `tone`
+    pub fn with_tone(tone: Tone) -> AudioTrack {
+        AudioTrack {
+            runs: Vec::new(),
+            tone,
+        }
+    }
+
+    /// Sets the beep of the ticks with the sound active, and the volume of the pattern,
+    /// for the whole track
+    pub fn set_tone(&mut self, tone: Tone) {
+        self.tone = tone;
+    }
+
+    /// Records a timer tick playing `sound`
+    pub fn record_tick(&mut self, sound: Sound) {
+        match self.runs.last_mut() {
+            Some((state, ticks)) if *state == sound => *ticks += 1,
+            _ => self.runs.push((sound, 1)),
+        }
+    }
+
+    /// Returns the number of ticks recorded
+    pub fn ticks(&self) -> u64 {
+        self.runs.iter().map(|&(_, ticks)| ticks).sum()
+    }
+
+    /// Writes the track as a 16-bit mono WAV file
+    /// # Errors
+    /// If writing fails, or if the track is too long for a WAV file (about 6 hours)
+    pub fn write_wav<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        // Each tick lasts exactly `735` samples
+        let samples_per_tick = (SAMPLE_RATE / Chip8::FRAME_RATE) as u64;
+        let data_size = self.ticks() * samples_per_tick * 2;
+        let data_size = u32::try_from(data_size)
+            .ok()
+            .filter(|&size| size <= u32::MAX - 36)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Audio track too long."))?;
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(36 + data_size).to_le_bytes())?;
+        writer.write_all(b"WAVEfmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?; // PCM
+        writer.write_all(&1u16.to_le_bytes())?; // Mono
+        writer.write_all(&SAMPLE_RATE.to_le_bytes())?;
+        writer.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?;
+        writer.write_all(&2u16.to_le_bytes())?;
+        writer.write_all(&16u16.to_le_bytes())?;
+        writer.write_all(b"data")?;
+        writer.write_all(&data_size.to_le_bytes())?;
+
+        let mut voice = Voice::new();
+        for (sound, ticks) in self.runs.iter() {
+            for _ in 0..ticks * samples_per_tick {
+                let value = (voice.next(sound, &self.tone, SAMPLE_RATE) * i16::MAX as f32) as i16;
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for AudioTrack {
+    fn default() -> AudioTrack {
+        AudioTrack::new()
+    }
+}
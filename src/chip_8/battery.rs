@@ -0,0 +1,63 @@
+use super::Chip8;
+use std::ops::Range;
+
+impl Chip8 {
+    /// Returns the `Storage` key of the battery-backed memory of the current ROM,
+    /// ex: `battery/8a3f0c2e`
+    pub fn battery_key(&self) -> String {
+        format!("battery/{:08x}", self.rom_crc32())
+    }
+
+    /// Returns the range of memory kept between runs for the current ROM, if its settings
+    /// have one and it fits in memory. See `RomSettings::battery`
+    pub fn battery_range(&self) -> Option<Range<usize>> {
+        self.rom_settings
+            .as_ref()
+            .and_then(|settings| settings.battery.clone())
+            .filter(|range| range.end <= self.main_memory.len())
+    }
+
+    /// Restores the battery-backed memory of the current ROM from the save storage, like
+    /// the score tables of cartridges with a battery. Without storage, or without memory
+    /// stored, the ROM starts with the memory it loads. Failures are reported on the `Osd`
+    pub(super) fn load_battery(&mut self) {
+        let range = match self.battery_range() {
+            Some(range) => range,
+            None => return,
+        };
+        let key = self.battery_key();
+        let stored = match &self.save_storage {
+            Some(storage) => storage.load(&key),
+            None => return,
+        };
+        match stored {
+            Ok(Some(bytes)) => {
+                let count = bytes.len().min(range.len());
+                self.main_memory[range.start..range.start + count].copy_from_slice(&bytes[..count]);
+            }
+            Ok(None) => {}
+            Err(e) => self
+                .osd
+                .show(format!("Failed to load the battery-backed memory: {}", e)),
+        }
+    }
+
+    /// Writes the battery-backed memory of the current ROM to the save storage, if it has
+    /// any and there's a storage. Frontends call it on exit, while resetting or switching
+    /// ROMs stores it on its own
+    /// # Errors
+    /// If the storage fails
+    pub fn store_battery(&mut self) -> Result<(), String> {
+        let range = match self.battery_range() {
+            Some(range) if !self.rom.is_empty() => range,
+            _ => return Ok(()),
+        };
+        let key = self.battery_key();
+        match &mut self.save_storage {
+            Some(storage) => storage
+                .store(&key, &self.main_memory[range])
+                .map_err(|e| format!("Failed to save the battery-backed memory: {}", e)),
+            None => Ok(()),
+        }
+    }
+}
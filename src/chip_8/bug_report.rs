@@ -0,0 +1,101 @@
+use super::dump::disassemble;
+use super::{Chip8, Quirk};
+use crate::zip::ZipWriter;
+use std::fs;
+use std::io;
+
+impl Chip8 {
+    /// Builds a bug report bundle: a ZIP archive holding everything needed to
+    /// reproduce an issue, ready to be attached to a report.
+    /// # Bundle contents
+    /// * `report.txt`: Emulator version, size and `CRC32` of the ROM, active `Profile`,
+    ///   quirks, stack depth, memory bounds, VIP timing, random seed and the given `config`
+    ///   (ex: the command line).
+    /// * `trace.txt`: Disassembly of the last instructions executed. See `History`.
+    /// * `registers.txt`: Registers, timers and stack.
+    /// * `state.c8s`: A save state, to resume right where the issue happened with
+    ///   `Chip8::load_state` and the same ROM.
+    /// * `memory.bin`: The whole main memory.
+    /// * `display.txt` and `screenshot.png`: The display, as text and as an image.
+    ///
+    /// **Note:** The ROM itself is not included, only its hash.
+    pub fn bug_report(&self, config: &str) -> io::Result<Vec<u8>> {
+        let quirks: Vec<&str> = Quirk::ALL
+            .iter()
+            .filter(|&&quirk| self.quirks.is_enabled(quirk))
+            .map(|quirk| quirk.name())
+            .collect();
+        let report = format!(
+            "Chip 8 {}\nROM: {} bytes, CRC32 {:08X}\nProfile: {}\nQuirks: {}\n\
+             Stack depth: {}\nMemory bounds: {}\nVIP timing: {}\nSeed: {}\n{}\n",
+            env!("CARGO_PKG_VERSION"),
+            self.rom.len(),
+            self.rom_crc32(),
+            self.profile.name(),
+            if quirks.is_empty() {
+                String::from("none")
+            } else {
+                quirks.join(", ")
+            },
+            self.stack.depth,
+            self.memory_bounds().name(),
+            if self.vip_timing() { "on" } else { "off" },
+            self.rng_seed
+                .map_or(String::from("random"), |seed| seed.to_string()),
+            config
+        );
+        let trace: String = self
+            .history
+            .iter()
+            .map(|&(address, opcode)| disassemble(address, opcode) + "\n")
+            .collect();
+
+        let mut zip = ZipWriter::new(Vec::new());
+        zip.add_file("report.txt", report.as_bytes())?;
+        zip.add_file("trace.txt", trace.as_bytes())?;
+        zip.add_file("registers.txt", self.register_dump().as_bytes())?;
+        zip.add_file("state.c8s", &self.save_state())?;
+        zip.add_file("memory.bin", &self.main_memory)?;
+        zip.add_file("display.txt", self.display_text().as_bytes())?;
+        zip.add_file("screenshot.png", &self.thumbnail_png())?;
+        zip.finish()
+    }
+
+    /// Writes a bug report bundle to the file at `path`. See `bug_report`
+    pub fn write_bug_report(&self, path: &str, config: &str) -> io::Result<()> {
+        fs::write(path, self.bug_report(config)?)
+    }
+}
+
+#[test]
+fn bug_reports_hold_the_settings_and_a_save_state() {
+    use super::{MemoryBounds, Profile};
+    use crate::zip::ZipArchive;
+
+    let mut vm = Chip8::new();
+    vm.apply_profile(Profile::Schip);
+    vm.set_memory_bounds(MemoryBounds::Wrap);
+    vm.set_rng_seed(42);
+    vm.load_rom_content(include_bytes!("../../roms/tests/ibm-logo.ch8").to_vec())
+        .unwrap();
+    vm.step().unwrap();
+
+    let bundle = vm.bug_report("chip8 run ibm-logo.ch8").unwrap();
+    let archive = ZipArchive::new(&bundle).unwrap();
+    let read = |name: &str| {
+        let entry = archive.entries().iter().find(|entry| entry.name == name);
+        archive.read(entry.unwrap()).unwrap()
+    };
+    let report = String::from_utf8(read("report.txt")).unwrap();
+    assert!(report.contains("Profile: schip\nQuirks: jump\n"));
+    assert!(report.contains("Memory bounds: wrap\nVIP timing: off\nSeed: 42\n"));
+    assert!(report.ends_with("chip8 run ibm-logo.ch8\n"));
+
+    let mut restored = Chip8::new();
+    restored.apply_profile(Profile::Schip);
+    restored
+        .load_rom_content(include_bytes!("../../roms/tests/ibm-logo.ch8").to_vec())
+        .unwrap();
+    restored.load_state(&read("state.c8s")).unwrap();
+    assert_eq!(restored.save_state(), vm.save_state());
+}
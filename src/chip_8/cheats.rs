@@ -0,0 +1,156 @@
+use super::triggers::parse_number;
+use super::Chip8;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A patch applied to the VM on every frame
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Cheat {
+    /// Writes `value` to the byte at `address`, ex: to keep the number of lives
+    Freeze { address: u16, value: u8 },
+    /// Writes `value` to the register `Vx`
+    FreezeRegister { x: usize, value: u8 },
+    /// Writes `bytes` from `address`, ex: to replace an instruction of the ROM
+    Patch { address: u16, bytes: Vec<u8> },
+}
+
+/// Cheats patching the memory or the registers of a VM on every frame, like the
+/// Game Genie of the time.
+/// # Cheats format
+/// A text file with one cheat per line, usually next to the ROM with the `.cht`
+/// extension. See `Cheats::path_for`. Everything after a `#` is a comment.
+/// Numbers are decimal, or hexadecimal with a `0x` prefix.
+/// * `freeze ADDRESS=VALUE`: Freezes a byte of memory.
+/// * `freeze Vx=VALUE`: Freezes a register, `x` being hexadecimal.
+/// * `patch ADDRESS=BYTES`: Writes bytes written in hexadecimal, ex: `patch 0x2A4=6005`.
+///
+/// Ex: `freeze 0x3A2=0x05  # infinite lives`
+pub struct Cheats {
+    cheats: Vec<Cheat>,
+}
+
+fn parse_cheat(text: &str) -> Option<Cheat> {
+    let (command, assignment) = text.split_once(' ')?;
+    let (target, value) = assignment.split_once('=')?;
+    let (target, value) = (target.trim(), value.trim());
+    match command {
+        "freeze" => {
+            let value = u8::try_from(parse_number(value)?).ok()?;
+            match target.strip_prefix('V') {
+                Some(x) if x.len() == 1 => Some(Cheat::FreezeRegister {
+                    x: usize::from_str_radix(x, 16).ok()?,
+                    value,
+                }),
+                Some(_) => None,
+                None => Some(Cheat::Freeze {
+                    address: parse_number(target)?,
+                    value,
+                }),
+            }
+        }
+        "patch" if !value.is_empty() && value.len() % 2 == 0 => {
+            let bytes = (0..value.len())
+                .step_by(2)
+                .map(|start| u8::from_str_radix(value.get(start..start + 2)?, 16).ok())
+                .collect::<Option<Vec<u8>>>()?;
+            Some(Cheat::Patch {
+                address: parse_number(target)?,
+                bytes,
+            })
+        }
+        _ => None,
+    }
+}
+
+impl Cheats {
+    /// Extension of cheat files
+    pub const EXTENSION: &'static str = "cht";
+
+    pub fn new() -> Cheats {
+        Cheats { cheats: Vec::new() }
+    }
+
+    /// Parses the cheats in `text`. See `Cheats` for the format
+    pub fn parse(text: &str) -> Result<Cheats, String> {
+        let mut cheats = Cheats::new();
+        for (number, line) in text.lines().enumerate() {
+            let cheat = match line.split_once('#') {
+                Some((cheat, _)) => cheat.trim(),
+                None => line.trim(),
+            };
+            if cheat.is_empty() {
+                continue;
+            }
+            let cheat = parse_cheat(cheat)
+                .ok_or_else(|| format!("Malformed cheat at line {}: '{}'", number + 1, line))?;
+            cheats.add(cheat);
+        }
+        Ok(cheats)
+    }
+
+    /// Reads the cheats file at `path`. See `Cheats` for the format
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Cheats, String> {
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("Failed to open the cheats: {}", e))?;
+        Cheats::parse(&text)
+    }
+
+    /// Returns the path of the cheats of the ROM at `rom_path`: the same path with the
+    /// `.cht` extension, ex: `roms/BRIX.cht` for `roms/BRIX`
+    pub fn path_for(rom_path: &str) -> PathBuf {
+        Path::new(rom_path).with_extension(Cheats::EXTENSION)
+    }
+
+    pub fn add(&mut self, cheat: Cheat) {
+        self.cheats.push(cheat);
+    }
+
+    /// Returns all cheats, in the order they were added
+    pub fn cheats(&self) -> &[Cheat] {
+        &self.cheats
+    }
+}
+
+impl Default for Cheats {
+    fn default() -> Cheats {
+        Cheats::new()
+    }
+}
+
+impl Chip8 {
+    /// Sets the cheats applied on every frame, replacing the previous ones. See `Cheats`
+    pub fn set_cheats(&mut self, cheats: Cheats) {
+        self.cheats = Some(cheats);
+    }
+
+    /// Returns the cheats applied on every frame, if any
+    pub fn cheats(&self) -> Option<&Cheats> {
+        self.cheats.as_ref()
+    }
+
+    /// Applies every cheat, when the timers tick. Bytes beyond the memory are ignored
+    pub(super) fn apply_cheats(&mut self) {
+        let cheats = match &self.cheats {
+            Some(cheats) => cheats,
+            None => return,
+        };
+        for cheat in cheats.cheats.iter() {
+            match cheat {
+                Cheat::Freeze { address, value } => {
+                    if let Some(byte) = self.main_memory.get_mut(*address as usize) {
+                        *byte = *value;
+                    }
+                }
+                Cheat::FreezeRegister { x, value } => self.regs.v[*x] = *value,
+                Cheat::Patch { address, bytes } => {
+                    for (offset, value) in bytes.iter().enumerate() {
+                        if let Some(byte) = self.main_memory.get_mut(*address as usize + offset) {
+                            *byte = *value;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Commands copying their standard input to the system clipboard, tried in order.
+/// Using the tools shipped with each platform avoids linking a clipboard library.
+const CLIPBOARD_COMMANDS: [(&str, &[&str]); 5] = [
+    ("pbcopy", &[]),
+    ("clip", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+/// Runs `program` with `args`, writing `text` to its standard input
+/// # Returns
+/// `true` if the program could be run and exited successfully
+fn pipe_to(program: &str, args: &[&str], text: &str) -> bool {
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => return false,
+    };
+
+    let written = match child.stdin.take() {
+        Some(mut stdin) => stdin.write_all(text.as_bytes()).is_ok(),
+        None => false,
+    };
+    let exited = child.wait().map(|status| status.success()).unwrap_or(false);
+    written && exited
+}
+
+/// Copies `text` to the system clipboard
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    if CLIPBOARD_COMMANDS
+        .iter()
+        .any(|(program, args)| pipe_to(program, args, text))
+    {
+        Ok(())
+    } else {
+        Err(String::from(
+            "No clipboard tool found (pbcopy, clip, wl-copy, xclip or xsel).",
+        ))
+    }
+}
@@ -0,0 +1,167 @@
+use super::{Chip8, Chip8Error, WatchHit};
+use std::collections::HashSet;
+
+/// Reason why a `Debugger` suspended the execution of a VM
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The VM was suspended before executing its first instruction
+    Entry,
+    /// The `PC` reached an address with a breakpoint
+    Breakpoint,
+    /// A step request was completed
+    Step,
+    /// The execution was explicitly paused
+    Pause,
+    /// An instruction faulted, usually left unexecuted. See `Chip8Error`
+    Fault(Chip8Error),
+    /// An instruction accessed memory watched by a `Watchpoint`, and was executed.
+    /// See `Chip8::set_watchpoints`
+    Watchpoint(WatchHit),
+}
+
+/// Execution state of a VM controlled by a `Debugger`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RunState {
+    Stopped,
+    Running,
+    /// Execute a single instruction
+    StepIn,
+    /// Execute instructions until the Stack Pointer is back to `depth` (skipping `CALL`s)
+    StepOver {
+        depth: usize,
+    },
+    /// Execute instructions until the Stack Pointer goes below `depth` (leaving a subroutine)
+    StepOut {
+        depth: usize,
+    },
+}
+
+/// Core debugging hooks for a `CHIP-8` VM.
+/// Keeps track of breakpoints and decides, before every cycle, whether the VM
+/// is allowed to execute its next instruction.
+///
+/// **Note:** A freshly created `Debugger` starts stopped, so frontends can
+/// configure breakpoints before the first instruction is executed.
+pub struct Debugger {
+    breakpoints: HashSet<u16>,
+    state: RunState,
+    /// Set when resuming so the instruction under a breakpoint can be executed
+    ignore_next_breakpoint: bool,
+}
+
+/// Hook invoked by the main loop of `Chip8::start`, letting an external debugger
+/// drive the execution of the VM instead of running a cycle on every tick
+pub trait DebugHook {
+    /// Called for every cycle due in the frames of the main loop.
+    /// Implementors decide whether the VM executes a cycle or not.
+    fn update(&mut self, vm: &mut Chip8);
+
+    /// Returns `true` when the main loop should be terminated
+    fn should_exit(&self) -> bool;
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            breakpoints: HashSet::new(),
+            state: RunState::Stopped,
+            ignore_next_breakpoint: false,
+        }
+    }
+
+    /// Replaces all breakpoints with the given addresses
+    pub fn set_breakpoints<I: IntoIterator<Item = u16>>(&mut self, addresses: I) {
+        self.breakpoints = addresses.into_iter().collect();
+    }
+
+    /// Returns `true` if the execution is currently suspended
+    pub fn is_stopped(&self) -> bool {
+        self.state == RunState::Stopped
+    }
+
+    /// Resumes the execution until a breakpoint is hit or a pause is requested
+    pub fn resume(&mut self) {
+        self.start(RunState::Running);
+    }
+
+    /// Executes a single instruction
+    pub fn step_in(&mut self) {
+        self.start(RunState::StepIn);
+    }
+
+    /// Executes a single instruction, running any subroutine called by it to completion
+    pub fn step_over(&mut self, vm: &Chip8) {
+        self.start(RunState::StepOver {
+            depth: vm.stack.pointer(),
+        });
+    }
+
+    /// Executes instructions until the current subroutine returns
+    pub fn step_out(&mut self, vm: &Chip8) {
+        if vm.stack.pointer() == 0 {
+            self.step_in();
+        } else {
+            self.start(RunState::StepOut {
+                depth: vm.stack.pointer(),
+            });
+        }
+    }
+
+    /// Suspends the execution.
+    /// # Returns
+    /// `true` if the VM was running before the call.
+    pub fn pause(&mut self) -> bool {
+        let was_running = !self.is_stopped();
+        self.state = RunState::Stopped;
+        was_running
+    }
+
+    fn start(&mut self, state: RunState) {
+        self.state = state;
+        self.ignore_next_breakpoint = true;
+    }
+
+    /// Executes a cycle of `vm` if the current state allows it.
+    /// # Returns
+    /// The reason why the execution was suspended, if it was suspended during this call.
+    pub fn cycle(&mut self, vm: &mut Chip8) -> Option<StopReason> {
+        if self.is_stopped() {
+            return None;
+        }
+
+        let ignore_breakpoint = self.ignore_next_breakpoint;
+        self.ignore_next_breakpoint = false;
+        if !ignore_breakpoint && self.breakpoints.contains(&vm.regs.pc) {
+            self.state = RunState::Stopped;
+            return Some(StopReason::Breakpoint);
+        }
+
+        if let Err(error) = vm.step() {
+            self.state = RunState::Stopped;
+            return Some(StopReason::Fault(error));
+        }
+        if let Some(hit) = vm.take_watch_hit() {
+            self.state = RunState::Stopped;
+            return Some(StopReason::Watchpoint(hit));
+        }
+
+        let step_completed = match self.state {
+            RunState::StepIn => true,
+            RunState::StepOver { depth } => vm.stack.pointer() <= depth,
+            RunState::StepOut { depth } => vm.stack.pointer() < depth,
+            _ => false,
+        };
+        if step_completed {
+            self.state = RunState::Stopped;
+            return Some(StopReason::Step);
+        }
+
+        None
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Debugger {
+        Debugger::new()
+    }
+}
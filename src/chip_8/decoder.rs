@@ -0,0 +1,690 @@
+use super::{Quirk, Quirks};
+use std::fmt;
+
+/// A decoded `CHIP-8` instruction along with its operands.
+/// Variants are named after the methods implementing them in `instructions.rs`,
+/// and operands follow the same conventions:
+/// * `x`, `y`: Index of a `v` register (`0x0` to `0xF`)
+/// * `kk`: 8-bit constant
+/// * `nnn`: 12-bit address
+/// * `n`: 4-bit constant
+///
+/// The last variants are extensions: from `SaveVxVy` to `Pitch` XO-CHIP ones, only executed
+/// with `Profile::XoChip`, then SUPER-CHIP ones, also executed with `Profile::Schip`.
+/// `ScrollUp` is an XO-CHIP addition to them. See `Profile::has_schip_instructions`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Cls,
+    Ret,
+    Jp(u16),
+    Call(u16),
+    SeVxByte(usize, u8),
+    SneVxByte(usize, u8),
+    SeVxVy(usize, usize),
+    LdVxValue(usize, u8),
+    AddVxByte(usize, u8),
+    LdVxVy(usize, usize),
+    OrVxVy(usize, usize),
+    AndVxVy(usize, usize),
+    XorVxVy(usize, usize),
+    AddVxVy(usize, usize),
+    SubVxVy(usize, usize),
+    ShrVx(usize, usize),
+    SubnVxVy(usize, usize),
+    ShlVx(usize, usize),
+    SneVxVy(usize, usize),
+    LdIAddr(u16),
+    JpV0Addr(u16),
+    RndVxByte(usize, u8),
+    DrwVxVyN(usize, usize, usize),
+    SkipVx(usize),
+    SkipNVx(usize),
+    LdVxDt(usize),
+    LdVxK(usize),
+    LdDtVx(usize),
+    LdStVx(usize),
+    AddIVx(usize),
+    LdFVx(usize),
+    LdBVx(usize),
+    LdIVx(usize),
+    LdVxI(usize),
+    SaveVxVy(usize, usize),
+    LoadVxVy(usize, usize),
+    LdILong,
+    Plane(usize),
+    Audio,
+    Pitch(usize),
+    LdRVx(usize),
+    LdVxR(usize),
+    ScrollDown(usize),
+    ScrollUp(usize),
+    ScrollRight,
+    ScrollLeft,
+    Exit,
+    Low,
+    High,
+    LdHfVx(usize),
+}
+
+/// Static information about an instruction, shared by all its possible operands
+pub struct InstructionInfo {
+    /// OP Code pattern, where lowercase letters are operands. Ex: `Dxyn`
+    pub pattern: &'static str,
+    /// Assembly syntax using the operand names of the pattern. Ex: `DRW Vx, Vy, n`
+    pub syntax: &'static str,
+    /// Behavior of the instruction as implemented by this VM
+    pub description: &'static str,
+}
+
+/// Characters accepted by `explain` as placeholders for any operand value
+const OPERAND_PLACEHOLDERS: [char; 4] = ['x', 'y', 'n', 'k'];
+
+impl Instruction {
+    /// Decodes an OP Code into an `Instruction`.
+    /// # Returns
+    /// `None` if the OP Code doesn't match any known instruction.
+    pub fn decode(opcode: u16) -> Option<Instruction> {
+        let nibbles = (
+            (opcode & 0xF000) >> 12,
+            (opcode & 0x0F00) >> 8,
+            (opcode & 0x00F0) >> 4,
+            opcode & 0x000F,
+        );
+
+        let nnn = opcode & 0x0FFF;
+        let kk = (opcode & 0x00FF) as u8;
+        let x = nibbles.1 as usize;
+        let y = nibbles.2 as usize;
+        let n = nibbles.3 as usize;
+
+        let instruction = match nibbles {
+            (0x0, 0x0, 0xE, 0x0) => Instruction::Cls,
+            (0x0, 0x0, 0xE, 0xE) => Instruction::Ret,
+            (0x0, 0x0, 0xC, _) => Instruction::ScrollDown(n),
+            (0x0, 0x0, 0xD, _) => Instruction::ScrollUp(n),
+            (0x0, 0x0, 0xF, 0xB) => Instruction::ScrollRight,
+            (0x0, 0x0, 0xF, 0xC) => Instruction::ScrollLeft,
+            (0x0, 0x0, 0xF, 0xD) => Instruction::Exit,
+            (0x0, 0x0, 0xF, 0xE) => Instruction::Low,
+            (0x0, 0x0, 0xF, 0xF) => Instruction::High,
+            (0x1, _, _, _) => Instruction::Jp(nnn),
+            (0x2, _, _, _) => Instruction::Call(nnn),
+            (0x3, _, _, _) => Instruction::SeVxByte(x, kk),
+            (0x4, _, _, _) => Instruction::SneVxByte(x, kk),
+            (0x5, _, _, 0x0) => Instruction::SeVxVy(x, y),
+            (0x5, _, _, 0x2) => Instruction::SaveVxVy(x, y),
+            (0x5, _, _, 0x3) => Instruction::LoadVxVy(x, y),
+            (0x6, _, _, _) => Instruction::LdVxValue(x, kk),
+            (0x7, _, _, _) => Instruction::AddVxByte(x, kk),
+            (0x8, _, _, 0x0) => Instruction::LdVxVy(x, y),
+            (0x8, _, _, 0x1) => Instruction::OrVxVy(x, y),
+            (0x8, _, _, 0x2) => Instruction::AndVxVy(x, y),
+            (0x8, _, _, 0x3) => Instruction::XorVxVy(x, y),
+            (0x8, _, _, 0x4) => Instruction::AddVxVy(x, y),
+            (0x8, _, _, 0x5) => Instruction::SubVxVy(x, y),
+            (0x8, _, _, 0x6) => Instruction::ShrVx(x, y),
+            (0x8, _, _, 0x7) => Instruction::SubnVxVy(x, y),
+            (0x8, _, _, 0xE) => Instruction::ShlVx(x, y),
+            (0x9, _, _, 0x0) => Instruction::SneVxVy(x, y),
+            (0xA, _, _, _) => Instruction::LdIAddr(nnn),
+            (0xB, _, _, _) => Instruction::JpV0Addr(nnn),
+            (0xC, _, _, _) => Instruction::RndVxByte(x, kk),
+            (0xD, _, _, _) => Instruction::DrwVxVyN(x, y, n),
+            (0xE, _, 0x9, 0xE) => Instruction::SkipVx(x),
+            (0xE, _, 0xA, 0x1) => Instruction::SkipNVx(x),
+            (0xF, 0x0, 0x0, 0x0) => Instruction::LdILong,
+            (0xF, _, 0x0, 0x1) => Instruction::Plane(x),
+            (0xF, 0x0, 0x0, 0x2) => Instruction::Audio,
+            (0xF, _, 0x0, 0x7) => Instruction::LdVxDt(x),
+            (0xF, _, 0x0, 0xA) => Instruction::LdVxK(x),
+            (0xF, _, 0x1, 0x5) => Instruction::LdDtVx(x),
+            (0xF, _, 0x1, 0x8) => Instruction::LdStVx(x),
+            (0xF, _, 0x1, 0xE) => Instruction::AddIVx(x),
+            (0xF, _, 0x2, 0x9) => Instruction::LdFVx(x),
+            (0xF, _, 0x3, 0x0) => Instruction::LdHfVx(x),
+            (0xF, _, 0x3, 0x3) => Instruction::LdBVx(x),
+            (0xF, _, 0x3, 0xA) => Instruction::Pitch(x),
+            (0xF, _, 0x5, 0x5) => Instruction::LdIVx(x),
+            (0xF, _, 0x6, 0x5) => Instruction::LdVxI(x),
+            (0xF, _, 0x7, 0x5) => Instruction::LdRVx(x),
+            (0xF, _, 0x8, 0x5) => Instruction::LdVxR(x),
+            _ => return None,
+        };
+
+        Some(instruction)
+    }
+
+    /// Encodes the instruction into its OP Code, the inverse of `Instruction::decode`.
+    /// Operands are truncated to the digits available for them.
+    ///
+    /// **Note:** `LdILong` is only the first word of `F000 NNNN`, the address follows it
+    pub fn opcode(&self) -> u16 {
+        let xy = |prefix: u16, x: usize, y: usize, suffix: u16| {
+            prefix << 12 | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4 | suffix
+        };
+        let xkk = |prefix: u16, x: usize, kk: u8| prefix << 12 | (x as u16 & 0xF) << 8 | kk as u16;
+        let fx = |x: usize, suffix: u16| 0xF000 | (x as u16 & 0xF) << 8 | suffix;
+
+        match *self {
+            Instruction::Cls => 0x00E0,
+            Instruction::Ret => 0x00EE,
+            Instruction::Jp(nnn) => 0x1000 | (nnn & 0xFFF),
+            Instruction::Call(nnn) => 0x2000 | (nnn & 0xFFF),
+            Instruction::SeVxByte(x, kk) => xkk(0x3, x, kk),
+            Instruction::SneVxByte(x, kk) => xkk(0x4, x, kk),
+            Instruction::SeVxVy(x, y) => xy(0x5, x, y, 0x0),
+            Instruction::LdVxValue(x, kk) => xkk(0x6, x, kk),
+            Instruction::AddVxByte(x, kk) => xkk(0x7, x, kk),
+            Instruction::LdVxVy(x, y) => xy(0x8, x, y, 0x0),
+            Instruction::OrVxVy(x, y) => xy(0x8, x, y, 0x1),
+            Instruction::AndVxVy(x, y) => xy(0x8, x, y, 0x2),
+            Instruction::XorVxVy(x, y) => xy(0x8, x, y, 0x3),
+            Instruction::AddVxVy(x, y) => xy(0x8, x, y, 0x4),
+            Instruction::SubVxVy(x, y) => xy(0x8, x, y, 0x5),
+            Instruction::ShrVx(x, y) => xy(0x8, x, y, 0x6),
+            Instruction::SubnVxVy(x, y) => xy(0x8, x, y, 0x7),
+            Instruction::ShlVx(x, y) => xy(0x8, x, y, 0xE),
+            Instruction::SneVxVy(x, y) => xy(0x9, x, y, 0x0),
+            Instruction::LdIAddr(nnn) => 0xA000 | (nnn & 0xFFF),
+            Instruction::JpV0Addr(nnn) => 0xB000 | (nnn & 0xFFF),
+            Instruction::RndVxByte(x, kk) => xkk(0xC, x, kk),
+            Instruction::DrwVxVyN(x, y, n) => xy(0xD, x, y, n as u16 & 0xF),
+            Instruction::SkipVx(x) => xkk(0xE, x, 0x9E),
+            Instruction::SkipNVx(x) => xkk(0xE, x, 0xA1),
+            Instruction::LdVxDt(x) => fx(x, 0x07),
+            Instruction::LdVxK(x) => fx(x, 0x0A),
+            Instruction::LdDtVx(x) => fx(x, 0x15),
+            Instruction::LdStVx(x) => fx(x, 0x18),
+            Instruction::AddIVx(x) => fx(x, 0x1E),
+            Instruction::LdFVx(x) => fx(x, 0x29),
+            Instruction::LdBVx(x) => fx(x, 0x33),
+            Instruction::LdIVx(x) => fx(x, 0x55),
+            Instruction::LdVxI(x) => fx(x, 0x65),
+            Instruction::SaveVxVy(x, y) => xy(0x5, x, y, 0x2),
+            Instruction::LoadVxVy(x, y) => xy(0x5, x, y, 0x3),
+            Instruction::LdILong => 0xF000,
+            Instruction::Plane(n) => fx(n, 0x01),
+            Instruction::Audio => 0xF002,
+            Instruction::Pitch(x) => fx(x, 0x3A),
+            Instruction::LdRVx(x) => fx(x, 0x75),
+            Instruction::LdVxR(x) => fx(x, 0x85),
+            Instruction::ScrollDown(n) => 0x00C0 | (n as u16 & 0xF),
+            Instruction::ScrollUp(n) => 0x00D0 | (n as u16 & 0xF),
+            Instruction::ScrollRight => 0x00FB,
+            Instruction::ScrollLeft => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::Low => 0x00FE,
+            Instruction::High => 0x00FF,
+            Instruction::LdHfVx(x) => fx(x, 0x30),
+        }
+    }
+
+    /// Returns the static information of the instruction: pattern, syntax and behavior
+    pub fn info(&self) -> InstructionInfo {
+        let (pattern, syntax, description) = match self {
+            Instruction::Cls => ("00E0", "CLS", "Clear the display."),
+            Instruction::Ret => (
+                "00EE",
+                "RET",
+                "Return from a subroutine: pop an address from the Stack into PC.",
+            ),
+            Instruction::Jp(_) => ("1nnn", "JP nnn", "Jump to address nnn."),
+            Instruction::Call(_) => (
+                "2nnn",
+                "CALL nnn",
+                "Call the subroutine at nnn: push PC into the Stack and jump to nnn.",
+            ),
+            Instruction::SeVxByte(_, _) => (
+                "3xkk",
+                "SE Vx, kk",
+                "Skip the next instruction if Vx == kk.",
+            ),
+            Instruction::SneVxByte(_, _) => (
+                "4xkk",
+                "SNE Vx, kk",
+                "Skip the next instruction if Vx != kk.",
+            ),
+            Instruction::SeVxVy(_, _) => (
+                "5xy0",
+                "SE Vx, Vy",
+                "Skip the next instruction if Vx == Vy.",
+            ),
+            Instruction::LdVxValue(_, _) => ("6xkk", "LD Vx, kk", "Set Vx = kk."),
+            Instruction::AddVxByte(_, _) => (
+                "7xkk",
+                "ADD Vx, kk",
+                "Set Vx = Vx + kk, wrapping around on overflow. VF is not affected.",
+            ),
+            Instruction::LdVxVy(_, _) => ("8xy0", "LD Vx, Vy", "Set Vx = Vy."),
+            Instruction::OrVxVy(_, _) => ("8xy1", "OR Vx, Vy", "Set Vx = Vx OR Vy."),
+            Instruction::AndVxVy(_, _) => ("8xy2", "AND Vx, Vy", "Set Vx = Vx AND Vy."),
+            Instruction::XorVxVy(_, _) => ("8xy3", "XOR Vx, Vy", "Set Vx = Vx XOR Vy."),
+            Instruction::AddVxVy(_, _) => (
+                "8xy4",
+                "ADD Vx, Vy",
+                "Set Vx = Vx + Vy, wrapping around on overflow. VF = 1 on carry, 0 otherwise.",
+            ),
+            Instruction::SubVxVy(_, _) => (
+                "8xy5",
+                "SUB Vx, Vy",
+                "Set Vx = Vx - Vy, wrapping around on underflow. VF = 1 if Vx > Vy (no borrow), 0 otherwise.",
+            ),
+            Instruction::ShrVx(_, _) => (
+                "8xy6",
+                "SHR Vx, Vy",
+                "Set Vx = Vx SHR 1. VF = least-significant bit of Vx before the shift. \
+                 Vy is ignored, unless the shift quirk shifts Vy instead.",
+            ),
+            Instruction::SubnVxVy(_, _) => (
+                "8xy7",
+                "SUBN Vx, Vy",
+                "Set Vx = Vy - Vx. VF = 1 if Vy > Vx (no borrow), 0 otherwise.",
+            ),
+            Instruction::ShlVx(_, _) => (
+                "8xyE",
+                "SHL Vx, Vy",
+                "Set Vx = Vx SHL 1. VF = most-significant bit of Vx before the shift. \
+                 Vy is ignored, unless the shift quirk shifts Vy instead.",
+            ),
+            Instruction::SneVxVy(_, _) => (
+                "9xy0",
+                "SNE Vx, Vy",
+                "Skip the next instruction if Vx != Vy.",
+            ),
+            Instruction::LdIAddr(_) => ("Annn", "LD I, nnn", "Set I = nnn."),
+            Instruction::JpV0Addr(_) => (
+                "Bnnn",
+                "JP V0, nnn",
+                "Jump to address V0 + nnn. The jump quirk uses Vx instead, x being the highest digit of nnn.",
+            ),
+            Instruction::RndVxByte(_, _) => (
+                "Cxkk",
+                "RND Vx, kk",
+                "Set Vx = random byte AND kk.",
+            ),
+            Instruction::DrwVxVyN(_, _, _) => (
+                "Dxyn",
+                "DRW Vx, Vy, n",
+                "Draw the n bytes long sprite stored at I on coordinates (Vx, Vy). \
+                 VF = 1 if any lit pixel was erased, 0 otherwise. SUPER-CHIP: n = 0 draws \
+                 a 16x16 sprite of 32 bytes.",
+            ),
+            Instruction::SkipVx(_) => (
+                "Ex9E",
+                "SKP Vx",
+                "Skip the next instruction if the key with the value of Vx is pressed.",
+            ),
+            Instruction::SkipNVx(_) => (
+                "ExA1",
+                "SKNP Vx",
+                "Skip the next instruction if the key with the value of Vx is not pressed.",
+            ),
+            Instruction::LdVxDt(_) => ("Fx07", "LD Vx, DT", "Set Vx = delay timer."),
+            Instruction::LdVxK(_) => (
+                "Fx0A",
+                "LD Vx, K",
+                "Wait for a key press and store the value of the key in Vx. \
+                 The instruction is repeated until a key is pressed.",
+            ),
+            Instruction::LdDtVx(_) => ("Fx15", "LD DT, Vx", "Set delay timer = Vx."),
+            Instruction::LdStVx(_) => ("Fx18", "LD ST, Vx", "Set sound timer = Vx."),
+            Instruction::AddIVx(_) => (
+                "Fx1E",
+                "ADD I, Vx",
+                "Set I = I + Vx. VF is not affected.",
+            ),
+            Instruction::LdFVx(_) => (
+                "Fx29",
+                "LD F, Vx",
+                "Set I = location of the built-in font sprite for the digit Vx.",
+            ),
+            Instruction::LdBVx(_) => (
+                "Fx33",
+                "LD B, Vx",
+                "Store the BCD representation of Vx in memory at I (hundreds), I + 1 (tens) and I + 2 (ones).",
+            ),
+            Instruction::LdIVx(_) => (
+                "Fx55",
+                "LD [I], Vx",
+                "Store registers V0 to Vx in memory starting at I. \
+                 I is not modified, unless the load/store quirk increments it by x + 1.",
+            ),
+            Instruction::LdVxI(_) => (
+                "Fx65",
+                "LD Vx, [I]",
+                "Read registers V0 to Vx from memory starting at I. \
+                 I is not modified, unless the load/store quirk increments it by x + 1.",
+            ),
+            Instruction::SaveVxVy(_, _) => (
+                "5xy2",
+                "SAVE Vx, Vy",
+                "XO-CHIP: Store registers Vx to Vy in memory starting at I, in descending order \
+                 if x > y. I is not modified.",
+            ),
+            Instruction::LoadVxVy(_, _) => (
+                "5xy3",
+                "LOAD Vx, Vy",
+                "XO-CHIP: Read registers Vx to Vy from memory starting at I, in descending order \
+                 if x > y. I is not modified.",
+            ),
+            Instruction::LdILong => (
+                "F000",
+                "LD I, LONG nnnn",
+                "XO-CHIP: Set I = the 16-bit address stored in the next 2 bytes, then skip them.",
+            ),
+            Instruction::Plane(_) => (
+                "Fn01",
+                "PLANE n",
+                "XO-CHIP: Select the display planes drawn and cleared by later instructions: \
+                 1 for the first, 2 for the second, 3 for both and 0 for none.",
+            ),
+            Instruction::Audio => (
+                "F002",
+                "AUDIO",
+                "XO-CHIP: Load the 16 bytes stored at I into the audio pattern buffer.",
+            ),
+            Instruction::Pitch(_) => (
+                "Fx3A",
+                "PITCH Vx",
+                "XO-CHIP: Set the pitch of the audio pattern = Vx, played at \
+                 4000 * 2^((Vx - 64) / 48) samples per second.",
+            ),
+            Instruction::LdRVx(_) => (
+                "Fx75",
+                "LD R, Vx",
+                "SUPER-CHIP: Store registers V0 through Vx in the RPL user flags, which \
+                 persist between runs.",
+            ),
+            Instruction::LdVxR(_) => (
+                "Fx85",
+                "LD Vx, R",
+                "SUPER-CHIP: Read registers V0 through Vx from the RPL user flags.",
+            ),
+            Instruction::ScrollDown(_) => (
+                "00Cn",
+                "SCD n",
+                "SUPER-CHIP: Scroll the selected display planes down by n pixels.",
+            ),
+            Instruction::ScrollUp(_) => (
+                "00Dn",
+                "SCU n",
+                "XO-CHIP: Scroll the selected display planes up by n pixels.",
+            ),
+            Instruction::ScrollRight => (
+                "00FB",
+                "SCR",
+                "SUPER-CHIP: Scroll the selected display planes right by 4 pixels.",
+            ),
+            Instruction::ScrollLeft => (
+                "00FC",
+                "SCL",
+                "SUPER-CHIP: Scroll the selected display planes left by 4 pixels.",
+            ),
+            Instruction::Exit => (
+                "00FD",
+                "EXIT",
+                "SUPER-CHIP: Exit the interpreter, halting the VM.",
+            ),
+            Instruction::Low => (
+                "00FE",
+                "LOW",
+                "SUPER-CHIP: Switch the display to the 64x32 low resolution, clearing it.",
+            ),
+            Instruction::High => (
+                "00FF",
+                "HIGH",
+                "SUPER-CHIP: Switch the display to the 128x64 high resolution, clearing it. \
+                 Dxy0 then draws 16x16 sprites.",
+            ),
+            Instruction::LdHfVx(_) => (
+                "Fx30",
+                "LD HF, Vx",
+                "SUPER-CHIP: Set I = location of the built-in 8x10 font sprite for the digit Vx.",
+            ),
+        };
+
+        InstructionInfo {
+            pattern,
+            syntax,
+            description,
+        }
+    }
+
+    /// Describes how the instruction behaves under `quirks`.
+    /// # Returns
+    /// The `Quirk` changing the behavior of the instruction along with the resulting
+    /// behavior, or `None` if the instruction doesn't depend on any quirk.
+    pub fn quirk_behavior(&self, quirks: Quirks) -> Option<(Quirk, &'static str)> {
+        let (quirk, enabled, disabled) = match self {
+            Instruction::ShrVx(_, _) => (
+                Quirk::Shift,
+                "Set Vx = Vy SHR 1. VF = least-significant bit of Vy before the shift.",
+                "Vx is shifted in place, Vy is ignored.",
+            ),
+            Instruction::ShlVx(_, _) => (
+                Quirk::Shift,
+                "Set Vx = Vy SHL 1. VF = most-significant bit of Vy before the shift.",
+                "Vx is shifted in place, Vy is ignored.",
+            ),
+            Instruction::LdIVx(_) | Instruction::LdVxI(_) => (
+                Quirk::LoadStore,
+                "I is left incremented by x + 1.",
+                "I is not modified.",
+            ),
+            Instruction::JpV0Addr(_) => (
+                Quirk::Jump,
+                "Jump to address Vx + nnn, x being the highest digit of nnn.",
+                "Jump to address V0 + nnn.",
+            ),
+            Instruction::DrwVxVyN(_, _, _) => (
+                Quirk::Wrap,
+                "Pixels beyond the right and bottom edges wrap around to the opposite side.",
+                "Pixels beyond the right and bottom edges are clipped.",
+            ),
+            Instruction::LdVxK(_) => (
+                Quirk::KeyRelease,
+                "Waits for a key to be released and stores that key.",
+                "Takes the first key held.",
+            ),
+            _ => return None,
+        };
+
+        match quirks.is_enabled(quirk) {
+            true => Some((quirk, enabled)),
+            false => Some((quirk, disabled)),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    /// Formats the instruction using its assembly syntax. Ex: `DRW V1, V2, 5`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::Cls => write!(f, "CLS"),
+            Instruction::Ret => write!(f, "RET"),
+            Instruction::Jp(nnn) => write!(f, "JP 0x{:03X}", nnn),
+            Instruction::Call(nnn) => write!(f, "CALL 0x{:03X}", nnn),
+            Instruction::SeVxByte(x, kk) => write!(f, "SE V{:X}, 0x{:02X}", x, kk),
+            Instruction::SneVxByte(x, kk) => write!(f, "SNE V{:X}, 0x{:02X}", x, kk),
+            Instruction::SeVxVy(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::LdVxValue(x, kk) => write!(f, "LD V{:X}, 0x{:02X}", x, kk),
+            Instruction::AddVxByte(x, kk) => write!(f, "ADD V{:X}, 0x{:02X}", x, kk),
+            Instruction::LdVxVy(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::OrVxVy(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::AndVxVy(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::XorVxVy(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::AddVxVy(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::SubVxVy(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::ShrVx(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::SubnVxVy(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShlVx(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SneVxVy(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::LdIAddr(nnn) => write!(f, "LD I, 0x{:03X}", nnn),
+            Instruction::JpV0Addr(nnn) => write!(f, "JP V0, 0x{:03X}", nnn),
+            Instruction::RndVxByte(x, kk) => write!(f, "RND V{:X}, 0x{:02X}", x, kk),
+            Instruction::DrwVxVyN(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipVx(x) => write!(f, "SKP V{:X}", x),
+            Instruction::SkipNVx(x) => write!(f, "SKNP V{:X}", x),
+            Instruction::LdVxDt(x) => write!(f, "LD V{:X}, DT", x),
+            Instruction::LdVxK(x) => write!(f, "LD V{:X}, K", x),
+            Instruction::LdDtVx(x) => write!(f, "LD DT, V{:X}", x),
+            Instruction::LdStVx(x) => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddIVx(x) => write!(f, "ADD I, V{:X}", x),
+            Instruction::LdFVx(x) => write!(f, "LD F, V{:X}", x),
+            Instruction::LdBVx(x) => write!(f, "LD B, V{:X}", x),
+            Instruction::LdIVx(x) => write!(f, "LD [I], V{:X}", x),
+            Instruction::LdVxI(x) => write!(f, "LD V{:X}, [I]", x),
+            Instruction::SaveVxVy(x, y) => write!(f, "SAVE V{:X}, V{:X}", x, y),
+            Instruction::LoadVxVy(x, y) => write!(f, "LOAD V{:X}, V{:X}", x, y),
+            Instruction::LdILong => write!(f, "LD I, LONG"),
+            Instruction::Plane(n) => write!(f, "PLANE {}", n),
+            Instruction::Audio => write!(f, "AUDIO"),
+            Instruction::Pitch(x) => write!(f, "PITCH V{:X}", x),
+            Instruction::LdRVx(x) => write!(f, "LD R, V{:X}", x),
+            Instruction::LdVxR(x) => write!(f, "LD V{:X}, R", x),
+            Instruction::ScrollDown(n) => write!(f, "SCD {}", n),
+            Instruction::ScrollUp(n) => write!(f, "SCU {}", n),
+            Instruction::ScrollRight => write!(f, "SCR"),
+            Instruction::ScrollLeft => write!(f, "SCL"),
+            Instruction::Exit => write!(f, "EXIT"),
+            Instruction::Low => write!(f, "LOW"),
+            Instruction::High => write!(f, "HIGH"),
+            Instruction::LdHfVx(x) => write!(f, "LD HF, V{:X}", x),
+        }
+    }
+}
+
+/// Describes an OP Code written as 4 hexadecimal digits, ex: `D123`.
+/// Operands can be left unspecified using the letters of the instruction
+/// pattern as placeholders, ex: `Dxy3`.
+/// # Returns
+/// A human readable explanation with the mnemonic, operands and behavior of the instruction,
+/// including how it behaves under `quirks` if it depends on any.
+pub fn explain(text: &str, quirks: Quirks) -> Result<String, String> {
+    let text = text.trim();
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    if digits.chars().count() != 4 {
+        return Err(format!("OP Codes must have 4 digits, got '{}'", text));
+    }
+
+    let mut opcode: u16 = 0;
+    for c in digits.chars() {
+        let value = match c.to_digit(16) {
+            Some(value) => value,
+            None if OPERAND_PLACEHOLDERS.contains(&c.to_ascii_lowercase()) => 0,
+            None => return Err(format!("Invalid OP Code digit '{}'", c)),
+        };
+        opcode = (opcode << 4) | value as u16;
+    }
+
+    let instruction =
+        Instruction::decode(opcode).ok_or_else(|| format!("Unknown OP Code: {}", digits))?;
+    let info = instruction.info();
+
+    // Group the digits of each operand according to the pattern, ex: `3xkk` -> x, kk
+    let mut operands: Vec<(char, String)> = Vec::new();
+    for (name, digit) in info.pattern.chars().zip(digits.chars()) {
+        let is_placeholder = digit.to_digit(16).is_none();
+        if !name.is_ascii_lowercase() {
+            if is_placeholder {
+                return Err(format!(
+                    "'{}' is not an operand of {} ({})",
+                    digit, info.pattern, info.syntax
+                ));
+            }
+            continue;
+        }
+
+        match operands.last_mut() {
+            Some((last, value)) if *last == name => value.push(digit),
+            _ => operands.push((name, digit.to_string())),
+        }
+    }
+
+    // An operand is only known if none of its digits is a placeholder
+    let known = |value: &String| value.chars().all(|c| c.is_ascii_hexdigit());
+    let mnemonic = info
+        .syntax
+        .split(' ')
+        .map(|word| {
+            let token = word.trim_end_matches(',');
+            let separator = &word[token.len()..];
+            let name = match token {
+                "Vx" => 'x',
+                "Vy" => 'y',
+                "n" | "nnn" => 'n',
+                "kk" => 'k',
+                _ => return word.to_string(),
+            };
+
+            match operands.iter().find(|(operand, _)| *operand == name) {
+                Some((_, value)) if known(value) => {
+                    let value = value.to_uppercase();
+                    match token {
+                        "Vx" | "Vy" => format!("V{}{}", value, separator),
+                        "n" => format!("{}{}", u8::from_str_radix(&value, 16).unwrap(), separator),
+                        _ => format!("0x{}{}", value, separator),
+                    }
+                }
+                _ => word.to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    let operands = if operands.is_empty() {
+        String::from("none")
+    } else {
+        operands
+            .iter()
+            .map(|(name, value)| {
+                let name = name.to_string().repeat(value.len());
+                if known(value) {
+                    format!("{} = 0x{}", name, value.to_uppercase())
+                } else {
+                    format!("{} = any", name)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    let mut explanation = format!(
+        "{}: {}\n  Pattern:   {} ({})\n  Operands:  {}\n  Behavior:  {}",
+        digits, mnemonic, info.pattern, info.syntax, operands, info.description
+    );
+    if let Some((quirk, behavior)) = instruction.quirk_behavior(quirks) {
+        let state = if quirks.is_enabled(quirk) {
+            "on"
+        } else {
+            "off"
+        };
+        explanation.push_str(&format!(
+            "\n  Quirk:     {} ({}): {}",
+            quirk.name(),
+            state,
+            behavior
+        ));
+    }
+    Ok(explanation)
+}
+
+#[test]
+fn explanations_follow_the_quirks() {
+    let standard = explain("8xy6", Quirks::default()).unwrap();
+    assert!(standard.ends_with("Quirk:     shift (off): Vx is shifted in place, Vy is ignored."));
+
+    let vip = explain("8xy6", Quirks::parse("shift").unwrap()).unwrap();
+    assert!(vip.ends_with(
+        "Quirk:     shift (on): Set Vx = Vy SHR 1. VF = least-significant bit of Vy before the shift."
+    ));
+
+    let jump = explain("B123", Quirks::parse("jump").unwrap()).unwrap();
+    assert!(jump.contains("jump (on): Jump to address Vx + nnn"));
+    assert!(!explain("00E0", Quirks::parse("shift").unwrap())
+        .unwrap()
+        .contains("Quirk:"));
+}
@@ -0,0 +1,100 @@
+use super::{Chip8, DebugHook};
+use std::time::Duration;
+
+/// A public domain ROM embedded in the emulator, offered when no ROM is given
+pub struct Demo {
+    pub name: &'static str,
+    pub rom: &'static [u8],
+}
+
+/// ROMs offered by the `DemoPicker`, chosen with the keys `1`, `2`...
+pub const DEMOS: [Demo; 2] = [
+    Demo {
+        name: "IBM Logo",
+        rom: include_bytes!("../../roms/tests/ibm-logo.ch8"),
+    },
+    Demo {
+        name: "Maze",
+        rom: include_bytes!("../../roms/MAZE"),
+    },
+];
+
+/// Draws the keys choosing each of the `DEMOS` with the font, side by side
+const MENU_ROM: [u8; 22] = [
+    0x00, 0xE0, // 200: CLS
+    0x60, 0x01, // 202: LD V0, 0x01
+    0x61, 0x0C, // 204: LD V1, 0x0C
+    0x62, 0x0D, // 206: LD V2, 0x0D
+    0xF0, 0x29, // 208: LD F, V0
+    0xD1, 0x25, // 20A: DRW V1, V2, 5
+    0x70, 0x01, // 20C: ADD V0, 0x01
+    0x71, 0x20, // 20E: ADD V1, 0x20
+    0x30, 0x03, // 210: SE V0, 0x03
+    0x12, 0x08, // 212: JP digit
+    0x12, 0x14, // 214: JP end
+];
+
+/// How long the names of the demos stay on screen, they are hidden once one is chosen
+const MENU_MESSAGE_DURATION: Duration = Duration::from_secs(3600);
+
+/// A `DebugHook` showing a menu of the embedded `DEMOS`, so the emulator can be tried
+/// without any ROM. The menu is a ROM drawing the key of each demo with the font,
+/// while their names are shown on the `Osd`. Pressing one of the keys runs its demo.
+pub struct DemoPicker {
+    chosen: Option<usize>,
+}
+
+impl DemoPicker {
+    pub fn new() -> DemoPicker {
+        DemoPicker { chosen: None }
+    }
+
+    /// Loads the menu in `vm`
+    pub fn start(&mut self, vm: &mut Chip8) {
+        self.chosen = None;
+        // The menu fits in the memory of every profile
+        let _ = vm.restart(&MENU_ROM);
+        vm.osd_mut().clear();
+        for (index, demo) in DEMOS.iter().enumerate() {
+            vm.osd_mut().show_for(
+                format!("{}: {}", index + 1, demo.name),
+                MENU_MESSAGE_DURATION,
+            );
+        }
+    }
+
+    /// Returns the demo running, `None` while the menu is shown
+    pub fn chosen(&self) -> Option<&Demo> {
+        self.chosen.map(|index| &DEMOS[index])
+    }
+}
+
+impl Default for DemoPicker {
+    fn default() -> DemoPicker {
+        DemoPicker::new()
+    }
+}
+
+impl DebugHook for DemoPicker {
+    fn update(&mut self, vm: &mut Chip8) {
+        if self.chosen.is_none() {
+            if let Some(index) = (0..DEMOS.len()).find(|&index| vm.input.key_status[index + 1]) {
+                self.chosen = Some(index);
+                vm.osd_mut().clear();
+                // The demos fit in the memory of every profile
+                let _ = vm.restart(DEMOS[index].rom);
+                vm.osd_mut().show(format!("Running {}", DEMOS[index].name));
+            }
+        }
+
+        // Paused on faults, the same as without a hook
+        if let Err(error) = vm.step() {
+            vm.paused = true;
+            vm.osd_mut().show(format!("Paused: {}", error));
+        }
+    }
+
+    fn should_exit(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,131 @@
+use super::Display;
+use std::collections::VecDeque;
+
+/// How the last frames are blended before being drawn, to hide flicker. See `FrameBlend`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AntiFlicker {
+    /// Every frame is drawn as is
+    #[default]
+    Off,
+    /// Pixels lit on any of the last frames are drawn lit
+    Max,
+    /// Pixels are as bright as the share of the last frames they're lit on, the most
+    /// recent frames weighing the most
+    Average,
+}
+
+impl AntiFlicker {
+    /// All available modes, in the order they are presented to users
+    pub const ALL: [AntiFlicker; 3] = [AntiFlicker::Off, AntiFlicker::Max, AntiFlicker::Average];
+
+    /// Returns the name used to select the mode, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            AntiFlicker::Off => "off",
+            AntiFlicker::Max => "max",
+            AntiFlicker::Average => "average",
+        }
+    }
+
+    /// Finds a mode by its name
+    pub fn from_name(name: &str) -> Option<AntiFlicker> {
+        AntiFlicker::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == name)
+    }
+}
+
+/// The last frames of a `Display`, blended to hide the flicker of games that erase their
+/// sprites with XOR and draw them again on every frame: a sprite erased on the last frame
+/// is still lit on the previous ones.
+///
+/// Frames are pushed once per emulated frame, so the blend doesn't depend on the rate
+/// frames are drawn at.
+pub struct FrameBlend {
+    mode: AntiFlicker,
+    /// Index in a `PaletteColors` of the color of every pixel, most recent frame first
+    frames: VecDeque<Vec<u8>>,
+    depth: usize,
+}
+
+impl FrameBlend {
+    /// Frames blended unless configured otherwise
+    pub const DEFAULT_FRAMES: usize = 2;
+    /// Most frames that can be blended: beyond them, moving sprites leave trails
+    pub const MAX_FRAMES: usize = 3;
+
+    /// Blends the last `frames` frames with `mode`
+    /// # Panics
+    /// If `frames` is not between `2` and `MAX_FRAMES`
+    pub fn new(mode: AntiFlicker, frames: usize) -> FrameBlend {
+        assert!(
+            (2..=FrameBlend::MAX_FRAMES).contains(&frames),
+            "Between 2 and {} frames can be blended.",
+            FrameBlend::MAX_FRAMES
+        );
+        FrameBlend {
+            mode,
+            frames: VecDeque::with_capacity(frames),
+            depth: frames,
+        }
+    }
+
+    pub fn mode(&self) -> AntiFlicker {
+        self.mode
+    }
+
+    /// Adds the frame currently on `display`, dropping the oldest one. Frames of another
+    /// resolution are forgotten, as their pixels don't line up
+    pub fn push(&mut self, display: &Display) {
+        if self
+            .frames
+            .front()
+            .is_some_and(|frame| frame.len() != display.size())
+        {
+            self.frames.clear();
+        }
+        let mut frame = match self.frames.len() {
+            len if len == self.depth => self.frames.pop_back().unwrap_or_default(),
+            _ => Vec::with_capacity(display.size()),
+        };
+        frame.clear();
+        frame.extend((0..display.size()).map(|pos| display.color(pos) as u8));
+        self.frames.push_front(frame);
+    }
+
+    /// Forgets the frames pushed, ex: when the ROM restarts
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Returns the color index of the pixel at `pos`, from the most recent frame it's
+    /// lit on, and its brightness from `0` (off) to `1`. Pixels beyond the frames are off
+    pub fn pixel(&self, pos: usize) -> (usize, f32) {
+        let lit = |frame: &Vec<u8>| frame.get(pos).is_some_and(|&color| color != 0);
+        let color = self
+            .frames
+            .iter()
+            .map(|frame| frame.get(pos).copied().unwrap_or_default() as usize)
+            .find(|&color| color != 0)
+            .unwrap_or_default();
+        let level = match self.mode {
+            _ if color == 0 => 0.0,
+            AntiFlicker::Off => lit(&self.frames[0]) as u8 as f32,
+            AntiFlicker::Max => 1.0,
+            AntiFlicker::Average => {
+                // Weights of `depth`, `depth - 1`, ... `1`, from the most recent frame
+                let total = (self.depth * (self.depth + 1) / 2) as f32;
+                let lit: usize = self
+                    .frames
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, frame)| lit(frame))
+                    .map(|(age, _)| self.depth - age)
+                    .sum();
+                lit as f32 / total
+            }
+        };
+        (color, level)
+    }
+}
@@ -0,0 +1,339 @@
+use super::{explain, Chip8, Display, Instruction};
+use std::ops::Range;
+
+/// Bytes shown on each line of a `Chip8::hex_dump`
+const HEX_DUMP_ROW_SIZE: usize = 16;
+
+/// Returns the disassembly of `opcode` located at `address`, ex: `0x2A4: D123  DRW V1, V2, 3`
+pub fn disassemble(address: u16, opcode: u16) -> String {
+    match Instruction::decode(opcode) {
+        Some(instruction) => format!("0x{:03X}: {:04X}  {}", address, opcode, instruction),
+        None => format!("0x{:03X}: {:04X}", address, opcode),
+    }
+}
+
+/// Returns the disassembly of `rom` as source for `asm::assemble`, which turns it back into
+/// the same bytes, along with the address of each line. The ROM is read linearly from the
+/// load address, and each line is commented with its address and OP Code, ex:
+/// `DRW V1, V2, 3        ; 0x2A4: D123`. The XO-CHIP `F000 NNNN` takes both words, other
+/// words that aren't instructions are kept with `DW`, and an odd trailing byte with `DB`
+pub fn disassemble_rom(rom: &[u8]) -> Vec<(u16, String)> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < rom.len() {
+        let address = (Chip8::INITIAL_MEMORY_ADDRESS + offset) as u16;
+        let (text, bytes) = match rom[offset..] {
+            [byte] => (format!("DB 0x{:02X}", byte), 1),
+            [high, low, ref rest @ ..] => {
+                let opcode = u16::from_be_bytes([high, low]);
+                match (Instruction::decode(opcode), rest) {
+                    (Some(Instruction::LdILong), [high, low, ..]) => {
+                        let long = u16::from_be_bytes([*high, *low]);
+                        (format!("{} 0x{:04X}", Instruction::LdILong, long), 4)
+                    }
+                    (Some(Instruction::LdILong), _) | (None, _) => {
+                        (format!("DW 0x{:04X}", opcode), 2)
+                    }
+                    (Some(instruction), _) => (instruction.to_string(), 2),
+                }
+            }
+            [] => unreachable!(),
+        };
+        let hex: Vec<String> = rom[offset..offset + bytes]
+            .chunks(2)
+            .map(|word| word.iter().map(|byte| format!("{:02X}", byte)).collect())
+            .collect();
+        lines.push((
+            address,
+            format!("{:<20} ; 0x{:03X}: {}", text, address, hex.join(" ")),
+        ));
+        offset += bytes;
+    }
+    lines
+}
+
+/// Separates the pixels of `rows`, as written by `Chip8::rows`, with spaces, ending with a
+/// new line like the rows of plain PBM and PGM images
+fn spaced(rows: &str) -> String {
+    let mut spaced = String::new();
+    for row in rows.lines() {
+        let pixels: Vec<String> = row.chars().map(String::from).collect();
+        spaced.push_str(&pixels.join(" "));
+        spaced.push('\n');
+    }
+    spaced
+}
+
+/// Parses a range of memory written as `START-END`, both included, or `START+LENGTH`,
+/// with hexadecimal numbers, ex: `200-2FF` or `0x3E0+20`
+/// # Errors
+/// If a number is invalid, or if the range is empty
+pub fn parse_memory_range(text: &str) -> Result<Range<usize>, String> {
+    let invalid = || {
+        format!(
+            "Invalid memory range '{}', expected START-END or START+LENGTH.",
+            text
+        )
+    };
+    let number = |text: &str| usize::from_str_radix(text.trim().trim_start_matches("0x"), 16);
+    let range = match (text.split_once('-'), text.split_once('+')) {
+        (Some((start, end)), None) => {
+            let start = number(start).map_err(|_| invalid())?;
+            start..number(end).map_err(|_| invalid())?.saturating_add(1)
+        }
+        (None, Some((start, length))) => {
+            let start = number(start).map_err(|_| invalid())?;
+            start..start.saturating_add(number(length).map_err(|_| invalid())?)
+        }
+        _ => return Err(invalid()),
+    };
+    if range.is_empty() {
+        return Err(format!("Empty memory range '{}'.", text));
+    }
+    Ok(range)
+}
+
+impl Chip8 {
+    /// Returns a hex dump of the memory in `range`, 16 bytes per line followed by their
+    /// ASCII characters. The bytes of the instruction at `PC` are marked with `>`,
+    /// and the byte at `I` with `*`:
+    /// ```text
+    /// 0x2D0: 76 FE 12 6C A2 F2 FE 33 F2 65>F1>29 64 14 65 00  |v..l...3.e.)d.e.|
+    /// ```
+    /// # Errors
+    /// If `range` goes beyond the memory. See `Chip8::memory_slice`
+    pub fn hex_dump(&self, range: Range<usize>) -> Result<String, String> {
+        let bytes = self.memory_slice(range.clone()).ok_or(format!(
+            "The range 0x{:03X}-0x{:03X} is out of the memory, which ends at 0x{:03X}.",
+            range.start,
+            range.end.saturating_sub(1),
+            self.main_memory.len() - 1
+        ))?;
+        let pc = self.regs.pc as usize;
+        let i = self.regs.i as usize;
+
+        let mut dump = String::new();
+        let first_row = range.start - range.start % HEX_DUMP_ROW_SIZE;
+        for row in (first_row..range.end).step_by(HEX_DUMP_ROW_SIZE) {
+            let mut ascii = String::new();
+            dump.push_str(&format!("0x{:03X}:", row));
+            for address in row..row + HEX_DUMP_ROW_SIZE {
+                if !range.contains(&address) {
+                    dump.push_str("   ");
+                    ascii.push(' ');
+                    continue;
+                }
+                let byte = bytes[address - range.start];
+                let marker = match address {
+                    _ if address == pc || address == pc + 1 => '>',
+                    _ if address == i => '*',
+                    _ => ' ',
+                };
+                dump.push_str(&format!("{}{:02X}", marker, byte));
+                ascii.push(match byte {
+                    b' '..=b'~' => byte as char,
+                    _ => '.',
+                });
+            }
+            dump.push_str(&format!("  |{}|\n", ascii));
+        }
+        dump.push_str(&format!("> PC={:03X}  * I={:03X}", pc, i));
+        Ok(dump)
+    }
+
+    /// Returns the disassembly of the instruction at `PC`, ex: `0x2A4: D123  DRW V1, V2, 3`
+    pub fn disassembly_line(&self) -> String {
+        self.disassembly_at(self.regs.pc)
+    }
+
+    /// Explains the instruction at `PC` like `explain`, under the active quirks
+    /// # Errors
+    /// If `PC` lies beyond the memory or doesn't point to a known instruction
+    pub fn explain_current(&self) -> Result<String, String> {
+        let pc = self.regs.pc as usize;
+        match (self.main_memory.get(pc), self.main_memory.get(pc + 1)) {
+            (Some(&high), Some(&low)) => explain(&format!("{:02X}{:02X}", high, low), self.quirks),
+            _ => Err(format!("PC 0x{:03X} lies beyond the memory", pc)),
+        }
+    }
+
+    /// Returns the chain of subroutines that led to the current instruction, one line per
+    /// frame from the innermost one: the instruction at `PC`, then the `CALL` site of every
+    /// return address stored in the Stack:
+    /// ```text
+    /// #0 0x2D4: A2F2  LD I, 0x2F2
+    /// #1 0x210: 22D4  CALL 0x2D4
+    /// ```
+    pub fn backtrace(&self) -> String {
+        let call_sites = self
+            .stack
+            .stored
+            .iter()
+            .rev()
+            .map(|return_address| return_address.wrapping_sub(2));
+        std::iter::once(self.regs.pc)
+            .chain(call_sites)
+            .enumerate()
+            .map(|(frame, address)| format!("#{} {}", frame, self.disassembly_at(address)))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Returns the disassembly of the instruction at `address`, with `????` if it's out of
+    /// the memory
+    fn disassembly_at(&self, address: u16) -> String {
+        let address = address as usize;
+        match (
+            self.main_memory.get(address),
+            self.main_memory.get(address + 1),
+        ) {
+            (Some(&high), Some(&low)) => {
+                disassemble(address as u16, ((high as u16) << 8) | low as u16)
+            }
+            _ => format!("0x{:03X}: ????", address),
+        }
+    }
+
+    /// Returns the trace of an instruction just executed: its disassembly followed by the
+    /// registers it changed, ex: `0x2A4: 7105  ADD V1, 0x05  V1: 03 -> 08`.
+    /// `v` and `i` hold the registers before the instruction. Jumps show the new `PC`
+    pub(super) fn trace_line(&self, address: u16, opcode: u16, v: &[u8; 16], i: u16) -> String {
+        let mut line = disassemble(address, opcode);
+        for (x, (before, after)) in v.iter().zip(self.regs.v.iter()).enumerate() {
+            if before != after {
+                line.push_str(&format!("  V{:X}: {:02X} -> {:02X}", x, before, after));
+            }
+        }
+        if i != self.regs.i {
+            line.push_str(&format!("  I: {:03X} -> {:03X}", i, self.regs.i));
+        }
+        if self.regs.pc != address.wrapping_add(2) {
+            line.push_str(&format!("  PC -> {:03X}", self.regs.pc));
+        }
+        line
+    }
+
+    /// Returns a dump of all registers, the stack and the timers in plain text
+    pub fn register_dump(&self) -> String {
+        let mut dump = String::new();
+        for (row, values) in self.regs.v.chunks(8).enumerate() {
+            let registers: Vec<String> = values
+                .iter()
+                .enumerate()
+                .map(|(x, value)| format!("V{:X}={:02X}", row * 8 + x, value))
+                .collect();
+            dump.push_str(&registers.join(" "));
+            dump.push('\n');
+        }
+
+        let stack: Vec<String> = self
+            .stack
+            .stored
+            .iter()
+            .map(|address| format!("{:03X}", address))
+            .collect();
+        dump.push_str(&format!(
+            "I={:03X} PC={:03X} SP={} DT={:02X} ST={:02X}\nStack: [{}]",
+            self.regs.i,
+            self.regs.pc,
+            self.stack.pointer(),
+            self.timers.delay,
+            self.timers.sound,
+            stack.join(", ")
+        ));
+        dump
+    }
+
+    /// Returns the display as text, one line per row: `#` for pixels on and `.` for pixels off
+    pub fn display_text(&self) -> String {
+        self.plane_text(0)
+    }
+
+    /// Returns a plane of the display as text, like `display_text`: `0` for the first plane,
+    /// `1` for the second one, only drawn with `Profile::XoChip`
+    pub fn plane_text(&self, plane: usize) -> String {
+        self.rows(|pos| match self.display.color(pos) >> plane & 1 {
+            0 => '.',
+            _ => '#',
+        })
+    }
+
+    /// Returns the planes of the display composited, as text: `.` for pixels off, then
+    /// `1`, `2` or `3` for pixels lit on the first plane, the second one or both, the index
+    /// of their color in the `Palette`
+    pub fn composite_text(&self) -> String {
+        self.rows(|pos| match self.display.color(pos) {
+            0 => '.',
+            color => char::from(b'0' + color as u8),
+        })
+    }
+
+    /// Returns each plane of the display and their composite as text, under a heading,
+    /// ex: to see which plane a sprite landed on
+    pub fn planes_text(&self) -> String {
+        let planes = (0..Display::PLANES)
+            .map(|plane| format!("Plane {}:\n{}", plane + 1, self.plane_text(plane)));
+        let composite = std::iter::once(format!("Composite:\n{}", self.composite_text()));
+        planes
+            .chain(composite)
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    /// Returns the display as a plain PBM image (`P1`): `1` for pixels on and `0` for pixels off
+    pub fn display_pbm(&self) -> String {
+        self.plane_pbm(0)
+    }
+
+    /// Returns a plane of the display as a plain PBM image, like `display_pbm`. See `plane_text`
+    pub fn plane_pbm(&self, plane: usize) -> String {
+        let pixels =
+            self.rows(|pos| char::from(b'0' + (self.display.color(pos) >> plane & 1) as u8));
+        format!(
+            "P1\n{} {}\n{}",
+            self.display.width(),
+            self.display.height(),
+            spaced(&pixels)
+        )
+    }
+
+    /// Returns the planes of the display composited as a plain PGM image (`P2`), with the
+    /// index of the color of each pixel as its gray level, from `0` to `3`.
+    /// See `composite_text`
+    pub fn composite_pgm(&self) -> String {
+        let pixels = self.rows(|pos| char::from(b'0' + self.display.color(pos) as u8));
+        format!(
+            "P2\n{} {}\n3\n{}",
+            self.display.width(),
+            self.display.height(),
+            spaced(&pixels)
+        )
+    }
+
+    /// Returns the display as text, one line per row of the current resolution, with
+    /// `pixel` giving the character of the pixel at each position
+    fn rows(&self, pixel: impl Fn(usize) -> char) -> String {
+        let width = self.display.width();
+        (0..self.display.height())
+            .map(|y| (0..width).map(|x| pixel(y * width + x)).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+#[test]
+fn planes_are_exported_separately_and_composited() {
+    let mut vm = Chip8::new();
+    vm.display.buffer[0] = true;
+    vm.display.second_plane[1] = true;
+    vm.display.buffer[2] = true;
+    vm.display.second_plane[2] = true;
+
+    let first_row = |text: String| text.lines().next().unwrap()[..4].to_string();
+    assert_eq!(first_row(vm.plane_text(0)), "#.#.");
+    assert_eq!(first_row(vm.plane_text(1)), ".##.");
+    assert_eq!(first_row(vm.composite_text()), "123.");
+    assert_eq!(vm.display_text(), vm.plane_text(0));
+    assert!(vm.composite_pgm().starts_with("P2\n64 32\n3\n1 2 3 0 "));
+    assert!(vm.plane_pbm(1).starts_with("P1\n64 32\n0 1 1 0 "));
+}
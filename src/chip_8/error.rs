@@ -0,0 +1,108 @@
+use super::Chip8;
+use std::error::Error;
+use std::fmt;
+
+/// A fault raised by an instruction the VM can't execute. The faulting instruction has
+/// no effect: the `PC` is left pointing at it, so the state can be inspected as it was
+/// right before the fault.
+///
+/// Every variant holds the address (`pc`) and `opcode` of the faulting instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// The opcode doesn't decode to any instruction. See `Instruction::decode`
+    UnknownOpcode { pc: u16, opcode: u16 },
+    /// A `2nnn` call was made with `depth` subroutines already on the stack
+    StackOverflow { pc: u16, opcode: u16, depth: usize },
+    /// A `00EE` return was made with no subroutine to return from
+    StackUnderflow { pc: u16, opcode: u16 },
+    /// The instruction accesses `address`, beyond the memory of the active `Profile`.
+    /// When the instruction itself lies beyond the memory, `opcode` is `0`
+    MemoryOutOfBounds {
+        pc: u16,
+        opcode: u16,
+        address: usize,
+    },
+    /// The instruction writes to `address`, below `0x200`, protected with
+    /// `WriteProtection::Error`
+    ProtectedWrite {
+        pc: u16,
+        opcode: u16,
+        address: usize,
+    },
+    /// An invariant of the VM doesn't hold around the instruction, in the strict spec mode.
+    /// Unlike other faults, the instruction may have been executed. See `Chip8::set_strict`
+    StrictViolation {
+        pc: u16,
+        opcode: u16,
+        reason: String,
+    },
+}
+
+impl Chip8Error {
+    /// Returns the address of the faulting instruction
+    pub fn pc(&self) -> u16 {
+        match *self {
+            Chip8Error::UnknownOpcode { pc, .. }
+            | Chip8Error::StackOverflow { pc, .. }
+            | Chip8Error::StackUnderflow { pc, .. }
+            | Chip8Error::MemoryOutOfBounds { pc, .. }
+            | Chip8Error::ProtectedWrite { pc, .. }
+            | Chip8Error::StrictViolation { pc, .. } => pc,
+        }
+    }
+
+    /// Returns the opcode of the faulting instruction
+    pub fn opcode(&self) -> u16 {
+        match *self {
+            Chip8Error::UnknownOpcode { opcode, .. }
+            | Chip8Error::StackOverflow { opcode, .. }
+            | Chip8Error::StackUnderflow { opcode, .. }
+            | Chip8Error::MemoryOutOfBounds { opcode, .. }
+            | Chip8Error::ProtectedWrite { opcode, .. }
+            | Chip8Error::StrictViolation { opcode, .. } => opcode,
+        }
+    }
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Chip8Error::UnknownOpcode { .. } => write!(f, "Unknown OP Code")?,
+            Chip8Error::StackOverflow { depth, .. } => write!(
+                f,
+                "Stack overflow: exceeded the maximum depth of {} levels",
+                depth
+            )?,
+            Chip8Error::StackUnderflow { .. } => write!(
+                f,
+                "Stack underflow: returned with no subroutine to return from"
+            )?,
+            Chip8Error::MemoryOutOfBounds { address, .. } => {
+                write!(f, "Memory access out of bounds at 0x{:04X}", address)?
+            }
+            Chip8Error::ProtectedWrite { address, .. } => write!(
+                f,
+                "Protected memory write at 0x{:03X} ({})",
+                address,
+                protected_area(address)
+            )?,
+            Chip8Error::StrictViolation { ref reason, .. } => {
+                write!(f, "Strict spec violation: {}", reason)?
+            }
+        }
+        write!(f, " ({:04X} at 0x{:03X})", self.opcode(), self.pc())
+    }
+}
+
+impl Error for Chip8Error {}
+
+/// Returns the name of the area below `0x200` holding `address`
+pub(super) fn protected_area(address: usize) -> &'static str {
+    let font_area = Chip8::INITIAL_FONTS_MEMORY_ADDRESS
+        ..Chip8::BIG_FONTS_MEMORY_ADDRESS + Chip8::BIG_FONTS.len();
+    if font_area.contains(&address) {
+        "font area"
+    } else {
+        "interpreter area"
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(feature = "window")]
+use super::Chip8;
+#[cfg(feature = "window")]
+use std::time::Duration;
+
+/// Visual filter drawn over the display by the window, to look like the screens of the time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// Sharp square pixels
+    #[default]
+    None,
+    /// Dark scanlines between the rows of pixels, and phosphors that keep glowing for a
+    /// moment once turned off
+    Crt,
+    /// A grid between the cells of the display, unlit cells slightly visible, and the
+    /// slow response of liquid crystals
+    Lcd,
+}
+
+impl Filter {
+    /// All available filters, in the order they are presented to users
+    pub const ALL: [Filter; 3] = [Filter::None, Filter::Crt, Filter::Lcd];
+
+    /// Returns the name used to select the filter, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Filter::None => "none",
+            Filter::Crt => "crt",
+            Filter::Lcd => "lcd",
+        }
+    }
+
+    /// Finds a filter by its name
+    pub fn from_name(name: &str) -> Option<Filter> {
+        Filter::ALL
+            .iter()
+            .copied()
+            .find(|filter| filter.name() == name)
+    }
+
+    /// Returns the brightness kept by turned off pixels after every frame, if they fade out.
+    /// See `Phosphor`
+    pub fn decay(&self) -> Option<f32> {
+        match self {
+            Filter::None => None,
+            Filter::Crt => Some(0.45),
+            Filter::Lcd => Some(0.6),
+        }
+    }
+
+    /// Returns whether scanlines are drawn between the rows of pixels
+    pub fn scanlines(&self) -> bool {
+        *self == Filter::Crt
+    }
+
+    /// Returns whether a grid is drawn between the pixels
+    pub fn grid(&self) -> bool {
+        *self == Filter::Lcd
+    }
+}
+
+/// Brightness of every pixel of the display, for filters where pixels fade out once
+/// turned off instead of disappearing right away. Games drawing with XOR erase their
+/// sprites and draw them again a little later, so they flicker whenever a frame is shown
+/// in between: fading pixels bridge that gap, like the persistence of old screens did.
+#[cfg(feature = "window")]
+pub struct Phosphor {
+    /// Brightness of each pixel, from `0` (off) to `1` (lit)
+    levels: Vec<f32>,
+    /// Index in a `PaletteColors` of the color each pixel had when last lit
+    colors: Vec<usize>,
+    /// Brightness kept after every frame, see `Filter::decay`
+    decay: f32,
+}
+
+#[cfg(feature = "window")]
+impl Phosphor {
+    /// Brightness below which fading pixels are turned off
+    const THRESHOLD: f32 = 0.02;
+
+    /// Creates a dark screen whose pixels keep `decay` of their brightness every frame,
+    /// large enough for the high resolution. See `Display::is_hires`
+    pub fn new(decay: f32) -> Phosphor {
+        let size = Chip8::HIRES_WIDTH * Chip8::HIRES_HEIGHT;
+        Phosphor {
+            levels: vec![0.0; size],
+            colors: vec![0; size],
+            decay,
+        }
+    }
+
+    /// Lights the pixels with a `color` other than `0` and fades the other ones, by
+    /// `elapsed` since the last update. Fading follows real time, whatever the amount of
+    /// frames drawn
+    pub fn update(&mut self, color: impl Fn(usize) -> usize, elapsed: Duration) {
+        let decay = self
+            .decay
+            .powf(elapsed.as_secs_f32() * Chip8::FRAME_RATE as f32);
+        for pos in 0..self.levels.len() {
+            match color(pos) {
+                0 if self.levels[pos] * decay < Phosphor::THRESHOLD => self.levels[pos] = 0.0,
+                0 => self.levels[pos] *= decay,
+                color => {
+                    self.levels[pos] = 1.0;
+                    self.colors[pos] = color;
+                }
+            }
+        }
+    }
+
+    /// Returns the color index and the brightness of the pixel at `pos`, unless it's off
+    pub fn pixel(&self, pos: usize) -> Option<(usize, f32)> {
+        match self.levels[pos] {
+            level if level > 0.0 => Some((self.colors[pos], level)),
+            _ => None,
+        }
+    }
+
+    /// Returns whether any pixel is fading out, so the screen needs to be drawn again
+    pub fn is_fading(&self) -> bool {
+        self.levels.iter().any(|&level| level > 0.0 && level < 1.0)
+    }
+}
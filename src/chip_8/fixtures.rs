@@ -0,0 +1,450 @@
+//! Instruction tests driven by TOML fixtures, so covering a new case of an instruction
+//! only takes a few lines of data. Every `*.toml` file in `tests/instructions` holds
+//! any number of tests, each executing a single OP Code on a VM set to an initial state:
+//! ```toml
+//! [[test]]
+//! name = "8xy4 sets VF on carry"
+//! opcode = 0x8124
+//! # Optional, see `Profile`
+//! profile = "standard"
+//! # Optional, see `Quirks::parse`
+//! quirks = "shift"
+//! # Optional, see `MemoryBounds`
+//! memory_bounds = "wrap"
+//! # Optional, see `Chip8::set_rng_seed`
+//! seed = 1
+//! # Optional, see `WriteProtection`
+//! write_protection = "error"
+//! # Optional, the start of the message of the fault expected, see `Chip8Error`
+//! error = "Stack overflow"
+//!
+//! [test.before]
+//! v = { 1 = 0xFF, 2 = 0x02 }
+//!
+//! [test.after]
+//! v = { 1 = 0x01, F = 1 }
+//! pc = 0x202
+//! ```
+//! # State
+//! Both `before` and `after` accept the same fields, all optional:
+//! * `v`: Registers by their hexadecimal index.
+//! * `i`, `pc`, `delay` and `sound`: Registers and timers. `pc` starts at `0x200`.
+//! * `stack`: Addresses stored in the Stack, from the bottom.
+//! * `memory`: Bytes by start address, ex: `{ "0x300" = [0xF0, 0x90] }`.
+//! * `pixels`: Coordinates of the pixels on, ex: `[[0, 0], [1, 0]]`.
+//! * `hires`: Whether the 128x64 high resolution is active. See `Profile::Schip`.
+//! * `keys`: Keys being pressed. Only accepted in `before`.
+//! * `halted`: Whether the VM halted, ex: with `00FD`. Only accepted in `after`.
+//! * `planes`: Display planes selected, as a bit mask. See `Profile::XoChip`.
+//! * `audio`: The 16 bytes of the audio pattern buffer. See `Profile::XoChip`.
+//! * `flags`: RPL user flags by index, like `v`. See `Profile::Schip`.
+//! * `pitch`: Pitch of the audio pattern, set with `Fx3A`. See `Profile::XoChip`.
+//!
+//! `after` only checks the fields it lists, but `stack` and `pixels` are compared as a whole.
+//! The OP Code is executed as if fetched from `pc`, without ticking the timers.
+//!
+//! Every instruction must be covered by at least one test.
+use super::{Chip8, Display, Instruction, MemoryBounds, Profile, Quirks, WriteProtection};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Directory of the fixtures, relative to the crate root
+const FIXTURES_DIRECTORY: &str = "tests/instructions";
+
+pub(super) fn integer(value: &Value, field: &str) -> Result<usize, String> {
+    value
+        .as_integer()
+        .filter(|&n| n >= 0)
+        .map(|n| n as usize)
+        .ok_or(format!("'{}' must be a non-negative integer", field))
+}
+
+pub(super) fn integers(value: &Value, field: &str) -> Result<Vec<usize>, String> {
+    value
+        .as_array()
+        .ok_or(format!("'{}' must be an array", field))?
+        .iter()
+        .map(|value| integer(value, field))
+        .collect()
+}
+
+/// Parses a table keyed by numbers written in hexadecimal, ex: `{ F = 1 }` or `{ "0x300" = 2 }`
+pub(super) fn hex_table(value: &Value, field: &str) -> Result<Vec<(usize, Value)>, String> {
+    value
+        .as_table()
+        .ok_or(format!("'{}' must be a table", field))?
+        .iter()
+        .map(|(key, value)| {
+            usize::from_str_radix(key.trim_start_matches("0x"), 16)
+                .map(|key| (key, value.clone()))
+                .map_err(|_| format!("Invalid key '{}' in '{}'", key, field))
+        })
+        .collect()
+}
+
+/// Parses the pixels on, in the current resolution of `display`
+fn pixels(value: &Value, display: &Display) -> Result<Vec<bool>, String> {
+    let (width, height) = (display.width(), display.height());
+    let mut buffer = vec![false; display.size()];
+    for pixel in value.as_array().ok_or("'pixels' must be an array")? {
+        match integers(pixel, "pixels")?.as_slice() {
+            &[x, y] if x < width && y < height => {
+                buffer[y * width + x] = true;
+            }
+            _ => return Err("Pixels must be [x, y] pairs within the display".to_string()),
+        }
+    }
+    Ok(buffer)
+}
+
+/// Sets the VM to the state described by the `before` table of a test
+fn apply(vm: &mut Chip8, state: &Table) -> Result<(), String> {
+    // First, as switching the resolution clears the display
+    if let Some(hires) = state.get("hires") {
+        let hires = hires.as_bool().ok_or("'hires' must be a boolean")?;
+        vm.display.set_hires(hires);
+    }
+    for (field, value) in state.iter() {
+        match field.as_str() {
+            "hires" => {}
+            "v" => {
+                for (x, value) in hex_table(value, "v")? {
+                    *vm.regs.v.get_mut(x).ok_or("Invalid register")? = integer(&value, "v")? as u8;
+                }
+            }
+            "i" => vm.regs.i = integer(value, "i")? as u16,
+            "pc" => vm.regs.pc = integer(value, "pc")? as u16,
+            "delay" => vm.timers.delay = integer(value, "delay")? as u8,
+            "sound" => vm.timers.sound = integer(value, "sound")? as u8,
+            "stack" => {
+                let addresses = integers(value, "stack")?;
+                vm.stack.stored = addresses.iter().map(|&address| address as u16).collect();
+            }
+            "memory" => {
+                for (address, bytes) in hex_table(value, "memory")? {
+                    for (offset, byte) in integers(&bytes, "memory")?.into_iter().enumerate() {
+                        vm.main_memory[address + offset] = byte as u8;
+                    }
+                }
+            }
+            "pixels" => {
+                let pixels = pixels(value, &vm.display)?;
+                vm.display.plane_mut(0).copy_from_slice(&pixels);
+            }
+            "keys" => {
+                for key in integers(value, "keys")? {
+                    *vm.input.key_status.get_mut(key).ok_or("Invalid key")? = true;
+                }
+            }
+            "planes" => vm.display.selected_planes = integer(value, "planes")? as u8,
+            "pitch" => vm.pitch = integer(value, "pitch")? as u8,
+            "flags" => {
+                for (x, value) in hex_table(value, "flags")? {
+                    *vm.rpl_flags.get_mut(x).ok_or("Invalid flag")? =
+                        integer(&value, "flags")? as u8;
+                }
+            }
+            "audio" => {
+                let pattern = integers(value, "audio")?;
+                if pattern.len() != vm.audio_pattern.len() {
+                    return Err("'audio' must hold 16 bytes".to_string());
+                }
+                for (byte, value) in vm.audio_pattern.iter_mut().zip(pattern) {
+                    *byte = value as u8;
+                }
+            }
+            _ => return Err(format!("Unknown field '{}'", field)),
+        }
+    }
+    Ok(())
+}
+
+/// Compares the VM against the state described by the `after` table of a test
+/// # Returns
+/// A description of every mismatch, empty if the VM matches.
+fn compare(vm: &Chip8, state: &Table) -> Result<Vec<String>, String> {
+    let mut mismatches = Vec::new();
+    let check = |mismatches: &mut Vec<String>, name: String, expected: usize, actual| {
+        if expected != actual {
+            mismatches.push(format!(
+                "{}: expected 0x{:X}, found 0x{:X}",
+                name, expected, actual
+            ));
+        }
+    };
+
+    for (field, value) in state.iter() {
+        match field.as_str() {
+            "v" => {
+                for (x, value) in hex_table(value, "v")? {
+                    let actual = *vm.regs.v.get(x).ok_or("Invalid register")? as usize;
+                    check(
+                        &mut mismatches,
+                        format!("V{:X}", x),
+                        integer(&value, "v")?,
+                        actual,
+                    );
+                }
+            }
+            "i" => check(
+                &mut mismatches,
+                "I".to_string(),
+                integer(value, "i")?,
+                vm.regs.i as usize,
+            ),
+            "pc" => check(
+                &mut mismatches,
+                "PC".to_string(),
+                integer(value, "pc")?,
+                vm.regs.pc as usize,
+            ),
+            "delay" => check(
+                &mut mismatches,
+                "DT".to_string(),
+                integer(value, "delay")?,
+                vm.timers.delay as usize,
+            ),
+            "sound" => check(
+                &mut mismatches,
+                "ST".to_string(),
+                integer(value, "sound")?,
+                vm.timers.sound as usize,
+            ),
+            "stack" => {
+                let expected = integers(value, "stack")?;
+                let actual: Vec<usize> = vm.stack.stored.iter().map(|&a| a as usize).collect();
+                if expected != actual {
+                    mismatches.push(format!(
+                        "Stack: expected {:X?}, found {:X?}",
+                        expected, actual
+                    ));
+                }
+            }
+            "memory" => {
+                for (address, bytes) in hex_table(value, "memory")? {
+                    for (offset, byte) in integers(&bytes, "memory")?.into_iter().enumerate() {
+                        let address = address + offset;
+                        check(
+                            &mut mismatches,
+                            format!("Memory at 0x{:03X}", address),
+                            byte,
+                            vm.main_memory[address] as usize,
+                        );
+                    }
+                }
+            }
+            "planes" => check(
+                &mut mismatches,
+                "Planes".to_string(),
+                integer(value, "planes")?,
+                vm.display.selected_planes as usize,
+            ),
+            "flags" => {
+                for (x, value) in hex_table(value, "flags")? {
+                    let actual = *vm.rpl_flags.get(x).ok_or("Invalid flag")? as usize;
+                    check(
+                        &mut mismatches,
+                        format!("Flag {:X}", x),
+                        integer(&value, "flags")?,
+                        actual,
+                    );
+                }
+            }
+            "pitch" => check(
+                &mut mismatches,
+                "Pitch".to_string(),
+                integer(value, "pitch")?,
+                vm.pitch as usize,
+            ),
+            "audio" => {
+                let expected = integers(value, "audio")?;
+                let actual: Vec<usize> = vm.audio_pattern.iter().map(|&b| b as usize).collect();
+                if expected != actual {
+                    mismatches.push(format!(
+                        "Audio pattern: expected {:X?}, found {:X?}",
+                        expected, actual
+                    ));
+                }
+            }
+            "halted" => {
+                let expected = value.as_bool().ok_or("'halted' must be a boolean")?;
+                if expected != vm.halted.is_some() {
+                    mismatches.push(format!(
+                        "Halted: expected {}, found {}",
+                        expected,
+                        vm.halted.is_some()
+                    ));
+                }
+            }
+            "hires" => {
+                let expected = value.as_bool().ok_or("'hires' must be a boolean")?;
+                if expected != vm.display.is_hires() {
+                    mismatches.push(format!(
+                        "High resolution: expected {}, found {}",
+                        expected,
+                        vm.display.is_hires()
+                    ));
+                }
+            }
+            "pixels" => {
+                let expected = pixels(value, &vm.display)?;
+                let width = vm.display.width();
+                for (pos, (&expected, &actual)) in
+                    expected.iter().zip(vm.display.plane(0)).enumerate()
+                {
+                    if expected != actual {
+                        mismatches.push(format!(
+                            "Pixel ({}, {}): expected {}, found {}",
+                            pos % width,
+                            pos / width,
+                            expected as u8,
+                            actual as u8
+                        ));
+                    }
+                }
+            }
+            _ => return Err(format!("Unknown field '{}'", field)),
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Runs a single test of a fixture
+/// # Returns
+/// A description of every mismatch, empty if the test passed.
+fn run(test: &Value) -> Result<Vec<String>, String> {
+    let empty = Table::new();
+    let state = |field: &str| match test.get(field) {
+        Some(value) => value
+            .as_table()
+            .ok_or(format!("'{}' must be a table", field)),
+        None => Ok(&empty),
+    };
+
+    let mut vm = Chip8::new();
+    if let Some(name) = test.get("profile") {
+        let name = name.as_str().ok_or("'profile' must be a string")?;
+        vm.set_profile(Profile::from_name(name).ok_or("Unknown profile")?);
+    }
+    if let Some(list) = test.get("quirks") {
+        let list = list.as_str().ok_or("'quirks' must be a string")?;
+        vm.set_quirks(Quirks::parse(list)?);
+    }
+    if let Some(name) = test.get("memory_bounds") {
+        let name = name.as_str().ok_or("'memory_bounds' must be a string")?;
+        vm.set_memory_bounds(MemoryBounds::from_name(name).ok_or("Unknown memory bounds")?);
+    }
+    if let Some(seed) = test.get("seed") {
+        vm.set_rng_seed(integer(seed, "seed")? as u64);
+    }
+    if let Some(name) = test.get("write_protection") {
+        let name = name.as_str().ok_or("'write_protection' must be a string")?;
+        vm.set_write_protection(
+            WriteProtection::from_name(name).ok_or("Unknown write protection")?,
+        );
+    }
+    apply(&mut vm, state("before")?)?;
+
+    let opcode = integer(test.get("opcode").ok_or("Missing 'opcode'")?, "opcode")?;
+    let address = vm.regs.pc as usize;
+    vm.main_memory[address..address + 2].copy_from_slice(&(opcode as u16).to_be_bytes());
+    vm.regs.pc = vm.regs.pc.wrapping_add(2);
+    vm.instruction = (address as u16, opcode as u16);
+    let executed = vm.execute(opcode as u16).map_err(|e| e.to_string());
+    let expected_error = match test.get("error") {
+        Some(error) => Some(error.as_str().ok_or("'error' must be a string")?),
+        None => None,
+    };
+
+    let mut mismatches = compare(&vm, state("after")?)?;
+    match (executed, expected_error) {
+        (Ok(()), None) => {}
+        (Err(error), Some(expected)) if error.starts_with(expected) => {}
+        (Err(error), None) => return Err(error),
+        (Ok(()), Some(expected)) => {
+            mismatches.push(format!("Error: expected '{}', found none", expected))
+        }
+        (Err(error), Some(expected)) => {
+            mismatches.push(format!("Error: expected '{}', found '{}'", expected, error))
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Returns the pattern of the instruction `test` executes, ex: `8xy4`
+fn pattern(test: &Value) -> Option<&'static str> {
+    let opcode = integer(test.get("opcode")?, "opcode").ok()?;
+    let instruction = Instruction::decode(opcode as u16)?;
+    Some(instruction.info().pattern)
+}
+
+#[test]
+fn instruction_fixtures() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURES_DIRECTORY);
+    let mut paths: Vec<_> = fs::read_dir(&directory)
+        .expect("Failed to list the fixtures")
+        .map(|entry| entry.expect("Failed to list the fixtures").path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .collect();
+    paths.sort();
+
+    let mut executed = 0;
+    let mut failures = Vec::new();
+    let mut covered = BTreeSet::new();
+    for path in paths.iter() {
+        let file = path.file_name().unwrap_or_default().to_string_lossy();
+        let content = fs::read_to_string(path).expect("Failed to read a fixture");
+        let fixture: Table = match content.parse() {
+            Ok(fixture) => fixture,
+            Err(e) => {
+                failures.push(format!("{}: {}", file, e));
+                continue;
+            }
+        };
+
+        let tests = fixture.get("test").and_then(Value::as_array);
+        for (index, test) in tests.into_iter().flatten().enumerate() {
+            let name = match test.get("name").and_then(Value::as_str) {
+                Some(name) => name.to_string(),
+                None => format!("test #{}", index + 1),
+            };
+            executed += 1;
+            covered.extend(pattern(test));
+            match run(test) {
+                Ok(mismatches) if mismatches.is_empty() => {}
+                Ok(mismatches) => failures.push(format!(
+                    "{}: {}\n    {}",
+                    file,
+                    name,
+                    mismatches.join("\n    ")
+                )),
+                Err(e) => failures.push(format!("{}: {}: {}", file, name, e)),
+            }
+        }
+    }
+
+    assert!(executed > 0, "No tests found in {}", directory.display());
+    assert!(
+        failures.is_empty(),
+        "{} of {} instruction tests failed:\n{}",
+        failures.len(),
+        executed,
+        failures.join("\n")
+    );
+
+    let missing: BTreeSet<&str> = (0..=u16::MAX)
+        .filter_map(Instruction::decode)
+        .map(|instruction| instruction.info().pattern)
+        .filter(|pattern| !covered.contains(pattern))
+        .collect();
+    assert!(
+        missing.is_empty(),
+        "Instructions without tests: {:?}",
+        missing
+    );
+}
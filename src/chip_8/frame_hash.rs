@@ -0,0 +1,253 @@
+use super::{Chip8, DebugHook, Profile};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+/// Hashes `bytes` into `hash` using `FNV-1a`.
+/// Unlike `std` hashers, the result is stable across platforms and Rust versions,
+/// so hashes can be stored and compared between different builds.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+impl Chip8 {
+    /// Returns a hash of the display and registers (`v`, `I`, `PC`, `SP` and timers)
+    pub fn state_hash(&self) -> u64 {
+        let mut hash = fnv1a(FNV_OFFSET_BASIS, &self.regs.v);
+        hash = fnv1a(hash, &self.regs.i.to_be_bytes());
+        hash = fnv1a(hash, &self.regs.pc.to_be_bytes());
+        hash = fnv1a(hash, &(self.stack.pointer() as u16).to_be_bytes());
+        hash = fnv1a(hash, &[self.timers.delay, self.timers.sound]);
+        // Only the pixels of the current resolution, which tells both resolutions apart
+        for &pixel in self.display.plane(0) {
+            hash = fnv1a(hash, &[pixel as u8]);
+        }
+        // Only hashed when drawable, so hashes of other profiles are left unchanged
+        if self.profile == Profile::XoChip {
+            for &pixel in self.display.plane(1) {
+                hash = fnv1a(hash, &[pixel as u8]);
+            }
+        }
+        hash
+    }
+}
+
+/// Hash of the state of a VM at the end of a frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameHash {
+    /// Number of the frame, starting from `0`
+    pub frame: u64,
+    /// Rolling hash: combines the state of this frame with the hash of the previous one,
+    /// so any divergence is carried to all following frames
+    pub hash: u64,
+}
+
+/// Computes a rolling hash of the state of a VM, one frame at a time.
+/// Two VMs running the same ROM with the same inputs produce the same sequence of hashes,
+/// so the first mismatching `FrameHash` pinpoints the frame where they diverged.
+///
+/// **Note:** `Cxkk` draws different numbers on every run, so ROMs using it diverge
+/// between runs unless both use the same seed. See `Chip8::set_rng_seed`
+pub struct FrameHasher {
+    frame: u64,
+    hash: u64,
+    /// Value of `Chip8::frames` the last time `update` was called
+    vm_frames: u64,
+    last: Option<FrameHash>,
+}
+
+impl FrameHasher {
+    pub fn new() -> FrameHasher {
+        FrameHasher {
+            frame: 0,
+            hash: FNV_OFFSET_BASIS,
+            vm_frames: 0,
+            last: None,
+        }
+    }
+
+    /// Folds the state of `vm` at the end of a frame into the rolling hash
+    pub fn push(&mut self, vm: &Chip8) -> FrameHash {
+        self.hash = fnv1a(self.hash, &vm.state_hash().to_be_bytes());
+        let frame_hash = FrameHash {
+            frame: self.frame,
+            hash: self.hash,
+        };
+        self.frame += 1;
+        self.last = Some(frame_hash);
+        frame_hash
+    }
+
+    /// Pushes the state of `vm` if one of its frames ended since the last call, so it can
+    /// be called after every instruction. See `Chip8::frames`
+    /// # Returns
+    /// The hash of the frame that ended, if any
+    pub fn update(&mut self, vm: &Chip8) -> Option<FrameHash> {
+        if vm.frames() == self.vm_frames {
+            return None;
+        }
+        self.vm_frames = vm.frames();
+        Some(self.push(vm))
+    }
+
+    /// Returns the hash of the last frame pushed, if any
+    pub fn last(&self) -> Option<FrameHash> {
+        self.last
+    }
+}
+
+enum TraceMode {
+    Record(BufWriter<File>),
+    Verify(io::Lines<BufReader<File>>),
+}
+
+/// A `DebugHook` that runs the VM while recording its frame hashes into a trace file,
+/// or verifying them against a previously recorded one. The outcome is shown on the `Osd`
+/// and kept in `FrameHashTrace::message`.
+/// # Trace format
+/// A text file with one line per frame: the frame number and its rolling hash
+/// in hexadecimal, separated by a space. Ex: `42 9a3f0c2e51b7d884`
+pub struct FrameHashTrace {
+    hasher: FrameHasher,
+    mode: TraceMode,
+    message: Option<String>,
+    exit: bool,
+}
+
+impl FrameHashTrace {
+    /// Records the frame hashes of the run into the file at `path`
+    pub fn record(path: &str) -> io::Result<FrameHashTrace> {
+        let file = File::create(path)?;
+        Ok(FrameHashTrace::with_mode(TraceMode::Record(
+            BufWriter::new(file),
+        )))
+    }
+
+    /// Compares the frame hashes of the run against the ones recorded in the file at `path`
+    pub fn verify(path: &str) -> io::Result<FrameHashTrace> {
+        let file = File::open(path)?;
+        Ok(FrameHashTrace::with_mode(TraceMode::Verify(
+            BufReader::new(file).lines(),
+        )))
+    }
+
+    fn with_mode(mode: TraceMode) -> FrameHashTrace {
+        FrameHashTrace {
+            hasher: FrameHasher::new(),
+            mode,
+            message: None,
+            exit: false,
+        }
+    }
+
+    /// Returns the hash of the last frame of the run, if any
+    pub fn last_hash(&self) -> Option<FrameHash> {
+        self.hasher.last()
+    }
+
+    /// Returns why the run stopped, if it did: the trace verified, the frame where it
+    /// diverged or an error
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Writes the hashes recorded but still buffered to the trace file. Call it once
+    /// the run is over
+    /// # Errors
+    /// If the trace file can't be written
+    pub fn finish(&mut self) -> io::Result<()> {
+        match &mut self.mode {
+            TraceMode::Record(writer) => writer.flush(),
+            TraceMode::Verify(_) => Ok(()),
+        }
+    }
+
+    /// Handles the hash of a frame, returning a message when the run must stop
+    fn handle(&mut self, frame_hash: FrameHash) -> Option<String> {
+        let expected = match &mut self.mode {
+            TraceMode::Record(writer) => {
+                // Buffered, the trace is written to disk by `finish`
+                return writeln!(writer, "{} {:016x}", frame_hash.frame, frame_hash.hash)
+                    .err()
+                    .map(|e| format!("Failed to record frame hashes: {}", e));
+            }
+            TraceMode::Verify(lines) => match lines.next() {
+                None => {
+                    return Some(format!(
+                        "Trace verified: {} frames match.",
+                        frame_hash.frame
+                    ))
+                }
+                Some(Err(e)) => return Some(format!("Failed to read frame hashes: {}", e)),
+                Some(Ok(line)) => line,
+            },
+        };
+
+        let expected_hash = expected
+            .split_whitespace()
+            .nth(1)
+            .and_then(|hash| u64::from_str_radix(hash, 16).ok());
+        match expected_hash {
+            Some(hash) if hash == frame_hash.hash => None,
+            Some(hash) => Some(format!(
+                "Divergence detected at frame {}: expected {:016x}, got {:016x}",
+                frame_hash.frame, hash, frame_hash.hash
+            )),
+            None => Some(format!("Malformed frame hash: '{}'", expected)),
+        }
+    }
+}
+
+impl DebugHook for FrameHashTrace {
+    fn update(&mut self, vm: &mut Chip8) {
+        if self.exit {
+            return;
+        }
+
+        let msg = match vm.step() {
+            Err(error) => format!("ERROR: {}", error),
+            Ok(_) => match self.hasher.update(vm).and_then(|hash| self.handle(hash)) {
+                Some(msg) => msg,
+                None => return,
+            },
+        };
+        vm.osd_mut().show(msg.clone());
+        self.message = Some(msg);
+        self.exit = true;
+    }
+
+    fn should_exit(&self) -> bool {
+        self.exit
+    }
+}
+
+impl Default for FrameHasher {
+    fn default() -> FrameHasher {
+        FrameHasher::new()
+    }
+}
+
+#[test]
+fn frames_are_hashed_once_they_end() {
+    let mut vm = Chip8::new();
+    vm.load_rom_content(include_bytes!("../../roms/tests/ibm-logo.ch8").to_vec())
+        .unwrap();
+    let mut hasher = FrameHasher::new();
+    let mut hashes = Vec::new();
+    while vm.frames() < 3 {
+        vm.step().unwrap();
+        hashes.extend(hasher.update(&vm));
+    }
+    assert_eq!(
+        hashes.iter().map(|hash| hash.frame).collect::<Vec<u64>>(),
+        [0, 1, 2]
+    );
+    assert_eq!(hasher.last(), hashes.last().copied());
+    assert_eq!(hasher.update(&vm), None);
+}
@@ -0,0 +1,88 @@
+use super::Osd;
+use std::time::{Duration, Instant};
+
+/// Controls how many frames are rendered, so slow hosts can spend their time
+/// emulating at full speed instead of drawing. Only 1 of every `skip` frames
+/// is rendered, while the VM keeps running every cycle.
+///
+/// In automatic mode, `skip` starts at `1` (every frame is rendered) and
+/// increases every time frame deadlines are missed `MISSED_DEADLINES_LIMIT` times in a row.
+pub struct FrameSkip {
+    skip: u32,
+    automatic: bool,
+    missed_deadlines: u32,
+    last_frame: Option<Instant>,
+}
+
+impl FrameSkip {
+    /// Frame rate when every frame is rendered
+    pub const BASE_FPS: u64 = 60;
+    /// Highest `skip` set by the automatic mode
+    pub const MAX_AUTOMATIC_SKIP: u32 = 4;
+    /// Consecutive missed deadlines before the automatic mode increases `skip`
+    const MISSED_DEADLINES_LIMIT: u32 = 30;
+
+    /// Renders 1 of every `skip` frames
+    /// # Panics
+    /// If `skip` is `0`
+    pub fn fixed(skip: u32) -> FrameSkip {
+        assert!(skip > 0, "At least 1 of every N frames must be rendered.");
+
+        FrameSkip {
+            skip,
+            automatic: false,
+            missed_deadlines: 0,
+            last_frame: None,
+        }
+    }
+
+    /// Renders every frame until deadlines are missed repeatedly
+    pub fn automatic() -> FrameSkip {
+        FrameSkip {
+            automatic: true,
+            ..FrameSkip::fixed(1)
+        }
+    }
+
+    /// Returns the frame rate to render at
+    pub fn max_fps(&self) -> u64 {
+        (FrameSkip::BASE_FPS / self.skip as u64).max(1)
+    }
+
+    /// Called once a frame was presented on screen at `now`. Changes of the automatic mode
+    /// are reported on `osd`
+    /// # Returns
+    /// The new frame rate to render at, if the automatic mode changed it
+    pub fn frame_presented(&mut self, now: Instant, osd: &mut Osd) -> Option<u64> {
+        let last_frame = self.last_frame.replace(now)?;
+        if !self.automatic || self.skip >= FrameSkip::MAX_AUTOMATIC_SKIP {
+            return None;
+        }
+
+        // A frame is late when it takes over 1.5 times its expected duration
+        let deadline = Duration::from_secs(1) * self.skip * 3 / (FrameSkip::BASE_FPS as u32 * 2);
+        if now.duration_since(last_frame) <= deadline {
+            self.missed_deadlines = 0;
+            return None;
+        }
+
+        self.missed_deadlines += 1;
+        if self.missed_deadlines < FrameSkip::MISSED_DEADLINES_LIMIT {
+            return None;
+        }
+
+        self.missed_deadlines = 0;
+        self.skip += 1;
+        osd.show(format!(
+            "Frame deadlines missed, rendering 1 of every {} frames",
+            self.skip
+        ));
+        Some(self.max_fps())
+    }
+}
+
+impl Default for FrameSkip {
+    fn default() -> FrameSkip {
+        FrameSkip::automatic()
+    }
+}
@@ -0,0 +1,191 @@
+use super::{pulse, Chip8, Chip8Error, Clock, Palette, Rgb};
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Keys of the host mapped to the 16 keys of the keypad, indexed by keypad key.
+/// Same layout as the `KeyMap` of the window: `1234`, `QWER`, `ASDF` and `ZXCV`
+const KEYS: [Key; 16] = [
+    Key::X,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Q,
+    Key::W,
+    Key::E,
+    Key::A,
+    Key::S,
+    Key::D,
+    Key::Z,
+    Key::C,
+    Key::Key4,
+    Key::R,
+    Key::F,
+    Key::V,
+];
+
+/// Title of the window, followed by the status
+const TITLE: &str = "CHIP-8";
+
+/// Keys of the emulator, shown in the title
+const STATUS: &str = "Esc: quit  P: pause  F12: reset";
+
+/// Converts a color to the `0RGB` pixels of `minifb`
+fn to_pixel([r, g, b]: Rgb) -> u32 {
+    u32::from(r) << 16 | u32::from(g) << 8 | u32::from(b)
+}
+
+impl Chip8 {
+    /// Starts the execution of the VM in a window drawn by the CPU, for systems without
+    /// OpenGL 3.2. Returns when the window is closed, `Esc` is pressed or the VM halts.
+    ///
+    /// Every pixel of the display is drawn as a `scale` by `scale` square of the window,
+    /// which has a fixed size. Keys are read with the conventional layout of `KeyMap`,
+    /// while `P` pauses and `F12` restarts the ROM. The loop is paced by frames like
+    /// `start`, and the status is shown in the title of the window. With `audio_reactive`,
+    /// the foreground pulses with the sound like in `start`.
+    ///
+    /// When an instruction faults the VM is paused, with the error in the title.
+    ///
+    /// **Note:** Only available with the `framebuffer` feature.
+    /// # Returns
+    /// The fault that paused the VM, if it wasn't restarted since
+    /// # Errors
+    /// If the window can't be created or drawn to
+    pub fn start_framebuffer(
+        &mut self,
+        scale: u32,
+        palette: Palette,
+        high_contrast: bool,
+        audio_reactive: bool,
+    ) -> Result<Option<Chip8Error>, String> {
+        let scale = scale as usize;
+        let (width, height) = (Chip8::VIDEO_WIDTH * scale, Chip8::VIDEO_HEIGHT * scale);
+        let mut window = Window::new(TITLE, width, height, WindowOptions::default())
+            .map_err(|e| format!("Failed to create the window: {}", e))?;
+        // Paced by the loop instead, like the other frontends
+        window.set_target_fps(0);
+        let colors = palette.colors(high_contrast);
+        self.run_framebuffer(&mut window, scale, colors, audio_reactive)
+            .map_err(|e| format!("Failed to draw the window: {}", e))
+    }
+
+    fn run_framebuffer(
+        &mut self,
+        window: &mut Window,
+        scale: usize,
+        palette_colors: [Rgb; 4],
+        audio_reactive: bool,
+    ) -> minifb::Result<Option<Chip8Error>> {
+        let frame_period = Duration::from_secs(1) / Chip8::FRAME_RATE;
+        let mut next_frame_time = Instant::now();
+        let mut cycle_budget: i64 = 0;
+        let mut colors = palette_colors.map(to_pixel);
+        let (width, height) = (Chip8::VIDEO_WIDTH * scale, Chip8::VIDEO_HEIGHT * scale);
+        let mut pixels = vec![colors[0]; width * height];
+        let mut status = String::from(STATUS);
+        // Set to draw the next frame even if the display didn't change, ex: for the status
+        let mut redraw = true;
+        let mut fault = None;
+
+        while window.is_open() && !window.is_key_down(Key::Escape) {
+            for key in window.get_keys_pressed(KeyRepeat::No) {
+                match key {
+                    Key::P | Key::F12 => {
+                        if key == Key::F12 {
+                            self.reset();
+                        } else {
+                            self.paused = !self.paused;
+                        }
+                        fault = None;
+                        status = String::from(STATUS);
+                        if self.paused {
+                            status.insert_str(0, "Paused - ");
+                        }
+                        redraw = true;
+                    }
+                    key => {
+                        if let Some(key) = KEYS.iter().position(|&k| k == key) {
+                            self.press_key(self.swapped_key(key));
+                        }
+                    }
+                }
+            }
+            for key in window.get_keys_released() {
+                if let Some(key) = KEYS.iter().position(|&k| k == key) {
+                    self.release_key(self.swapped_key(key));
+                }
+            }
+
+            if !self.paused {
+                cycle_budget += self.clock.rate() as i64;
+                while cycle_budget >= Chip8::FRAME_RATE as i64 {
+                    if self.halted.is_some() {
+                        break;
+                    }
+                    let elapsed = self.clock.elapsed;
+                    // Paused, so the last frame stays on screen along with the fault
+                    if let Err(error) = self.step() {
+                        self.paused = true;
+                        status = format!("Paused: {} - {}", error, STATUS);
+                        fault = Some(error);
+                        redraw = true;
+                        break;
+                    }
+                    cycle_budget -= self.clock.spent_since(elapsed) * Chip8::FRAME_RATE as i64;
+                }
+                cycle_budget = Clock::carried_budget(cycle_budget);
+            }
+            if self.halted.is_some() {
+                break;
+            }
+            if let Some(message) = self.osd.take_log().pop() {
+                status = message;
+                redraw = true;
+            }
+
+            if redraw {
+                window.set_title(&format!("{} - {}", TITLE, status));
+            }
+            let mut recolor = false;
+            if audio_reactive {
+                let pulsed = pulse(palette_colors, self.sound_level()).map(to_pixel);
+                recolor = pulsed != colors;
+                colors = pulsed;
+            }
+            if self.display.take_dirty() || redraw || recolor {
+                self.blit(&mut pixels, width, &colors);
+                window.update_with_buffer(&pixels, width, height)?;
+                redraw = false;
+            } else {
+                window.update();
+            }
+
+            // Late frames are dropped instead of run at once
+            let now = Instant::now();
+            next_frame_time = (next_frame_time + frame_period).max(now);
+            thread::sleep(next_frame_time - now);
+        }
+        Ok(fault)
+    }
+
+    /// Draws the display to `pixels`, lines of `width` host pixels, stretched to fill them
+    /// whatever its resolution: every pixel of the 64x32 one is a `scale` by `scale`
+    /// square, and every pixel of the 128x64 one a quarter of it
+    fn blit(&self, pixels: &mut [u32], width: usize, colors: &[u32; 4]) {
+        let (columns, rows) = (self.display.width(), self.display.height());
+        let height = pixels.len() / width;
+        for y in 0..height {
+            let row = y * rows / height;
+            let start = y * width;
+            // Lines of the same row of the display are copies of the first one
+            if y > 0 && (y - 1) * rows / height == row {
+                pixels.copy_within(start - width..start, start);
+                continue;
+            }
+            for (x, pixel) in pixels[start..start + width].iter_mut().enumerate() {
+                *pixel = colors[self.display.color(row * columns + x * columns / width)];
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+use std::fs;
+
+/// Buttons that can be named in a mapping, along with their names.
+/// Names follow the position of the button, so they're the same on any controller:
+/// `south` is `A` on Xbox controllers and `Cross` on PlayStation ones
+const BUTTONS: [(Button, &str); 17] = [
+    (Button::DPadUp, "up"),
+    (Button::DPadDown, "down"),
+    (Button::DPadLeft, "left"),
+    (Button::DPadRight, "right"),
+    (Button::South, "south"),
+    (Button::East, "east"),
+    (Button::West, "west"),
+    (Button::North, "north"),
+    (Button::LeftTrigger, "l1"),
+    (Button::RightTrigger, "r1"),
+    (Button::LeftTrigger2, "l2"),
+    (Button::RightTrigger2, "r2"),
+    (Button::Select, "select"),
+    (Button::Start, "start"),
+    (Button::Mode, "mode"),
+    (Button::LeftThumb, "l3"),
+    (Button::RightThumb, "r3"),
+];
+
+/// D-pad buttons mirrored by the left stick, in the order of `Gamepads::stick`
+const STICK_BUTTONS: [Button; 4] = [
+    Button::DPadLeft,
+    Button::DPadRight,
+    Button::DPadDown,
+    Button::DPadUp,
+];
+
+/// How far the left stick has to be tilted to press a direction, from `0.0` to `1.0`
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// Maps the buttons of gamepads to the 16 keys of the `CHIP-8` keypad, from `0x0` to `0xF`.
+/// By default the D-pad (and the left stick) maps to `2`, `8`, `4` and `6`, the arrows
+/// of most games, and the face buttons to `5`, `0`, `7` and `9`:
+/// ```text
+/// Button    Key      Button    Key
+/// up        2        south     5
+/// down      8        east      0
+/// left      4        west      7
+/// right     6        north     9
+/// l1 / r1   1 / C    select    E
+/// l2 / r2   A / B    start     F
+/// ```
+/// Several buttons can map to the same keypad key.
+/// # Mapping format
+/// A text file with one `BUTTON = KEY` binding per line, applied over the default
+/// mapping. Keys are hexadecimal, and `none` unbinds the button. Empty lines and lines
+/// starting with `#` are ignored.
+///
+/// Ex: `south = 4` for the rotation of Tetris
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GamepadMap {
+    /// Keypad key of each button, in the order of `BUTTONS`
+    keys: [Option<usize>; BUTTONS.len()],
+}
+
+impl GamepadMap {
+    /// Creates the map with the default bindings
+    pub fn new() -> GamepadMap {
+        GamepadMap {
+            keys: [
+                Some(0x2),
+                Some(0x8),
+                Some(0x4),
+                Some(0x6),
+                Some(0x5),
+                Some(0x0),
+                Some(0x7),
+                Some(0x9),
+                Some(0x1),
+                Some(0xC),
+                Some(0xA),
+                Some(0xB),
+                Some(0xE),
+                Some(0xF),
+                None,
+                None,
+                None,
+            ],
+        }
+    }
+
+    /// Returns the keypad key mapped to `button`, if any
+    pub fn keypad_key(&self, button: Button) -> Option<usize> {
+        let index = BUTTONS.iter().position(|&(b, _)| b == button)?;
+        self.keys[index]
+    }
+
+    /// Maps `button` to `keypad_key`, or unbinds it with `None`
+    /// # Errors
+    /// If `button` can't be mapped, or if `keypad_key` is above `0xF`
+    pub fn bind(&mut self, button: Button, keypad_key: Option<usize>) -> Result<(), String> {
+        let index = BUTTONS
+            .iter()
+            .position(|&(b, _)| b == button)
+            .ok_or(format!("The button {:?} can't be mapped.", button))?;
+        if let Some(key) = keypad_key.filter(|&key| key > 0xF) {
+            return Err(format!("Invalid keypad key {:X}.", key));
+        }
+        self.keys[index] = keypad_key;
+        Ok(())
+    }
+
+    /// Parses the bindings in `text` over the default mapping. See `GamepadMap` for the format
+    pub fn parse(text: &str) -> Result<GamepadMap, String> {
+        let mut map = GamepadMap::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || format!("Malformed binding at line {}: '{}'", number + 1, line);
+            let (name, key) = line.split_once('=').ok_or_else(invalid)?;
+            let button = BUTTONS
+                .iter()
+                .find(|(_, n)| n.eq_ignore_ascii_case(name.trim()))
+                .map(|&(button, _)| button)
+                .ok_or_else(invalid)?;
+            let key = match key.trim() {
+                "none" => None,
+                key => Some(usize::from_str_radix(key, 16).map_err(|_| invalid())?),
+            };
+            map.bind(button, key).map_err(|_| invalid())?;
+        }
+        Ok(map)
+    }
+
+    /// Reads the mapping file at `path`. See `GamepadMap` for the format
+    pub fn load(path: &str) -> Result<GamepadMap, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to open the gamepad mapping: {}", e))?;
+        GamepadMap::parse(&text)
+    }
+}
+
+impl Default for GamepadMap {
+    fn default() -> Self {
+        GamepadMap::new()
+    }
+}
+
+/// The gamepads connected to the host, polled by the main loop for button events.
+///
+/// **Note:** Only available with the `gamepad` feature.
+pub struct Gamepads {
+    gilrs: Gilrs,
+    /// Directions of the left stick currently pressed, in the order of `STICK_BUTTONS`
+    stick: [bool; 4],
+}
+
+impl Gamepads {
+    /// Starts listening to the gamepads, including the ones connected later
+    /// # Errors
+    /// If the gamepad backend of the platform is unavailable
+    pub fn open() -> Result<Gamepads, String> {
+        let gilrs = Gilrs::new().map_err(|e| format!("Failed to open the gamepads: {}", e))?;
+        Ok(Gamepads {
+            gilrs,
+            stick: [false; 4],
+        })
+    }
+
+    /// Returns the buttons pressed (`true`) and released (`false`) since the last poll.
+    /// Tilting the left stick presses the D-pad buttons
+    pub fn poll(&mut self) -> Vec<(Button, bool)> {
+        let mut changes = Vec::new();
+        while let Some(event) = self.gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) => changes.push((button, true)),
+                EventType::ButtonReleased(button, _) => changes.push((button, false)),
+                EventType::AxisChanged(axis @ (Axis::LeftStickX | Axis::LeftStickY), value, _) => {
+                    // Negative then positive direction of the axis
+                    let first = if axis == Axis::LeftStickX { 0 } else { 2 };
+                    let tilted = [value < -STICK_THRESHOLD, value > STICK_THRESHOLD];
+                    for (direction, &pressed) in tilted.iter().enumerate() {
+                        if self.stick[first + direction] != pressed {
+                            self.stick[first + direction] = pressed;
+                            changes.push((STICK_BUTTONS[first + direction], pressed));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        changes
+    }
+}
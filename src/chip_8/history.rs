@@ -0,0 +1,45 @@
+use std::collections::VecDeque;
+
+/// Ring buffer holding the last instructions executed by the VM, oldest first,
+/// to find out how the VM got into its current state.
+pub struct History {
+    /// Address and opcode of each instruction
+    entries: VecDeque<(u16, u16)>,
+    capacity: usize,
+}
+
+impl History {
+    /// Default amount of instructions kept
+    pub const DEFAULT_CAPACITY: usize = 256;
+
+    pub fn new(capacity: usize) -> History {
+        History {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Records the execution of `opcode` at `address`, dropping the oldest entry when full
+    pub fn record(&mut self, address: u16, opcode: u16) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((address, opcode));
+    }
+
+    /// Returns the address and opcode of the recorded instructions, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &(u16, u16)> {
+        self.entries.iter()
+    }
+
+    /// Removes all recorded instructions
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Default for History {
+    fn default() -> History {
+        History::new(History::DEFAULT_CAPACITY)
+    }
+}
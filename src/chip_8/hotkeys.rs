@@ -0,0 +1,317 @@
+use super::KeyMap;
+use piston::input::Key;
+
+/// An action of the emulator triggered by a host key, as opposed to the keys of the ROM
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HostAction {
+    /// Closes the window. Ignored in kiosk mode
+    Quit,
+    /// Enables or disables the auto-fire of the turbo keys. See `Turbo`
+    ToggleTurbo,
+    /// Copies the disassembly of the current instruction to the clipboard
+    CopyDisassembly,
+    /// Copies a dump of the registers to the clipboard
+    CopyRegisters,
+    /// Copies the display as text to the clipboard
+    CopyDisplay,
+    /// Saves a bug report bundle. See `Chip8::write_bug_report`
+    BugReport,
+    /// Starts the recording of an input macro. See `Macros`
+    RecordMacro,
+    /// Binds the macro being recorded to a slot, or replays the macro bound to it
+    Macro(usize),
+    /// Shows or hides the on-screen keypad, labeled with the host keys of the `KeyMap`
+    ToggleKeypad,
+    /// Saves the state of the VM to the save slot. See `Chip8::save_to_slot`
+    SaveState,
+    /// Restores the state saved in the save slot. See `Chip8::load_from_slot`
+    LoadState,
+    /// Selects the next save slot, up to `Chip8::SAVE_SLOTS`, showing the thumbnail of the
+    /// state it holds. See `Chip8::slot_thumbnail`
+    NextSlot,
+    /// Steps the execution backwards while held down. See `Rewind`
+    Rewind,
+    /// Pauses or resumes the VM. See `Chip8::set_paused`
+    TogglePause,
+    /// Restarts the ROM from its initial state. See `Chip8::reset`
+    Reset,
+    /// Remaps the keypad while held down: the first key pressed selects a keypad key,
+    /// by its current host key, and the next one becomes its new host key.
+    /// See `Chip8::set_config_path` to keep the new map
+    RemapKeys,
+    /// Doubles the speed of the emulation, timers included. See `SpeedControl`
+    SpeedUp,
+    /// Halves the speed of the emulation, timers included. See `SpeedControl`
+    SlowDown,
+    /// Runs the emulation much faster while held down. See `SpeedControl`
+    FastForward,
+    /// Shows or hides the rates of the emulation, the registers and the timers.
+    /// See `DebugOverlay`
+    ToggleOverlay,
+    /// Switches the window between fullscreen and windowed. Ignored in kiosk mode
+    ToggleFullscreen,
+}
+
+impl HostAction {
+    /// All available actions, in the order they are presented to users
+    pub const ALL: [HostAction; 24] = [
+        HostAction::Quit,
+        HostAction::ToggleTurbo,
+        HostAction::CopyDisassembly,
+        HostAction::CopyRegisters,
+        HostAction::CopyDisplay,
+        HostAction::BugReport,
+        HostAction::RecordMacro,
+        HostAction::Macro(0),
+        HostAction::Macro(1),
+        HostAction::Macro(2),
+        HostAction::Macro(3),
+        HostAction::ToggleKeypad,
+        HostAction::SaveState,
+        HostAction::LoadState,
+        HostAction::NextSlot,
+        HostAction::Rewind,
+        HostAction::TogglePause,
+        HostAction::Reset,
+        HostAction::RemapKeys,
+        HostAction::SpeedUp,
+        HostAction::SlowDown,
+        HostAction::FastForward,
+        HostAction::ToggleOverlay,
+        HostAction::ToggleFullscreen,
+    ];
+
+    /// Returns the name used to select the action, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            HostAction::Quit => "quit",
+            HostAction::ToggleTurbo => "turbo",
+            HostAction::CopyDisassembly => "copy-disassembly",
+            HostAction::CopyRegisters => "copy-registers",
+            HostAction::CopyDisplay => "copy-display",
+            HostAction::BugReport => "bug-report",
+            HostAction::RecordMacro => "record-macro",
+            HostAction::Macro(0) => "macro-1",
+            HostAction::Macro(1) => "macro-2",
+            HostAction::Macro(2) => "macro-3",
+            HostAction::Macro(_) => "macro-4",
+            HostAction::ToggleKeypad => "keypad",
+            HostAction::SaveState => "save-state",
+            HostAction::LoadState => "load-state",
+            HostAction::NextSlot => "next-slot",
+            HostAction::Rewind => "rewind",
+            HostAction::TogglePause => "pause",
+            HostAction::Reset => "reset",
+            HostAction::RemapKeys => "remap-keys",
+            HostAction::SpeedUp => "speed-up",
+            HostAction::SlowDown => "slow-down",
+            HostAction::FastForward => "fast-forward",
+            HostAction::ToggleOverlay => "debug-overlay",
+            HostAction::ToggleFullscreen => "fullscreen",
+        }
+    }
+
+    /// Finds an action by its name
+    pub fn from_name(name: &str) -> Option<HostAction> {
+        HostAction::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+
+    /// Returns the position of the action in `ALL`
+    fn index(&self) -> usize {
+        HostAction::ALL
+            .iter()
+            .position(|action| action == self)
+            .unwrap_or_default()
+    }
+}
+
+/// Host keys that can be named in a binding, along with their names.
+/// Keys of the `CHIP-8` keypad are listed too, so they can be rejected with a clear error
+const HOST_KEYS: [(Key, &str); 68] = [
+    (Key::Escape, "Esc"),
+    (Key::Tab, "Tab"),
+    (Key::Space, "Space"),
+    (Key::Backspace, "Backspace"),
+    (Key::Return, "Enter"),
+    (Key::Insert, "Insert"),
+    (Key::Delete, "Delete"),
+    (Key::Home, "Home"),
+    (Key::End, "End"),
+    (Key::PageUp, "PageUp"),
+    (Key::PageDown, "PageDown"),
+    (Key::Pause, "Pause"),
+    (Key::F1, "F1"),
+    (Key::F2, "F2"),
+    (Key::F3, "F3"),
+    (Key::F4, "F4"),
+    (Key::F5, "F5"),
+    (Key::F6, "F6"),
+    (Key::F7, "F7"),
+    (Key::F8, "F8"),
+    (Key::F9, "F9"),
+    (Key::F10, "F10"),
+    (Key::F11, "F11"),
+    (Key::F12, "F12"),
+    (Key::D0, "0"),
+    (Key::D1, "1"),
+    (Key::D2, "2"),
+    (Key::D3, "3"),
+    (Key::D4, "4"),
+    (Key::D5, "5"),
+    (Key::D6, "6"),
+    (Key::D7, "7"),
+    (Key::D8, "8"),
+    (Key::D9, "9"),
+    (Key::A, "A"),
+    (Key::B, "B"),
+    (Key::C, "C"),
+    (Key::D, "D"),
+    (Key::E, "E"),
+    (Key::F, "F"),
+    (Key::G, "G"),
+    (Key::H, "H"),
+    (Key::I, "I"),
+    (Key::J, "J"),
+    (Key::K, "K"),
+    (Key::L, "L"),
+    (Key::M, "M"),
+    (Key::N, "N"),
+    (Key::O, "O"),
+    (Key::P, "P"),
+    (Key::Q, "Q"),
+    (Key::R, "R"),
+    (Key::S, "S"),
+    (Key::T, "T"),
+    (Key::U, "U"),
+    (Key::V, "V"),
+    (Key::W, "W"),
+    (Key::X, "X"),
+    (Key::Y, "Y"),
+    (Key::Z, "Z"),
+    (Key::Minus, "Minus"),
+    (Key::Equals, "Equals"),
+    (Key::Comma, "Comma"),
+    (Key::Period, "Period"),
+    (Key::Slash, "Slash"),
+    (Key::Backslash, "Backslash"),
+    (Key::LeftBracket, "LeftBracket"),
+    (Key::RightBracket, "RightBracket"),
+];
+
+/// Returns the name of a host key, ex: `F5`
+pub fn key_name(key: Key) -> String {
+    match HOST_KEYS.iter().find(|&&(k, _)| k == key) {
+        Some((_, name)) => name.to_string(),
+        None => format!("{:?}", key),
+    }
+}
+
+/// Finds a host key by its name, ignoring case
+pub fn key_from_name(name: &str) -> Option<Key> {
+    HOST_KEYS
+        .iter()
+        .find(|(_, key_name)| key_name.eq_ignore_ascii_case(name))
+        .map(|&(key, _)| key)
+}
+
+/// Table of the host keys bound to each `HostAction`, kept apart from the keys of the
+/// `CHIP-8` keypad so an action never steals a key a ROM needs.
+/// Every action has at most one key, and every key triggers at most one action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hotkeys {
+    /// Key bound to each action, in the order of `HostAction::ALL`
+    keys: [Option<Key>; HostAction::ALL.len()],
+}
+
+impl Hotkeys {
+    /// Creates the table with the default bindings
+    pub fn new() -> Hotkeys {
+        Hotkeys {
+            keys: [
+                Some(Key::Escape),
+                Some(Key::T),
+                Some(Key::F1),
+                Some(Key::F2),
+                Some(Key::Insert),
+                Some(Key::F4),
+                Some(Key::M),
+                Some(Key::F5),
+                Some(Key::F6),
+                Some(Key::F7),
+                Some(Key::F8),
+                Some(Key::F9),
+                Some(Key::F10),
+                Some(Key::F11),
+                Some(Key::PageDown),
+                Some(Key::Backspace),
+                Some(Key::P),
+                Some(Key::F12),
+                Some(Key::K),
+                Some(Key::Equals),
+                Some(Key::Minus),
+                Some(Key::Tab),
+                Some(Key::F3),
+                Some(Key::Return),
+            ],
+        }
+    }
+
+    /// Returns the key bound to `action`, if any
+    pub fn key(&self, action: HostAction) -> Option<Key> {
+        self.keys[action.index()]
+    }
+
+    /// Returns the action bound to `key`, if any
+    pub fn action(&self, key: Key) -> Option<HostAction> {
+        let index = self.keys.iter().position(|&k| k == Some(key))?;
+        Some(HostAction::ALL[index])
+    }
+
+    /// Binds `key` to `action`, replacing its previous key. `None` leaves the action unbound
+    /// # Errors
+    /// If `key` is mapped to the `CHIP-8` keypad by the default `KeyMap`,
+    /// or already bound to another action
+    pub fn bind(&mut self, action: HostAction, key: Option<Key>) -> Result<(), String> {
+        if let Some(key) = key {
+            if KeyMap::new().keypad_key(key).is_some() {
+                return Err(format!("{} is a key of the keypad.", key_name(key)));
+            }
+            if let Some(other) = self.action(key).filter(|&other| other != action) {
+                return Err(format!(
+                    "{} is already bound to {}.",
+                    key_name(key),
+                    other.name()
+                ));
+            }
+        }
+        self.keys[action.index()] = key;
+        Ok(())
+    }
+
+    /// Parses and applies a binding written as `action=key`, ex: `quit=End` or `quit=none`
+    /// # Errors
+    /// If the action or the key are unknown, or if the binding is rejected by `bind`
+    pub fn bind_str(&mut self, binding: &str) -> Result<(), String> {
+        let (action_name, key_text) = binding.split_once('=').ok_or(format!(
+            "Invalid hotkey '{}', expected ACTION=KEY.",
+            binding
+        ))?;
+        let action = HostAction::from_name(action_name)
+            .ok_or(format!("Unknown hotkey action '{}'.", action_name))?;
+        let key = if key_text.eq_ignore_ascii_case("none") {
+            None
+        } else {
+            Some(key_from_name(key_text).ok_or(format!("Unknown key '{}'.", key_text))?)
+        };
+        self.bind(action, key)
+    }
+}
+
+impl Default for Hotkeys {
+    fn default() -> Hotkeys {
+        Hotkeys::new()
+    }
+}
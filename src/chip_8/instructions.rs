@@ -1,18 +1,57 @@
-use super::Chip8;
+use super::{Chip8, Chip8Error, HaltReport, Limit};
+use std::time::Duration;
 
 impl Chip8 {
+    /// Notifies the `LatencyProbe`, if any, that an instruction saw `key` as pressed
+    fn key_observed(&mut self, key: usize) {
+        if let Some(probe) = &mut self.latency_probe {
+            probe.key_observed(key);
+        }
+    }
+
+    /// Notifies the `LatencyProbe` and `ExecutionLimits`, if any, that an instruction
+    /// modified the display
+    fn display_changed(&mut self) {
+        if let Some(probe) = &mut self.latency_probe {
+            probe.display_changed();
+        }
+        if let Some(guard) = &mut self.limits {
+            guard.draw();
+        }
+    }
+
+    /// Skips the next instruction, which is 4 bytes long for the XO-CHIP `F000 NNNN`
+    fn skip_next_instruction(&mut self) {
+        let pc = self.regs.pc as usize;
+        let long = self.profile.has_xo_chip_instructions()
+            && self.main_memory.read(pc) == 0xF0
+            && self.main_memory.read(pc + 1) == 0x00;
+        self.regs.pc = self.regs.pc.wrapping_add(if long { 4 } else { 2 });
+    }
+
     /// **OP Code:** `00E0`
     ///
-    /// Clear the display
+    /// Clear the display: only the selected planes with `Profile::XoChip`
     pub fn cls(&mut self) {
-        self.display.buffer = [false; Chip8::VIDEO_WIDTH * Chip8::VIDEO_HEIGHT];
+        for plane in self.display.selected().collect::<Vec<usize>>() {
+            self.display.plane_mut(plane).fill(false);
+        }
+        self.display.dirty = true;
+        self.display_changed();
     }
 
     /// **OP Code:** `00EE`
     ///
     /// Return from a subroutine
-    pub fn ret(&mut self) {
-        self.regs.pc = self.stack.pop();
+    /// # Errors
+    /// `Chip8Error::StackUnderflow` if there's no subroutine to return from
+    pub fn ret(&mut self) -> Result<(), Chip8Error> {
+        let (pc, opcode) = self.instruction;
+        self.regs.pc = self
+            .stack
+            .pop()
+            .ok_or(Chip8Error::StackUnderflow { pc, opcode })?;
+        Ok(())
     }
 
     /// **OP Code:** `1nnn`
@@ -25,9 +64,19 @@ impl Chip8 {
     /// **OP Code:** `2nnn`
     ///
     /// Call subroutine at `nnn`
-    pub fn call(&mut self, nnn: u16) {
-        self.stack.push(self.regs.pc);
+    /// # Errors
+    /// `Chip8Error::StackOverflow` if the stack is full
+    pub fn call(&mut self, nnn: u16) -> Result<(), Chip8Error> {
+        if !self.stack.push(self.regs.pc) {
+            let (pc, opcode) = self.instruction;
+            return Err(Chip8Error::StackOverflow {
+                pc,
+                opcode,
+                depth: self.stack.depth,
+            });
+        }
         self.regs.pc = nnn;
+        Ok(())
     }
 
     /// **OP Code:** `3xkk`
@@ -35,7 +84,7 @@ impl Chip8 {
     /// Skip next instruction if `v[x]` == `kk`
     pub fn se_vx_byte(&mut self, x: usize, kk: u8) {
         if self.regs.v[x] == kk {
-            self.regs.pc += 2;
+            self.skip_next_instruction();
         }
     }
 
@@ -44,7 +93,7 @@ impl Chip8 {
     /// Skip next instruction if `v[x] != kk`
     pub fn sne_vx_byte(&mut self, x: usize, kk: u8) {
         if self.regs.v[x] != kk {
-            self.regs.pc += 2;
+            self.skip_next_instruction();
         }
     }
 
@@ -53,7 +102,7 @@ impl Chip8 {
     /// Skip next instruction if `v[x] == v[y]`
     pub fn se_vx_vy(&mut self, x: usize, y: usize) {
         if self.regs.v[x] == self.regs.v[y] {
-            self.regs.pc += 2;
+            self.skip_next_instruction();
         }
     }
 
@@ -66,10 +115,9 @@ impl Chip8 {
 
     /// **OP Code:** `7xkk`
     ///
-    /// Set `v[x] = v[x] + kk`
+    /// Set `v[x] = v[x] + kk`, wrapping around without touching `v[0xF]`
     pub fn add_vx_byte(&mut self, x: usize, kk: u8) {
-        let result = (self.regs.v[x] as u16) + (kk as u16);
-        self.regs.v[x] = result as u8;
+        self.regs.v[x] = self.regs.v[x].wrapping_add(kk);
     }
 
     /// **OP Code:** `8xy0`
@@ -102,56 +150,53 @@ impl Chip8 {
 
     /// **OP Code:** `8xy4`
     ///
-    /// Set `v[x] = v[x] + v[y]` and set `v[0xF] = carry`
+    /// Set `v[x] = v[x] + v[y]` and set `v[0xF] = carry`.
+    /// Like every instruction setting a flag, `v[0xF]` is written last, so it holds
+    /// the flag when `x` is `0xF`
     pub fn add_vx_vy(&mut self, x: usize, y: usize) {
-        let sum = (self.regs.v[x] as u16) + (self.regs.v[y] as u16);
-
-        self.regs.v[0xF] = if sum > 255 { 1 } else { 0 };
-        self.regs.v[x] = (sum as u8) & 0xFF;
+        let (sum, carry) = self.regs.v[x].overflowing_add(self.regs.v[y]);
+        self.regs.v[x] = sum;
+        self.regs.v[0xF] = carry as u8;
     }
 
     /// **OP Code:** `8xy5`
     ///
     /// Set `v[x] = v[x] - v[y]` and set `v[0xF] = not borrow`
     pub fn sub_vx_vy(&mut self, x: usize, y: usize) {
-        self.regs.v[0xF] = if self.regs.v[x] > self.regs.v[y] {
-            1
-        } else {
-            0
-        };
-        self.regs.v[x] = self.regs.v[x].wrapping_sub(self.regs.v[y]);
+        let (difference, borrow) = self.regs.v[x].overflowing_sub(self.regs.v[y]);
+        self.regs.v[x] = difference;
+        self.regs.v[0xF] = !borrow as u8;
     }
 
     /// **OP Code:** `8xy6`
     ///
-    /// Set `v[x] = v[x] SHR 1`
+    /// Set `v[x] = v[x] SHR 1`, or `v[x] = v[y] SHR 1` with the shift quirk
     ///
-    /// Set `v[0xF] = least-significant bit of v[x]`
-    pub fn shr_vx(&mut self, x: usize) {
-        self.regs.v[0xF] = self.regs.v[x] & 0x1;
-        self.regs.v[x] >>= 1;
+    /// Set `v[0xF] = least-significant bit of the shifted value`
+    pub fn shr_vx(&mut self, x: usize, y: usize) {
+        let value = self.regs.v[if self.quirks.shift_vy { y } else { x }];
+        self.regs.v[x] = value >> 1;
+        self.regs.v[0xF] = value & 0x1;
     }
 
     /// **OP Code:** `8xy7`
     ///
     /// Set `v[x] = v[y] - v[x]` and set `v[0xF] = not borrow`
     pub fn subn_vx_vy(&mut self, x: usize, y: usize) {
-        self.regs.v[0xF] = if self.regs.v[y] > self.regs.v[x] {
-            1
-        } else {
-            0
-        };
-        self.regs.v[x] = self.regs.v[y] - self.regs.v[x];
+        let (difference, borrow) = self.regs.v[y].overflowing_sub(self.regs.v[x]);
+        self.regs.v[x] = difference;
+        self.regs.v[0xF] = !borrow as u8;
     }
 
     /// **OP Code:** `8xyE`
     ///
-    /// Set `v[x] = v[x] SHL 1`
+    /// Set `v[x] = v[x] SHL 1`, or `v[x] = v[y] SHL 1` with the shift quirk
     ///
-    /// Set `v[0xF] = most-significant bit of v[x]`
-    pub fn shl_vx(&mut self, x: usize) {
-        self.regs.v[0xF] = (self.regs.v[x] & 0x80) >> 7;
-        self.regs.v[x] <<= 1;
+    /// Set `v[0xF] = most-significant bit of the shifted value`
+    pub fn shl_vx(&mut self, x: usize, y: usize) {
+        let value = self.regs.v[if self.quirks.shift_vy { y } else { x }];
+        self.regs.v[x] = value << 1;
+        self.regs.v[0xF] = (value & 0x80) >> 7;
     }
 
     /// **OP Code:** `9xy0`
@@ -159,7 +204,7 @@ impl Chip8 {
     /// Skip next instruction if `v[x] != v[y]`
     pub fn sne_vx_vy(&mut self, x: usize, y: usize) {
         if self.regs.v[x] != self.regs.v[y] {
-            self.regs.pc += 2;
+            self.skip_next_instruction();
         }
     }
 
@@ -172,16 +217,22 @@ impl Chip8 {
 
     /// **OP Code:** `Bnnn`
     ///
-    /// Jump to address `v[0] + nnn`
+    /// Jump to address `v[0] + nnn`, or `v[x] + nnn` with the jump quirk,
+    /// `x` being the highest digit of `nnn`
     pub fn jp_v0_addr(&mut self, nnn: u16) {
-        self.regs.pc = (self.regs.v[0] as u16) + nnn;
+        let x = if self.quirks.jump_vx {
+            (nnn >> 8) as usize
+        } else {
+            0
+        };
+        self.regs.pc = nnn.wrapping_add(self.regs.v[x] as u16);
     }
 
     /// **OP Code:** `Cxkk`
     ///
-    /// Set `v[x] = random byte AND kk`
+    /// Set `v[x] = random byte AND kk`. See `Chip8::set_rng_seed`
     pub fn rnd_vx_byte(&mut self, x: usize, kk: u8) {
-        let rand: u8 = rand::random();
+        let rand = self.rng.next_u8();
 
         self.regs.v[x] = rand & kk;
     }
@@ -193,63 +244,95 @@ impl Chip8 {
     /// coordinates `(v[x], v[y])`. Sprites are `XORed` onto the existing screen.
     /// Set `v[0xF] = any pixel was erased`
     ///
-    /// **Note:** If the sprite is positioned so part of it is outside
-    /// the coordinates of the display, it wraps around to the opposite side
-    pub fn drw_vx_vy_n(&mut self, x: usize, y: usize, n: usize) {
+    /// With `Profile::XoChip` the sprite is drawn on every selected plane, each one
+    /// reading its own `n` bytes: the first plane from `I`, the second one right after.
+    ///
+    /// Profiles with the SUPER-CHIP instructions draw a 16x16 sprite when `n = 0`, in
+    /// both resolutions: 32 bytes per plane, 2 for each row.
+    ///
+    /// **Note:** The position of the sprite wraps around the display, but the pixels of
+    /// a sprite beyond its right and bottom edges are clipped, unless
+    /// `Quirks::wrap_sprites` wraps them around to the opposite side
+    /// # Errors
+    /// `Chip8Error::MemoryOutOfBounds` if the sprite lies beyond the memory
+    pub fn drw_vx_vy_n(&mut self, x: usize, y: usize, n: usize) -> Result<(), Chip8Error> {
+        let (rows, row_bytes) = match n == 0 && self.profile.has_schip_instructions() {
+            true => (16, 2),
+            false => (n, 1),
+        };
+        let sprite_size = rows * row_bytes;
+        self.check_memory(
+            self.regs.i as usize,
+            sprite_size * self.display.selected().count(),
+        )?;
         self.regs.v[0xF] = 0;
-
-        let x_pos: usize = (self.regs.v[x] as usize) % Chip8::VIDEO_WIDTH;
-        let y_pos: usize = (self.regs.v[y] as usize) % Chip8::VIDEO_HEIGHT;
-
-        for row in 0..n {
-            let sprite_byte = self.main_memory[(self.regs.i as usize) + row];
-            for col in 0..8 {
-                let sprite_pixel = sprite_byte & (0x80 >> col);
-                let screen_pixel =
-                    &mut self.display.buffer[(y_pos + row) * Chip8::VIDEO_WIDTH + (x_pos + col)];
-
-                if sprite_pixel != 0 {
-                    if *screen_pixel {
-                        self.regs.v[0xF] = 1;
+        self.display_changed();
+
+        let (width, height) = (self.display.width(), self.display.height());
+        let x_pos: usize = (self.regs.v[x] as usize) % width;
+        let y_pos: usize = (self.regs.v[y] as usize) % height;
+        let cols = 8 * row_bytes;
+
+        let planes: Vec<usize> = self.display.selected().collect();
+        for (index, plane) in planes.into_iter().enumerate() {
+            let sprite_address = (self.regs.i as usize) + index * sprite_size;
+            for row in 0..rows {
+                let mut sprite_row: u16 = 0;
+                for byte in 0..row_bytes {
+                    let address = sprite_address + row * row_bytes + byte;
+                    sprite_row = sprite_row << 8 | self.read(address) as u16;
+                }
+                for col in 0..cols {
+                    let (mut pixel_x, mut pixel_y) = (x_pos + col, y_pos + row);
+                    if pixel_x >= width || pixel_y >= height {
+                        if !self.quirks.wrap_sprites {
+                            continue;
+                        }
+                        pixel_x %= width;
+                        pixel_y %= height;
+                    }
+                    let sprite_pixel = sprite_row & (1 << (cols - 1 - col));
+                    let screen_pixel =
+                        &mut self.display.plane_mut(plane)[pixel_y * width + pixel_x];
+
+                    // Lit bits of the sprite flip the pixel, erasing it if it was on
+                    if sprite_pixel != 0 {
+                        if *screen_pixel {
+                            self.regs.v[0xF] = 1;
+                        }
+                        *screen_pixel ^= true;
+                        self.display.dirty = true;
                     }
-
-                    *screen_pixel |= true;
                 }
             }
         }
-
-        // ? Old implementation: Didn't work
-        // for byte in 0..n {
-        //     let row = (y + byte) % Chip8::VIDEO_HEIGHT;
-        //     for bit in 0..8 {
-        //         let col = (x + bit) % Chip8::VIDEO_WIDTH;
-        //         let color = (self.main_memory[self.regs.i as usize + byte] >> (7 - bit)) & 1;
-        //         let existing_color = self.display.buffer[row][col];
-        //         self.regs.v[0xF] |= color & (existing_color as u8);
-        //         self.display.buffer[y][x] ^= color != 0;
-        //     }
-        // }
+        Ok(())
     }
 
     /// **OP Code:** `Ex9E`
     ///
-    /// Skip next instruction if the key with the value of `v[x]` is pressed
+    /// Skip next instruction if the key with the value of `v[x]` is pressed.
+    /// Only the lowest digit of `v[x]` selects the key, as the original COSMAC VIP
     pub fn skip_vx(&mut self, x: usize) {
-        let key = self.regs.v[x] as usize;
+        let key = (self.regs.v[x] & 0xF) as usize;
 
-        if self.input.key_status[key] {
-            self.regs.pc += 2;
+        if self.input.is_pressed(key) {
+            self.key_observed(key);
+            self.skip_next_instruction();
         }
     }
 
     /// **OP Code:** `ExA1`
     ///
-    /// Skip next instruction if the key with the value of `v[x]` is pressed
+    /// Skip next instruction if the key with the value of `v[x]` is not pressed.
+    /// Only the lowest digit of `v[x]` selects the key, as the original COSMAC VIP
     pub fn skip_n_vx(&mut self, x: usize) {
-        let key = self.regs.v[x] as usize;
+        let key = (self.regs.v[x] & 0xF) as usize;
 
-        if !self.input.key_status[key] {
-            self.regs.pc += 2;
+        if !self.input.is_pressed(key) {
+            self.skip_next_instruction();
+        } else {
+            self.key_observed(key);
         }
     }
 
@@ -262,20 +345,24 @@ impl Chip8 {
 
     /// **OP Code:** `Fx0A`
     ///
-    /// Wait for a key press and store the value of the key in `v[x]`
+    /// Wait for a key press and store the value of the key in `v[x]`.
+    /// With `Quirks::wait_release` it waits for a key to be released instead
     pub fn ld_vx_k(&mut self, x: usize) {
-        match self
-            .input
-            .key_status
-            .iter()
-            .enumerate()
-            .find(|(_, &is_active)| is_active)
-        {
-            Some((i, _)) => {
+        let key = match self.quirks.wait_release {
+            true => self.input.take_released(),
+            false => self
+                .input
+                .key_status
+                .iter()
+                .position(|&is_active| is_active),
+        };
+        match key {
+            Some(i) => {
+                self.key_observed(i);
                 self.regs.v[x] = i as u8;
             }
             None => {
-                self.regs.pc -= 2;
+                self.regs.pc = self.regs.pc.wrapping_sub(2);
             }
         };
     }
@@ -296,9 +383,9 @@ impl Chip8 {
 
     /// **OP Code:** `Fx1E`
     ///
-    /// Set `I = I + v[x]`
+    /// Set `I = I + v[x]`, wrapping around at `0xFFFF` without touching `v[0xF]`
     pub fn add_i_vx(&mut self, x: usize) {
-        self.regs.i += self.regs.v[x] as u16;
+        self.regs.i = self.regs.i.wrapping_add(self.regs.v[x] as u16);
     }
 
     /// **OP Code:** `Fx29`
@@ -310,30 +397,240 @@ impl Chip8 {
         self.regs.i = (Chip8::INITIAL_FONTS_MEMORY_ADDRESS as u16) + (5 * digit);
     }
 
+    /// **OP Code:** `Fx30` (SUPER-CHIP)
+    ///
+    /// Set `I = location of the 8x10 sprite for digit v[x]`, from the big font
+    pub fn ld_hf_vx(&mut self, x: usize) {
+        let digit = self.regs.v[x] as u16;
+
+        self.regs.i = (Chip8::BIG_FONTS_MEMORY_ADDRESS as u16) + (10 * digit);
+    }
+
     /// **OP Code:** `Fx33`
     ///
     /// Store BCD representation of `v[x]` in memory locations `[I, I + 1, I + 2]`
-    pub fn ld_b_vx(&mut self, x: usize) {
+    /// # Errors
+    /// `Chip8Error::MemoryOutOfBounds` if the locations lie beyond the memory, or
+    /// `Chip8Error::ProtectedWrite` if they are protected. See `WriteProtection`
+    pub fn ld_b_vx(&mut self, x: usize) -> Result<(), Chip8Error> {
+        self.check_writes(self.regs.i as usize, 3)?;
         let value = self.regs.v[x];
 
-        self.main_memory[(self.regs.i as usize) + 2] = (value) % 10;
-        self.main_memory[(self.regs.i as usize) + 1] = (value / 10) % 10;
-        self.main_memory[self.regs.i as usize] = (value / 100) % 10;
+        self.write((self.regs.i as usize) + 2, (value) % 10);
+        self.write((self.regs.i as usize) + 1, (value / 10) % 10);
+        self.write(self.regs.i as usize, (value / 100) % 10);
+        Ok(())
     }
 
     /// **OP Code:** `Fx55`
     ///
-    /// Store registers `v[0..X]` in memory starting at location `I`
-    pub fn ld_i_vx(&mut self, x: usize) {
-        let memory_range = (self.regs.i as usize)..=(self.regs.i as usize) + x;
-        self.main_memory[memory_range].copy_from_slice(&self.regs.v[0..=x]);
+    /// Store registers `v[0..X]` in memory starting at location `I`.
+    /// With the load/store quirk, set `I = I + x + 1` afterwards
+    /// # Errors
+    /// `Chip8Error::MemoryOutOfBounds` if the locations lie beyond the memory, or
+    /// `Chip8Error::ProtectedWrite` if they are protected. See `WriteProtection`
+    pub fn ld_i_vx(&mut self, x: usize) -> Result<(), Chip8Error> {
+        self.check_writes(self.regs.i as usize, x + 1)?;
+        for offset in 0..=x {
+            self.write((self.regs.i as usize) + offset, self.regs.v[offset]);
+        }
+        if self.quirks.increment_i {
+            self.regs.i = self.regs.i.wrapping_add(x as u16 + 1);
+        }
+        Ok(())
     }
 
     /// **OP Code:** `Fx65`
     ///
-    /// Read registers `v[0..X]` from memory starting at location `I`
-    pub fn ld_vx_i(&mut self, x: usize) {
-        let memory_range = (self.regs.i as usize)..=(self.regs.i as usize) + x;
-        self.regs.v[0..=x].copy_from_slice(&self.main_memory[memory_range]);
+    /// Read registers `v[0..X]` from memory starting at location `I`.
+    /// With the load/store quirk, set `I = I + x + 1` afterwards
+    /// # Errors
+    /// `Chip8Error::MemoryOutOfBounds` if the locations lie beyond the memory
+    pub fn ld_vx_i(&mut self, x: usize) -> Result<(), Chip8Error> {
+        self.check_memory(self.regs.i as usize, x + 1)?;
+        for offset in 0..=x {
+            self.regs.v[offset] = self.read((self.regs.i as usize) + offset);
+        }
+        if self.quirks.increment_i {
+            self.regs.i = self.regs.i.wrapping_add(x as u16 + 1);
+        }
+        Ok(())
+    }
+
+    /// **OP Code:** `5xy2` (XO-CHIP)
+    ///
+    /// Store registers `v[x..y]` in memory starting at location `I`,
+    /// in descending order if `x > y`
+    /// # Errors
+    /// `Chip8Error::MemoryOutOfBounds` if the locations lie beyond the memory, or
+    /// `Chip8Error::ProtectedWrite` if they are protected. See `WriteProtection`
+    pub fn save_vx_vy(&mut self, x: usize, y: usize) -> Result<(), Chip8Error> {
+        self.check_writes(self.regs.i as usize, register_range(x, y).count())?;
+        for (offset, register) in register_range(x, y).enumerate() {
+            self.write((self.regs.i as usize) + offset, self.regs.v[register]);
+        }
+        Ok(())
+    }
+
+    /// **OP Code:** `5xy3` (XO-CHIP)
+    ///
+    /// Read registers `v[x..y]` from memory starting at location `I`,
+    /// in descending order if `x > y`
+    /// # Errors
+    /// `Chip8Error::MemoryOutOfBounds` if the locations lie beyond the memory
+    pub fn load_vx_vy(&mut self, x: usize, y: usize) -> Result<(), Chip8Error> {
+        self.check_memory(self.regs.i as usize, register_range(x, y).count())?;
+        for (offset, register) in register_range(x, y).enumerate() {
+            self.regs.v[register] = self.read((self.regs.i as usize) + offset);
+        }
+        Ok(())
+    }
+
+    /// **OP Code:** `F000 NNNN` (XO-CHIP)
+    ///
+    /// Set `I = NNNN`, the 16-bit address following the instruction
+    /// # Errors
+    /// `Chip8Error::MemoryOutOfBounds` if the address lies beyond the memory
+    pub fn ld_i_long(&mut self) -> Result<(), Chip8Error> {
+        let pc = self.regs.pc as usize;
+        self.check_memory(pc, 2)?;
+        self.regs.i = ((self.read(pc) as u16) << 8) | self.read(pc + 1) as u16;
+        self.regs.pc = self.regs.pc.wrapping_add(2);
+        Ok(())
+    }
+
+    /// **OP Code:** `Fn01` (XO-CHIP)
+    ///
+    /// Select the display planes `n` affected by `Dxyn` and `00E0`, as a bit mask
+    pub fn plane(&mut self, n: usize) {
+        self.display.selected_planes = (n & 0x3) as u8;
+    }
+
+    /// **OP Code:** `F002` (XO-CHIP)
+    ///
+    /// Load the 16 bytes stored at `I` into the audio pattern buffer
+    /// # Errors
+    /// `Chip8Error::MemoryOutOfBounds` if the pattern lies beyond the memory
+    pub fn audio(&mut self) -> Result<(), Chip8Error> {
+        self.check_memory(self.regs.i as usize, self.audio_pattern.len())?;
+        for offset in 0..self.audio_pattern.len() {
+            self.audio_pattern[offset] = self.read((self.regs.i as usize) + offset);
+        }
+        Ok(())
+    }
+
+    /// **OP Code:** `Fx3A` (XO-CHIP)
+    ///
+    /// Set the pitch of the audio pattern = `Vx`. See `AudioPattern::rate`
+    pub fn pitch_vx(&mut self, x: usize) {
+        self.pitch = self.regs.v[x];
+    }
+
+    /// **OP Code:** `Fx75` (SUPER-CHIP)
+    ///
+    /// Store registers `V0` through `Vx` in the RPL user flags, and persist them to the
+    /// save storage. Registers beyond the flags of the `Profile` are ignored
+    pub fn ld_r_vx(&mut self, x: usize) {
+        let count = (x + 1).min(self.profile.rpl_flags());
+        self.rpl_flags[..count].copy_from_slice(&self.regs.v[..count]);
+        self.store_rpl_flags();
+    }
+
+    /// **OP Code:** `Fx85` (SUPER-CHIP)
+    ///
+    /// Read registers `V0` through `Vx` from the RPL user flags. Registers beyond the
+    /// flags of the `Profile` are left untouched
+    pub fn ld_vx_r(&mut self, x: usize) {
+        let count = (x + 1).min(self.profile.rpl_flags());
+        self.regs.v[..count].copy_from_slice(&self.rpl_flags[..count]);
+    }
+
+    /// **OP Code:** `00Cn` (SUPER-CHIP)
+    ///
+    /// Scroll the selected planes down by `n` pixels of the current resolution
+    pub fn scroll_down(&mut self, n: usize) {
+        self.scroll(0, n as isize);
+    }
+
+    /// **OP Code:** `00Dn` (XO-CHIP)
+    ///
+    /// Scroll the selected planes up by `n` pixels of the current resolution
+    pub fn scroll_up(&mut self, n: usize) {
+        self.scroll(0, -(n as isize));
+    }
+
+    /// **OP Code:** `00FB` (SUPER-CHIP)
+    ///
+    /// Scroll the selected planes right by 4 pixels of the current resolution
+    pub fn scroll_right(&mut self) {
+        self.scroll(4, 0);
+    }
+
+    /// **OP Code:** `00FC` (SUPER-CHIP)
+    ///
+    /// Scroll the selected planes left by 4 pixels of the current resolution
+    pub fn scroll_left(&mut self) {
+        self.scroll(-4, 0);
+    }
+
+    /// **OP Code:** `00FD` (SUPER-CHIP)
+    ///
+    /// Exit the interpreter: the VM halts like when it reaches a limit, with `Limit::Exit`.
+    /// See `Chip8::halt_report`
+    ///
+    /// **Note:** Without `ExecutionLimits` the host time isn't tracked, the report then
+    /// tells no time elapsed
+    pub fn exit(&mut self) {
+        self.halted = Some(HaltReport {
+            limit: Limit::Exit,
+            cycles: self.clock.instructions + 1,
+            elapsed: Duration::ZERO,
+            pc: self.regs.pc,
+        });
+    }
+
+    /// **OP Code:** `00FE` (SUPER-CHIP)
+    ///
+    /// Switch the display to the 64x32 low resolution, clearing every plane
+    pub fn low(&mut self) {
+        self.display.set_hires(false);
+        self.display_changed();
+    }
+
+    /// **OP Code:** `00FF` (SUPER-CHIP)
+    ///
+    /// Switch the display to the 128x64 high resolution, clearing every plane
+    pub fn high(&mut self) {
+        self.display.set_hires(true);
+        self.display_changed();
+    }
+
+    /// Moves the pixels of the selected planes by `dx` columns and `dy` rows. Pixels moved
+    /// beyond the edges are lost, and blank ones fill the pixels left behind
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let (width, height) = (self.display.width(), self.display.height());
+        for plane in self.display.selected().collect::<Vec<usize>>() {
+            let pixels = self.display.plane_mut(plane);
+            let before = pixels.to_vec();
+            for (pos, pixel) in pixels.iter_mut().enumerate() {
+                let from_x = (pos % width) as isize - dx;
+                let from_y = (pos / width) as isize - dy;
+                let inside =
+                    (0..width as isize).contains(&from_x) && (0..height as isize).contains(&from_y);
+                *pixel = inside && before[from_y as usize * width + from_x as usize];
+            }
+        }
+        self.display.dirty = true;
+        self.display_changed();
+    }
+}
+
+/// Returns the indexes of the registers from `v[x]` to `v[y]`, both included,
+/// in descending order if `x > y`
+fn register_range(x: usize, y: usize) -> Box<dyn Iterator<Item = usize>> {
+    if x <= y {
+        Box::new(x..=y)
+    } else {
+        Box::new((y..=x).rev())
     }
 }
@@ -0,0 +1,138 @@
+use super::hotkeys::{key_from_name, key_name};
+use piston::input::Key;
+
+/// Maps host keys to the 16 keys of the `CHIP-8` keypad, from `0x0` to `0xF`.
+/// By default it uses the conventional layout on the left side of a QWERTY keyboard:
+/// ```text
+/// Keypad      Keyboard
+/// 1 2 3 C     1 2 3 4
+/// 4 5 6 D     Q W E R
+/// 7 8 9 E     A S D F
+/// A 0 B F     Z X C V
+/// ```
+/// Every keypad key has exactly one host key, and every host key maps to at most one keypad key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyMap {
+    /// Host key of each keypad key, indexed by keypad key
+    keys: [Key; 16],
+}
+
+impl KeyMap {
+    /// Keys of the `CHIP-8` keypad as laid out on the original hardware, row by row
+    pub const LAYOUT: [[usize; 4]; 4] = [
+        [0x1, 0x2, 0x3, 0xC],
+        [0x4, 0x5, 0x6, 0xD],
+        [0x7, 0x8, 0x9, 0xE],
+        [0xA, 0x0, 0xB, 0xF],
+    ];
+
+    /// Returns the label of `keypad_key` showing its host key, ex: `C 4`.
+    /// Host keys are named after the keys reported by the system, so the labels follow
+    /// the active keyboard layout
+    /// # Panics
+    /// If `keypad_key` is above `0xF`
+    pub fn label(&self, keypad_key: usize) -> String {
+        format!("{:X} {}", keypad_key, key_name(self.host_key(keypad_key)))
+    }
+
+    /// Creates the map with the conventional layout
+    pub fn new() -> KeyMap {
+        KeyMap {
+            keys: [
+                Key::X,  // 0
+                Key::D1, // 1
+                Key::D2, // 2
+                Key::D3, // 3
+                Key::Q,  // 4
+                Key::W,  // 5
+                Key::E,  // 6
+                Key::A,  // 7
+                Key::S,  // 8
+                Key::D,  // 9
+                Key::Z,  // A
+                Key::C,  // B
+                Key::D4, // C
+                Key::R,  // D
+                Key::F,  // E
+                Key::V,  // F
+            ],
+        }
+    }
+
+    /// Returns the keypad key mapped to the host `key`, if any
+    pub fn keypad_key(&self, key: Key) -> Option<usize> {
+        self.keys.iter().position(|&k| k == key)
+    }
+
+    /// Returns the host key mapped to `keypad_key`
+    /// # Panics
+    /// If `keypad_key` is above `0xF`
+    pub fn host_key(&self, keypad_key: usize) -> Key {
+        self.keys[keypad_key]
+    }
+
+    /// Maps the host `key` to `keypad_key`, replacing its previous host key
+    /// # Errors
+    /// If `keypad_key` is above `0xF`, or if `key` is already mapped to another keypad key
+    pub fn bind(&mut self, keypad_key: usize, key: Key) -> Result<(), String> {
+        if keypad_key >= self.keys.len() {
+            return Err(format!("Invalid keypad key {:X}.", keypad_key));
+        }
+        if let Some(other) = self.keypad_key(key).filter(|&other| other != keypad_key) {
+            return Err(format!(
+                "{} is already mapped to the keypad key {:X}.",
+                key_name(key),
+                other
+            ));
+        }
+        self.keys[keypad_key] = key;
+        Ok(())
+    }
+
+    /// Parses and applies a binding written as `keypad=key`, ex: `5=Space`, with the keypad
+    /// key in hexadecimal. If the host key was mapped to another keypad key, the two keypad
+    /// keys exchange their host keys, so bindings can be given in any order
+    /// # Errors
+    /// If the keypad key or the host key are unknown
+    pub fn bind_str(&mut self, binding: &str) -> Result<(), String> {
+        let (keypad_text, key_text) = binding.split_once('=').ok_or(format!(
+            "Invalid key binding '{}', expected KEYPAD=KEY.",
+            binding
+        ))?;
+        let keypad_key = usize::from_str_radix(keypad_text, 16)
+            .ok()
+            .filter(|&k| k < self.keys.len())
+            .ok_or(format!("Invalid keypad key '{}'.", keypad_text))?;
+        let key = key_from_name(key_text).ok_or(format!("Unknown key '{}'.", key_text))?;
+        self.exchange(keypad_key, key);
+        Ok(())
+    }
+
+    /// Maps the host `key` to `keypad_key`. If the host key was mapped to another keypad
+    /// key, the two keypad keys exchange their host keys
+    /// # Panics
+    /// If `keypad_key` is above `0xF`
+    pub fn exchange(&mut self, keypad_key: usize, key: Key) {
+        if let Some(other) = self.keypad_key(key) {
+            self.keys[other] = self.keys[keypad_key];
+        }
+        self.keys[keypad_key] = key;
+    }
+
+    /// Returns the name of the host key of every keypad key mapped differently from the
+    /// conventional layout, ex: `(0x5, "Space")`. Applied in order with `bind_str`,
+    /// they turn a new map into this one
+    pub fn bindings(&self) -> Vec<(usize, String)> {
+        let default = KeyMap::new();
+        (0..self.keys.len())
+            .filter(|&keypad_key| self.keys[keypad_key] != default.keys[keypad_key])
+            .map(|keypad_key| (keypad_key, key_name(self.keys[keypad_key])))
+            .collect()
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        KeyMap::new()
+    }
+}
@@ -0,0 +1,70 @@
+use super::{Chip8, DebugHook, Playlist};
+use std::time::{Duration, Instant};
+
+/// Keypad keys that restart the current ROM when held down at the same time:
+/// the four corners of the keypad (`1`, `C`, `A` and `F`).
+pub const KIOSK_RESET_COMBO: [usize; 4] = [0x1, 0xC, 0xA, 0xF];
+
+/// A `DebugHook` for unattended setups such as arcade cabinets or exhibits.
+/// It runs the VM while cycling through a `Playlist` whenever nobody has
+/// touched the keypad for a while, and restarts the current ROM when the
+/// `KIOSK_RESET_COMBO` is held down.
+///
+/// Entries switch after their duration without keypad activity, or after the idle
+/// timeout if they don't set one. Demo inputs stop as soon as a player presses a key.
+pub struct Kiosk {
+    playlist: Playlist,
+    idle_timeout: Duration,
+    last_activity: Instant,
+    last_keys: [bool; 16],
+}
+
+impl Kiosk {
+    /// Default time without keypad activity before switching to the next ROM
+    pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+    /// Creates a `Kiosk` cycling through the entries of `playlist`.
+    /// The playlist must already be started. See `Playlist::start`
+    pub fn new(playlist: Playlist, idle_timeout: Duration) -> Kiosk {
+        Kiosk {
+            playlist,
+            idle_timeout,
+            last_activity: Instant::now(),
+            last_keys: [false; 16],
+        }
+    }
+}
+
+impl DebugHook for Kiosk {
+    fn update(&mut self, vm: &mut Chip8) {
+        // Keys changed since the end of the last update were touched by a player,
+        // the ones changed by demo inputs are already accounted for in `last_keys`
+        let keys = vm.input.key_status;
+        let idle_timeout = self
+            .playlist
+            .current()
+            .duration
+            .unwrap_or(self.idle_timeout);
+        if keys != self.last_keys {
+            self.last_activity = Instant::now();
+            self.playlist.stop_demo();
+
+            let combo_held = |keys: &[bool; 16]| KIOSK_RESET_COMBO.iter().all(|&key| keys[key]);
+            if combo_held(&keys) && !combo_held(&self.last_keys) {
+                self.playlist.restart_current(vm);
+            }
+        } else if self.playlist.entries().len() > 1
+            && self.last_activity.elapsed() >= idle_timeout
+            && vm.virtual_time() >= idle_timeout
+        {
+            self.playlist.next(vm);
+        }
+
+        self.playlist.run(vm);
+        self.last_keys = vm.input.key_status;
+    }
+
+    fn should_exit(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,103 @@
+use std::time::{Duration, Instant};
+
+/// Diagnostic tool measuring the input latency of the emulator.
+/// For every host key press it measures the delay until:
+/// * **Observed**: An instruction reading the keypad (`Ex9E`, `ExA1` or `Fx0A`)
+///   sees the key as pressed.
+/// * **Presented**: The first display change after the observation is rendered on screen.
+///
+/// Only the first press of a key is measured until the VM observes it,
+/// so holding a key doesn't produce bogus samples.
+pub struct LatencyProbe {
+    /// Host timestamps of the key presses not yet observed by the VM
+    pending_presses: [Option<Instant>; 16],
+    /// Timestamp of the press whose observation is waiting for a display change
+    awaiting_display: Option<Instant>,
+    /// Timestamp of the press whose display change is waiting to be presented
+    awaiting_present: Option<Instant>,
+    observed: Vec<Duration>,
+    presented: Vec<Duration>,
+}
+
+/// Returns the `percentile` (`0` to `100`) of the sorted `samples` using the nearest-rank method
+fn percentile(samples: &[Duration], percentile: usize) -> Duration {
+    let rank = (percentile * samples.len()).div_ceil(100).max(1);
+    samples[rank - 1]
+}
+
+/// Formats a summary of `samples`: count, percentiles and maximum
+fn summary(name: &str, samples: &[Duration]) -> String {
+    if samples.is_empty() {
+        return format!("{:<10} no samples", name);
+    }
+
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+    format!(
+        "{:<10} n={:<5} p50={:>8.2}ms p90={:>8.2}ms p99={:>8.2}ms max={:>8.2}ms",
+        name,
+        sorted.len(),
+        ms(percentile(&sorted, 50)),
+        ms(percentile(&sorted, 90)),
+        ms(percentile(&sorted, 99)),
+        ms(*sorted.last().unwrap())
+    )
+}
+
+impl LatencyProbe {
+    pub fn new() -> LatencyProbe {
+        LatencyProbe {
+            pending_presses: [None; 16],
+            awaiting_display: None,
+            awaiting_present: None,
+            observed: Vec::new(),
+            presented: Vec::new(),
+        }
+    }
+
+    /// Records the host timestamp of a key press
+    pub fn key_pressed(&mut self, key: usize) {
+        if self.pending_presses[key].is_none() {
+            self.pending_presses[key] = Some(Instant::now());
+        }
+    }
+
+    /// Called when an instruction sees `key` as pressed
+    pub fn key_observed(&mut self, key: usize) {
+        if let Some(pressed_at) = self.pending_presses[key].take() {
+            self.observed.push(pressed_at.elapsed());
+            self.awaiting_display = Some(pressed_at);
+        }
+    }
+
+    /// Called when an instruction modifies the display
+    pub fn display_changed(&mut self) {
+        if let Some(pressed_at) = self.awaiting_display.take() {
+            self.awaiting_present = Some(pressed_at);
+        }
+    }
+
+    /// Called once a frame was presented on screen
+    pub fn frame_presented(&mut self) {
+        if let Some(pressed_at) = self.awaiting_present.take() {
+            self.presented.push(pressed_at.elapsed());
+        }
+    }
+
+    /// Returns a human readable report of all measurements
+    pub fn report(&self) -> String {
+        format!(
+            "Input latency (from host key press):\n  {}\n  {}",
+            summary("Observed", &self.observed),
+            summary("Presented", &self.presented)
+        )
+    }
+}
+
+impl Default for LatencyProbe {
+    fn default() -> LatencyProbe {
+        LatencyProbe::new()
+    }
+}
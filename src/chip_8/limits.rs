@@ -0,0 +1,128 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Hard limits on the execution of a VM, so untrusted ROMs can be run safely,
+/// ex: by web playgrounds or graders. Once any limit is exceeded the VM halts:
+/// it stops executing instructions and provides a `HaltReport`.
+///
+/// All limits are disabled (`None`) by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    /// Maximum amount of cycles executed: the VM halts right after the last one
+    pub max_cycles: Option<u64>,
+    /// Maximum host time elapsed since the first cycle
+    pub max_wall_time: Option<Duration>,
+    /// Maximum amount of instructions modifying the display (`00E0`, `Dxyn` and the
+    /// SUPER-CHIP scrolls and resolution switches) between two ticks of the timers
+    pub max_draws_per_frame: Option<u32>,
+}
+
+/// The limit that halted a VM
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Cycles,
+    WallTime,
+    DrawsPerFrame,
+    /// The ROM exited on its own, with the SUPER-CHIP `00FD`
+    Exit,
+}
+
+/// Describes why and when a VM halted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HaltReport {
+    pub limit: Limit,
+    /// Amount of cycles executed, including the one reaching the limit
+    pub cycles: u64,
+    /// Host time elapsed since the first cycle
+    pub elapsed: Duration,
+    /// Address of the next instruction that would have been executed
+    pub pc: u16,
+}
+
+impl fmt::Display for HaltReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = match self.limit {
+            Limit::Cycles => "maximum cycles",
+            Limit::WallTime => "maximum wall time",
+            Limit::DrawsPerFrame => "maximum draws per frame",
+            Limit::Exit => "exit instruction",
+        };
+        write!(
+            f,
+            "VM halted: {} reached after {} cycles ({:.3}s), PC at 0x{:03X}",
+            limit,
+            self.cycles,
+            self.elapsed.as_secs_f64(),
+            self.pc
+        )
+    }
+}
+
+/// Keeps track of the execution of a VM, checking it against its `ExecutionLimits`
+pub struct LimitGuard {
+    limits: ExecutionLimits,
+    started_at: Option<Instant>,
+    cycles: u64,
+    draws: u32,
+}
+
+impl LimitGuard {
+    pub fn new(limits: ExecutionLimits) -> LimitGuard {
+        LimitGuard {
+            limits,
+            started_at: None,
+            cycles: 0,
+            draws: 0,
+        }
+    }
+
+    /// Called when an instruction modifies the display
+    pub fn draw(&mut self) {
+        self.draws += 1;
+    }
+
+    /// Called when the timers tick, starting a new frame
+    pub fn frame(&mut self) {
+        self.draws = 0;
+    }
+
+    /// Called before executing a cycle, starting the wall time on the first one
+    pub fn start_cycle(&mut self) {
+        self.started_at.get_or_insert_with(Instant::now);
+    }
+
+    /// Called after executing a cycle, before the timers tick. `exited` tells whether the
+    /// ROM exited during the cycle, which halts it whatever the limits
+    /// # Returns
+    /// A report if the ROM exited or any limit was exceeded, for a VM whose next
+    /// instruction is at `pc`
+    pub fn end_cycle(&mut self, pc: u16, exited: bool) -> Option<HaltReport> {
+        self.cycles += 1;
+        let elapsed = self
+            .started_at
+            .map_or(Duration::ZERO, |start| start.elapsed());
+
+        let limit = if exited {
+            Limit::Exit
+        } else if self.limits.max_cycles.is_some_and(|max| self.cycles >= max) {
+            Limit::Cycles
+        } else if self.limits.max_wall_time.is_some_and(|max| elapsed > max) {
+            Limit::WallTime
+        } else if self
+            .limits
+            .max_draws_per_frame
+            .is_some_and(|max| self.draws > max)
+        {
+            Limit::DrawsPerFrame
+        } else {
+            return None;
+        };
+
+        Some(HaltReport {
+            limit,
+            cycles: self.cycles,
+            elapsed,
+            pc,
+        })
+    }
+}
@@ -0,0 +1,149 @@
+use super::{Chip8, Chip8Error, DebugHook};
+use crate::rom::{LoadProgress, RomLoader};
+
+/// Callback run once the ROM of a `RomLoading` is in memory, ex: to apply cheats or load
+/// a save state
+type OnLoaded<'a> = Box<dyn FnOnce(&mut Chip8) -> Result<(), String> + 'a>;
+
+/// A `DebugHook` starting a ROM loaded in the background by a `RomLoader`, so the window or
+/// the terminal keeps responding while a big ROM is downloaded or extracted. The progress
+/// is shown on the `Osd` meanwhile.
+///
+/// Once the ROM is loaded the VM runs through the `next` hook, or on its own, paused on
+/// faults like without any hook. Frontends stop when the ROM fails to load, see
+/// `RomLoading::error`.
+pub struct RomLoading<'a> {
+    loader: Option<RomLoader>,
+    on_loaded: Option<OnLoaded<'a>>,
+    next: Option<&'a mut dyn DebugHook>,
+    progress: Option<LoadProgress>,
+    error: Option<String>,
+    fault: Option<Chip8Error>,
+}
+
+impl<'a> RomLoading<'a> {
+    /// Creates a `RomLoading` of the ROM of `loader`, calling `on_loaded` once it's loaded
+    /// and running the VM through `next` afterwards, if any
+    pub fn new<F>(loader: RomLoader, on_loaded: F, next: Option<&'a mut dyn DebugHook>) -> Self
+    where
+        F: FnOnce(&mut Chip8) -> Result<(), String> + 'a,
+    {
+        RomLoading {
+            loader: Some(loader),
+            on_loaded: Some(Box::new(on_loaded)),
+            next,
+            progress: None,
+            error: None,
+            fault: None,
+        }
+    }
+
+    /// Returns why the ROM couldn't be loaded, if it failed
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Returns the fault that paused the VM, if it wasn't resumed since. Only tracked
+    /// without a `next` hook
+    pub fn fault(&self) -> Option<&Chip8Error> {
+        self.fault.as_ref()
+    }
+
+    /// Loads the ROM in `vm` if it's ready, showing the progress otherwise
+    /// # Returns
+    /// `true` once the ROM is loaded.
+    fn poll(&mut self, vm: &mut Chip8) -> bool {
+        let loader = match &mut self.loader {
+            Some(loader) => loader,
+            None => return true,
+        };
+        let loaded = match loader.poll() {
+            Some(loaded) => loaded,
+            None => {
+                if loader.progress() != self.progress {
+                    self.progress = loader.progress();
+                    // Replaces the previous step instead of piling up below it
+                    vm.osd_mut().clear();
+                    vm.osd_mut()
+                        .show(self.progress.map_or(String::new(), |p| p.to_string()));
+                }
+                return false;
+            }
+        };
+        self.loader = None;
+        if self.progress.is_some() {
+            vm.osd_mut().clear();
+        }
+
+        let on_loaded = self.on_loaded.take();
+        let loaded = loaded.and_then(|content| {
+            vm.load_rom_content(content)
+                .map(|_| ())
+                .map_err(String::from)
+        });
+        match loaded.and_then(|()| on_loaded.map_or(Ok(()), |on_loaded| on_loaded(vm))) {
+            Ok(()) => true,
+            Err(msg) => {
+                self.error = Some(msg);
+                false
+            }
+        }
+    }
+}
+
+impl DebugHook for RomLoading<'_> {
+    fn update(&mut self, vm: &mut Chip8) {
+        if !self.poll(vm) {
+            return;
+        }
+
+        match self.next.as_deref_mut() {
+            Some(next) => next.update(vm),
+            None => {
+                self.fault = None;
+                // Paused on faults, the same as without a hook
+                if let Err(error) = vm.step() {
+                    vm.paused = true;
+                    vm.osd_mut().show(format!("Paused: {}", error));
+                    self.fault = Some(error);
+                }
+            }
+        }
+    }
+
+    fn should_exit(&self) -> bool {
+        self.error.is_some() || self.next.as_ref().is_some_and(|next| next.should_exit())
+    }
+}
+
+#[test]
+fn roms_start_once_loaded_in_the_background() {
+    let mut vm = Chip8::new();
+    let mut loaded = false;
+    let loader = RomLoader::start("roms/tests/ibm-logo.ch8", None);
+    let mut loading = RomLoading::new(
+        loader,
+        |vm: &mut Chip8| {
+            loaded = vm.rom() == include_bytes!("../../roms/tests/ibm-logo.ch8");
+            Ok(())
+        },
+        None,
+    );
+    while vm.rom().is_empty() {
+        loading.update(&mut vm);
+        std::thread::yield_now();
+    }
+    assert!(!loading.should_exit());
+    drop(loading);
+    assert!(loaded);
+
+    let mut loading = RomLoading::new(RomLoader::start("roms/missing.ch8", None), |_| Ok(()), None);
+    while !loading.should_exit() {
+        loading.update(&mut vm);
+        std::thread::yield_now();
+    }
+    assert!(loading
+        .error()
+        .unwrap()
+        .starts_with("Failed to open the ROM"));
+}
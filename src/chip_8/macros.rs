@@ -0,0 +1,159 @@
+use super::DemoInput;
+
+/// Input macros: short key sequences recorded from the user and replayed on demand,
+/// ex: the exact taps needed to get past a menu. Each macro is bound to one of
+/// `Macros::SLOTS` slots, usually mapped to host hotkeys.
+///
+/// Timing is frame-accurate: inputs are stored at the cycle they happened,
+/// relative to the start of the recording, and replayed at the same offsets.
+/// Keys still held when the recording stops are released at its end,
+/// so replaying a macro never leaves keys stuck.
+pub struct Macros {
+    slots: [Vec<DemoInput>; Macros::SLOTS],
+    recording: Option<Recording>,
+    playback: Option<Playback>,
+}
+
+/// A macro being recorded
+struct Recording {
+    /// Cycles executed since the recording started
+    cycles: u64,
+    inputs: Vec<DemoInput>,
+    held: [bool; 16],
+}
+
+/// A macro being replayed
+struct Playback {
+    /// Cycles executed since the playback started
+    cycles: u64,
+    inputs: Vec<DemoInput>,
+    next_input: usize,
+}
+
+impl Macros {
+    /// Number of macros that can be bound at once
+    pub const SLOTS: usize = 4;
+
+    /// Creates `Macros` with all slots empty
+    pub fn new() -> Macros {
+        Macros {
+            slots: Default::default(),
+            recording: None,
+            playback: None,
+        }
+    }
+
+    /// Starts recording a new macro, discarding the one being recorded if any
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording {
+            cycles: 0,
+            inputs: Vec::new(),
+            held: [false; 16],
+        });
+    }
+
+    /// Returns `true` while a macro is being recorded
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Stops the recording and binds the recorded macro to `slot`,
+    /// replacing the macro bound to it
+    /// # Returns
+    /// The number of inputs recorded, or `None` if no macro was being recorded.
+    /// # Panics
+    /// If `slot` is not lower than `SLOTS`
+    pub fn stop_recording(&mut self, slot: usize) -> Option<usize> {
+        let mut recording = self.recording.take()?;
+        for key in 0..recording.held.len() {
+            if recording.held[key] {
+                recording.inputs.push(DemoInput {
+                    cycle: recording.cycles,
+                    key,
+                    pressed: false,
+                });
+            }
+        }
+
+        let recorded = recording.inputs.len();
+        self.slots[slot] = recording.inputs;
+        Some(recorded)
+    }
+
+    /// Returns the inputs of the macro bound to `slot`, empty if there is none
+    /// # Panics
+    /// If `slot` is not lower than `SLOTS`
+    pub fn get(&self, slot: usize) -> &[DemoInput] {
+        &self.slots[slot]
+    }
+
+    /// Starts replaying the macro bound to `slot`, interrupting the one being replayed if any
+    /// # Returns
+    /// `false` if no macro is bound to `slot`.
+    /// # Panics
+    /// If `slot` is not lower than `SLOTS`
+    pub fn play(&mut self, slot: usize) -> bool {
+        if self.slots[slot].is_empty() {
+            return false;
+        }
+
+        self.playback = Some(Playback {
+            cycles: 0,
+            inputs: self.slots[slot].clone(),
+            next_input: 0,
+        });
+        true
+    }
+
+    /// Returns `true` while a macro is being replayed
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// Records a key press or release, if a macro is being recorded.
+    /// Inputs replayed by a macro are never recorded
+    pub fn record(&mut self, key: usize, pressed: bool) {
+        if self.playback.is_some() {
+            return;
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.held[key] = pressed;
+            recording.inputs.push(DemoInput {
+                cycle: recording.cycles,
+                key,
+                pressed,
+            });
+        }
+    }
+
+    /// Called before executing a cycle
+    /// # Returns
+    /// The inputs of the macro being replayed that are due on this cycle.
+    pub fn start_cycle(&mut self) -> Vec<DemoInput> {
+        let mut due = Vec::new();
+        if let Some(playback) = &mut self.playback {
+            while let Some(&input) = playback.inputs.get(playback.next_input) {
+                if input.cycle > playback.cycles {
+                    break;
+                }
+                due.push(input);
+                playback.next_input += 1;
+            }
+
+            playback.cycles += 1;
+            if playback.next_input == playback.inputs.len() {
+                self.playback = None;
+            }
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.cycles += 1;
+        }
+        due
+    }
+}
+
+impl Default for Macros {
+    fn default() -> Macros {
+        Macros::new()
+    }
+}
@@ -0,0 +1,138 @@
+use std::ops::{Deref, DerefMut};
+
+/// How accesses beyond the end of memory are handled, ex: `I` pointing past it after
+/// an `Fx1E`. Well-behaved ROMs never access such addresses, but interpreters of the
+/// time didn't check them either.
+///
+/// Faults are reported by default, see `Chip8Error::MemoryOutOfBounds`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MemoryBounds {
+    /// The access stops the VM with a `Chip8Error::MemoryOutOfBounds`
+    #[default]
+    Fault,
+    /// Addresses wrap around, masked to 12 bits (16 with `Profile::XoChip`) like on an
+    /// address bus without the upper lines
+    Wrap,
+    /// Reads return `Memory::OPEN_BUS` and writes are discarded, like on a bus where
+    /// no memory answers
+    OpenBus,
+}
+
+impl MemoryBounds {
+    /// All available modes, in the order they are presented to users
+    pub const ALL: [MemoryBounds; 3] = [
+        MemoryBounds::Fault,
+        MemoryBounds::Wrap,
+        MemoryBounds::OpenBus,
+    ];
+
+    /// Returns the name used to select the mode, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemoryBounds::Fault => "fault",
+            MemoryBounds::Wrap => "wrap",
+            MemoryBounds::OpenBus => "open-bus",
+        }
+    }
+
+    /// Finds a mode by its name
+    pub fn from_name(name: &str) -> Option<MemoryBounds> {
+        MemoryBounds::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == name)
+    }
+}
+
+/// The main memory of a VM: its bytes, along with how accesses beyond them are handled.
+///
+/// `read` and `write` never panic, whatever the address: with `MemoryBounds::Fault` the
+/// VM checks the accesses with `Memory::contains` before performing them.
+/// The bytes can be accessed as a slice, ex: to load a ROM or dump the memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Memory {
+    bytes: Vec<u8>,
+    bounds: MemoryBounds,
+}
+
+impl Memory {
+    /// Value read from addresses without memory with `MemoryBounds::OpenBus`
+    pub const OPEN_BUS: u8 = 0;
+
+    /// Creates a memory of `size` bytes, all `0`
+    /// # Panics
+    /// If `size` is not a power of two, as addresses couldn't wrap around with a mask
+    pub fn new(size: usize, bounds: MemoryBounds) -> Memory {
+        assert!(
+            size.is_power_of_two(),
+            "Memory sizes must be a power of two."
+        );
+        Memory {
+            bytes: vec![0; size],
+            bounds,
+        }
+    }
+
+    /// Returns how accesses beyond the end of memory are handled
+    pub fn bounds(&self) -> MemoryBounds {
+        self.bounds
+    }
+
+    pub fn set_bounds(&mut self, bounds: MemoryBounds) {
+        self.bounds = bounds;
+    }
+
+    /// Changes the size of the memory, keeping the content that fits in it
+    /// # Panics
+    /// If `size` is not a power of two. See `Memory::new`
+    pub fn resize(&mut self, size: usize) {
+        assert!(
+            size.is_power_of_two(),
+            "Memory sizes must be a power of two."
+        );
+        self.bytes.resize(size, 0);
+    }
+
+    /// Returns whether `len` bytes from `address` can be accessed: always, unless
+    /// out of bounds accesses are a `MemoryBounds::Fault`
+    pub fn contains(&self, address: usize, len: usize) -> bool {
+        self.bounds != MemoryBounds::Fault || address + len <= self.bytes.len()
+    }
+
+    /// Returns the index of the byte at `address`, wrapped around with
+    /// `MemoryBounds::Wrap`, or `None` if there's no memory there
+    pub fn resolve(&self, address: usize) -> Option<usize> {
+        match self.bounds {
+            _ if address < self.bytes.len() => Some(address),
+            MemoryBounds::Wrap => Some(address & (self.bytes.len() - 1)),
+            MemoryBounds::Fault | MemoryBounds::OpenBus => None,
+        }
+    }
+
+    /// Reads the byte at `address`, `OPEN_BUS` if there's no memory there
+    pub fn read(&self, address: usize) -> u8 {
+        self.resolve(address)
+            .map_or(Memory::OPEN_BUS, |index| self.bytes[index])
+    }
+
+    /// Writes the byte at `address`, discarded if there's no memory there
+    pub fn write(&mut self, address: usize, value: u8) {
+        if let Some(index) = self.resolve(address) {
+            self.bytes[index] = value;
+        }
+    }
+}
+
+impl Deref for Memory {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl DerefMut for Memory {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+}
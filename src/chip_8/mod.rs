@@ -1,9 +1,153 @@
+pub mod asm;
+mod audio;
+mod battery;
+mod bug_report;
+mod cheats;
+#[cfg(feature = "window")]
+mod clipboard;
+mod debugger;
+mod decoder;
+mod demos;
+mod display;
+mod dump;
+mod error;
+mod filter;
+#[cfg(test)]
+mod fixtures;
+mod frame_hash;
+#[cfg(feature = "window")]
+mod frame_skip;
+#[cfg(feature = "framebuffer")]
+mod framebuffer;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod history;
+#[cfg(feature = "window")]
+mod hotkeys;
 mod instructions;
+#[cfg(feature = "window")]
+mod keymap;
+mod kiosk;
+mod latency;
+mod limits;
+mod loading;
+mod macros;
+mod memory;
+mod movie;
+mod osd;
+#[cfg(feature = "window")]
+mod overlay;
+mod palette;
+mod peripherals;
+mod playlist;
+mod profile;
+mod profiler;
+mod protection;
+mod quirks;
+mod ram_search;
+mod rewind;
+mod rgba;
+mod rng;
+mod rom_database;
+mod rpl_flags;
+mod save_state;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod selftest;
+mod shared;
+#[cfg(test)]
+mod snapshots;
+#[cfg(feature = "sound")]
+mod sound;
+#[cfg(feature = "window")]
+mod speed;
+mod step;
+mod strict;
+#[cfg(test)]
+mod test_roms;
+mod thumbnail;
+mod timing;
+mod triggers;
+#[cfg(feature = "tui")]
+mod tui;
+mod turbo;
+#[cfg(feature = "window")]
 mod utils;
+mod video;
+mod watchpoints;
 
+pub use audio::{AudioPattern, AudioTrack, Sound, Tone, WavSample, Waveform};
+pub use cheats::{Cheat, Cheats};
+pub use debugger::{DebugHook, Debugger, StopReason};
+pub use decoder::{explain, Instruction};
+pub use demos::{Demo, DemoPicker, DEMOS};
+pub use display::{AntiFlicker, FrameBlend};
+pub use dump::{disassemble, disassemble_rom, parse_memory_range};
+pub use error::Chip8Error;
+pub use filter::Filter;
+#[cfg(feature = "window")]
+use filter::Phosphor;
+pub use frame_hash::{FrameHash, FrameHashTrace, FrameHasher};
+#[cfg(feature = "gamepad")]
+pub use gamepad::GamepadMap;
+pub use history::History;
+#[cfg(feature = "window")]
+pub use hotkeys::{HostAction, Hotkeys};
+#[cfg(feature = "window")]
+pub use keymap::KeyMap;
+pub use kiosk::{Kiosk, KIOSK_RESET_COMBO};
+pub use latency::LatencyProbe;
+pub use limits::{ExecutionLimits, HaltReport, Limit};
+pub use loading::RomLoading;
+pub use macros::Macros;
+pub use memory::MemoryBounds;
+pub use movie::Movie;
+pub use osd::Osd;
+#[cfg(feature = "window")]
+pub use overlay::DebugOverlay;
+pub use palette::{parse_rgb, pulse, Palette, PaletteColors, Rgb};
+pub use peripherals::Peripherals;
+pub use playlist::{DemoInput, Playlist, PlaylistEntry};
+pub use profile::Profile;
+pub use profiler::InstructionProfiler;
+pub use protection::WriteProtection;
+pub use quirks::{Quirk, Quirks};
+pub use ram_search::{RamSearch, SearchFilter};
+pub use rewind::Rewind;
+pub use rom_database::{rom_sha1, RomDatabase, RomSettings, SpeedClass};
+#[cfg(feature = "scripting")]
+pub use scripting::{Script, ScriptEvent};
+pub use selftest::{self_test, SelfTestResult, SelfTestStatus};
+pub use shared::{DisplaySnapshot, SharedChip8};
+#[cfg(feature = "sound")]
+pub use sound::Beeper;
+#[cfg(feature = "window")]
+pub use speed::SpeedControl;
+pub use step::StepReport;
+pub use thumbnail::{decode_thumbnail, thumbnail_text};
+pub use triggers::{Condition, MemoryChange, Trigger, TriggerAction, Triggers};
+pub use turbo::Turbo;
+#[cfg(feature = "window")]
+pub use utils::check_video_backend;
+pub use video::{Geometry, Scaling, VideoSettings};
+pub use watchpoints::{Access, WatchHit, Watchpoint};
+
+use crate::storage::Storage;
+use limits::LimitGuard;
+use memory::Memory;
+use movie::MovieState;
+use rng::Rng;
+use std::ops::Range;
+use std::time::Duration;
+use strict::StrictCheck;
+
+#[cfg(feature = "window")]
 extern crate glutin_window;
+#[cfg(feature = "window")]
 extern crate graphics;
+#[cfg(feature = "window")]
 extern crate opengl_graphics;
+#[cfg(feature = "window")]
 extern crate piston;
 
 /// An instance of a `CHIP-8` VM holding all necessary state,
@@ -15,19 +159,96 @@ extern crate piston;
 /// * `0x000` - `0x1FF`: Originally reserved for the `CHIP-8` interpreter.
 /// * `0x050` - `0x0A0`: Storage for the 16 built-in characters.
 /// * `0x200` - `0xFFF`: ROM instructions are loaded in this region and
-/// all remaining space is free to be used as the developer sees fit.
+///   all remaining space is free to be used as the developer sees fit.
+/// * `0xFF0` - `0xFFF`: Routed to the `Peripherals` instead, only with `Profile::Peripherals`.
+///
+/// `Profile::XoChip` extends the memory to `65536` bytes, up to `0xFFFF`.
 ///
 /// ### Notes:
 /// All opcodes are 2 bytes long, so:
 /// * All fetches will build a proper opcode by joining `PC` & `PC + 1`
 /// * The program counter must be incremented +2 after every fetch.
 pub struct Chip8 {
-    main_memory: [u8; Chip8::MAX_MEMORY_ADDRESS],
+    main_memory: Memory,
     regs: Registers,
     stack: Stack,
     input: Input,
     display: Display,
     timers: Timers,
+    clock: Clock,
+    profile: Profile,
+    quirks: Quirks,
+    peripherals: Option<Peripherals>,
+    latency_probe: Option<LatencyProbe>,
+    audio_track: Option<AudioTrack>,
+    /// Beep played while the sound timer is active. See `Chip8::set_tone`
+    tone: Tone,
+    profiler: Option<InstructionProfiler>,
+    turbo: Option<Turbo>,
+    triggers: Option<Triggers>,
+    /// Patches applied on every frame. See `Chip8::set_cheats`
+    cheats: Option<Cheats>,
+    /// Called on the events of the VM. See `Chip8::set_script`
+    #[cfg(feature = "scripting")]
+    script: Option<Script>,
+    /// Addresses rewritten with a fixed value after every cycle. See `Chip8::freeze`
+    frozen: Vec<(u16, u8)>,
+    /// Data breakpoints. See `Chip8::set_watchpoints`
+    watchpoints: Vec<Watchpoint>,
+    /// First access of the current instruction that hit a watchpoint
+    watch_hit: Option<WatchHit>,
+    macros: Macros,
+    /// Movie being recorded or replayed. See `Chip8::record_movie`
+    movie: Option<MovieState>,
+    /// Host keys bound to the actions of the emulator
+    #[cfg(feature = "window")]
+    hotkeys: Hotkeys,
+    /// Host keys mapped to the keypad
+    #[cfg(feature = "window")]
+    keymap: KeyMap,
+    /// Storage and key of the config the keypad map is saved to when remapped.
+    /// See `Chip8::set_config_storage`
+    #[cfg(feature = "window")]
+    config_storage: Option<(Box<dyn Storage + Send>, String)>,
+    /// Gamepad buttons mapped to the keypad
+    #[cfg(feature = "gamepad")]
+    gamepad_map: GamepadMap,
+    osd: Osd,
+    write_protection: WriteProtection,
+    /// Checks invariants around every instruction. See `Chip8::set_strict`
+    strict: bool,
+    /// Protected addresses already reported by `WriteProtection::Warn`
+    protected_writes: [bool; Chip8::INITIAL_MEMORY_ADDRESS],
+    rom: Vec<u8>,
+    history: History,
+    /// Ticks of the timers since the ROM started. See `Chip8::frames`
+    frames: u64,
+    limits: Option<LimitGuard>,
+    halted: Option<HaltReport>,
+    /// Stops `start` from running cycles. See `Chip8::set_paused`
+    paused: bool,
+    /// Pattern played by the XO-CHIP sound, 1 bit per sample. See `Chip8::audio_pattern`
+    audio_pattern: [u8; 16],
+    /// Rate of the XO-CHIP audio pattern, set with `Fx3A`. See `AudioPattern::rate`
+    pitch: u8,
+    /// Flags saved with `Fx75`, persisted to the save storage. See `Chip8::rpl_flags`
+    rpl_flags: [u8; 16],
+    /// Generator of `Cxkk`, seeded with `rng_seed` if set, otherwise randomly
+    rng: Rng,
+    /// See `Chip8::set_rng_seed`
+    rng_seed: Option<u64>,
+    /// Where save states and RPL flags are kept. See `Chip8::set_save_storage`
+    save_storage: Option<Box<dyn Storage + Send>>,
+    /// Slot used by the `save-state` and `load-state` hotkeys
+    save_slot: u32,
+    /// States of the last frames, to step the execution backwards. See `Chip8::enable_rewind`
+    rewind: Option<Rewind>,
+    /// Database of known ROMs and the settings overriding it. See `Chip8::set_rom_database`
+    rom_database: Option<rom_database::RomDatabaseState>,
+    /// Settings applied to the last ROM loaded from the database
+    rom_settings: Option<RomSettings>,
+    /// Address and opcode of the instruction being executed, reported by `Chip8Error`
+    instruction: (u16, u16),
 }
 
 /// The `CHIP-8` uses 16 8-bit general purpose registers, labeled `v[0x0]` to `v[0xF]`
@@ -42,29 +263,95 @@ pub struct Registers {
     pc: u16,
 }
 
-/// Holds the 16-level `CHIP-8` Stack and a single Stack Pointer.
+/// Holds the `CHIP-8` Stack and a single Stack Pointer.
 /// # Stored Addresses:
 /// Holds an ordered list of addresses which come from the PC in a `CHIP-8` VM
 /// # Stack Pointer:
-/// Points to the next valid position in `stored_addresses` in which a memory address coming
-/// from the PC can be stored with a `CALL` instruction
+/// Implicitly given by the amount of `stored` addresses: the next `CALL` instruction
+/// stores the PC right after the last one.
+/// # Depth:
+/// The original `CHIP-8` allows 16 nested subroutines, but some interpreters allowed more.
+/// Pushing beyond the configured `depth` is reported as a `Chip8Error::StackOverflow`.
 pub struct Stack {
-    pointer: u8,
-    stored: [u16; 16],
+    stored: Vec<u16>,
+    depth: usize,
 }
 
-/// Stores the current status of each 16 input keys, mapped from `0x0` to `0xF`
+/// Stores the current status of each 16 input keys, mapped from `0x0` to `0xF`,
+/// along with the keys pressed and released since the start of the frame
 pub struct Input {
     key_status: [bool; 16],
+    pressed: [bool; 16],
+    released: [bool; 16],
+}
+
+impl Input {
+    /// Creates the input with every key released
+    fn new() -> Input {
+        Input {
+            key_status: [false; 16],
+            pressed: [false; 16],
+            released: [false; 16],
+        }
+    }
+
+    /// Sets the status of `key`, recording the transition if it changed
+    fn set_key(&mut self, key: usize, pressed: bool) {
+        if self.key_status[key] != pressed {
+            match pressed {
+                true => self.pressed[key] = true,
+                false => self.released[key] = true,
+            }
+        }
+        self.key_status[key] = pressed;
+    }
+
+    /// Returns whether `key` is held, keys beyond `0xF` never are
+    fn is_pressed(&self, key: usize) -> bool {
+        self.key_status.get(key).copied().unwrap_or(false)
+    }
+
+    /// Returns the first key released since the start of the frame, forgetting its release
+    /// so it's only reported once
+    fn take_released(&mut self) -> Option<usize> {
+        let key = self.released.iter().position(|&released| released)?;
+        self.released[key] = false;
+        Some(key)
+    }
+
+    /// Forgets the transitions of the previous frame
+    fn start_frame(&mut self) {
+        self.pressed = [false; 16];
+        self.released = [false; 16];
+    }
 }
 
 /// Stores the display buffer of the `CHIP-8` VM.
 /// The buffer is 64 pixels wide and 32 pixels high.
 /// Only two values are accepted for each pixel: On or Off. We don't have color.
+/// Frontends render it with `Display::to_rgba`, whatever its size.
 ///
-/// **Note:** All instruction that write outside the buffer valid range will wrap around.
+/// Profiles with the SUPER-CHIP instructions can switch to a high resolution of 128x64
+/// pixels with `00FF`, and back with `00FE`. Switching clears the display. Pixels are
+/// stored row by row with the width of the current resolution, see `Display::width`.
+///
+/// `Profile::XoChip` adds a second plane of the same size. Each pixel then takes one of
+/// 4 colors, depending on the planes it's lit on. Drawing and clearing only affect
+/// the planes selected with `Fn01`, by default the first one.
+///
+/// **Note:** Sprites drawn beyond the edges are clipped, unless `Quirks::wrap_sprites` is enabled.
 pub struct Display {
-    buffer: [bool; Chip8::VIDEO_WIDTH * Chip8::VIDEO_HEIGHT],
+    /// Pixels of the first plane, sized for the high resolution. Only the first
+    /// `Display::size` are in use
+    buffer: [bool; Chip8::HIRES_WIDTH * Chip8::HIRES_HEIGHT],
+    second_plane: [bool; Chip8::HIRES_WIDTH * Chip8::HIRES_HEIGHT],
+    /// Whether the 128x64 high resolution of SUPER-CHIP is active
+    hires: bool,
+    /// Bit mask of the planes affected by `Dxyn`, `00E0` and the scrolls: `1` for the
+    /// first plane, `2` for the second one
+    selected_planes: u8,
+    /// Set whenever a pixel changes, until taken by the frontend. See `Display::take_dirty`
+    dirty: bool,
 }
 
 pub struct Timers {
@@ -72,25 +359,184 @@ pub struct Timers {
     sound: u8,
 }
 
+/// Clock of the emulated hardware, which ticks the `Timers` exactly `Chip8::FRAME_RATE`
+/// times per emulated second, whatever the amount of instructions executed per second.
+/// Every cycle lasts `1 / cycle_rate` seconds. The part of a frame left at the end of a
+/// cycle is carried over to the next ones, so rounding never adds nor drops a tick.
+///
+/// With the VIP timing the clock counts machine cycles of the COSMAC VIP instead, and
+/// every instruction lasts as many of them as it cost. See `Chip8::set_vip_timing`
+pub struct Clock {
+    /// Instructions executed per emulated second
+    cycle_rate: u32,
+    /// Progress towards the next tick, in `1 / (rate * FRAME_RATE)` seconds
+    phase: u32,
+    vip_timing: bool,
+    /// Cycles elapsed since the VM was created, so schedulers can tell how long the
+    /// instructions they ran lasted
+    elapsed: u64,
+    /// Instructions executed since the VM was created, ex: to measure their rate
+    instructions: u64,
+}
+
+impl Clock {
+    /// Instructions executed per second unless configured otherwise: 10 per frame
+    pub const DEFAULT_CYCLE_RATE: u32 = 600;
+
+    /// Returns the cycles elapsed since `elapsed`, at least one so a scheduler always
+    /// makes progress, ex: while a `DebugHook` doesn't run the VM
+    #[cfg(any(feature = "window", feature = "tui", feature = "framebuffer"))]
+    fn spent_since(&self, elapsed: u64) -> i64 {
+        (self.elapsed - elapsed).max(1) as i64
+    }
+
+    /// Returns the part of a `cycle_budget` carried over to the next frame: debts from
+    /// instructions that lasted longer than the cycles left, but no whole cycle left
+    /// unused, ex: when the VM got paused
+    #[cfg(any(feature = "window", feature = "tui", feature = "framebuffer"))]
+    fn carried_budget(cycle_budget: i64) -> i64 {
+        match cycle_budget > 0 {
+            true => cycle_budget % Chip8::FRAME_RATE as i64,
+            false => cycle_budget,
+        }
+    }
+
+    /// Returns the cycles per emulated second: instructions, or machine cycles with the
+    /// VIP timing
+    fn rate(&self) -> u32 {
+        match self.vip_timing {
+            true => timing::VIP_CYCLE_RATE,
+            false => self.cycle_rate,
+        }
+    }
+
+    /// Advances the clock by an instruction lasting `cycles`
+    /// # Returns
+    /// The number of ticks of the timers due: 0 or 1, unless the cycles last longer than a frame
+    fn advance(&mut self, cycles: u32) -> u32 {
+        self.instructions += 1;
+        self.elapsed += cycles as u64;
+        self.phase += Chip8::FRAME_RATE * cycles;
+        let ticks = self.phase / self.rate();
+        self.phase %= self.rate();
+        ticks
+    }
+}
+
+impl Display {
+    /// Number of display planes available with `Profile::XoChip`
+    const PLANES: usize = 2;
+
+    /// Instantiates a blank display, with only the first plane selected
+    fn new() -> Display {
+        Display {
+            buffer: [false; Chip8::HIRES_WIDTH * Chip8::HIRES_HEIGHT],
+            second_plane: [false; Chip8::HIRES_WIDTH * Chip8::HIRES_HEIGHT],
+            hires: false,
+            selected_planes: 1,
+            dirty: true,
+        }
+    }
+
+    /// Returns whether any pixel changed since the last call, so frontends only redraw
+    /// when needed. A new display is dirty, so its first frame is always drawn
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.dirty, false)
+    }
+
+    /// Returns the indexes of the selected planes, `0` being the first one
+    fn selected(&self) -> impl Iterator<Item = usize> {
+        let mask = self.selected_planes;
+        (0..Display::PLANES).filter(move |plane| mask & (1 << plane) != 0)
+    }
+
+    /// Returns whether the 128x64 high resolution is active
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    /// Switches between the 64x32 and 128x64 resolutions, clearing every plane
+    fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.buffer = [false; Chip8::HIRES_WIDTH * Chip8::HIRES_HEIGHT];
+        self.second_plane = [false; Chip8::HIRES_WIDTH * Chip8::HIRES_HEIGHT];
+        self.dirty = true;
+    }
+
+    /// Returns the pixels of a plane in the current resolution, `0` being the first one
+    fn plane(&self, plane: usize) -> &[bool] {
+        let size = self.size();
+        match plane {
+            0 => &self.buffer[..size],
+            _ => &self.second_plane[..size],
+        }
+    }
+
+    /// Returns the pixels of a plane in the current resolution, `0` being the first one
+    fn plane_mut(&mut self, plane: usize) -> &mut [bool] {
+        let size = self.size();
+        match plane {
+            0 => &mut self.buffer[..size],
+            _ => &mut self.second_plane[..size],
+        }
+    }
+
+    /// Returns the index in a `PaletteColors` of the color of the pixel at `pos`:
+    /// `0` when off, otherwise `1` on the first plane, `2` on the second and `3` on both
+    fn color(&self, pos: usize) -> usize {
+        self.buffer[pos] as usize | (self.second_plane[pos] as usize) << 1
+    }
+}
+
 impl Stack {
+    /// Amount of levels of the original `CHIP-8` Stack
+    pub const DEFAULT_DEPTH: usize = 16;
+
+    /// Returns the current value of the Stack Pointer
+    fn pointer(&self) -> usize {
+        self.stored.len()
+    }
+
     /// Stores a `u16` value in the Stack
-    fn push(&mut self, value: u16) {
-        self.stored[self.pointer as usize] = value;
-        self.pointer += 1;
+    /// # Returns
+    /// `false`, leaving the Stack untouched, if it already holds `depth` values (Stack overflow).
+    fn push(&mut self, value: u16) -> bool {
+        if self.stored.len() >= self.depth {
+            return false;
+        }
+        self.stored.push(value);
+        true
     }
 
     /// Removes the top of the Stack and returns it
-    fn pop(&mut self) -> u16 {
-        self.pointer -= 1;
-        return self.stored[self.pointer as usize];
+    /// # Returns
+    /// `None` if the Stack is empty (Stack underflow).
+    fn pop(&mut self) -> Option<u16> {
+        self.stored.pop()
+    }
+}
+
+impl Default for Chip8 {
+    fn default() -> Chip8 {
+        Chip8::new()
     }
 }
 
 impl Chip8 {
-    const INITIAL_MEMORY_ADDRESS: usize = 0x200;
-    const MAX_MEMORY_ADDRESS: usize = 4096;
+    /// Address where ROMs are loaded
+    pub const INITIAL_MEMORY_ADDRESS: usize = 0x200;
+    pub const MAX_MEMORY_ADDRESS: usize = 4096;
+    /// Maximum size of a ROM: all memory after `INITIAL_MEMORY_ADDRESS`
+    pub const MAX_ROM_SIZE: usize = Chip8::MAX_MEMORY_ADDRESS - Chip8::INITIAL_MEMORY_ADDRESS;
+    /// Frequency of the delay and sound timers, in Hz. Each of their ticks is a frame
+    /// of the virtual clock. See `Chip8::frames`
+    pub const FRAME_RATE: u32 = 60;
+    /// Save slots cycled through by the `next-slot` hotkey. See `HostAction::NextSlot`
+    pub const SAVE_SLOTS: u32 = 10;
     const VIDEO_WIDTH: usize = 64;
     const VIDEO_HEIGHT: usize = 32;
+    const HIRES_WIDTH: usize = 128;
+    const HIRES_HEIGHT: usize = 64;
 
     const INITIAL_FONTS_MEMORY_ADDRESS: usize = 0x50;
     const FONTS: [u8; 5 * 16] = [
@@ -111,68 +557,141 @@ impl Chip8 {
         0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
         0xF0, 0x80, 0xF0, 0x80, 0x80, // F
     ];
+    /// Address of the 8x10 font of SUPER-CHIP, right after `FONTS`. See `Fx30`
+    const BIG_FONTS_MEMORY_ADDRESS: usize = 0xA0;
+    const BIG_FONTS: [u8; 10 * 16] = [
+        0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+        0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+        0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+        0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+        0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+        0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+        0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+        0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+        0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+        0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+        0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+        0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+    ];
 
     /// Instantiates a new `CHIP-8` VM with proper initial values.
     /// # Initial values:
     /// * **Registers**: All set to `0x0`,
     /// * **Program Counter**: Set to `INITIAL_MEMORY_ADDRESS`,
     /// * **Memory**: All addresses set to `0x0`,
-    /// * **Stack**: All addresses set to `0x0` and the SP set to `0`,
+    /// * **Stack**: Empty (SP set to `0`) with a depth of `Stack::DEFAULT_DEPTH` levels,
     /// * **Input**: All 16 keys are set to `false` (non-pressed),
-    /// * **Display**: All 32x64 pixels are set to `false`.
+    /// * **Display**: All 32x64 pixels are set to `false`,
+    /// * **Fonts**: The small and big fonts are loaded below `INITIAL_MEMORY_ADDRESS`.
     /// # Panics
     /// If the VM can't load the initial fonts to memory. This should never happen
     pub fn new() -> Chip8 {
         let mut instance = Chip8 {
-            main_memory: [0; 4096],
+            main_memory: Memory::new(Chip8::MAX_MEMORY_ADDRESS, MemoryBounds::Fault),
             regs: Registers {
                 v: [0; 16],
                 i: 0,
                 pc: Chip8::INITIAL_MEMORY_ADDRESS as u16,
             },
             stack: Stack {
-                pointer: 0,
-                stored: [0; 16],
-            },
-            input: Input {
-                key_status: [false; 16],
-            },
-            display: Display {
-                buffer: [false; Chip8::VIDEO_WIDTH * Chip8::VIDEO_HEIGHT],
+                stored: Vec::with_capacity(Stack::DEFAULT_DEPTH),
+                depth: Stack::DEFAULT_DEPTH,
             },
+            input: Input::new(),
+            display: Display::new(),
             timers: Timers { delay: 0, sound: 0 },
+            clock: Clock {
+                cycle_rate: Clock::DEFAULT_CYCLE_RATE,
+                phase: 0,
+                vip_timing: false,
+                elapsed: 0,
+                instructions: 0,
+            },
+            profile: Profile::Standard,
+            quirks: Quirks::default(),
+            peripherals: None,
+            latency_probe: None,
+            audio_track: None,
+            tone: Tone::DEFAULT,
+            profiler: None,
+            turbo: None,
+            triggers: None,
+            cheats: None,
+            #[cfg(feature = "scripting")]
+            script: None,
+            frozen: Vec::new(),
+            watchpoints: Vec::new(),
+            watch_hit: None,
+            macros: Macros::new(),
+            movie: None,
+            #[cfg(feature = "window")]
+            hotkeys: Hotkeys::new(),
+            #[cfg(feature = "window")]
+            keymap: KeyMap::new(),
+            #[cfg(feature = "window")]
+            config_storage: None,
+            #[cfg(feature = "gamepad")]
+            gamepad_map: GamepadMap::new(),
+            osd: Osd::new(),
+            write_protection: WriteProtection::Off,
+            strict: false,
+            protected_writes: [false; Chip8::INITIAL_MEMORY_ADDRESS],
+            rom: Vec::new(),
+            history: History::default(),
+            frames: 0,
+            limits: None,
+            halted: None,
+            paused: false,
+            audio_pattern: [0; 16],
+            pitch: AudioPattern::DEFAULT_PITCH,
+            rpl_flags: [0; 16],
+            rng: Rng::new(rand::random()),
+            rng_seed: None,
+            save_storage: None,
+            save_slot: 1,
+            rewind: None,
+            rom_database: None,
+            rom_settings: None,
+            instruction: (0, 0),
         };
 
         if instance
             .load_to_memory(Chip8::INITIAL_FONTS_MEMORY_ADDRESS, &Chip8::FONTS)
             .is_err()
+            || instance
+                .load_to_memory(Chip8::BIG_FONTS_MEMORY_ADDRESS, &Chip8::BIG_FONTS)
+                .is_err()
         {
             panic!("Failed to load initial fonts. VM could not be initialized.");
         }
 
-        return instance;
+        instance
     }
 
     /// Loads to the `main_memory` some binary content stored as `&Vec<u8>` in a specified `initial_address`
     /// # Returns
     /// The amount of bytes that were loaded into `main_memory`.
     /// # Panics
-    /// If the `initial_address` exceeds the `MAX_MEMORY_ADDRESS` or if the content is too big
+    /// If the `initial_address` exceeds the size of the `main_memory` or if the content is too big
     /// to be stored in the `main_memory`
     fn load_to_memory(&mut self, initial_address: usize, content: &[u8]) -> Result<usize, &str> {
-        if initial_address > Chip8::MAX_MEMORY_ADDRESS {
-            return Err("Invalid initial address: exceeds MAX_MEMORY_ADDRESS");
+        if initial_address > self.main_memory.len() {
+            return Err("Invalid initial address: exceeds the memory size");
         }
 
         let content_size = content.len();
         let end_address = initial_address + content_size;
-        if end_address > Chip8::MAX_MEMORY_ADDRESS {
+        if end_address > self.main_memory.len() {
             return Err("Content can't be loaded outside memory bounds.");
         }
 
         self.main_memory[initial_address..end_address].copy_from_slice(content);
 
-        return Ok(content_size);
+        Ok(content_size)
     }
 
     /// Loads the binary content of a ROM stored as a `Vec<u8>` inside a VM instance
@@ -182,10 +701,737 @@ impl Chip8 {
     /// # Panics
     /// If the ROM is too big to be stored in memory.
     pub fn load_rom_content(&mut self, content: Vec<u8>) -> Result<usize, &str> {
-        return match self.load_to_memory(Chip8::INITIAL_MEMORY_ADDRESS, &content) {
-            Ok(content_size) => Ok(content_size),
+        match self.load_to_memory(Chip8::INITIAL_MEMORY_ADDRESS, &content) {
+            Ok(content_size) => {
+                self.apply_rom_database(&content);
+                self.rom = content;
+                self.load_rpl_flags();
+                self.load_battery();
+                if let Some(rewind) = self.rewind.as_mut() {
+                    rewind.clear();
+                }
+                Ok(content_size)
+            }
             Err(_) => Err("ROM size exceeds memory capacity."),
+        }
+    }
+
+    /// Brings the VM back to its initial state and loads `rom`, keeping the active
+    /// `Profile`, the stack depth and the host integrations (latency probe, turbo...)
+    fn restart(&mut self, rom: &[u8]) -> Result<usize, &str> {
+        if let Err(msg) = self.store_battery() {
+            self.osd.show(msg);
+        }
+        let fonts_start = Chip8::INITIAL_FONTS_MEMORY_ADDRESS;
+        let fonts_end = fonts_start + Chip8::FONTS.len();
+        let big_fonts_start = Chip8::BIG_FONTS_MEMORY_ADDRESS;
+        let big_fonts_end = big_fonts_start + Chip8::BIG_FONTS.len();
+        self.main_memory = Memory::new(self.profile.memory_size(), self.main_memory.bounds());
+        self.main_memory[fonts_start..fonts_end].copy_from_slice(&Chip8::FONTS);
+        self.main_memory[big_fonts_start..big_fonts_end].copy_from_slice(&Chip8::BIG_FONTS);
+        self.regs = Registers {
+            v: [0; 16],
+            i: 0,
+            pc: Chip8::INITIAL_MEMORY_ADDRESS as u16,
+        };
+        self.stack.stored.clear();
+        self.display = Display::new();
+        self.audio_pattern = [0; 16];
+        self.pitch = AudioPattern::DEFAULT_PITCH;
+        self.rng = Rng::new(self.rng_seed.unwrap_or_else(rand::random));
+        self.timers = Timers { delay: 0, sound: 0 };
+        self.input = Input::new();
+        self.history.clear();
+        self.frames = 0;
+        self.clock.phase = 0;
+        self.set_profile(self.profile);
+
+        self.load_rom_content(rom.to_vec())
+    }
+    /// Restarts the last ROM loaded, as if it was just loaded: the memory, registers,
+    /// timers, display and keys are brought back to their initial state, and the VM is
+    /// resumed. Settings and host integrations are kept
+    pub fn reset(&mut self) {
+        // Kept until restarting, so the battery-backed memory is stored under its `CRC32`
+        let rom = self.rom.clone();
+        // The ROM already fit in memory when it was loaded
+        let _ = self.restart(&rom);
+        self.restart_movie();
+        self.paused = false;
+        self.halted = None;
+    }
+
+    /// Returns the content of the last ROM loaded
+    pub fn rom(&self) -> &[u8] {
+        &self.rom
+    }
+
+    /// Returns the `CRC32` of the last ROM loaded, identifying it regardless of its file name
+    pub fn rom_crc32(&self) -> u32 {
+        let mut crc = flate2::Crc::new();
+        crc.update(&self.rom);
+        crc.sum()
+    }
+
+    /// Sets hard limits on the execution. See `ExecutionLimits`
+    pub fn set_limits(&mut self, limits: ExecutionLimits) {
+        self.limits = Some(LimitGuard::new(limits));
+        self.halted = None;
+    }
+
+    /// Returns why the VM halted, if any limit was reached or the ROM exited
+    pub fn halt_report(&self) -> Option<&HaltReport> {
+        self.halted.as_ref()
+    }
+
+    /// Runs the VM without any window until it halts, ex: to run untrusted ROMs on a server
+    /// # Returns
+    /// Why the VM halted.
+    /// # Errors
+    /// If an instruction faults before any limit is reached. See `Chip8Error`
+    /// # Panics
+    /// If neither `max_cycles` nor `max_wall_time` is set, as the VM would never halt
+    pub fn run_until_halted(&mut self, limits: ExecutionLimits) -> Result<HaltReport, Chip8Error> {
+        assert!(
+            limits.max_cycles.is_some() || limits.max_wall_time.is_some(),
+            "A maximum amount of cycles or wall time is needed to run until halted."
+        );
+
+        self.set_limits(limits);
+        loop {
+            self.step()?;
+            if let Some(report) = self.halted {
+                return Ok(report);
+            }
+        }
+    }
+
+    /// Returns the last instructions executed. See `History`
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Returns the active `Profile`
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    /// Activates a `Profile`, enabling or disabling the extensions it provides.
+    /// The memory is resized to the size of the profile, keeping its content;
+    /// addresses frozen beyond the new size are unfrozen. Profiles without the high
+    /// resolution switch the display back to the low one, see `Display`
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+        self.peripherals = match profile {
+            Profile::Peripherals => Some(Peripherals::new()),
+            _ => None,
+        };
+        self.main_memory.resize(profile.memory_size());
+        let size = self.main_memory.len();
+        self.frozen
+            .retain(|&(address, _)| (address as usize) < size);
+        if self.display.is_hires() && !profile.has_schip_instructions() {
+            self.display.set_hires(false);
+        }
+    }
+
+    /// Activates a `Profile` along with the `Quirks` and the instructions per frame
+    /// of the interpreter it stands for, ex: to run a SUPER-CHIP ROM as intended.
+    /// See `Chip8::set_profile`
+    pub fn apply_profile(&mut self, profile: Profile) {
+        self.set_profile(profile);
+        self.set_quirks(profile.quirks());
+        self.set_instructions_per_frame(profile.instructions_per_frame());
+    }
+
+    /// Returns the active `Quirks`
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Sets the `Quirks` respected by the instructions, ex: for ROMs written for
+    /// another interpreter
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Returns the memory-mapped `Peripherals`, if enabled by the active `Profile`
+    pub fn peripherals_mut(&mut self) -> Option<&mut Peripherals> {
+        self.peripherals.as_mut()
+    }
+
+    /// Enables the measurement of the input latency. See `LatencyProbe`
+    pub fn enable_latency_probe(&mut self) {
+        self.latency_probe = Some(LatencyProbe::new());
+    }
+
+    /// Returns the `LatencyProbe`, if enabled
+    pub fn latency_probe(&self) -> Option<&LatencyProbe> {
+        self.latency_probe.as_ref()
+    }
+
+    /// Enables the offline synthesis of the audio, one tick at a time. See `AudioTrack`
+    pub fn enable_audio_track(&mut self) {
+        self.audio_track = Some(AudioTrack::with_tone(self.tone.clone()));
+    }
+
+    /// Sets the beep played while the sound timer is active, by the `AudioTrack` and,
+    /// with the `sound` feature, through the audio device while `start` runs.
+    /// A volume of `0` mutes it
+    pub fn set_tone(&mut self, tone: Tone) {
+        if let Some(track) = &mut self.audio_track {
+            track.set_tone(tone.clone());
+        }
+        self.tone = tone;
+    }
+
+    /// Returns the beep played while the sound timer is active
+    pub fn tone(&self) -> &Tone {
+        &self.tone
+    }
+
+    /// Returns the `AudioTrack`, if enabled
+    pub fn audio_track(&self) -> Option<&AudioTrack> {
+        self.audio_track.as_ref()
+    }
+
+    /// Enables the measurement of the host time spent on each instruction, and of the
+    /// executions of each address. See `InstructionProfiler`
+    pub fn enable_profiler(&mut self) {
+        self.profiler = Some(InstructionProfiler::new());
+    }
+
+    /// Returns the `InstructionProfiler`, if enabled
+    pub fn profiler(&self) -> Option<&InstructionProfiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Enables the auto-fire of the turbo keys. See `Turbo`
+    pub fn set_turbo(&mut self, turbo: Turbo) {
+        self.turbo = Some(turbo);
+    }
+
+    /// Returns the `Turbo` settings, if enabled
+    pub fn turbo_mut(&mut self) -> Option<&mut Turbo> {
+        self.turbo.as_mut()
+    }
+
+    /// Returns how writes below `0x200` are handled
+    pub fn write_protection(&self) -> WriteProtection {
+        self.write_protection
+    }
+
+    /// Sets how writes below `0x200` are handled. See `WriteProtection`
+    pub fn set_write_protection(&mut self, write_protection: WriteProtection) {
+        self.write_protection = write_protection;
+    }
+
+    /// Returns the seed of the random numbers of `Cxkk`, if set with `set_rng_seed`
+    pub fn rng_seed(&self) -> Option<u64> {
+        self.rng_seed
+    }
+
+    /// Seeds the random numbers of `Cxkk`, so runs with the same seed and inputs are
+    /// identical, ex: to replay recordings or compare `FrameHash` traces. The seed is
+    /// kept by `Chip8::reset`. Without one, every run draws different numbers
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng_seed = Some(seed);
+        self.rng = Rng::new(seed);
+    }
+
+    /// Returns how accesses beyond the end of memory are handled
+    pub fn memory_bounds(&self) -> MemoryBounds {
+        self.main_memory.bounds()
+    }
+
+    /// Sets how accesses beyond the end of memory are handled, ex: to wrap addresses
+    /// around like the original interpreters. See `MemoryBounds`
+    pub fn set_memory_bounds(&mut self, bounds: MemoryBounds) {
+        self.main_memory.set_bounds(bounds);
+    }
+
+    /// Enables or disables the strict spec mode, a development aid for anyone working on
+    /// the core or porting it: the invariants of the VM are checked around every instruction,
+    /// stopping at the first violation with a `Chip8Error::StrictViolation`.
+    /// `Chip8::fault_context` then reports the instruction, the registers and the last
+    /// instructions executed.
+    /// # Invariants
+    /// Before executing an instruction:
+    /// * Every `v` register index it uses is at most `0xF`.
+    /// * The memory it accesses through `I` (`Dxyn`, `Fx33`, `Fx55` and `Fx65`) is within memory.
+    /// * The key of `Ex9E` and `ExA1` and the digit of `Fx29` are at most `0xF`.
+    ///
+    /// After executing it:
+    /// * `PC` is even and within `0x200` - `0xFFE`.
+    /// * The Stack Pointer doesn't exceed the stack depth.
+    /// * `I` is within memory.
+    /// * `v[0xF]` is `0` or `1` after instructions using it as a flag.
+    /// * The display is empty after `CLS`, and `v[0xF]` matches the collisions of `Dxyn`.
+    ///
+    /// **Note:** Some ROMs legitimately break these invariants (ex: code at odd addresses),
+    /// so this mode is meant for known-good ROMs like test suites.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Returns `true` if the strict spec mode is enabled. See `Chip8::set_strict`
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Pauses or resumes the VM. While paused, `start` keeps rendering and handling host
+    /// keys but runs no cycles, so the timers and the sound stop along with the ROM.
+    /// Pausing takes effect between two cycles, and resuming continues from the very
+    /// same cycle without catching up on the time spent paused
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Sets the triggers checked after every cycle, replacing the previous ones. See `Triggers`
+    pub fn set_triggers(&mut self, triggers: Triggers) {
+        self.triggers = Some(triggers);
+    }
+
+    /// Returns the triggers checked after every cycle, if any
+    pub fn triggers(&self) -> Option<&Triggers> {
+        self.triggers.as_ref()
+    }
+
+    /// Returns `true` while the VM is paused. See `Chip8::set_paused`
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Returns the table of host keys bound to the actions of the emulator
+    #[cfg(feature = "window")]
+    pub fn hotkeys_mut(&mut self) -> &mut Hotkeys {
+        &mut self.hotkeys
+    }
+
+    /// Returns the map of host keys to the keypad
+    #[cfg(feature = "window")]
+    pub fn keymap(&self) -> &KeyMap {
+        &self.keymap
+    }
+
+    /// Swaps the map of host keys to the keypad, ex: for another keyboard layout.
+    /// Takes effect on the next key event, even while the window is open
+    /// # Errors
+    /// If the map uses a host key bound to a `HostAction`. The current map is kept
+    #[cfg(feature = "window")]
+    pub fn set_keymap(&mut self, keymap: KeyMap) -> Result<(), String> {
+        for keypad_key in 0..16 {
+            let key = keymap.host_key(keypad_key);
+            if let Some(action) = self.hotkeys.action(key) {
+                return Err(format!(
+                    "{} is bound to {}.",
+                    hotkeys::key_name(key),
+                    action.name()
+                ));
+            }
+        }
+        self.keymap = keymap;
+        Ok(())
+    }
+
+    /// Sets the config the keypad map is saved to when remapped with the `remap-keys`
+    /// hotkey, stored under `key` in `storage`, so the new keys are kept for the next runs.
+    /// See `config::save_keys`
+    #[cfg(feature = "window")]
+    pub fn set_config_storage(&mut self, storage: Box<dyn Storage + Send>, key: String) {
+        self.config_storage = Some((storage, key));
+    }
+
+    /// Handles a host key pressed while the `remap-keys` hotkey is held down: the first key
+    /// selects the keypad key it's mapped to, the next one becomes its new host key.
+    /// `selected` keeps the keypad key between the two presses
+    #[cfg(feature = "window")]
+    fn remap_key(&mut self, selected: &mut Option<usize>, key: piston::input::Key) {
+        use hotkeys::key_name;
+
+        let keypad_key = match selected.take() {
+            Some(keypad_key) => keypad_key,
+            None => {
+                match self.keymap.keypad_key(key) {
+                    Some(keypad_key) => {
+                        *selected = Some(keypad_key);
+                        self.osd
+                            .show(format!("Press the new key of {:X}.", keypad_key));
+                    }
+                    None => self
+                        .osd
+                        .show(format!("{} is not a key of the keypad.", key_name(key))),
+                }
+                return;
+            }
+        };
+
+        let previous = self.keymap;
+        let mut keymap = previous;
+        keymap.exchange(keypad_key, key);
+        if let Err(msg) = self.set_keymap(keymap) {
+            self.osd.show(msg);
+            return;
+        }
+        // Keys held down under their previous host keys would never be released
+        for other in (0..16).filter(|&k| keymap.host_key(k) != previous.host_key(k)) {
+            self.release_host_key(other);
+        }
+        self.osd
+            .show(format!("{:X} mapped to {}.", keypad_key, key_name(key)));
+        if let Some((storage, config_key)) = &mut self.config_storage {
+            match crate::config::save_keys(storage.as_mut(), config_key, &keymap.bindings()) {
+                Ok(()) => self.osd.show(format!("Keys saved to {}", config_key)),
+                Err(msg) => self.osd.show(msg),
+            }
+        }
+    }
+
+    /// Returns the map of gamepad buttons to the keypad
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_map(&self) -> &GamepadMap {
+        &self.gamepad_map
+    }
+
+    /// Swaps the map of gamepad buttons to the keypad. Takes effect on the next button event
+    #[cfg(feature = "gamepad")]
+    pub fn set_gamepad_map(&mut self, gamepad_map: GamepadMap) {
+        self.gamepad_map = gamepad_map;
+    }
+
+    /// Returns the on-screen display
+    pub fn osd(&self) -> &Osd {
+        &self.osd
+    }
+
+    /// Returns the on-screen display, ex: to show messages from a frontend
+    pub fn osd_mut(&mut self) -> &mut Osd {
+        &mut self.osd
+    }
+
+    /// Returns the input `Macros`
+    pub fn macros_mut(&mut self) -> &mut Macros {
+        &mut self.macros
+    }
+
+    /// Handles a host press of one of the 16 keys (`0x0` to `0xF`),
+    /// letting the `Turbo` take over if it's a turbo key
+    #[cfg(feature = "window")]
+    fn press_host_key(&mut self, key: usize) {
+        let key = self.swapped_key(key);
+        match &mut self.turbo {
+            Some(turbo) if turbo.is_turbo_key(key) => turbo.hold(key),
+            _ => self.press_key(key),
+        }
+    }
+
+    /// Handles a host release of one of the 16 keys (`0x0` to `0xF`)
+    #[cfg(feature = "window")]
+    fn release_host_key(&mut self, key: usize) {
+        let key = self.swapped_key(key);
+        if let Some(turbo) = &mut self.turbo {
+            turbo.release(key);
+        }
+        self.release_key(key);
+    }
+
+    /// Applies the synthetic presses and releases of the held turbo keys
+    #[cfg(feature = "window")]
+    fn update_turbo_keys(&mut self, now: std::time::Instant) {
+        for key in 0..self.input.key_status.len() {
+            let status = match &self.turbo {
+                Some(turbo) => turbo.status(key, now),
+                None => return,
+            };
+            match status {
+                Some(true) if !self.input.key_status[key] => self.press_key(key),
+                Some(false) if self.input.key_status[key] => self.release_key(key),
+                _ => {}
+            }
+        }
+    }
+
+    /// Marks one of the 16 keys (`0x0` to `0xF`) as pressed. Other keys are ignored
+    pub fn press_key(&mut self, key: usize) {
+        if key > 0xF {
+            return;
+        }
+        self.set_host_key(key, true);
+        self.macros.record(key, true);
+        if let Some(probe) = &mut self.latency_probe {
+            probe.key_pressed(key);
+        }
+    }
+
+    /// Marks one of the 16 keys (`0x0` to `0xF`) as released. Other keys are ignored
+    pub fn release_key(&mut self, key: usize) {
+        if key > 0xF {
+            return;
+        }
+        self.set_host_key(key, false);
+        self.macros.record(key, false);
+    }
+
+    /// Returns the keys pressed since the start of the frame, even if already released
+    pub fn keys_pressed(&self) -> [bool; 16] {
+        self.input.pressed
+    }
+
+    /// Returns the keys released since the start of the frame, except the ones already
+    /// taken by `Fx0A`. See `Quirks::wait_release`
+    pub fn keys_released(&self) -> [bool; 16] {
+        self.input.released
+    }
+
+    /// Checks that the `len` bytes starting at `address` can be accessed, so instructions
+    /// can fail before changing anything. See `MemoryBounds`
+    /// # Errors
+    /// `Chip8Error::MemoryOutOfBounds` with the first address beyond the memory,
+    /// with `MemoryBounds::Fault`
+    fn check_memory(&self, address: usize, len: usize) -> Result<(), Chip8Error> {
+        if self.main_memory.contains(address, len) {
+            return Ok(());
+        }
+        let (pc, opcode) = self.instruction;
+        Err(Chip8Error::MemoryOutOfBounds {
+            pc,
+            opcode,
+            address: address.max(self.main_memory.len()),
+        })
+    }
+
+    /// Reads a byte of data from memory, routing the access to the `Peripherals` if mapped.
+    /// Addresses beyond the memory are handled according to the `MemoryBounds`
+    fn read(&mut self, address: usize) -> u8 {
+        let resolved = self.main_memory.resolve(address);
+        if !self.watchpoints.is_empty() {
+            self.check_watchpoints(resolved.unwrap_or(address), false);
+        }
+        match (&mut self.peripherals, resolved) {
+            (Some(peripherals), Some(address)) if Peripherals::is_mapped(address) => {
+                peripherals.read(address)
+            }
+            _ => self.main_memory.read(address),
+        }
+    }
+
+    /// Checks that the `len` bytes starting at `address` can be written, so instructions
+    /// can fail before changing anything. See `Chip8::check_memory`
+    /// # Errors
+    /// `Chip8Error::MemoryOutOfBounds` like `Chip8::check_memory`, or
+    /// `Chip8Error::ProtectedWrite` with the first address below `0x200`, with
+    /// `WriteProtection::Error`
+    fn check_writes(&self, address: usize, len: usize) -> Result<(), Chip8Error> {
+        self.check_memory(address, len)?;
+        if self.write_protection != WriteProtection::Error {
+            return Ok(());
+        }
+        let protected = (address..address + len)
+            .filter_map(|address| self.main_memory.resolve(address))
+            .find(|&address| address < Chip8::INITIAL_MEMORY_ADDRESS);
+        match protected {
+            Some(address) => {
+                let (pc, opcode) = self.instruction;
+                Err(Chip8Error::ProtectedWrite {
+                    pc,
+                    opcode,
+                    address,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Writes a byte of data to memory, routing the access to the `Peripherals` if mapped.
+    /// Addresses beyond the memory are handled according to the `MemoryBounds`.
+    ///
+    /// **Note:** Instructions check their writes with `Chip8::check_writes` first, so writes
+    /// protected with `WriteProtection::Error` never get here
+    fn write(&mut self, address: usize, value: u8) {
+        let resolved = self.main_memory.resolve(address);
+        if !self.watchpoints.is_empty() {
+            self.check_watchpoints(resolved.unwrap_or(address), true);
+        }
+        let address = match resolved {
+            Some(address) => address,
+            None => return,
         };
+        if address < Chip8::INITIAL_MEMORY_ADDRESS {
+            self.warn_protected_write(address, value);
+        }
+
+        match &mut self.peripherals {
+            Some(peripherals) if Peripherals::is_mapped(address) => {
+                peripherals.write(address, value)
+            }
+            _ => self.main_memory.write(address, value),
+        }
+    }
+
+    /// Prints a warning the first time each address below `INITIAL_MEMORY_ADDRESS` is
+    /// written, with `WriteProtection::Warn`
+    fn warn_protected_write(&mut self, address: usize, value: u8) {
+        if self.write_protection != WriteProtection::Warn || self.protected_writes[address] {
+            return;
+        }
+        self.protected_writes[address] = true;
+        eprintln!(
+            "WARNING: Protected memory write: 0x{:02X} to 0x{:03X} ({}) by the instruction at 0x{:03X}",
+            value,
+            address,
+            error::protected_area(address),
+            self.instruction.0
+        );
+    }
+
+    /// Returns the current value of the `Program Counter`
+    pub fn pc(&self) -> u16 {
+        self.regs.pc
+    }
+
+    /// Returns the current value of the `I` (index) register
+    pub fn index(&self) -> u16 {
+        self.regs.i
+    }
+
+    /// Returns the 16 general purpose registers `v[0x0]` to `v[0xF]`
+    pub fn v(&self) -> &[u8; 16] {
+        &self.regs.v
+    }
+
+    /// Returns the return addresses currently stored in the Stack, from bottom to top
+    pub fn stack(&self) -> &[u16] {
+        &self.stack.stored
+    }
+
+    /// Returns the maximum amount of nested subroutines
+    pub fn stack_depth(&self) -> usize {
+        self.stack.depth
+    }
+
+    /// Sets the maximum amount of nested subroutines.
+    /// Stored addresses beyond the new `depth` are discarded.
+    /// # Panics
+    /// If `depth` is `0`
+    pub fn set_stack_depth(&mut self, depth: usize) {
+        assert!(depth > 0, "The stack must hold at least 1 address.");
+        self.stack.depth = depth;
+        self.stack.stored.truncate(depth);
+    }
+
+    /// Returns the current values of the `(delay, sound)` timers
+    pub fn timers(&self) -> (u8, u8) {
+        (self.timers.delay, self.timers.sound)
+    }
+
+    /// Returns the whole `main_memory`
+    pub fn memory(&self) -> &[u8] {
+        &self.main_memory
+    }
+
+    /// Returns the bytes of `main_memory` in `range`, ex: `0x200..0x210`
+    /// # Returns
+    /// `None` if `range` goes beyond the memory. See `Profile::memory_size`
+    pub fn memory_slice(&self, range: Range<usize>) -> Option<&[u8]> {
+        self.main_memory.get(range)
+    }
+
+    /// Returns whether any pixel changed since the last call. See `Display::take_dirty`
+    pub fn take_display_dirty(&mut self) -> bool {
+        self.display.take_dirty()
+    }
+
+    /// Returns both display planes, 64x32 pixels each or 128x64 in high resolution. The
+    /// second one is only drawn with `Profile::XoChip`. See `Display`
+    pub fn planes(&self) -> [&[bool]; 2] {
+        [self.display.plane(0), self.display.plane(1)]
+    }
+
+    /// Returns the display, ex: to render it as RGBA with `Display::to_rgba`
+    pub fn display(&self) -> &Display {
+        &self.display
+    }
+
+    /// Returns the 16 bytes of the XO-CHIP audio pattern buffer, loaded with `F002`:
+    /// 128 samples of 1 bit, played from the most significant bit of the first byte
+    /// while the sound timer is active
+    pub fn audio_pattern(&self) -> &[u8; 16] {
+        &self.audio_pattern
+    }
+
+    /// Returns the pitch of the XO-CHIP audio pattern, set with `Fx3A`.
+    /// See `AudioPattern::rate`
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    /// Returns what the VM plays right now: silence unless the sound timer is active,
+    /// then the audio pattern with `Profile::XoChip` once one is loaded, otherwise the
+    /// beep of the `Tone`.
+    ///
+    /// **Note:** An empty pattern plays the beep as well, so XO-CHIP ROMs that never
+    /// use `F002` still sound like on any other profile.
+    pub fn sound(&self) -> Sound {
+        if self.timers.sound == 0 {
+            Sound::Silent
+        } else if self.profile.has_xo_chip_instructions() && self.audio_pattern != [0; 16] {
+            Sound::Pattern(AudioPattern {
+                bits: self.audio_pattern,
+                pitch: self.pitch,
+            })
+        } else {
+            Sound::Beep
+        }
+    }
+
+    /// Returns the level of what the VM plays right now, from `0.0` while silent or paused
+    /// to `1.0`, fading out over the last 8 ticks of the sound timer. An audio pattern
+    /// plays at a level proportional to its bits set, up to half of them, so sparse
+    /// patterns are quieter. See `Chip8::sound` and `pulse`
+    pub fn sound_level(&self) -> f32 {
+        const FULL_LEVEL_SOUND_TIMER: u8 = 8;
+        if self.paused {
+            return 0.0;
+        }
+        let fade =
+            self.timers.sound.min(FULL_LEVEL_SOUND_TIMER) as f32 / FULL_LEVEL_SOUND_TIMER as f32;
+        match self.sound() {
+            Sound::Silent => 0.0,
+            Sound::Beep => fade,
+            Sound::Pattern(pattern) => {
+                let bits: u32 = pattern.bits.iter().map(|byte| byte.count_ones()).sum();
+                fade * (bits as f32 / 64.0).min(1.0)
+            }
+        }
+    }
+
+    /// Freezes the byte at `address` to `value`: it's written back after every cycle,
+    /// so the ROM always reads `value` there, ex: to keep a number of lives found with
+    /// a `RamSearch`. Freezing a frozen address replaces its value
+    /// # Errors
+    /// If `address` is outside of memory
+    pub fn freeze(&mut self, address: u16, value: u8) -> Result<(), String> {
+        if address as usize >= self.main_memory.len() {
+            return Err(format!("Address 0x{:X} is outside of memory.", address));
+        }
+        self.unfreeze(address);
+        self.frozen.push((address, value));
+        self.main_memory[address as usize] = value;
+        Ok(())
+    }
+
+    /// Stops freezing the byte at `address`
+    /// # Returns
+    /// `true` if the address was frozen.
+    pub fn unfreeze(&mut self, address: u16) -> bool {
+        let frozen = self.frozen.len();
+        self.frozen.retain(|&(frozen, _)| frozen != address);
+        self.frozen.len() != frozen
+    }
+
+    /// Returns the frozen addresses along with their values, in the order they were frozen
+    pub fn frozen(&self) -> &[(u16, u8)] {
+        &self.frozen
     }
 
     /// Cycle emulation for a VM.
@@ -193,32 +1439,203 @@ impl Chip8 {
     /// - Fetch the next instruction
     /// - Update the `Program Counter` before any instruction execution takes place
     /// - Decode the instruction and execute it
-    /// - Update both Timers (`delay` and `sound`) if needed
-    fn cycle(&mut self) {
+    /// - Update both Timers (`delay` and `sound`) when the `Clock` reaches a new frame
+    /// # Errors
+    /// If the instruction faults. It then has no effect and the `PC` is left pointing at it,
+    /// so the cycle ends right away. See `Chip8Error`
+    fn cycle(&mut self) -> Result<(), Chip8Error> {
+        if self.halted.is_some() {
+            return Ok(());
+        }
+        if let Some(guard) = &mut self.limits {
+            guard.start_cycle();
+        }
+        self.watch_hit = None;
+
+        // Replay macros
+        for input in self.macros.start_cycle() {
+            self.set_host_key(input.key, input.pressed);
+        }
+
         // Fetch
-        let opcode = self.fetch();
+        let address = self.regs.pc;
+        let opcode = self.fetch()?;
+        self.instruction = (address, opcode);
+        self.history.record(address, opcode);
+        let strict = match self.strict {
+            true => Some(StrictCheck::before(self, address, opcode)?),
+            false => None,
+        };
+        let traced = log::log_enabled!(log::Level::Trace).then_some((self.regs.v, self.regs.i));
 
         // Update PC
-        self.regs.pc += 2;
+        self.regs.pc = self.regs.pc.wrapping_add(2);
 
         // Decode and Execute
-        self.execute(opcode);
+        let executed = if self.profiler.is_some() {
+            let started_at = std::time::Instant::now();
+            let executed = self.execute(opcode);
+            let elapsed = started_at.elapsed();
+            if let Some(profiler) = &mut self.profiler {
+                profiler.record(address, opcode, elapsed);
+            }
+            executed
+        } else {
+            self.execute(opcode)
+        };
+        if let Err(error) = executed {
+            self.regs.pc = address;
+            return Err(error);
+        }
+        if let Some(check) = strict {
+            check.after(self)?;
+        }
+        if let Some((v, i)) = traced {
+            log::trace!("{}", self.trace_line(address, opcode, &v, i));
+        }
+        for &(address, value) in self.frozen.iter() {
+            self.main_memory[address as usize] = value;
+        }
+
+        // Check limits. Only `00FD` halts the VM while executing, the report is then
+        // replaced with the one of the guard, which tracks the cycles and time
+        if let Some(guard) = &mut self.limits {
+            self.halted = guard.end_cycle(self.regs.pc, self.halted.is_some());
+        }
+
+        // Check triggers
+        let fired = match &mut self.triggers {
+            Some(triggers) => triggers.check(&self.main_memory, self.regs.pc),
+            None => Vec::new(),
+        };
+        for (action, count) in fired {
+            self.run_trigger_action(&action, count);
+        }
+
+        #[cfg(feature = "scripting")]
+        self.run_script_cycle(address, opcode);
 
         // Handle timers
-        self.handle_timers();
+        let cycles = match self.clock.vip_timing {
+            true => timing::vip_cycles(opcode, self.regs.pc == address.wrapping_add(4)),
+            false => 1,
+        };
+        for _ in 0..self.clock.advance(cycles) {
+            self.handle_timers();
+        }
+        Ok(())
     }
 
     /// Fetches an OP Code as `u16` from the `main_memory` according to the current PC
     /// and returns it
-    fn fetch(&mut self) -> u16 {
-        let lows = (self.main_memory[self.regs.pc as usize] as u16) << 8;
-        let highs = self.main_memory[(self.regs.pc as usize) + 1] as u16;
-        return lows | highs;
+    /// # Errors
+    /// If the OP Code lies beyond the memory
+    fn fetch(&mut self) -> Result<u16, Chip8Error> {
+        self.instruction = (self.regs.pc, 0);
+        self.check_memory(self.regs.pc as usize, 2)?;
+        let lows = (self.main_memory.read(self.regs.pc as usize) as u16) << 8;
+        let highs = self.main_memory.read((self.regs.pc as usize) + 1) as u16;
+        Ok(lows | highs)
+    }
+
+    /// Returns the number of frames of the virtual clock elapsed since the ROM started:
+    /// ticks of the timers, which run at `FRAME_RATE` on the emulated hardware.
+    /// Unlike wall time, it doesn't depend on the speed of the host, so recordings and
+    /// replays timestamped with it stay in sync when fast-forwarding or when the host hiccups
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Returns the time elapsed on the virtual clock since the ROM started. See `Chip8::frames`
+    pub fn virtual_time(&self) -> Duration {
+        Duration::from_secs(self.frames) / Chip8::FRAME_RATE
+    }
+
+    /// Returns the number of instructions executed per emulated second. See `Clock`
+    pub fn cycle_rate(&self) -> u32 {
+        self.clock.cycle_rate
+    }
+
+    /// Sets the number of instructions executed per emulated second, which is also the pace
+    /// of `start`. The timers keep ticking at `FRAME_RATE`
+    /// # Panics
+    /// If `cycle_rate` is `0`
+    pub fn set_cycle_rate(&mut self, cycle_rate: u32) {
+        assert!(cycle_rate > 0, "The cycle rate must be positive.");
+        self.clock.cycle_rate = cycle_rate;
+        self.clock.phase = 0;
+    }
+
+    /// Enables or disables the timing of the COSMAC VIP: instead of running at the cycle
+    /// rate, every instruction lasts the machine cycles it took on the original
+    /// interpreter, ex: `Dxyn` lasts much longer than `7xkk`. The cycle rate is kept for
+    /// when it's disabled
+    pub fn set_vip_timing(&mut self, enabled: bool) {
+        self.clock.vip_timing = enabled;
+        self.clock.phase = 0;
+    }
+
+    /// Returns `true` if the timing of the COSMAC VIP is enabled. See `Chip8::set_vip_timing`
+    pub fn vip_timing(&self) -> bool {
+        self.clock.vip_timing
+    }
+
+    /// Sets the cycle rate to run `instructions` per frame, ex: `11` for about the speed of
+    /// the original COSMAC VIP. See `Chip8::set_cycle_rate`
+    /// # Panics
+    /// If `instructions` is `0`
+    pub fn set_instructions_per_frame(&mut self, instructions: u32) {
+        self.set_cycle_rate(instructions.saturating_mul(Chip8::FRAME_RATE));
+    }
+
+    /// Runs the action of a trigger that fired for the `count`-th time
+    fn run_trigger_action(&mut self, action: &TriggerAction, count: u32) {
+        match action {
+            TriggerAction::Log(message) => self.osd.show(message.clone()),
+            TriggerAction::Screenshot(path) => {
+                let path = path.replace("{n}", &count.to_string());
+                let image = if path.ends_with(".pbm") {
+                    self.display_pbm().into_bytes()
+                } else {
+                    self.thumbnail_png()
+                };
+                match std::fs::write(&path, image) {
+                    Ok(()) => self.osd.show(format!("Screenshot saved to {}", path)),
+                    Err(e) => self
+                        .osd
+                        .show(format!("Failed to save the screenshot {}: {}", path, e)),
+                }
+            }
+            TriggerAction::Pause => {
+                self.paused = true;
+                self.osd.show("Paused by a trigger.");
+            }
+            TriggerAction::Sound => {
+                // Standard error, so it never ends up in images written to standard output
+                use std::io::Write;
+                let mut stderr = std::io::stderr();
+                let _ = stderr.write_all(b"\x07").and_then(|_| stderr.flush());
+            }
+        }
     }
 
     /// Updates both timers in an instance of a `VM`
     /// If a timers is higher than `0` then it's decremented by `1`
     fn handle_timers(&mut self) {
+        if let Some(guard) = &mut self.limits {
+            guard.frame();
+        }
+        self.frames += 1;
+        self.input.start_frame();
+        self.advance_movie();
+        self.apply_cheats();
+        #[cfg(feature = "scripting")]
+        self.run_script(ScriptEvent::Frame, (self.frames as i64,));
+        let sound = self.sound();
+        if let Some(track) = &mut self.audio_track {
+            track.record_tick(sound);
+        }
+
         if self.timers.delay > 0 {
             self.timers.delay -= 1;
         }
@@ -228,113 +1645,737 @@ impl Chip8 {
         }
     }
 
-    /// Decodes and executes the next instruction according to the current PC
-    /// In case the decoding fails - ex: invalid OP Code - the execution
-    /// is treated as a `NO-OP` (No Operation)
-    fn execute(&mut self, opcode: u16) {
-        let nibbles = (
-            (opcode & 0xF000) >> 12 as u8,
-            (opcode & 0x0F00) >> 8 as u8,
-            (opcode & 0x00F0) >> 4 as u8,
-            (opcode & 0x000F) as u8,
-        );
+    /// Decodes and executes the next instruction according to the current PC.
+    /// The SUPER-CHIP instructions need `Profile::Schip` or `Profile::XoChip`, and the
+    /// XO-CHIP instructions `Profile::XoChip`: other profiles don't know them
+    /// # Errors
+    /// If the decoding fails - ex: invalid OP Code, or an instruction of an extension the
+    /// profile lacks - or if the instruction faults
+    fn execute(&mut self, opcode: u16) -> Result<(), Chip8Error> {
+        let unknown = Chip8Error::UnknownOpcode {
+            pc: self.instruction.0,
+            opcode,
+        };
+        let instruction = Instruction::decode(opcode).ok_or_else(|| unknown.clone())?;
 
-        let nnn = (opcode & 0x0FFF) as u16;
-        let kk = (opcode & 0x00FF) as u8;
-        let x = nibbles.1 as usize;
-        let y = nibbles.2 as usize;
-        let n = nibbles.3 as usize;
-
-        match nibbles {
-            (0x0, 0x0, 0xE, 0x0) => self.cls(),
-            (0x0, 0x0, 0xE, 0xE) => self.ret(),
-            (0x1, _, _, _) => self.jp(nnn),
-            (0x2, _, _, _) => self.call(nnn),
-            (0x3, _, _, _) => self.se_vx_byte(x, kk),
-            (0x4, _, _, _) => self.sne_vx_byte(x, kk),
-            (0x5, _, _, 0x0) => self.se_vx_vy(x, y),
-            (0x6, _, _, _) => self.ld_vx_value(x, kk),
-            (0x7, _, _, _) => self.add_vx_byte(x, kk),
-            (0x8, _, _, 0x0) => self.ld_vx_vy(x, y),
-            (0x8, _, _, 0x1) => self.or_vx_vy(x, y),
-            (0x8, _, _, 0x2) => self.and_vx_vy(x, y),
-            (0x8, _, _, 0x3) => self.xor_vx_vy(x, y),
-            (0x8, _, _, 0x4) => self.add_vx_vy(x, y),
-            (0x8, _, _, 0x5) => self.sub_vx_vy(x, y),
-            (0x8, _, _, 0x6) => self.shr_vx(x),
-            (0x8, _, _, 0x7) => self.subn_vx_vy(x, y),
-            (0x8, _, _, 0xE) => self.shl_vx(x),
-            (0x9, _, _, 0x0) => self.sne_vx_vy(x, y),
-            (0xA, _, _, _) => self.ld_i_addr(nnn),
-            (0xB, _, _, _) => self.jp_v0_addr(nnn),
-            (0xC, _, _, _) => self.rnd_vx_byte(x, kk),
-            (0xD, _, _, _) => self.drw_vx_vy_n(x, y, n),
-            (0xE, _, 0x9, 0xE) => self.skip_vx(x),
-            (0xE, _, 0xA, 0x1) => self.skip_n_vx(x),
-            (0xF, _, 0x0, 0x7) => self.ld_vx_dt(x),
-            (0xF, _, 0x0, 0xA) => self.ld_vx_k(x),
-            (0xF, _, 0x1, 0x5) => self.ld_dt_vx(x),
-            (0xF, _, 0x1, 0x8) => self.ld_st_vx(x),
-            (0xF, _, 0x1, 0xE) => self.add_i_vx(x),
-            (0xF, _, 0x2, 0x9) => self.ld_f_vx(x),
-            (0xF, _, 0x3, 0x3) => self.ld_b_vx(x),
-            (0xF, _, 0x5, 0x5) => self.ld_i_vx(x),
-            (0xF, _, 0x6, 0x5) => self.ld_vx_i(x),
-            _ => {}
+        match instruction {
+            Instruction::Cls => self.cls(),
+            Instruction::Ret => self.ret()?,
+            Instruction::Jp(nnn) => self.jp(nnn),
+            Instruction::Call(nnn) => self.call(nnn)?,
+            Instruction::SeVxByte(x, kk) => self.se_vx_byte(x, kk),
+            Instruction::SneVxByte(x, kk) => self.sne_vx_byte(x, kk),
+            Instruction::SeVxVy(x, y) => self.se_vx_vy(x, y),
+            Instruction::LdVxValue(x, kk) => self.ld_vx_value(x, kk),
+            Instruction::AddVxByte(x, kk) => self.add_vx_byte(x, kk),
+            Instruction::LdVxVy(x, y) => self.ld_vx_vy(x, y),
+            Instruction::OrVxVy(x, y) => self.or_vx_vy(x, y),
+            Instruction::AndVxVy(x, y) => self.and_vx_vy(x, y),
+            Instruction::XorVxVy(x, y) => self.xor_vx_vy(x, y),
+            Instruction::AddVxVy(x, y) => self.add_vx_vy(x, y),
+            Instruction::SubVxVy(x, y) => self.sub_vx_vy(x, y),
+            Instruction::ShrVx(x, y) => self.shr_vx(x, y),
+            Instruction::SubnVxVy(x, y) => self.subn_vx_vy(x, y),
+            Instruction::ShlVx(x, y) => self.shl_vx(x, y),
+            Instruction::SneVxVy(x, y) => self.sne_vx_vy(x, y),
+            Instruction::LdIAddr(nnn) => self.ld_i_addr(nnn),
+            Instruction::JpV0Addr(nnn) => self.jp_v0_addr(nnn),
+            Instruction::RndVxByte(x, kk) => self.rnd_vx_byte(x, kk),
+            Instruction::DrwVxVyN(x, y, n) => self.drw_vx_vy_n(x, y, n)?,
+            Instruction::SkipVx(x) => self.skip_vx(x),
+            Instruction::SkipNVx(x) => self.skip_n_vx(x),
+            Instruction::LdVxDt(x) => self.ld_vx_dt(x),
+            Instruction::LdVxK(x) => self.ld_vx_k(x),
+            Instruction::LdDtVx(x) => self.ld_dt_vx(x),
+            Instruction::LdStVx(x) => self.ld_st_vx(x),
+            Instruction::AddIVx(x) => self.add_i_vx(x),
+            Instruction::LdFVx(x) => self.ld_f_vx(x),
+            Instruction::LdBVx(x) => self.ld_b_vx(x)?,
+            Instruction::LdIVx(x) => self.ld_i_vx(x)?,
+            Instruction::LdVxI(x) => self.ld_vx_i(x)?,
+            // Every profile with the XO-CHIP instructions has the SUPER-CHIP ones as well
+            _ if !self.profile.has_schip_instructions() => return Err(unknown),
+            Instruction::LdRVx(x) => self.ld_r_vx(x),
+            Instruction::LdVxR(x) => self.ld_vx_r(x),
+            Instruction::ScrollDown(n) => self.scroll_down(n),
+            Instruction::ScrollRight => self.scroll_right(),
+            Instruction::ScrollLeft => self.scroll_left(),
+            Instruction::Exit => self.exit(),
+            Instruction::Low => self.low(),
+            Instruction::High => self.high(),
+            Instruction::LdHfVx(x) => self.ld_hf_vx(x),
+            _ if !self.profile.has_xo_chip_instructions() => return Err(unknown),
+            Instruction::ScrollUp(n) => self.scroll_up(n),
+            Instruction::SaveVxVy(x, y) => self.save_vx_vy(x, y)?,
+            Instruction::LoadVxVy(x, y) => self.load_vx_vy(x, y)?,
+            Instruction::LdILong => self.ld_i_long()?,
+            Instruction::Plane(n) => self.plane(n),
+            Instruction::Audio => self.audio()?,
+            Instruction::Pitch(x) => self.pitch_vx(x),
         };
+        Ok(())
     }
 
     /// Starts an the execution of a `CHIP-8` VM.
     /// This will create a main window and manage an infinite loop
-    pub fn start(&mut self, cycle_delay: u64) {
+    ///
+    /// The loop is paced by frames: `FRAME_RATE` times per second it runs the cycles of a
+    /// frame at the cycle rate of the VM, ex: 11 for `660` instructions per second, during
+    /// which the timers tick once, and the display is rendered. Between frames the thread
+    /// sleeps. See `Chip8::set_instructions_per_frame`
+    ///
+    /// When a `debug_hook` is provided it takes control of the execution:
+    /// the hook is responsible for running cycles and can terminate the loop.
+    /// **Note:** Only available with the `window` feature.
+    #[cfg(feature = "window")]
+    pub fn start(
+        &mut self,
+        video_settings: VideoSettings,
+        mut debug_hook: Option<&mut dyn DebugHook>,
+    ) {
+        use frame_skip::FrameSkip;
+        use hotkeys::key_name;
         use piston::input::*;
-        use piston::{EventSettings, Events};
-        use std::time::{Duration, Instant};
+        use piston::{EventLoop, EventSettings, Events, Window};
+        use std::time::Instant;
         use utils::*;
 
-        let mut window = build_window();
+        let mut window = build_window(video_settings);
         let mut gl = build_graphics();
-        let cycle_delay = Duration::from_millis(cycle_delay);
+        let frame_period = Duration::from_secs(1) / Chip8::FRAME_RATE;
+        // Longest run of late frames executed at once, dropped beyond it (ex: after a stall)
+        let max_catch_up = Duration::from_millis(100);
 
-        let mut last_cycle_time = Instant::now();
+        let mut next_frame_time = Instant::now();
+        // Cycles owed to the next frames, in `1 / FRAME_RATE` cycles, so cycle rates that
+        // aren't a multiple of the frame rate keep their average speed. Negative when the
+        // last instruction lasted longer than the cycles left, see `Chip8::set_vip_timing`
+        let mut cycle_budget: i64 = 0;
+        // Set when the VM was paused by `pause_on_focus_loss`, so only those pauses are lifted
+        // when the window gets the focus back
+        let mut paused_by_focus = false;
+        let mut show_keypad = video_settings.show_keypad;
+        // Thumbnail of the save slot selected or loaded last, shown as long as OSD messages
+        let mut slot_preview: Option<(Vec<usize>, Instant)> = None;
+        // Set while the `rewind` hotkey is held down, frames are then rewound instead of run
+        let mut rewinding = false;
+        // Set while the `remap-keys` hotkey is held down, keys then remap the keypad
+        // instead of reaching the ROM, see `Chip8::remap_key`
+        let mut remapping = false;
+        let mut remapped_key = None;
+        // Changed by the `speed-up`, `slow-down` and `fast-forward` hotkeys
+        let mut speed = SpeedControl::new();
+        let mut overlay = DebugOverlay::new();
+        // Brightness of the pixels fading out, with filters imitating slow screens
+        let mut phosphor = video_settings.filter.decay().map(Phosphor::new);
+        // Resolution last drawn, as pixels of the other one can't fade into it
+        let mut hires = self.display.is_hires();
+        // Last frames emulated, blended to hide flicker
+        let mut blend = match video_settings.anti_flicker {
+            AntiFlicker::Off => None,
+            mode => Some(FrameBlend::new(
+                mode,
+                video_settings
+                    .anti_flicker_frames
+                    .unwrap_or(FrameBlend::DEFAULT_FRAMES),
+            )),
+        };
+        let mut last_render = Instant::now();
+
+        let mut frame_skip = match video_settings.frame_skip {
+            Some(skip) => FrameSkip::fixed(skip),
+            None => FrameSkip::automatic(),
+        };
+        // Without an audio device the ROM keeps running silently
+        #[cfg(feature = "sound")]
+        let beeper = match self.tone.volume {
+            0 => None,
+            _ => match sound::Beeper::open(self.tone.clone()) {
+                Ok(beeper) => Some(beeper),
+                Err(msg) => {
+                    self.osd.show(format!("Sound disabled: {}", msg));
+                    None
+                }
+            },
+        };
 
-        let mut events = Events::new(EventSettings::new());
+        // Without gamepad support the keyboard keeps working
+        #[cfg(feature = "gamepad")]
+        let mut gamepads = match gamepad::Gamepads::open() {
+            Ok(gamepads) => Some(gamepads),
+            Err(msg) => {
+                self.osd.show(format!("Gamepads disabled: {}", msg));
+                None
+            }
+        };
+
+        // Buffers are only swapped when redrawn, so the last frame stays on screen
+        let mut events = Events::new(
+            EventSettings::new()
+                .ups(Chip8::FRAME_RATE as u64)
+                .max_fps(frame_skip.max_fps())
+                .swap_buffers(false),
+        );
+        // Set when the last frame drawn needs to be drawn again even if the display didn't
+        // change, ex: after a resize or when overlays were shown on top of it
+        let mut redraw = true;
+        let mut window_size = [0.0; 2];
         while let Some(e) = events.next(&mut window) {
-            if let Some(Button::Keyboard(_key)) = e.press_args() {
-                // TODO: Handle key press
+            if let Some(Button::Keyboard(key)) = e.press_args() {
+                if remapping && self.hotkeys.action(key) != Some(HostAction::RemapKeys) {
+                    self.remap_key(&mut remapped_key, key);
+                } else if let Some(key) = self.keymap.keypad_key(key) {
+                    self.press_host_key(key);
+                } else if let Some(action) = self.hotkeys.action(key) {
+                    match action {
+                        HostAction::Quit if !video_settings.kiosk => break,
+                        HostAction::Quit => {}
+                        HostAction::ToggleTurbo => {
+                            if let Some(turbo) = self.turbo_mut() {
+                                let enabled = turbo.toggle();
+                                self.osd.show(format!(
+                                    "Turbo {}.",
+                                    if enabled { "enabled" } else { "disabled" }
+                                ));
+                            }
+                        }
+                        HostAction::CopyDisassembly
+                        | HostAction::CopyRegisters
+                        | HostAction::CopyDisplay => {
+                            let (name, text) = match action {
+                                HostAction::CopyDisassembly => {
+                                    ("Disassembly", self.disassembly_line())
+                                }
+                                HostAction::CopyRegisters => ("Registers", self.register_dump()),
+                                _ => ("Display", self.display_text()),
+                            };
+                            match clipboard::copy_to_clipboard(&text) {
+                                Ok(()) => {
+                                    self.osd.show(format!("{} copied to the clipboard.", name))
+                                }
+                                Err(msg) => self
+                                    .osd
+                                    .show(format!("Failed to copy to the clipboard: {}", msg)),
+                            }
+                        }
+                        HostAction::RecordMacro => {
+                            self.macros.start_recording();
+                            let keys: Vec<String> = (0..Macros::SLOTS)
+                                .filter_map(|slot| self.hotkeys.key(HostAction::Macro(slot)))
+                                .map(key_name)
+                                .collect();
+                            self.osd.show(format!(
+                                "Recording macro, press {} to bind it.",
+                                keys.join(", ")
+                            ));
+                        }
+                        HostAction::Macro(slot) => {
+                            if let Some(recorded) = self.macros.stop_recording(slot) {
+                                self.osd.show(format!(
+                                    "Macro bound to {} ({} inputs).",
+                                    key_name(key),
+                                    recorded
+                                ));
+                            } else if !self.macros.play(slot) {
+                                self.osd
+                                    .show(format!("No macro bound to {}.", key_name(key)));
+                            }
+                        }
+                        HostAction::BugReport => {
+                            let timestamp = std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .map_or(0, |elapsed| elapsed.as_secs());
+                            let path = format!("bug-report-{}.zip", timestamp);
+                            let config = format!(
+                                "Command line: {}\nCycle rate: {}Hz\nVideo: {:?}",
+                                std::env::args().collect::<Vec<String>>().join(" "),
+                                self.cycle_rate(),
+                                video_settings
+                            );
+                            match self.write_bug_report(&path, &config) {
+                                Ok(()) => self.osd.show(format!("Bug report saved to {}", path)),
+                                Err(msg) => self
+                                    .osd
+                                    .show(format!("Failed to save the bug report: {}", msg)),
+                            }
+                        }
+                        HostAction::ToggleKeypad => show_keypad = !show_keypad,
+                        HostAction::SaveState => {
+                            let slot = self.save_slot;
+                            match self.save_to_slot(slot) {
+                                Ok(()) => self.osd.show(format!("State saved to slot {}.", slot)),
+                                Err(msg) => self.osd.show(msg),
+                            }
+                        }
+                        HostAction::LoadState => {
+                            let slot = self.save_slot;
+                            match self.load_from_slot(slot) {
+                                Ok(()) => {
+                                    slot_preview = self.slot_preview(slot);
+                                    self.osd.show(format!("State loaded from slot {}.", slot))
+                                }
+                                Err(msg) => self.osd.show(msg),
+                            }
+                        }
+                        HostAction::NextSlot => {
+                            self.save_slot = self.save_slot % Chip8::SAVE_SLOTS + 1;
+                            let slot = self.save_slot;
+                            slot_preview = self.slot_preview(slot);
+                            match slot_preview {
+                                Some(_) => self.osd.show(format!("Slot {} selected.", slot)),
+                                None => self.osd.show(format!("Slot {} selected, empty.", slot)),
+                            }
+                        }
+                        HostAction::Rewind => match self.rewind() {
+                            None => self.osd.show("Rewind is disabled."),
+                            // Key repeats are ignored, and so is a press with nothing to rewind
+                            Some(rewind) if !rewinding && !rewind.is_empty() => {
+                                rewinding = true;
+                                self.osd.show("Rewinding...");
+                            }
+                            Some(_) => {}
+                        },
+                        HostAction::TogglePause => {
+                            self.paused = !self.paused;
+                            paused_by_focus = false;
+                            self.osd
+                                .show(if self.paused { "Paused." } else { "Resumed." });
+                        }
+                        HostAction::Reset => {
+                            self.reset();
+                            self.osd.show("Reset.");
+                        }
+                        // Key repeats are ignored, and so is remapping in kiosk mode
+                        HostAction::RemapKeys if !remapping && !video_settings.kiosk => {
+                            remapping = true;
+                            remapped_key = None;
+                            self.osd
+                                .show("Press a key of the keypad, then its new key.");
+                        }
+                        HostAction::RemapKeys => {}
+                        HostAction::SpeedUp | HostAction::SlowDown => {
+                            match action {
+                                HostAction::SpeedUp => speed.faster(),
+                                _ => speed.slower(),
+                            }
+                            self.osd.show(format!("Speed {}.", speed.label()));
+                        }
+                        HostAction::FastForward => speed.set_fast_forward(true),
+                        HostAction::ToggleOverlay => overlay.toggle(),
+                        HostAction::ToggleFullscreen if !video_settings.kiosk => {
+                            toggle_fullscreen(&window);
+                            redraw = true;
+                        }
+                        HostAction::ToggleFullscreen => {}
+                    }
+                }
             };
-            if let Some(button) = e.release_args() {
-                if let Button::Keyboard(_key) = button {
-                    // TODO: Handle key release
-                };
+            if let Some(Button::Keyboard(key)) = e.release_args() {
+                if let Some(key) = self.keymap.keypad_key(key) {
+                    self.release_host_key(key);
+                } else {
+                    match self.hotkeys.action(key) {
+                        Some(HostAction::Rewind) => rewinding = false,
+                        Some(HostAction::RemapKeys) => remapping = false,
+                        Some(HostAction::FastForward) => speed.set_fast_forward(false),
+                        _ => {}
+                    }
+                }
             };
+            if let Some(focused) = e.focus_args() {
+                redraw = true;
+                if video_settings.pause_on_focus_loss {
+                    if !focused && !self.paused {
+                        self.paused = true;
+                        paused_by_focus = true;
+                        self.osd.show("Paused while the window is inactive.");
+                    } else if focused && paused_by_focus {
+                        self.paused = false;
+                        paused_by_focus = false;
+                        self.osd.show("Resumed.");
+                    }
+                }
+            }
+            #[cfg(feature = "gamepad")]
+            if let Some(gamepads) = gamepads.as_mut() {
+                for (button, pressed) in gamepads.poll() {
+                    match self.gamepad_map.keypad_key(button) {
+                        Some(key) if pressed => self.press_host_key(key),
+                        Some(key) => self.release_host_key(key),
+                        None => {}
+                    }
+                }
+            }
+            self.update_turbo_keys(Instant::now());
+            for message in self.osd.take_log() {
+                println!("{}", message);
+            }
+            if let Some([x, _]) = e.mouse_cursor_args() {
+                let width = window.size().width;
+                if let Some(peripherals) = self.peripherals_mut() {
+                    peripherals.set_adc(((x / width).clamp(0.0, 1.0) * 255.0) as u8);
+                }
+            }
 
-            if let Some(args) = e.render_args() {
+            // Only drawn when something changed since the last frame drawn
+            let render_args = e.render_args().filter(|args| {
+                let overlays = show_keypad
+                    || overlay.visible()
+                    || !speed.is_normal()
+                    || !self.osd.visible(Instant::now()).is_empty()
+                    || video_settings.audio_reactive
+                    || slot_preview
+                        .as_ref()
+                        .is_some_and(|(_, shown)| shown.elapsed() < Osd::DEFAULT_DURATION)
+                    || phosphor.as_ref().is_some_and(Phosphor::is_fading);
+                let draw = self.display.take_dirty()
+                    || redraw
+                    || overlays
+                    || args.window_size != window_size;
+                redraw = overlays;
+                window_size = args.window_size;
+                draw
+            });
+            if let Some(args) = render_args {
                 use graphics::*;
 
-                let pixel_size: f64 = 20.0;
-                let square = rectangle::square(0.0, 0.0, pixel_size);
+                // Scale the display to fit the window, centered
+                let [width, height] = args.window_size;
+                let (columns, rows) = (self.display.width(), self.display.height());
+                let Geometry {
+                    offset: [offset_x, offset_y],
+                    pixel: [pixel_width, pixel_height],
+                } = video_settings
+                    .scaling
+                    .geometry([width, height], [columns, rows]);
+                let square = [0.0, 0.0, pixel_width, pixel_height];
+                // Gaps between the cells of the grid, when they're large enough to show it
+                let cell = match pixel_width.min(pixel_height) {
+                    size if video_settings.filter.grid() && size >= 4.0 => {
+                        let gap = (size / 8.0).floor().max(1.0);
+                        [0.0, 0.0, pixel_width - gap, pixel_height - gap]
+                    }
+                    _ => square,
+                };
+                let now = Instant::now();
+                if hires != self.display.is_hires() {
+                    hires = self.display.is_hires();
+                    phosphor = video_settings.filter.decay().map(Phosphor::new);
+                }
+                if let Some(phosphor) = &mut phosphor {
+                    let display = &self.display;
+                    match &blend {
+                        Some(blend) => phosphor.update(|pos| blend.pixel(pos).0, now - last_render),
+                        None => phosphor.update(|pos| display.color(pos), now - last_render),
+                    }
+                }
+                last_render = now;
+                let mut colors = video_settings.palette.colors(video_settings.high_contrast);
+                if video_settings.audio_reactive {
+                    colors = pulse(colors, self.sound_level());
+                }
+                let background = to_color(colors[0]);
+                let foregrounds = [
+                    background,
+                    to_color(colors[1]),
+                    to_color(colors[2]),
+                    to_color(colors[3]),
+                ];
 
                 gl.draw(args.viewport(), |ctx, gl| {
-                    clear(BLACK, gl);
-                    for (pos, &is_pixel_on) in self.display.buffer.iter().enumerate() {
-                        let x: f64 = (pos % Chip8::VIDEO_WIDTH) as f64 * pixel_size;
-                        let y: f64 = (pos / Chip8::VIDEO_WIDTH) as f64 * pixel_size;
+                    clear(background, gl);
+                    for pos in 0..self.display.size() {
+                        let (color, level) = match (&phosphor, &blend) {
+                            (Some(phosphor), _) => phosphor.pixel(pos).unwrap_or((0, 0.0)),
+                            (None, Some(blend)) => blend.pixel(pos),
+                            (None, None) => (self.display.color(pos), 1.0),
+                        };
+                        let x: f64 = offset_x + (pos % columns) as f64 * pixel_width;
+                        let y: f64 = offset_y + (pos / columns) as f64 * pixel_height;
                         let transform = ctx.transform.trans(x, y);
-                        if is_pixel_on {
-                            rectangle(WHITE, square, transform, gl);
+                        let mut color = match color {
+                            // Unlit cells of an LCD stay faintly visible
+                            0 if video_settings.filter.grid() => {
+                                let mut unlit = foregrounds[1];
+                                unlit[3] = LCD_UNLIT_ALPHA;
+                                unlit
+                            }
+                            0 => continue,
+                            color => foregrounds[color],
+                        };
+                        color[3] *= level;
+                        rectangle(color, cell, transform, gl);
+                    }
+                    if video_settings.filter.scanlines() && pixel_height >= 3.0 {
+                        let line = [
+                            0.0,
+                            0.0,
+                            pixel_width * columns as f64,
+                            (pixel_height / 3.0).floor(),
+                        ];
+                        for row in 0..rows {
+                            let y = offset_y + (row + 1) as f64 * pixel_height - line[3];
+                            rectangle(SCANLINE, line, ctx.transform.trans(offset_x, y), gl);
+                        }
+                    }
+
+                    // The same in both resolutions: a quarter of a pixel of the low one
+                    let lores_pixel =
+                        pixel_width.min(pixel_height) * (columns / Chip8::VIDEO_WIDTH) as f64;
+                    let text_pixel = (lores_pixel / 4.0).max(2.0);
+
+                    // Speed, unless running in real time, and the debug overlay in the
+                    // top left corner
+                    let mut lines = Vec::new();
+                    if !speed.is_normal() {
+                        lines.push(speed.label());
+                    }
+                    if overlay.visible() {
+                        lines.extend(overlay.lines(self));
+                    }
+                    let line_height = (GLYPH_HEIGHT + 3) as f64 * text_pixel;
+                    for (line, text) in lines.iter().enumerate() {
+                        let x = text_pixel * 2.0;
+                        let y = text_pixel * 2.0 + line as f64 * (line_height + text_pixel);
+                        let size = text_size(text);
+                        let backdrop = [
+                            0.0,
+                            0.0,
+                            (size[0] + 2) as f64 * text_pixel,
+                            (size[1] + 2) as f64 * text_pixel,
+                        ];
+                        rectangle(OSD_BACKDROP, backdrop, ctx.transform.trans(x, y), gl);
+                        for [col, row] in text_pixels(text) {
+                            let transform = ctx.transform.trans(
+                                x + (col + 1) as f64 * text_pixel,
+                                y + (row + 1) as f64 * text_pixel,
+                            );
+                            rectangle(
+                                OSD_TEXT,
+                                rectangle::square(0.0, 0.0, text_pixel),
+                                transform,
+                                gl,
+                            );
+                        }
+                    }
+
+                    // Keypad in the top right corner, labeled from the current `KeyMap`
+                    if show_keypad {
+                        let labels: Vec<String> = (0..16).map(|k| self.keymap.label(k)).collect();
+                        let cell_width = labels
+                            .iter()
+                            .map(|label| text_size(label)[0] + 2)
+                            .max()
+                            .unwrap_or_default() as f64
+                            * text_pixel;
+                        let cell_height = (GLYPH_HEIGHT + 2) as f64 * text_pixel;
+                        let left = width - 4.0 * cell_width - text_pixel * 2.0;
+                        let top = text_pixel * 2.0;
+                        let backdrop = [0.0, 0.0, 4.0 * cell_width, 4.0 * cell_height];
+                        rectangle(OSD_BACKDROP, backdrop, ctx.transform.trans(left, top), gl);
+                        for (row, keys) in KeyMap::LAYOUT.iter().enumerate() {
+                            for (col, &key) in keys.iter().enumerate() {
+                                let x = left + col as f64 * cell_width;
+                                let y = top + row as f64 * cell_height;
+                                if self.input.key_status[key] {
+                                    let cell = [0.0, 0.0, cell_width, cell_height];
+                                    rectangle(KEYPAD_PRESSED, cell, ctx.transform.trans(x, y), gl);
+                                }
+                                for [text_col, text_row] in text_pixels(&labels[key]) {
+                                    let transform = ctx.transform.trans(
+                                        x + (text_col + 1) as f64 * text_pixel,
+                                        y + (text_row + 1) as f64 * text_pixel,
+                                    );
+                                    rectangle(
+                                        OSD_TEXT,
+                                        rectangle::square(0.0, 0.0, text_pixel),
+                                        transform,
+                                        gl,
+                                    );
+                                }
+                            }
+                        }
+                    }
+
+                    // Thumbnail of the save slot in the bottom right corner, at the scale
+                    // of the text
+                    let preview = slot_preview
+                        .as_ref()
+                        .filter(|(_, shown)| shown.elapsed() < Osd::DEFAULT_DURATION);
+                    if let Some((pixels, _)) = preview {
+                        let left = width - (Chip8::VIDEO_WIDTH + 3) as f64 * text_pixel;
+                        let top = height - (Chip8::VIDEO_HEIGHT + 3) as f64 * text_pixel;
+                        let backdrop = [
+                            0.0,
+                            0.0,
+                            (Chip8::VIDEO_WIDTH + 2) as f64 * text_pixel,
+                            (Chip8::VIDEO_HEIGHT + 2) as f64 * text_pixel,
+                        ];
+                        rectangle(OSD_BACKDROP, backdrop, ctx.transform.trans(left, top), gl);
+                        for (pos, &color) in pixels.iter().enumerate().filter(|(_, &c)| c != 0) {
+                            let transform = ctx.transform.trans(
+                                left + (pos % Chip8::VIDEO_WIDTH + 1) as f64 * text_pixel,
+                                top + (pos / Chip8::VIDEO_WIDTH + 1) as f64 * text_pixel,
+                            );
+                            rectangle(
+                                foregrounds[color],
+                                rectangle::square(0.0, 0.0, text_pixel),
+                                transform,
+                                gl,
+                            );
+                        }
+                    }
+
+                    // Messages of the OSD, newest at the bottom
+                    let line_height = (GLYPH_HEIGHT + 3) as f64 * text_pixel;
+                    let messages = self.osd.visible(Instant::now());
+                    for (line, message) in messages.iter().rev().enumerate() {
+                        let x = text_pixel * 2.0;
+                        let y = height - (line + 1) as f64 * (line_height + text_pixel);
+                        let size = text_size(message);
+                        let backdrop = [
+                            0.0,
+                            0.0,
+                            (size[0] + 2) as f64 * text_pixel,
+                            (size[1] + 2) as f64 * text_pixel,
+                        ];
+                        rectangle(OSD_BACKDROP, backdrop, ctx.transform.trans(x, y), gl);
+                        for [col, row] in text_pixels(message) {
+                            let transform = ctx.transform.trans(
+                                x + (col + 1) as f64 * text_pixel,
+                                y + (row + 1) as f64 * text_pixel,
+                            );
+                            rectangle(
+                                OSD_TEXT,
+                                rectangle::square(0.0, 0.0, text_pixel),
+                                transform,
+                                gl,
+                            );
                         }
                     }
                 });
+                window.swap_buffers();
+
+                if overlay.visible() {
+                    overlay.frame_presented(Instant::now(), self);
+                }
+                if let Some(probe) = &mut self.latency_probe {
+                    probe.frame_presented();
+                }
+                if let Some(max_fps) = frame_skip.frame_presented(Instant::now(), &mut self.osd) {
+                    events.set_max_fps(max_fps);
+                }
             }
 
-            if let Some(_) = e.update_args() {
-                let dt = last_cycle_time.elapsed();
-                if dt > cycle_delay {
-                    self.cycle();
-                    last_cycle_time = Instant::now();
+            if e.update_args().is_some() {
+                // Run every frame due since the last update, so the timers tick in real time
+                // even when an update comes late
+                let now = Instant::now();
+                if (self.paused && !rewinding) || now > next_frame_time + max_catch_up {
+                    next_frame_time = now;
+                }
+                while next_frame_time <= now && self.halted.is_none() && (rewinding || !self.paused)
+                {
+                    if rewinding {
+                        // Resumes from the rewound frame, ex: to try again after a fault
+                        if self.rewind_frame() {
+                            self.paused = false;
+                        } else {
+                            rewinding = false;
+                            self.osd.show("Nothing left to rewind.");
+                        }
+                        next_frame_time += speed.frame_period(frame_period);
+                        continue;
+                    }
+                    cycle_budget += self.clock.rate() as i64;
+                    while cycle_budget >= Chip8::FRAME_RATE as i64 {
+                        if self.halted.is_some() || self.paused {
+                            break;
+                        }
+                        let elapsed = self.clock.elapsed;
+                        match debug_hook.as_deref_mut() {
+                            Some(hook) if hook.should_exit() => break,
+                            Some(hook) => hook.update(self),
+                            None => {
+                                // Paused, so the state can be inspected (ex: copied) as it
+                                // was right before the fault
+                                if let Err(error) = self.step() {
+                                    self.paused = true;
+                                    self.osd.show(format!("Paused: {}", error));
+                                }
+                            }
+                        }
+                        cycle_budget -= self.clock.spent_since(elapsed) * Chip8::FRAME_RATE as i64;
+                    }
+                    cycle_budget = Clock::carried_budget(cycle_budget);
+                    self.record_frame();
+                    if let Some(blend) = &mut blend {
+                        blend.push(&self.display);
+                        redraw = true;
+                    }
+                    next_frame_time += speed.frame_period(frame_period);
                 }
+                #[cfg(feature = "sound")]
+                if let Some(beeper) = &beeper {
+                    beeper.set_sound(if self.paused {
+                        Sound::Silent
+                    } else {
+                        self.sound()
+                    });
+                }
+            };
+
+            if let Some(hook) = &debug_hook {
+                if hook.should_exit() {
+                    break;
+                }
+            }
+            if let Some(report) = self.halt_report() {
+                println!("{}", report);
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn shrinking_memory_unfreezes_addresses_beyond_it() {
+    let mut vm = Chip8::new();
+    vm.set_profile(Profile::XoChip);
+    vm.freeze(0x300, 0xAA).unwrap();
+    vm.freeze(0xF000, 0xBB).unwrap();
+    vm.set_profile(Profile::Standard);
+    assert_eq!(vm.frozen(), &[(0x300, 0xAA)]);
+    // 00E0, the frozen byte is written back without indexing past memory
+    vm.main_memory[0x200] = 0x00;
+    vm.main_memory[0x201] = 0xE0;
+    vm.cycle().unwrap();
+    assert_eq!(vm.main_memory[0x300], 0xAA);
+}
+
+#[test]
+fn fetching_across_the_end_of_memory_follows_the_memory_bounds() {
+    for profile in [Profile::Standard, Profile::XoChip] {
+        let last = profile.memory_size() - 1;
+        for bounds in MemoryBounds::ALL {
+            let mut vm = Chip8::new();
+            vm.set_profile(profile);
+            vm.set_memory_bounds(bounds);
+            // `1234` when the second byte wraps around, `1200` when it reads the open bus
+            vm.main_memory[last] = 0x12;
+            vm.main_memory[0] = 0x34;
+            vm.regs.pc = last as u16;
+            let fetched = vm.cycle().map(|()| vm.regs.pc);
+            let expected = match bounds {
+                MemoryBounds::Fault => Err(Chip8Error::MemoryOutOfBounds {
+                    pc: last as u16,
+                    opcode: 0,
+                    address: last + 1,
+                }),
+                MemoryBounds::Wrap => Ok(0x234),
+                MemoryBounds::OpenBus => Ok(0x200),
             };
+            assert_eq!(fetched, expected, "{:?} {:?}", profile, bounds);
         }
     }
 }
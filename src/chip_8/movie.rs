@@ -0,0 +1,328 @@
+use super::Chip8;
+use std::convert::TryFrom;
+use std::fs;
+
+/// Identifies movie files, followed by the version of their format
+const MAGIC: &[u8; 4] = b"C8MV";
+
+/// Version of the format written by `Movie::to_bytes`
+const VERSION: u8 = 1;
+
+/// Longest movie `Movie::parse` accepts, a day of frames, so a corrupted or hostile file
+/// can't claim billions of frames and exhaust the memory
+const MAX_FRAMES: usize = 24 * 60 * 60 * Chip8::FRAME_RATE as usize;
+
+/// The inputs of a run, recorded from power-on to replay it exactly, ex: for tool-assisted
+/// runs or bug reports. Holds the keys held on every frame along with the seed of the
+/// random generator, so `Cxkk` draws the same numbers. See `Chip8::record_movie`
+///
+/// **Note:** Movies only replay the same run with the same `Profile`, `Quirks` and speed,
+/// which are not recorded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Movie {
+    rom_crc32: u32,
+    seed: u64,
+    /// Keys held on every frame, as bit masks where bit `n` is key `n`
+    frames: Vec<u16>,
+}
+
+/// A movie being recorded or replayed by a `Chip8`
+pub(super) enum MovieState {
+    Recording {
+        movie: Movie,
+        /// Keys held by the host, applied on the next frame
+        keys: u16,
+    },
+    Playback {
+        movie: Movie,
+        /// Next frame replayed
+        frame: usize,
+    },
+}
+
+impl Movie {
+    /// Creates an empty movie of the ROM with `rom_crc32`, whose `Cxkk` draws from `seed`
+    pub fn new(rom_crc32: u32, seed: u64) -> Movie {
+        Movie {
+            rom_crc32,
+            seed,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Returns the `CRC32` of the ROM recorded. See `Chip8::rom_crc32`
+    pub fn rom_crc32(&self) -> u32 {
+        self.rom_crc32
+    }
+
+    /// Returns the seed of the random generator. See `Chip8::set_rng_seed`
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the number of frames recorded
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns the keys held on `frame`, as a bit mask where bit `n` is key `n`,
+    /// or `None` past the end of the movie
+    pub fn keys(&self, frame: usize) -> Option<u16> {
+        self.frames.get(frame).copied()
+    }
+
+    /// Appends a frame holding `keys`, as a bit mask where bit `n` is key `n`
+    pub fn push(&mut self, keys: u16) {
+        self.frames.push(keys);
+    }
+
+    /// Encodes the movie. Frames are stored as runs of the same keys, so a movie takes
+    /// a few bytes per key press.
+    /// # Format
+    /// A versioned binary layout, big-endian: `C8MV`, the version, the `CRC32` of the ROM,
+    /// the seed, the number of runs, and every run: its frames (`u32`) and keys (`u16`).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut runs: Vec<(u32, u16)> = Vec::new();
+        for &keys in self.frames.iter() {
+            match runs.last_mut() {
+                Some((frames, held)) if *held == keys && *frames < u32::MAX => *frames += 1,
+                _ => runs.push((1, keys)),
+            }
+        }
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.rom_crc32.to_be_bytes());
+        bytes.extend_from_slice(&self.seed.to_be_bytes());
+        bytes.extend_from_slice(&(runs.len() as u32).to_be_bytes());
+        for (frames, keys) in runs {
+            bytes.extend_from_slice(&frames.to_be_bytes());
+            bytes.extend_from_slice(&keys.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes a movie encoded with `to_bytes`
+    /// # Errors
+    /// If the data is not a movie, was written by an unknown version, is truncated, or
+    /// holds more than a day of frames
+    pub fn parse(bytes: &[u8]) -> Result<Movie, String> {
+        let field = |start: usize, len: usize| {
+            bytes
+                .get(start..start + len)
+                .ok_or_else(|| "Truncated movie.".to_string())
+        };
+        if field(0, MAGIC.len())? != MAGIC {
+            return Err("Not a movie.".to_string());
+        }
+        let version = field(4, 1)?[0];
+        if version != VERSION {
+            return Err(format!("Unsupported movie version {}.", version));
+        }
+        let rom_crc32 = u32::from_be_bytes(<[u8; 4]>::try_from(field(5, 4)?).unwrap());
+        let seed = u64::from_be_bytes(<[u8; 8]>::try_from(field(9, 8)?).unwrap());
+        let runs = u32::from_be_bytes(<[u8; 4]>::try_from(field(17, 4)?).unwrap()) as usize;
+
+        let mut movie = Movie::new(rom_crc32, seed);
+        for run in 0..runs {
+            let run = field(21 + run * 6, 6)?;
+            let frames = u32::from_be_bytes(<[u8; 4]>::try_from(&run[..4]).unwrap());
+            let keys = u16::from_be_bytes(<[u8; 2]>::try_from(&run[4..]).unwrap());
+            if frames as usize > MAX_FRAMES - movie.frames.len() {
+                return Err("Movie too long.".to_string());
+            }
+            movie
+                .frames
+                .extend(std::iter::repeat_n(keys, frames as usize));
+        }
+        Ok(movie)
+    }
+
+    /// Reads a movie from the file at `path`. See `Movie::parse`
+    /// # Errors
+    /// If the file can't be read or is not a valid movie
+    pub fn load(path: &str) -> Result<Movie, String> {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        Movie::parse(&bytes).map_err(|e| format!("Invalid movie {}: {}", path, e))
+    }
+
+    /// Writes the movie to the file at `path`. See `Movie::to_bytes`
+    /// # Errors
+    /// If the file can't be written
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_bytes()).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+}
+
+impl Chip8 {
+    /// Restarts the ROM and records its inputs into a `Movie`, until `stop_movie`.
+    /// The random generator is seeded, with a random seed unless one was set with
+    /// `set_rng_seed`, so the movie replays the same numbers.
+    ///
+    /// Keys are sampled once per frame, so while recording, the keys pressed and
+    /// released take effect on the next frame: replays then see them on the same cycle.
+    pub fn record_movie(&mut self) {
+        let seed = self.rng_seed.unwrap_or_else(rand::random);
+        self.set_rng_seed(seed);
+        self.reset();
+        self.movie = Some(MovieState::Recording {
+            movie: Movie::new(self.rom_crc32(), seed),
+            keys: 0,
+        });
+    }
+
+    /// Restarts the ROM and replays `movie`, with its seed. Keys from the host are ignored
+    /// until the movie ends, then the keys are released and the host gets the control back
+    /// # Errors
+    /// If the movie was recorded from another ROM
+    pub fn play_movie(&mut self, movie: Movie) -> Result<(), String> {
+        if movie.rom_crc32() != self.rom_crc32() {
+            return Err("The movie was recorded from another ROM.".to_string());
+        }
+        self.set_rng_seed(movie.seed());
+        self.reset();
+        self.movie = Some(MovieState::Playback { movie, frame: 0 });
+        Ok(())
+    }
+
+    /// Stops recording or replaying a movie
+    /// # Returns
+    /// The movie recorded or being replayed, if any.
+    pub fn stop_movie(&mut self) -> Option<Movie> {
+        match self.movie.take()? {
+            MovieState::Recording { movie, .. } | MovieState::Playback { movie, .. } => Some(movie),
+        }
+    }
+
+    /// Returns `true` while a movie is being recorded
+    pub fn is_recording_movie(&self) -> bool {
+        matches!(self.movie, Some(MovieState::Recording { .. }))
+    }
+
+    /// Returns `true` while a movie is being replayed
+    pub fn is_playing_movie(&self) -> bool {
+        matches!(self.movie, Some(MovieState::Playback { .. }))
+    }
+
+    /// Sets a key from the host: deferred to the next frame while recording a movie,
+    /// and ignored while replaying one
+    pub(super) fn set_host_key(&mut self, key: usize, pressed: bool) {
+        match &mut self.movie {
+            Some(MovieState::Recording { keys, .. }) if pressed => *keys |= 1 << key,
+            Some(MovieState::Recording { keys, .. }) => *keys &= !(1 << key),
+            Some(MovieState::Playback { .. }) => {}
+            None => self.set_keypad_key(key, pressed),
+        }
+    }
+
+    /// Sets the status of a key of the keypad read by the ROM, telling the script when
+    /// it changes
+    fn set_keypad_key(&mut self, key: usize, pressed: bool) {
+        if self.input.key_status[key] != pressed {
+            self.input.set_key(key, pressed);
+            #[cfg(feature = "scripting")]
+            self.run_script(super::ScriptEvent::Key, (key as i64, pressed));
+        }
+    }
+
+    /// Starts the movie over, ex: when the ROM is reset
+    pub(super) fn restart_movie(&mut self) {
+        match &mut self.movie {
+            Some(MovieState::Recording { movie, .. }) => movie.frames.clear(),
+            Some(MovieState::Playback { frame, .. }) => *frame = 0,
+            None => {}
+        }
+    }
+
+    /// Records or replays the keys of a new frame of the movie, if any
+    pub(super) fn advance_movie(&mut self) {
+        let keys = match &mut self.movie {
+            Some(MovieState::Recording { movie, keys }) => {
+                movie.push(*keys);
+                *keys
+            }
+            Some(MovieState::Playback { movie, frame }) => match movie.keys(*frame) {
+                Some(keys) => {
+                    *frame += 1;
+                    keys
+                }
+                None => {
+                    self.movie = None;
+                    self.osd.show("Movie finished.");
+                    0
+                }
+            },
+            None => return,
+        };
+        for key in 0..self.input.key_status.len() {
+            self.set_keypad_key(key, keys & (1 << key) != 0);
+        }
+    }
+}
+
+#[test]
+fn movies_round_trip() {
+    let mut movie = Movie::new(0x1234_5678, 42);
+    for keys in [0, 0, 0b1, 0b1, 0b1, 0x8001, 0, 0xFFFF] {
+        movie.push(keys);
+    }
+    let bytes = movie.to_bytes();
+    // Header and 5 runs
+    assert_eq!(bytes.len(), 21 + 5 * 6);
+    assert_eq!(Movie::parse(&bytes), Ok(movie));
+
+    let empty = Movie::new(0, 0);
+    assert_eq!(Movie::parse(&empty.to_bytes()), Ok(empty));
+}
+
+#[test]
+fn truncated_movies_are_rejected() {
+    let mut movie = Movie::new(0xCAFE, 7);
+    movie.push(0b10);
+    movie.push(0);
+    let bytes = movie.to_bytes();
+    for len in 4..bytes.len() {
+        assert_eq!(
+            Movie::parse(&bytes[..len]),
+            Err("Truncated movie.".to_string()),
+            "{} bytes",
+            len
+        );
+    }
+}
+
+#[test]
+fn garbage_is_rejected() {
+    assert_eq!(Movie::parse(b""), Err("Truncated movie.".to_string()));
+    assert_eq!(
+        Movie::parse(b"RIFF\x01\x02\x03\x04"),
+        Err("Not a movie.".to_string())
+    );
+
+    let mut bytes = Movie::new(0, 0).to_bytes();
+    bytes[4] = VERSION + 1;
+    assert_eq!(
+        Movie::parse(&bytes),
+        Err(format!("Unsupported movie version {}.", VERSION + 1))
+    );
+
+    // A single run of u32::MAX frames, about 8 GB of keys
+    let mut bytes = Movie::new(0, 0).to_bytes();
+    bytes[17..21].copy_from_slice(&1u32.to_be_bytes());
+    bytes.extend_from_slice(&u32::MAX.to_be_bytes());
+    bytes.extend_from_slice(&0u16.to_be_bytes());
+    assert_eq!(Movie::parse(&bytes), Err("Movie too long.".to_string()));
+
+    // Runs within the limit on their own, but not together
+    let mut bytes = Movie::new(0, 0).to_bytes();
+    bytes[17..21].copy_from_slice(&2u32.to_be_bytes());
+    for _ in 0..2 {
+        bytes.extend_from_slice(&(MAX_FRAMES as u32).to_be_bytes());
+        bytes.extend_from_slice(&0u16.to_be_bytes());
+    }
+    assert_eq!(Movie::parse(&bytes), Err("Movie too long.".to_string()));
+}
@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// On-screen display: a queue of short timed messages (ex: "Turbo enabled.")
+/// giving feedback about host actions, independent of any frontend.
+/// Graphical frontends render the `visible` messages over the display,
+/// while every message is also kept for the log until taken with `take_log`,
+/// so frontends without a screen (ex: `headless`) can print them instead.
+pub struct Osd {
+    /// Messages that can be shown, oldest first
+    toasts: VecDeque<Toast>,
+    /// Messages not yet taken by `take_log`, oldest first
+    log: Vec<String>,
+}
+
+/// A message of the `Osd`
+struct Toast {
+    text: String,
+    expires_at: Instant,
+}
+
+impl Osd {
+    /// How long messages are shown by default
+    pub const DEFAULT_DURATION: Duration = Duration::from_secs(2);
+
+    /// Maximum number of messages shown at once: older ones are dropped
+    pub const MAX_VISIBLE: usize = 4;
+
+    pub fn new() -> Osd {
+        Osd {
+            toasts: VecDeque::with_capacity(Osd::MAX_VISIBLE),
+            log: Vec::new(),
+        }
+    }
+
+    /// Shows `text` for `DEFAULT_DURATION`
+    pub fn show<S: Into<String>>(&mut self, text: S) {
+        self.show_for(text, Osd::DEFAULT_DURATION);
+    }
+
+    /// Shows `text` for `duration`
+    pub fn show_for<S: Into<String>>(&mut self, text: S, duration: Duration) {
+        let text = text.into();
+        self.log.push(text.clone());
+        if self.toasts.len() == Osd::MAX_VISIBLE {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            text,
+            expires_at: Instant::now() + duration,
+        });
+    }
+
+    /// Hides all messages, ex: when the screen they describe goes away
+    pub fn clear(&mut self) {
+        self.toasts.clear();
+    }
+
+    /// Returns the messages to show at `now`, oldest first
+    pub fn visible(&self, now: Instant) -> Vec<&str> {
+        self.toasts
+            .iter()
+            .filter(|toast| toast.expires_at > now)
+            .map(|toast| toast.text.as_str())
+            .collect()
+    }
+
+    /// Returns the messages shown since the last call, oldest first, ex: to print them
+    pub fn take_log(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.log)
+    }
+}
+
+impl Default for Osd {
+    fn default() -> Osd {
+        Osd::new()
+    }
+}
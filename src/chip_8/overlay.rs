@@ -0,0 +1,84 @@
+use super::Chip8;
+use std::time::{Duration, Instant};
+
+/// Debug overlay of the window, listing the measured frame and instruction rates, the
+/// registers and the timers. Toggled by the `debug-overlay` hotkey.
+///
+/// Rates are measured over periods of `MEASURE_PERIOD`, so they stay readable
+/// while the values they average change every frame.
+pub struct DebugOverlay {
+    visible: bool,
+    /// Start of the current measure, with the instructions executed at that time
+    measure_start: Option<(Instant, u64)>,
+    /// Frames presented since the start of the current measure
+    frames: u32,
+    /// Rates of the last measure: frames and instructions per second
+    fps: f64,
+    ips: f64,
+}
+
+impl DebugOverlay {
+    /// Duration over which rates are averaged
+    const MEASURE_PERIOD: Duration = Duration::from_secs(1);
+
+    /// Creates the overlay, hidden
+    pub fn new() -> DebugOverlay {
+        DebugOverlay {
+            visible: false,
+            measure_start: None,
+            frames: 0,
+            fps: 0.0,
+            ips: 0.0,
+        }
+    }
+
+    /// Shows or hides the overlay
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.measure_start = None;
+    }
+
+    /// Returns whether the overlay is shown
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Called once a frame was presented on screen at `now`, with the instructions
+    /// executed by `vm` so far
+    pub fn frame_presented(&mut self, now: Instant, vm: &Chip8) {
+        let instructions = vm.clock.instructions;
+        let (started_at, started_with) = *self.measure_start.get_or_insert((now, instructions));
+        self.frames += 1;
+        let elapsed = now.duration_since(started_at);
+        if elapsed >= DebugOverlay::MEASURE_PERIOD {
+            self.fps = self.frames as f64 / elapsed.as_secs_f64();
+            self.ips = (instructions - started_with) as f64 / elapsed.as_secs_f64();
+            self.frames = 0;
+            self.measure_start = Some((now, instructions));
+        }
+    }
+
+    /// Returns the lines of the overlay for `vm`, ex: `PC 2A4  I 3F0`
+    pub fn lines(&self, vm: &Chip8) -> Vec<String> {
+        let registers = |range: std::ops::Range<usize>| {
+            vm.regs.v[range]
+                .iter()
+                .map(|value| format!("{:02X}", value))
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+        vec![
+            format!("FPS {:.0}  IPS {:.0}", self.fps, self.ips),
+            format!("PC {:03X}  I {:03X}", vm.regs.pc, vm.regs.i),
+            format!("V0-7 {}", registers(0..8)),
+            format!("V8-F {}", registers(8..16)),
+            format!("DT {:02X}  ST {:02X}", vm.timers.delay, vm.timers.sound),
+        ]
+    }
+}
+
+impl Default for DebugOverlay {
+    fn default() -> DebugOverlay {
+        DebugOverlay::new()
+    }
+}
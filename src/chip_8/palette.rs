@@ -0,0 +1,222 @@
+/// A color as red, green and blue components
+pub type Rgb = [u8; 3];
+
+/// Colors of a `Palette`, indexed by the planes a pixel is lit on: background,
+/// first plane, second plane and both planes. The original `CHIP-8` only uses
+/// the first two, the others are meant for XO-CHIP's second plane.
+pub type PaletteColors = [Rgb; 4];
+
+/// Named color schemes used to render the display, shared by all frontends.
+/// Besides the classic black and white and the looks of old screens (phosphor monitors
+/// and LCDs), presets are chosen for the most common color-vision deficiencies: their
+/// colors differ along the hues each deficiency still tells apart.
+///
+/// Every preset also has a high contrast variant: a black background and colors
+/// with clearly distinct luminance, so each plane can be told apart even without
+/// any color perception. The variants keep a contrast ratio of at least `4.5:1`
+/// against the background and `1.8:1` between planes (`WCAG` relative luminance).
+///
+/// `Custom` colors are used as given, with or without high contrast.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Palette {
+    /// White on black
+    #[default]
+    Classic,
+    /// Amber and blue, for deuteranopia (red-green, the most common deficiency)
+    Deuteranopia,
+    /// Yellow and sky blue, for protanopia (red-green, with dimmed reds)
+    Protanopia,
+    /// Red and teal, for tritanopia (blue-yellow)
+    Tritanopia,
+    /// Green phosphor monitor
+    Green,
+    /// Amber phosphor monitor
+    Amber,
+    /// Dark pixels on the green-gray of early handheld LCDs
+    Lcd,
+    /// User defined colors, ex: from `--fg` and `--bg`. The second XO-CHIP plane is
+    /// drawn halfway between both colors, and pixels on both planes with a lighter
+    /// foreground
+    Custom { foreground: Rgb, background: Rgb },
+}
+
+impl Palette {
+    /// All available presets, in the order they are presented to users
+    pub const ALL: [Palette; 7] = [
+        Palette::Classic,
+        Palette::Deuteranopia,
+        Palette::Protanopia,
+        Palette::Tritanopia,
+        Palette::Green,
+        Palette::Amber,
+        Palette::Lcd,
+    ];
+
+    /// Returns the name used to select the palette, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Classic => "classic",
+            Palette::Deuteranopia => "deuteranopia",
+            Palette::Protanopia => "protanopia",
+            Palette::Tritanopia => "tritanopia",
+            Palette::Green => "green",
+            Palette::Amber => "amber",
+            Palette::Lcd => "lcd",
+            Palette::Custom { .. } => "custom",
+        }
+    }
+
+    /// Finds a preset by its name
+    pub fn from_name(name: &str) -> Option<Palette> {
+        Palette::ALL
+            .iter()
+            .copied()
+            .find(|palette| palette.name() == name)
+    }
+
+    /// Returns the colors of the palette, or of its high contrast variant
+    pub fn colors(&self, high_contrast: bool) -> PaletteColors {
+        match (self, high_contrast) {
+            (Palette::Classic, false) => [
+                [0x00, 0x00, 0x00],
+                [0xFF, 0xFF, 0xFF],
+                [0xAA, 0xAA, 0xAA],
+                [0x55, 0x55, 0x55],
+            ],
+            (Palette::Classic, true) => [
+                [0x00, 0x00, 0x00],
+                [0xE0, 0xBD, 0x00],
+                [0x29, 0x7B, 0xCC],
+                [0xFF, 0xFF, 0xFF],
+            ],
+            (Palette::Deuteranopia, false) => [
+                [0x10, 0x10, 0x10],
+                [0xFF, 0xB0, 0x00],
+                [0x64, 0x8F, 0xFF],
+                [0xF0, 0xF0, 0xF0],
+            ],
+            (Palette::Deuteranopia, true) => [
+                [0x00, 0x00, 0x00],
+                [0xFF, 0xA0, 0x00],
+                [0x26, 0x72, 0xF3],
+                [0xFF, 0xFF, 0xFF],
+            ],
+            (Palette::Protanopia, false) => [
+                [0x10, 0x10, 0x10],
+                [0xF0, 0xE4, 0x42],
+                [0x56, 0xB4, 0xE9],
+                [0xF0, 0xF0, 0xF0],
+            ],
+            (Palette::Protanopia, true) => [
+                [0x00, 0x00, 0x00],
+                [0xEB, 0xB8, 0x00],
+                [0x00, 0x79, 0xDD],
+                [0xFF, 0xFF, 0xFF],
+            ],
+            (Palette::Tritanopia, false) => [
+                [0x10, 0x10, 0x10],
+                [0xFF, 0x80, 0x80],
+                [0x00, 0xC2, 0xC2],
+                [0xF0, 0xF0, 0xF0],
+            ],
+            (Palette::Tritanopia, true) => [
+                [0x00, 0x00, 0x00],
+                [0x00, 0xD4, 0xD4],
+                [0xEA, 0x25, 0x25],
+                [0xFF, 0xFF, 0xFF],
+            ],
+            (Palette::Green, false) => [
+                [0x00, 0x14, 0x00],
+                [0x33, 0xFF, 0x33],
+                [0x1A, 0x99, 0x1A],
+                [0xCC, 0xFF, 0xCC],
+            ],
+            (Palette::Green, true) => [
+                [0x00, 0x00, 0x00],
+                [0x20, 0xCC, 0x20],
+                [0x00, 0x8C, 0x00],
+                [0xE6, 0xFF, 0xE6],
+            ],
+            (Palette::Amber, false) => [
+                [0x1A, 0x0E, 0x00],
+                [0xFF, 0xB0, 0x00],
+                [0x99, 0x66, 0x00],
+                [0xFF, 0xE0, 0xA0],
+            ],
+            (Palette::Amber, true) => [
+                [0x00, 0x00, 0x00],
+                [0xFF, 0xA0, 0x00],
+                [0xB3, 0x6B, 0x00],
+                [0xFF, 0xF5, 0xE0],
+            ],
+            (Palette::Lcd, false) => [
+                [0x9B, 0xBC, 0x0F],
+                [0x0F, 0x38, 0x0F],
+                [0x5C, 0x80, 0x0F],
+                [0x30, 0x62, 0x30],
+            ],
+            (Palette::Lcd, true) => [
+                [0x00, 0x00, 0x00],
+                [0xA0, 0xC8, 0x20],
+                [0x5C, 0x8C, 0x10],
+                [0xF0, 0xFF, 0xC0],
+            ],
+            (
+                Palette::Custom {
+                    foreground,
+                    background,
+                },
+                _,
+            ) => [
+                *background,
+                *foreground,
+                mix(*foreground, *background),
+                mix(*foreground, [0xFF, 0xFF, 0xFF]),
+            ],
+        }
+    }
+}
+
+/// Brightness of the foreground while no sound plays, with `pulse`
+const PULSE_IDLE_BRIGHTNESS: f32 = 0.6;
+
+/// Scales the brightness of the foreground colors of a palette with the `level` of the
+/// sound, from `0.0` to `1.0`, so the display pulses with it: dimmed while silent and
+/// at full brightness at full level. Shared by every frontend for `audio_reactive`,
+/// see `Chip8::sound_level`
+pub fn pulse(colors: PaletteColors, level: f32) -> PaletteColors {
+    let brightness = PULSE_IDLE_BRIGHTNESS + (1.0 - PULSE_IDLE_BRIGHTNESS) * level.clamp(0.0, 1.0);
+    let dim = |rgb: Rgb| rgb.map(|component| (component as f32 * brightness).round() as u8);
+    [colors[0], dim(colors[1]), dim(colors[2]), dim(colors[3])]
+}
+
+/// Returns the color halfway between `a` and `b`
+fn mix(a: Rgb, b: Rgb) -> Rgb {
+    [
+        ((a[0] as u16 + b[0] as u16) / 2) as u8,
+        ((a[1] as u16 + b[1] as u16) / 2) as u8,
+        ((a[2] as u16 + b[2] as u16) / 2) as u8,
+    ]
+}
+
+/// Parses a color in hexadecimal notation, ex: `#00FF00` or `00ff00`
+/// # Errors
+/// If `text` isn't 6 hexadecimal digits, optionally preceded by `#`
+pub fn parse_rgb(text: &str) -> Result<Rgb, String> {
+    let digits = text.strip_prefix('#').unwrap_or(text);
+    let component = |pos: usize| u8::from_str_radix(digits.get(pos..pos + 2)?, 16).ok();
+    match (digits.len(), component(0), component(2), component(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok([r, g, b]),
+        _ => Err(format!("Invalid color '{}', expected ex: #00FF00.", text)),
+    }
+}
+
+#[test]
+fn foregrounds_pulse_with_the_sound() {
+    let colors = Palette::Classic.colors(false);
+    assert_eq!(pulse(colors, 1.0), colors);
+    assert_eq!(
+        pulse(colors, 0.0),
+        [[0x00; 3], [0x99; 3], [0x66; 3], [0x33; 3]]
+    );
+}
@@ -0,0 +1,98 @@
+use std::io::{self, Write};
+use std::time::Instant;
+
+/// Memory-mapped pseudo-peripherals, letting ROMs talk to the host through a
+/// reserved range of high memory. Only available with `Profile::Peripherals`.
+/// # Memory map
+/// * `0xFF0` **Console** (write): Prints the written byte as an ASCII character
+///   on the host console. Output is flushed on every new line.
+/// * `0xFF1` - `0xFF2` **Timer** (read): Milliseconds elapsed since the timer was
+///   last reset, as a 16-bit big-endian value that wraps around.
+///   Reading `0xFF1` latches the value, so a following read of `0xFF2` is consistent.
+///   Writing any value to either address resets the timer.
+/// * `0xFF3` **ADC** (read): 8-bit analog input sampled by the host,
+///   ex: the horizontal position of the mouse inside the window.
+///
+/// All other addresses in the range are unmapped: reads return `0x0` and writes are ignored.
+pub struct Peripherals {
+    console: Box<dyn Write + Send>,
+    timer_start: Instant,
+    timer_latch: Option<u16>,
+    adc: u8,
+}
+
+impl Peripherals {
+    /// First address of the reserved range
+    pub const FIRST_ADDRESS: usize = 0xFF0;
+    /// Last address of the reserved range
+    pub const LAST_ADDRESS: usize = 0xFFF;
+
+    const CONSOLE: usize = 0xFF0;
+    const TIMER_HIGH: usize = 0xFF1;
+    const TIMER_LOW: usize = 0xFF2;
+    const ADC: usize = 0xFF3;
+
+    /// Creates the peripherals with the console connected to the host `stdout`
+    pub fn new() -> Peripherals {
+        Peripherals::with_console(Box::new(io::stdout()))
+    }
+
+    /// Creates the peripherals with the console connected to `console`
+    pub fn with_console(console: Box<dyn Write + Send>) -> Peripherals {
+        Peripherals {
+            console,
+            timer_start: Instant::now(),
+            timer_latch: None,
+            adc: 0,
+        }
+    }
+
+    /// Returns `true` if `address` is handled by the peripherals instead of the main memory
+    pub fn is_mapped(address: usize) -> bool {
+        (Peripherals::FIRST_ADDRESS..=Peripherals::LAST_ADDRESS).contains(&address)
+    }
+
+    /// Sets the value returned by the ADC
+    pub fn set_adc(&mut self, value: u8) {
+        self.adc = value;
+    }
+
+    /// Handles a read from a mapped `address`
+    pub fn read(&mut self, address: usize) -> u8 {
+        let elapsed = self.timer_start.elapsed().as_millis() as u16;
+
+        match address {
+            Peripherals::TIMER_HIGH => {
+                self.timer_latch = Some(elapsed);
+                (elapsed >> 8) as u8
+            }
+            Peripherals::TIMER_LOW => self.timer_latch.take().unwrap_or(elapsed) as u8,
+            Peripherals::ADC => self.adc,
+            _ => 0,
+        }
+    }
+
+    /// Handles a write of `value` to a mapped `address`
+    pub fn write(&mut self, address: usize, value: u8) {
+        match address {
+            Peripherals::CONSOLE => {
+                // The console is a best-effort output: ROMs have no way to handle host errors
+                let _ = self.console.write_all(&[value]);
+                if value == b'\n' {
+                    let _ = self.console.flush();
+                }
+            }
+            Peripherals::TIMER_HIGH | Peripherals::TIMER_LOW => {
+                self.timer_start = Instant::now();
+                self.timer_latch = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for Peripherals {
+    fn default() -> Peripherals {
+        Peripherals::new()
+    }
+}
@@ -0,0 +1,384 @@
+use super::{Chip8, DebugHook, Profile, Quirks};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// A key press or release played back at a given cycle of a ROM
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DemoInput {
+    /// Number of cycles executed since the ROM started
+    pub cycle: u64,
+    /// One of the 16 keys, from `0x0` to `0xF`
+    pub key: usize,
+    /// `true` for a press, `false` for a release
+    pub pressed: bool,
+}
+
+/// An entry of a `Playlist`: a ROM and the settings used to run it
+#[derive(Clone, Debug)]
+pub struct PlaylistEntry {
+    /// Name shown when the entry starts, usually the path of the ROM
+    pub name: String,
+    pub rom: Vec<u8>,
+    /// How long the entry runs before moving to the next one, on the virtual clock
+    /// (see `Chip8::virtual_time`). When `None`, `Playlist::DEFAULT_DURATION` is used
+    pub duration: Option<Duration>,
+    /// When `None`, the `Profile`, quirks and speed active when the playlist started
+    /// are used
+    pub profile: Option<Profile>,
+    /// Multiplier of the instructions per frame of the profile, ex: `2` to run twice as
+    /// many instructions every frame
+    pub speed: u32,
+    /// Inputs played back while the entry runs, sorted by cycle
+    pub inputs: Vec<DemoInput>,
+}
+
+/// A list of ROMs run in sequence, looping back to the first one after the last.
+/// As a `DebugHook` it runs an attract mode, ex: for demo reels.
+/// # Playlist format
+/// A text file with one entry per line: the path of the ROM followed by optional
+/// `key=value` settings. Empty lines and lines starting with `#` are ignored.
+/// Relative paths are resolved from the directory of the playlist.
+/// * `duration=SECS`: How long the entry runs, on the virtual clock of the VM.
+/// * `profile=NAME`: `Profile` used to run the ROM.
+/// * `speed=N`: Runs `N` times the instructions per frame of the profile.
+/// * `inputs=FILE`: Demo inputs played back while the entry runs.
+///
+/// Ex: `roms/PONG duration=30 speed=2 inputs=pong.demo`
+/// # Demo inputs format
+/// A text file with one input per line: the cycle at which it happens, the key
+/// in hexadecimal and either `press` or `release`. Ex: `120 5 press`
+pub struct Playlist {
+    entries: Vec<PlaylistEntry>,
+    current: usize,
+    default_profile: Profile,
+    default_quirks: Quirks,
+    default_instructions_per_frame: u32,
+    cycles: u64,
+    next_input: usize,
+    demo_enabled: bool,
+}
+
+/// Parses the demo inputs in `text`, returning them sorted by cycle
+fn parse_demo_inputs(text: &str) -> Result<Vec<DemoInput>, String> {
+    let mut inputs = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let input = match fields.as_slice() {
+            [cycle, key, action] => {
+                let cycle = cycle.parse::<u64>().ok();
+                let key = usize::from_str_radix(key, 16).ok().filter(|&key| key < 16);
+                let pressed = match *action {
+                    "press" => Some(true),
+                    "release" => Some(false),
+                    _ => None,
+                };
+                match (cycle, key, pressed) {
+                    (Some(cycle), Some(key), Some(pressed)) => Some(DemoInput {
+                        cycle,
+                        key,
+                        pressed,
+                    }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        inputs.push(input.ok_or(format!("Malformed demo input: '{}'", line))?);
+    }
+
+    inputs.sort_by_key(|input| input.cycle);
+    Ok(inputs)
+}
+
+impl PlaylistEntry {
+    /// Creates an entry running `rom` with the default settings
+    pub fn new(name: String, rom: Vec<u8>) -> PlaylistEntry {
+        PlaylistEntry {
+            name,
+            rom,
+            duration: None,
+            profile: None,
+            speed: 1,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Parses a `line` of a playlist, reading the files it references from `base_dir`
+    fn parse(line: &str, base_dir: &Path) -> Result<PlaylistEntry, String> {
+        let mut fields = line.split_whitespace();
+        let path = base_dir.join(fields.next().unwrap_or_default());
+        let rom = fs::read(&path)
+            .map_err(|e| format!("Failed to open the ROM {}: {}", path.display(), e))?;
+        let mut entry = PlaylistEntry::new(path.display().to_string(), rom);
+
+        for setting in fields {
+            let invalid = || format!("Invalid setting '{}' for {}", setting, entry.name);
+            let (key, value) = setting.split_once('=').ok_or_else(invalid)?;
+            match key {
+                "duration" => {
+                    let secs = value.parse::<u64>().map_err(|_| invalid())?;
+                    entry.duration = Some(Duration::from_secs(secs));
+                }
+                "profile" => entry.profile = Some(Profile::from_name(value).ok_or_else(invalid)?),
+                "speed" => {
+                    entry.speed = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|&speed| speed > 0)
+                        .ok_or_else(invalid)?
+                }
+                "inputs" => {
+                    let path = base_dir.join(value);
+                    let text = fs::read_to_string(&path).map_err(|e| {
+                        format!("Failed to open the inputs {}: {}", path.display(), e)
+                    })?;
+                    entry.inputs = parse_demo_inputs(&text)?;
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(entry)
+    }
+}
+
+impl Playlist {
+    /// Duration of the entries that don't set one
+    pub const DEFAULT_DURATION: Duration = Duration::from_secs(60);
+
+    /// Creates a playlist running `entries` in order
+    /// # Panics
+    /// If `entries` is empty
+    pub fn new(entries: Vec<PlaylistEntry>) -> Playlist {
+        assert!(!entries.is_empty(), "The playlist needs at least one ROM.");
+
+        Playlist {
+            entries,
+            current: 0,
+            default_profile: Profile::Standard,
+            default_quirks: Profile::Standard.quirks(),
+            default_instructions_per_frame: Profile::Standard.instructions_per_frame(),
+            cycles: 0,
+            next_input: 0,
+            demo_enabled: true,
+        }
+    }
+
+    /// Reads the playlist file at `path`. See `Playlist` for the format
+    pub fn load(path: &str) -> Result<Playlist, String> {
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("Failed to open the playlist: {}", e))?;
+        let base_dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+        let entries = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| PlaylistEntry::parse(line, base_dir))
+            .collect::<Result<Vec<_>, _>>()?;
+        if entries.is_empty() {
+            return Err(String::from("The playlist contains no ROMs."));
+        }
+
+        Ok(Playlist::new(entries))
+    }
+
+    /// Returns all entries, in order
+    pub fn entries(&self) -> &[PlaylistEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry currently running
+    pub fn current(&self) -> &PlaylistEntry {
+        &self.entries[self.current]
+    }
+
+    /// Loads the first entry in `vm`. The `Profile`, quirks and instructions per frame
+    /// active in `vm` are used for all entries that don't set a profile
+    pub fn start(&mut self, vm: &mut Chip8) {
+        self.default_profile = vm.profile();
+        self.default_quirks = vm.quirks();
+        self.default_instructions_per_frame = (vm.cycle_rate() / Chip8::FRAME_RATE).max(1);
+        self.switch_to(vm, 0);
+    }
+
+    /// Restarts `vm` with the entry at `index`
+    fn switch_to(&mut self, vm: &mut Chip8, index: usize) {
+        self.current = index;
+        self.cycles = 0;
+        self.next_input = 0;
+        self.demo_enabled = true;
+
+        let entry = &self.entries[index];
+        let instructions_per_frame = match entry.profile {
+            Some(profile) => {
+                vm.apply_profile(profile);
+                profile.instructions_per_frame()
+            }
+            None => {
+                vm.apply_profile(self.default_profile);
+                vm.set_quirks(self.default_quirks);
+                self.default_instructions_per_frame
+            }
+        };
+        vm.set_instructions_per_frame(instructions_per_frame.saturating_mul(entry.speed));
+        let message = match vm.restart(&entry.rom) {
+            Ok(_) => format!("Playlist: running {}", entry.name),
+            Err(msg) => format!("Playlist: failed to load {}: {}", entry.name, msg),
+        };
+        vm.osd_mut().show(message);
+    }
+
+    /// Restarts `vm` with the current entry
+    pub fn restart_current(&mut self, vm: &mut Chip8) {
+        self.switch_to(vm, self.current);
+    }
+
+    /// Moves `vm` to the next entry, looping back to the first one after the last
+    pub fn next(&mut self, vm: &mut Chip8) {
+        self.switch_to(vm, (self.current + 1) % self.entries.len());
+    }
+
+    /// Stops playing back the demo inputs of the current entry, ex: when a player takes over
+    pub fn stop_demo(&mut self) {
+        self.demo_enabled = false;
+    }
+
+    /// Runs the next instruction of the current entry, playing back its demo inputs.
+    /// If the entry faults, moves on to the next one. See `Chip8Error`
+    pub fn run(&mut self, vm: &mut Chip8) {
+        let entry = &self.entries[self.current];
+        while let Some(input) = entry.inputs.get(self.next_input) {
+            if input.cycle > self.cycles {
+                break;
+            }
+            if self.demo_enabled {
+                if input.pressed {
+                    vm.press_key(input.key);
+                } else {
+                    vm.release_key(input.key);
+                }
+            }
+            self.next_input += 1;
+        }
+
+        if let Err(error) = vm.step() {
+            let name = entry.name.clone();
+            vm.osd_mut()
+                .show(format!("Playlist: {} faulted: {}", name, error));
+            self.next(vm);
+            return;
+        }
+        self.cycles += 1;
+    }
+}
+
+impl DebugHook for Playlist {
+    fn update(&mut self, vm: &mut Chip8) {
+        let duration = self
+            .current()
+            .duration
+            .unwrap_or(Playlist::DEFAULT_DURATION);
+        if vm.virtual_time() >= duration {
+            self.next(vm);
+        }
+        self.run(vm);
+    }
+
+    fn should_exit(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn demo_inputs_are_parsed_sorted_by_cycle() {
+    let inputs = parse_demo_inputs("# Serve\n120 5 press\n\n60 a release\n").unwrap();
+    assert_eq!(
+        inputs,
+        [
+            DemoInput {
+                cycle: 60,
+                key: 0xA,
+                pressed: false
+            },
+            DemoInput {
+                cycle: 120,
+                key: 0x5,
+                pressed: true
+            },
+        ]
+    );
+
+    for line in ["120 5", "120 10 press", "x 5 press", "120 5 hold"] {
+        assert_eq!(
+            parse_demo_inputs(line),
+            Err(format!("Malformed demo input: '{}'", line))
+        );
+    }
+}
+
+#[test]
+fn playlist_entries_are_parsed() {
+    let base_dir = Path::new("roms");
+    let entry = PlaylistEntry::parse(
+        "tests/ibm-logo.ch8 duration=30 profile=schip speed=2",
+        base_dir,
+    )
+    .unwrap();
+    assert_eq!(entry.rom, include_bytes!("../../roms/tests/ibm-logo.ch8"));
+    assert_eq!(entry.duration, Some(Duration::from_secs(30)));
+    assert_eq!(entry.profile, Some(Profile::Schip));
+    assert_eq!(entry.speed, 2);
+    assert!(entry.inputs.is_empty());
+
+    let entry = PlaylistEntry::parse("tests/ibm-logo.ch8", base_dir).unwrap();
+    assert_eq!(
+        (entry.duration, entry.profile, entry.speed),
+        (None, None, 1)
+    );
+
+    for line in [
+        "tests/ibm-logo.ch8 speed=0",
+        "tests/ibm-logo.ch8 profile=unknown",
+        "tests/ibm-logo.ch8 duration",
+        "tests/ibm-logo.ch8 volume=2",
+    ] {
+        assert!(PlaylistEntry::parse(line, base_dir)
+            .unwrap_err()
+            .starts_with("Invalid setting"));
+    }
+    assert!(PlaylistEntry::parse("missing.ch8", base_dir)
+        .unwrap_err()
+        .starts_with("Failed to open the ROM"));
+}
+
+#[test]
+fn entries_run_with_their_profile_and_speed() {
+    let rom = include_bytes!("../../roms/tests/ibm-logo.ch8").to_vec();
+    let mut fast = PlaylistEntry::new(String::from("fast"), rom.clone());
+    fast.profile = Some(Profile::Schip);
+    fast.speed = 2;
+    let mut playlist = Playlist::new(vec![fast, PlaylistEntry::new(String::from("default"), rom)]);
+
+    let quirks = Quirks::parse("shift").unwrap();
+    let mut vm = Chip8::new();
+    vm.set_quirks(quirks);
+    vm.set_instructions_per_frame(20);
+    playlist.start(&mut vm);
+    assert_eq!(vm.profile(), Profile::Schip);
+    assert_eq!(vm.quirks(), Profile::Schip.quirks());
+    assert_eq!(
+        vm.cycle_rate(),
+        Profile::Schip.instructions_per_frame() * 2 * Chip8::FRAME_RATE
+    );
+
+    playlist.next(&mut vm);
+    assert_eq!(vm.profile(), Profile::Standard);
+    assert_eq!(vm.quirks(), quirks);
+    assert_eq!(vm.cycle_rate(), 20 * Chip8::FRAME_RATE);
+}
@@ -0,0 +1,126 @@
+use super::{Chip8, Quirks};
+
+/// Named configurations of a `CHIP-8` VM.
+/// Profiles enable optional extensions that are not part of the original `CHIP-8`,
+/// so they are never active unless explicitly selected.
+///
+/// Profiles named after an interpreter also come with the `Quirks` and the speed ROMs
+/// written for it expect. See `Chip8::apply_profile`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// The original `CHIP-8`, without any extension
+    #[default]
+    Standard,
+    /// The original `CHIP-8` plus memory-mapped pseudo-peripherals.
+    /// See `Peripherals` for the memory map.
+    Peripherals,
+    /// The XO-CHIP extensions: 64KB of memory, a second display plane drawn in 4 colors,
+    /// long index loads, register range save/load, an audio pattern buffer and 16 RPL flags,
+    /// on top of the SUPER-CHIP instructions. See `Chip8::planes` for the display.
+    XoChip,
+    /// The interpreter of the original COSMAC VIP: shifts of `v[y]`, `I` incremented
+    /// by `Fx55` and `Fx65`, and `Fx0A` waiting for a key release
+    CosmacVip,
+    /// The CHIP-48 interpreter of the HP-48 calculators: `Bnnn` jumps with `v[x]`
+    Chip48,
+    /// The SUPER-CHIP interpreter, with the quirks of the CHIP-48 at a higher speed.
+    /// Adds the 128x64 high resolution, scrolling, 16x16 sprites, a big font, `00FD` to
+    /// exit and 8 RPL flags. See `Display`
+    Schip,
+}
+
+impl Profile {
+    /// All available profiles, in the order they are presented to users
+    pub const ALL: [Profile; 6] = [
+        Profile::Standard,
+        Profile::Peripherals,
+        Profile::XoChip,
+        Profile::CosmacVip,
+        Profile::Chip48,
+        Profile::Schip,
+    ];
+
+    /// Returns the name used to select the profile, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Profile::Standard => "standard",
+            Profile::Peripherals => "peripherals",
+            Profile::XoChip => "xo-chip",
+            Profile::CosmacVip => "cosmac-vip",
+            Profile::Chip48 => "chip48",
+            Profile::Schip => "schip",
+        }
+    }
+
+    /// Returns the size of the memory addressable with the profile, in bytes
+    pub fn memory_size(&self) -> usize {
+        match self {
+            Profile::XoChip => 0x10000,
+            _ => Chip8::MAX_MEMORY_ADDRESS,
+        }
+    }
+
+    /// Returns whether the SUPER-CHIP instructions are decoded, including the high
+    /// resolution. See `Instruction`
+    pub fn has_schip_instructions(&self) -> bool {
+        matches!(self, Profile::Schip | Profile::XoChip)
+    }
+
+    /// Returns whether the XO-CHIP instructions are decoded. See `Instruction`
+    pub fn has_xo_chip_instructions(&self) -> bool {
+        *self == Profile::XoChip
+    }
+
+    /// Returns the number of RPL user flags saved by `Fx75` and restored by `Fx85`,
+    /// `0` if the profile lacks those instructions
+    pub fn rpl_flags(&self) -> usize {
+        match self {
+            Profile::Schip => 8,
+            Profile::XoChip => 16,
+            _ => 0,
+        }
+    }
+
+    /// Returns the `Quirks` expected by ROMs written for the profile
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Profile::Standard | Profile::Peripherals => Quirks::default(),
+            Profile::CosmacVip => Quirks {
+                shift_vy: true,
+                increment_i: true,
+                wait_release: true,
+                ..Quirks::default()
+            },
+            Profile::Chip48 | Profile::Schip => Quirks {
+                jump_vx: true,
+                ..Quirks::default()
+            },
+            Profile::XoChip => Quirks {
+                shift_vy: true,
+                increment_i: true,
+                wrap_sprites: true,
+                wait_release: true,
+                ..Quirks::default()
+            },
+        }
+    }
+
+    /// Returns the instructions per frame ROMs written for the profile expect.
+    /// See `Chip8::set_instructions_per_frame`
+    pub fn instructions_per_frame(&self) -> u32 {
+        match self {
+            Profile::Standard | Profile::Peripherals | Profile::CosmacVip => 11,
+            Profile::Chip48 => 15,
+            Profile::Schip => 30,
+            Profile::XoChip => 1000,
+        }
+    }
+
+    /// Finds a profile by its name
+    pub fn from_name(name: &str) -> Option<Profile> {
+        Profile::ALL
+            .iter()
+            .copied()
+            .find(|profile| profile.name() == name)
+    }
+}
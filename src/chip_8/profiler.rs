@@ -0,0 +1,184 @@
+use super::dump::disassemble;
+use super::Instruction;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// Upper bound of the first bucket of the timing histograms, in nanoseconds.
+/// Each following bucket doubles it, and the last one has no upper bound
+const FIRST_BUCKET_NANOS: u64 = 32;
+
+/// Number of buckets of the timing histograms: from `< 32ns` to `>= 32µs`
+const BUCKETS: usize = 11;
+
+/// Characters drawing the height of a histogram bar, from empty to the tallest bucket
+const BAR_LEVELS: [char; 9] = [' ', '.', ':', '-', '=', '+', '*', '#', '@'];
+
+/// Family used for OP Codes that don't match any instruction
+const INVALID_FAMILY: &str = "????";
+
+/// Number of addresses listed by the report, the most executed ones
+const HOT_ADDRESSES: usize = 20;
+
+/// Execution statistics of an opcode family
+#[derive(Clone, Copy, Debug, Default)]
+struct FamilyStats {
+    count: u64,
+    total: Duration,
+    histogram: [u64; BUCKETS],
+}
+
+/// Execution statistics of an address of the ROM
+#[derive(Clone, Copy, Debug, Default)]
+struct AddressStats {
+    count: u64,
+    total: Duration,
+    /// Last OP Code executed at the address, which self-modifying code can change
+    opcode: u16,
+}
+
+/// Diagnostic tool measuring the host time spent executing each opcode family
+/// (ex: `Dxyn` or `8xy4`), to find host-side hotspots like the expansion of sprites
+/// by `Dxyn` compared to ALU operations.
+/// Besides counts and totals, it keeps a histogram of the duration of every execution.
+/// It also counts the executions of every address, so ROM authors can find the
+/// loops their programs spend most of their time in.
+///
+/// **Note:** Timestamps are taken around the execution of each instruction,
+/// so fetching, decoding and the timers are not included. Measuring has a cost
+/// of its own (tens of nanoseconds), comparable to the fastest instructions.
+pub struct InstructionProfiler {
+    /// Statistics by OP Code pattern. See `InstructionInfo`
+    families: BTreeMap<&'static str, FamilyStats>,
+    /// Statistics by address of the instructions
+    addresses: HashMap<u16, AddressStats>,
+}
+
+/// Returns the histogram bucket of an execution that took `duration`
+fn bucket(duration: Duration) -> usize {
+    let mut bucket = 0;
+    let mut bound = FIRST_BUCKET_NANOS as u128;
+    while bucket < BUCKETS - 1 && duration.as_nanos() >= bound {
+        bucket += 1;
+        bound *= 2;
+    }
+    bucket
+}
+
+/// Draws `histogram` as one character per bucket, scaled to its tallest bucket
+fn histogram_bars(histogram: &[u64; BUCKETS]) -> String {
+    let tallest = histogram.iter().copied().max().unwrap_or_default().max(1);
+    histogram
+        .iter()
+        .map(|&count| {
+            let levels = (BAR_LEVELS.len() - 1) as u64;
+            // Any non-empty bucket is drawn, even if tiny compared to the tallest
+            let level = (count * levels).div_ceil(tallest);
+            BAR_LEVELS[level as usize]
+        })
+        .collect()
+}
+
+impl InstructionProfiler {
+    pub fn new() -> InstructionProfiler {
+        InstructionProfiler {
+            families: BTreeMap::new(),
+            addresses: HashMap::new(),
+        }
+    }
+
+    /// Records an execution of `opcode`, located at `address`, that took `duration`
+    pub fn record(&mut self, address: u16, opcode: u16, duration: Duration) {
+        let family = match Instruction::decode(opcode) {
+            Some(instruction) => instruction.info().pattern,
+            None => INVALID_FAMILY,
+        };
+
+        let stats = self.families.entry(family).or_default();
+        stats.count += 1;
+        stats.total += duration;
+        stats.histogram[bucket(duration)] += 1;
+
+        let stats = self.addresses.entry(address).or_default();
+        stats.count += 1;
+        stats.total += duration;
+        stats.opcode = opcode;
+    }
+
+    /// Returns the number of instructions recorded
+    pub fn executions(&self) -> u64 {
+        self.families.values().map(|stats| stats.count).sum()
+    }
+
+    /// Returns a human readable report of all measurements: one line per opcode family,
+    /// sorted by total host time, along with the histogram of the execution times.
+    /// It's followed by the `HOT_ADDRESSES` most executed addresses and their disassembly
+    pub fn report(&self) -> String {
+        let total: Duration = self.families.values().map(|stats| stats.total).sum();
+        let mut families: Vec<(&&str, &FamilyStats)> = self.families.iter().collect();
+        families.sort_by_key(|(_, stats)| Reverse(stats.total));
+
+        let mut report = format!(
+            "Instruction timing ({} executions, {:.3}ms):\n  {:<6} {:>10} {:>10} {:>8} {:>6}  {}\n",
+            self.executions(),
+            total.as_secs_f64() * 1000.0,
+            "Family",
+            "Count",
+            "Total",
+            "Mean",
+            "Share",
+            "Histogram"
+        );
+        for (family, stats) in families {
+            let share = if total.is_zero() {
+                0.0
+            } else {
+                stats.total.as_secs_f64() / total.as_secs_f64() * 100.0
+            };
+            report += &format!(
+                "  {:<6} {:>10} {:>8.3}ms {:>6}ns {:>5.1}%  [{}]\n",
+                family,
+                stats.count,
+                stats.total.as_secs_f64() * 1000.0,
+                stats.total.as_nanos() / stats.count as u128,
+                share,
+                histogram_bars(&stats.histogram)
+            );
+        }
+        report += &format!(
+            "  Histogram buckets: from <{}ns to >={}µs, doubling each time",
+            FIRST_BUCKET_NANOS,
+            (FIRST_BUCKET_NANOS << (BUCKETS - 1)) / 1000
+        );
+
+        let mut addresses: Vec<(&u16, &AddressStats)> = self.addresses.iter().collect();
+        addresses.sort_by_key(|(&address, stats)| (Reverse(stats.count), address));
+        report += &format!(
+            "\nHottest addresses ({} of {}):\n  {:>10} {:>10} {:>6}  {}\n",
+            addresses.len().min(HOT_ADDRESSES),
+            addresses.len(),
+            "Count",
+            "Total",
+            "Share",
+            "Instruction"
+        );
+        let executions = self.executions().max(1);
+        for (&address, stats) in addresses.into_iter().take(HOT_ADDRESSES) {
+            report += &format!(
+                "  {:>10} {:>8.3}ms {:>5.1}%  {}\n",
+                stats.count,
+                stats.total.as_secs_f64() * 1000.0,
+                stats.count as f64 / executions as f64 * 100.0,
+                disassemble(address, stats.opcode)
+            );
+        }
+        report.pop();
+        report
+    }
+}
+
+impl Default for InstructionProfiler {
+    fn default() -> InstructionProfiler {
+        InstructionProfiler::new()
+    }
+}
@@ -0,0 +1,42 @@
+/// How writes to the memory below `0x200`, reserved for the interpreter and the fonts,
+/// are handled. Well-behaved ROMs never write there, so such writes almost always
+/// reveal a bug in the ROM or in the emulator.
+///
+/// Protection is disabled by default: writes are performed silently.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WriteProtection {
+    /// Writes are performed silently
+    #[default]
+    Off,
+    /// Writes are performed, printing a warning the first time each address is written
+    Warn,
+    /// Writes stop the VM with a `Chip8Error::ProtectedWrite`, before the instruction
+    /// changes anything
+    Error,
+}
+
+impl WriteProtection {
+    /// All available modes, in the order they are presented to users
+    pub const ALL: [WriteProtection; 3] = [
+        WriteProtection::Off,
+        WriteProtection::Warn,
+        WriteProtection::Error,
+    ];
+
+    /// Returns the name used to select the mode, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            WriteProtection::Off => "off",
+            WriteProtection::Warn => "warn",
+            WriteProtection::Error => "error",
+        }
+    }
+
+    /// Finds a mode by its name
+    pub fn from_name(name: &str) -> Option<WriteProtection> {
+        WriteProtection::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == name)
+    }
+}
@@ -0,0 +1,109 @@
+/// A behavior that differs between `CHIP-8` interpreters. ROMs written for one of
+/// them may rely on it, so each quirk can be enabled to run them as intended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quirk {
+    /// See `Quirks::shift_vy`
+    Shift,
+    /// See `Quirks::increment_i`
+    LoadStore,
+    /// See `Quirks::jump_vx`
+    Jump,
+    /// See `Quirks::wrap_sprites`
+    Wrap,
+    /// See `Quirks::wait_release`
+    KeyRelease,
+}
+
+impl Quirk {
+    /// All available quirks, in the order they are presented to users
+    pub const ALL: [Quirk; 5] = [
+        Quirk::Shift,
+        Quirk::LoadStore,
+        Quirk::Jump,
+        Quirk::Wrap,
+        Quirk::KeyRelease,
+    ];
+
+    /// Returns the name used to select the quirk, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quirk::Shift => "shift",
+            Quirk::LoadStore => "load-store",
+            Quirk::Jump => "jump",
+            Quirk::Wrap => "wrap",
+            Quirk::KeyRelease => "key-release",
+        }
+    }
+
+    /// Finds a quirk by its name
+    pub fn from_name(name: &str) -> Option<Quirk> {
+        Quirk::ALL
+            .iter()
+            .copied()
+            .find(|quirk| quirk.name() == name)
+    }
+}
+
+/// Quirks of the instructions, respected by their implementations.
+///
+/// All quirks are disabled by default: the VM then behaves as documented in `Instruction::info`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8xy6` and `8xyE` shift `v[y]` and store the result in `v[x]`, as the original
+    /// COSMAC VIP, instead of shifting `v[x]` in place
+    pub shift_vy: bool,
+    /// `Fx55` and `Fx65` leave `I` incremented by `x + 1`, as the original COSMAC VIP
+    pub increment_i: bool,
+    /// `Bnnn` jumps to `nnn + v[x]`, `x` being the highest digit of `nnn`, as the
+    /// CHIP-48 and SUPER-CHIP, instead of `nnn + v[0]`
+    pub jump_vx: bool,
+    /// `Dxyn` wraps the pixels of sprites beyond the right and bottom edges of the display
+    /// around to the opposite side, as XO-CHIP, instead of clipping them
+    pub wrap_sprites: bool,
+    /// `Fx0A` waits for a key to be released instead of taking the first key held,
+    /// as the original COSMAC VIP, so ROMs polling it in a loop get each key once
+    pub wait_release: bool,
+}
+
+impl Quirks {
+    /// Returns whether `quirk` is enabled
+    pub fn is_enabled(&self, quirk: Quirk) -> bool {
+        match quirk {
+            Quirk::Shift => self.shift_vy,
+            Quirk::LoadStore => self.increment_i,
+            Quirk::Jump => self.jump_vx,
+            Quirk::Wrap => self.wrap_sprites,
+            Quirk::KeyRelease => self.wait_release,
+        }
+    }
+
+    /// Enables or disables `quirk`
+    pub fn set(&mut self, quirk: Quirk, enabled: bool) {
+        let flag = match quirk {
+            Quirk::Shift => &mut self.shift_vy,
+            Quirk::LoadStore => &mut self.increment_i,
+            Quirk::Jump => &mut self.jump_vx,
+            Quirk::Wrap => &mut self.wrap_sprites,
+            Quirk::KeyRelease => &mut self.wait_release,
+        };
+        *flag = enabled;
+    }
+
+    /// Parses a comma separated list of quirk names, ex: `shift,load-store`.
+    /// Listed quirks are enabled, unless prefixed with `no-` to disable them
+    /// # Errors
+    /// If any name is unknown
+    pub fn parse(text: &str) -> Result<Quirks, String> {
+        let mut quirks = Quirks::default();
+        for name in text.split(',').map(str::trim) {
+            let (name, enabled) = match name.strip_prefix("no-") {
+                Some(name) => (name, false),
+                None => (name, true),
+            };
+            let quirk =
+                Quirk::from_name(name).ok_or_else(|| format!("Unknown quirk '{}'.", name))?;
+            quirks.set(quirk, enabled);
+        }
+        Ok(quirks)
+    }
+}
@@ -0,0 +1,134 @@
+use super::Chip8;
+
+/// How the value of an address must have changed since the last snapshot of a
+/// `RamSearch` for the address to stay a candidate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchFilter {
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    /// The current value is the given one, whatever the previous one
+    Equals(u8),
+}
+
+impl SearchFilter {
+    /// Filters selected by name, in the order they are presented to users.
+    /// `Equals` is written as `== VALUE` instead
+    pub const ALL: [SearchFilter; 4] = [
+        SearchFilter::Changed,
+        SearchFilter::Unchanged,
+        SearchFilter::Increased,
+        SearchFilter::Decreased,
+    ];
+
+    /// Returns the name used to select the filter, ex: from the debug console
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchFilter::Changed => "changed",
+            SearchFilter::Unchanged => "unchanged",
+            SearchFilter::Increased => "increased",
+            SearchFilter::Decreased => "decreased",
+            SearchFilter::Equals(_) => "==",
+        }
+    }
+
+    /// Finds a filter by its name, or parses `== VALUE` where the value is decimal,
+    /// or hexadecimal with a `0x` prefix
+    pub fn from_name(name: &str) -> Option<SearchFilter> {
+        if let Some(value) = name.strip_prefix("==") {
+            let value = value.trim();
+            let value = match value.strip_prefix("0x") {
+                Some(hex) => u8::from_str_radix(hex, 16).ok(),
+                None => value.parse::<u8>().ok(),
+            };
+            return value.map(SearchFilter::Equals);
+        }
+        SearchFilter::ALL
+            .iter()
+            .copied()
+            .find(|filter| filter.name() == name)
+    }
+
+    fn matches(&self, previous: u8, current: u8) -> bool {
+        match self {
+            SearchFilter::Changed => current != previous,
+            SearchFilter::Unchanged => current == previous,
+            SearchFilter::Increased => current > previous,
+            SearchFilter::Decreased => current < previous,
+            SearchFilter::Equals(value) => current == *value,
+        }
+    }
+}
+
+/// RAM scanner in the style of classic cheat finders, to locate where a ROM keeps
+/// a value (ex: lives or a score) without knowing its code.
+/// Starting with every address of memory as a candidate, each `filter` keeps only
+/// the addresses whose value changed as requested since the last snapshot, then takes
+/// a new snapshot. A few rounds of play and filtering usually narrow the candidates
+/// down to a handful, which can then be watched or frozen with `Chip8::freeze`.
+pub struct RamSearch {
+    /// Memory when the last filter was applied
+    snapshot: Vec<u8>,
+    /// Addresses still matching every filter applied, in ascending order
+    candidates: Vec<u16>,
+}
+
+impl RamSearch {
+    /// Maximum number of candidates listed by `report`
+    pub const MAX_REPORTED: usize = 32;
+
+    /// Starts a search on the current memory of `vm`, with every address as a candidate
+    pub fn new(vm: &Chip8) -> RamSearch {
+        RamSearch {
+            snapshot: vm.memory().to_vec(),
+            candidates: (0..vm.memory().len() as u16).collect(),
+        }
+    }
+
+    /// Keeps only the candidates matching `filter` against the memory of `vm`,
+    /// then snapshots it for the next filter
+    /// # Returns
+    /// The number of candidates left.
+    pub fn filter(&mut self, vm: &Chip8, filter: SearchFilter) -> usize {
+        let memory = vm.memory();
+        let snapshot = &self.snapshot;
+        self.candidates.retain(|&address| {
+            let address = address as usize;
+            filter.matches(snapshot[address], memory[address])
+        });
+        self.snapshot = memory.to_vec();
+        self.candidates.len()
+    }
+
+    /// Returns the addresses still matching every filter applied, in ascending order
+    pub fn candidates(&self) -> &[u16] {
+        &self.candidates
+    }
+
+    /// Returns the candidates with their value in the last snapshot and in `vm`,
+    /// one per line, up to `MAX_REPORTED`
+    pub fn report(&self, vm: &Chip8) -> String {
+        let mut lines: Vec<String> = self
+            .candidates
+            .iter()
+            .take(RamSearch::MAX_REPORTED)
+            .map(|&address| {
+                format!(
+                    "0x{:03X}: {:02X} -> {:02X}",
+                    address,
+                    self.snapshot[address as usize],
+                    vm.memory()[address as usize]
+                )
+            })
+            .collect();
+        if self.candidates.len() > RamSearch::MAX_REPORTED {
+            lines.push(format!(
+                "... {} more",
+                self.candidates.len() - RamSearch::MAX_REPORTED
+            ));
+        }
+        lines.insert(0, format!("{} candidates", self.candidates.len()));
+        lines.join("\n")
+    }
+}
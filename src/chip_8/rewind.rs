@@ -0,0 +1,93 @@
+use super::Chip8;
+use std::collections::VecDeque;
+
+/// Ring buffer of the states of the last frames, so the execution can be stepped
+/// backwards. States are snapshots taken with `Chip8::save_state`: about 4.7 KB each,
+/// or 280 KB per second (64 KB each with `Profile::XoChip`).
+///
+/// Once full, the oldest state is dropped on every new frame.
+pub struct Rewind {
+    states: VecDeque<Vec<u8>>,
+    capacity: usize,
+}
+
+impl Rewind {
+    /// Default length of the buffer, in seconds
+    pub const DEFAULT_SECONDS: u32 = 10;
+
+    /// Creates an empty buffer holding up to `seconds` of frames
+    pub fn new(seconds: u32) -> Rewind {
+        Rewind {
+            states: VecDeque::new(),
+            capacity: seconds.saturating_mul(Chip8::FRAME_RATE) as usize,
+        }
+    }
+
+    /// Records the state of a frame, dropping the oldest one if the buffer is full
+    pub fn push(&mut self, state: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.states.len() == self.capacity {
+            self.states.pop_front();
+        }
+        self.states.push_back(state);
+    }
+
+    /// Removes the most recent state and returns it, if any
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        self.states.pop_back()
+    }
+
+    /// Returns the number of frames that can be rewound
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns `true` if there's no frame left to rewind
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Drops every recorded state, ex: when another ROM is loaded
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+}
+
+impl Chip8 {
+    /// Keeps the state of every frame run by `start` for the last `seconds`, so the
+    /// `rewind` hotkey can step the execution backwards. `0` disables it
+    pub fn enable_rewind(&mut self, seconds: u32) {
+        self.rewind = match seconds {
+            0 => None,
+            _ => Some(Rewind::new(seconds)),
+        };
+    }
+
+    /// Returns the states recorded for rewinding, if enabled
+    pub fn rewind(&self) -> Option<&Rewind> {
+        self.rewind.as_ref()
+    }
+
+    /// Records the current state as the last frame. Does nothing if rewind is disabled
+    pub fn record_frame(&mut self) {
+        if self.rewind.is_some() {
+            let state = self.save_state();
+            if let Some(rewind) = self.rewind.as_mut() {
+                rewind.push(state);
+            }
+        }
+    }
+
+    /// Restores the state of the last recorded frame, dropping it from the buffer
+    /// # Returns
+    /// `false` if there's no frame left to rewind
+    pub fn rewind_frame(&mut self) -> bool {
+        match self.rewind.as_mut().and_then(|rewind| rewind.pop()) {
+            // States are saved from this same ROM, so they always load
+            Some(state) => self.load_state(&state).is_ok(),
+            None => false,
+        }
+    }
+}
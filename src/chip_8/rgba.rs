@@ -0,0 +1,51 @@
+use super::{Chip8, Display, PaletteColors};
+
+impl Display {
+    /// Bytes of every pixel written by `write_rgba`: red, green, blue and alpha
+    pub const RGBA_BYTES: usize = 4;
+
+    /// Returns the width of the display in pixels, in the current resolution
+    pub fn width(&self) -> usize {
+        match self.is_hires() {
+            true => Chip8::HIRES_WIDTH,
+            false => Chip8::VIDEO_WIDTH,
+        }
+    }
+
+    /// Returns the height of the display in pixels, in the current resolution
+    pub fn height(&self) -> usize {
+        match self.is_hires() {
+            true => Chip8::HIRES_HEIGHT,
+            false => Chip8::VIDEO_HEIGHT,
+        }
+    }
+
+    /// Returns the number of pixels of every plane: `width * height`
+    pub fn size(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    /// Renders the display as packed RGBA pixels, row by row, with `colors` indexed like
+    /// in a `PaletteColors`, ex: to upload it as a texture or draw it on a WASM canvas.
+    /// Pixels are opaque. See `Display::write_rgba` to render without allocating
+    pub fn to_rgba(&self, colors: PaletteColors) -> Vec<u8> {
+        let mut rgba = vec![0; self.width() * self.height() * Display::RGBA_BYTES];
+        self.write_rgba(colors, &mut rgba);
+        rgba
+    }
+
+    /// Renders the display into `rgba`, as packed RGBA pixels like `to_rgba`
+    /// # Panics
+    /// If `rgba` doesn't take exactly `width * height * RGBA_BYTES` bytes
+    pub fn write_rgba(&self, colors: PaletteColors, rgba: &mut [u8]) {
+        assert_eq!(
+            rgba.len(),
+            self.width() * self.height() * Display::RGBA_BYTES,
+            "The RGBA buffer doesn't match the size of the display."
+        );
+        for (pos, pixel) in rgba.chunks_exact_mut(Display::RGBA_BYTES).enumerate() {
+            let [r, g, b] = colors[self.color(pos)];
+            pixel.copy_from_slice(&[r, g, b, 0xFF]);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+/// Random generator of `Cxkk`: `xoshiro256**`, seeded through `SplitMix64`.
+///
+/// Implemented here rather than taken from `rand`, so the numbers drawn from a seed never
+/// change with the version of a dependency, and recordings made with a seed keep
+/// replaying the same game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    /// Creates a generator drawing the same numbers for the same `seed`
+    pub fn new(seed: u64) -> Rng {
+        let mut seed = seed;
+        let mut split_mix = || {
+            seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+        Rng {
+            state: [split_mix(), split_mix(), split_mix(), split_mix()],
+        }
+    }
+
+    /// Restores a generator from its `state`, ex: from a save state.
+    /// An all-zero state, which would only draw zeros, is replaced by the one of seed `0`
+    pub fn from_state(state: [u64; 4]) -> Rng {
+        match state {
+            [0, 0, 0, 0] => Rng::new(0),
+            state => Rng { state },
+        }
+    }
+
+    /// Returns the state of the generator, to continue the same sequence with `from_state`
+    pub fn state(&self) -> [u64; 4] {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Returns a random byte, from the upper bits which are the most random ones
+    pub fn next_u8(&mut self) -> u8 {
+        (self.next_u64() >> 56) as u8
+    }
+}
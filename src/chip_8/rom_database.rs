@@ -0,0 +1,274 @@
+use super::{parse_memory_range, Chip8, Profile, Quirks};
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::ops::Range;
+
+/// Settings recommended for a ROM. Settings left as `None` keep the current ones of the VM
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RomSettings {
+    /// Title of the ROM, for display purposes
+    pub title: Option<String>,
+    pub quirks: Option<Quirks>,
+    /// See `Chip8::set_instructions_per_frame`
+    pub instructions_per_frame: Option<u32>,
+    /// Pairs of keypad keys exchanged, ex: `(0x5, 0x8)` so the host key of `5` presses `8`
+    /// and the other way around
+    pub key_swaps: Vec<(usize, usize)>,
+    /// Range of memory kept between runs, like the battery-backed RAM of cartridges, ex: for
+    /// score tables. See `Chip8::store_battery`
+    pub battery: Option<Range<usize>>,
+}
+
+impl RomSettings {
+    /// Returns these settings, filling the ones left as `None` from `fallback`.
+    /// Key swaps are taken from `fallback` only if there are none
+    pub fn or(self, fallback: RomSettings) -> RomSettings {
+        RomSettings {
+            title: self.title.or(fallback.title),
+            quirks: self.quirks.or(fallback.quirks),
+            instructions_per_frame: self
+                .instructions_per_frame
+                .or(fallback.instructions_per_frame),
+            key_swaps: match self.key_swaps.is_empty() {
+                true => fallback.key_swaps,
+                false => self.key_swaps,
+            },
+            battery: self.battery.or(fallback.battery),
+        }
+    }
+}
+
+/// Rough speed of a ROM, grouping the instructions per frame of the interpreters ROMs are
+/// written for, so they can be told apart and adjusted without knowing exact numbers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpeedClass {
+    /// Up to 8 instructions per frame, ex: games tuned on a slow COSMAC VIP
+    Slow,
+    /// From 9 to 15 instructions per frame, the speed of most `CHIP-8` ROMs
+    Normal,
+    /// From 16 to 100 instructions per frame, ex: SUPER-CHIP games
+    Fast,
+    /// Over 100 instructions per frame, ex: XO-CHIP games
+    Turbo,
+}
+
+impl SpeedClass {
+    /// All speed classes, from the slowest to the fastest
+    pub const ALL: [SpeedClass; 4] = [
+        SpeedClass::Slow,
+        SpeedClass::Normal,
+        SpeedClass::Fast,
+        SpeedClass::Turbo,
+    ];
+
+    /// Returns the name used to select the class, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpeedClass::Slow => "slow",
+            SpeedClass::Normal => "normal",
+            SpeedClass::Fast => "fast",
+            SpeedClass::Turbo => "turbo",
+        }
+    }
+
+    /// Finds a speed class by its name
+    pub fn from_name(name: &str) -> Option<SpeedClass> {
+        SpeedClass::ALL
+            .iter()
+            .copied()
+            .find(|class| class.name() == name)
+    }
+
+    /// Returns the class of a ROM running `instructions` per frame
+    pub fn of(instructions: u32) -> SpeedClass {
+        match instructions {
+            0..=8 => SpeedClass::Slow,
+            9..=15 => SpeedClass::Normal,
+            16..=100 => SpeedClass::Fast,
+            _ => SpeedClass::Turbo,
+        }
+    }
+
+    /// Returns the instructions per frame ROMs of the class run at when picked by name,
+    /// those of the matching `Profile`. See `Profile::instructions_per_frame`
+    pub fn instructions_per_frame(&self) -> u32 {
+        match self {
+            SpeedClass::Slow => 7,
+            SpeedClass::Normal => Profile::Standard.instructions_per_frame(),
+            SpeedClass::Fast => Profile::Schip.instructions_per_frame(),
+            SpeedClass::Turbo => Profile::XoChip.instructions_per_frame(),
+        }
+    }
+}
+
+/// Settings of known ROMs keyed by their `SHA-1`, so ROMs run as intended without
+/// knowing which quirks or speed they need. An embedded database covers the ROMs
+/// shipped in `roms/`, see `RomDatabase::embedded`.
+/// # Database format
+/// A text file with one ROM per line, as `SHA-1 | TITLE | SETTINGS`. The `SHA-1` is in
+/// hexadecimal and the settings are a space separated list of:
+/// * `quirks=LIST`: Quirks of the ROM, as accepted by `Quirks::parse`.
+/// * `ipf=N`: Instructions per frame.
+/// * `swap=A:B`: Exchanges two keypad keys, in hexadecimal. Can be repeated.
+/// * `battery=RANGE`: Memory kept between runs, as accepted by `parse_memory_range`.
+///
+/// Empty lines and lines starting with `#` are ignored.
+///
+/// Ex: `6f6509f38220e057a7e32ebb22dd353c1078e3e7 | Blitz | quirks=shift ipf=15`
+pub struct RomDatabase {
+    entries: Vec<(String, RomSettings)>,
+}
+
+/// A `RomDatabase` set on a VM, along with the settings it falls back to
+pub(super) struct RomDatabaseState {
+    database: RomDatabase,
+    overrides: RomSettings,
+    /// Quirks of the VM when the database was set
+    quirks: Quirks,
+    /// Cycle rate of the VM when the database was set
+    cycle_rate: u32,
+}
+
+/// Returns the `SHA-1` of `rom` in lowercase hexadecimal
+pub fn rom_sha1(rom: &[u8]) -> String {
+    Sha1::digest(rom)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn parse_settings(text: &str) -> Option<RomSettings> {
+    let mut settings = RomSettings::default();
+    for setting in text.split_whitespace() {
+        let (key, value) = setting.split_once('=')?;
+        match key {
+            "quirks" => settings.quirks = Some(Quirks::parse(value).ok()?),
+            "ipf" => {
+                settings.instructions_per_frame =
+                    Some(value.parse::<u32>().ok().filter(|&ipf| ipf > 0)?)
+            }
+            "swap" => {
+                let (a, b) = value.split_once(':')?;
+                let key = |key: &str| usize::from_str_radix(key, 16).ok().filter(|&k| k < 16);
+                settings.key_swaps.push((key(a)?, key(b)?));
+            }
+            "battery" => settings.battery = Some(parse_memory_range(value).ok()?),
+            _ => return None,
+        }
+    }
+    Some(settings)
+}
+
+impl RomDatabase {
+    /// Returns the database embedded in the emulator
+    pub fn embedded() -> RomDatabase {
+        RomDatabase::parse(include_str!("rom_database.txt"))
+            .expect("The embedded ROM database is malformed.")
+    }
+
+    /// Parses the ROMs in `text`. See `RomDatabase` for the format
+    pub fn parse(text: &str) -> Result<RomDatabase, String> {
+        let mut entries = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || format!("Malformed ROM at line {}: '{}'", number + 1, line);
+            let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+            let (sha1, title, settings) = match fields.as_slice() {
+                [sha1, title, settings] => (sha1.to_ascii_lowercase(), title, settings),
+                _ => return Err(invalid()),
+            };
+            if sha1.len() != 40 || !sha1.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+            let mut settings = parse_settings(settings).ok_or_else(invalid)?;
+            settings.title = Some(title.to_string()).filter(|title| !title.is_empty());
+            entries.push((sha1, settings));
+        }
+        Ok(RomDatabase { entries })
+    }
+
+    /// Reads the database file at `path`. See `RomDatabase` for the format
+    pub fn load(path: &str) -> Result<RomDatabase, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to open the ROM database: {}", e))?;
+        RomDatabase::parse(&text)
+    }
+
+    /// Returns the settings of `rom`, if known
+    pub fn lookup(&self, rom: &[u8]) -> Option<&RomSettings> {
+        let sha1 = rom_sha1(rom);
+        self.entries
+            .iter()
+            .find(|(key, _)| *key == sha1)
+            .map(|(_, settings)| settings)
+    }
+}
+
+impl Chip8 {
+    /// Looks up every ROM loaded afterwards in `database`, and applies its settings.
+    /// The `overrides` take precedence over the ones of the database, ex: settings from
+    /// the command line, and apply to unknown ROMs too. Settings that neither sets are
+    /// reset to the current ones of the VM, so they don't leak from one ROM to the next.
+    /// See `Chip8::rom_settings`
+    pub fn set_rom_database(&mut self, database: RomDatabase, overrides: RomSettings) {
+        self.rom_database = Some(RomDatabaseState {
+            database,
+            overrides,
+            quirks: self.quirks,
+            cycle_rate: self.cycle_rate(),
+        });
+    }
+
+    /// Returns the settings applied to the last ROM loaded, if there's a ROM database
+    pub fn rom_settings(&self) -> Option<&RomSettings> {
+        self.rom_settings.as_ref()
+    }
+
+    /// Applies the settings of the database to `rom`, as it's loaded
+    pub(super) fn apply_rom_database(&mut self, rom: &[u8]) {
+        let state = match &self.rom_database {
+            Some(state) => state,
+            None => return,
+        };
+        let entry = state.database.lookup(rom).cloned().unwrap_or_default();
+        let settings = state.overrides.clone().or(entry);
+        let (quirks, cycle_rate) = (state.quirks, state.cycle_rate);
+
+        self.quirks = settings.quirks.unwrap_or(quirks);
+        match settings.instructions_per_frame {
+            Some(instructions) => self.set_instructions_per_frame(instructions),
+            None => self.set_cycle_rate(cycle_rate),
+        }
+        self.rom_settings = Some(settings);
+    }
+
+    /// Returns the keypad key pressed by the host key of `key`, after the key swaps of the
+    /// ROM. See `RomSettings::key_swaps`
+    #[cfg(any(feature = "window", feature = "tui", feature = "framebuffer"))]
+    pub(super) fn swapped_key(&self, key: usize) -> usize {
+        let swaps = self.rom_settings.iter().flat_map(|s| s.key_swaps.iter());
+        for &(a, b) in swaps {
+            if key == a {
+                return b;
+            } else if key == b {
+                return a;
+            }
+        }
+        key
+    }
+}
+
+#[test]
+fn speed_classes_match_their_instructions_per_frame() {
+    for class in SpeedClass::ALL {
+        assert_eq!(SpeedClass::of(class.instructions_per_frame()), class);
+        assert_eq!(SpeedClass::from_name(class.name()), Some(class));
+    }
+    assert_eq!(SpeedClass::of(15), SpeedClass::Normal);
+    assert_eq!(SpeedClass::of(16), SpeedClass::Fast);
+    assert_eq!(SpeedClass::from_name("warp"), None);
+}
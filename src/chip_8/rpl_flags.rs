@@ -0,0 +1,51 @@
+use super::Chip8;
+
+impl Chip8 {
+    /// Returns the RPL user flags saved with `Fx75` and restored with `Fx85`.
+    /// Only the first `Profile::rpl_flags` are used
+    pub fn rpl_flags(&self) -> &[u8; 16] {
+        &self.rpl_flags
+    }
+
+    /// Returns the `Storage` key of the RPL flags of the current ROM, ex: `flags/8a3f0c2e`
+    pub fn rpl_flags_key(&self) -> String {
+        format!("flags/{:08x}", self.rom_crc32())
+    }
+
+    /// Restores the RPL flags of the current ROM from the save storage, like the HP-48
+    /// keeps them between runs. Without storage, or without flags stored, they're cleared.
+    /// Failures are reported on the `Osd`
+    pub(super) fn load_rpl_flags(&mut self) {
+        self.rpl_flags = [0; 16];
+        let key = self.rpl_flags_key();
+        let stored = match &self.save_storage {
+            Some(storage) => storage.load(&key),
+            None => return,
+        };
+        match stored {
+            Ok(Some(flags)) => {
+                let count = flags.len().min(self.rpl_flags.len());
+                self.rpl_flags[..count].copy_from_slice(&flags[..count]);
+            }
+            Ok(None) => {}
+            Err(e) => self
+                .osd
+                .show(format!("Failed to load the RPL flags: {}", e)),
+        }
+    }
+
+    /// Writes the RPL flags of the current ROM to the save storage, if any.
+    /// Failures are reported on the `Osd`, the ROM keeps running with the flags in memory
+    pub(super) fn store_rpl_flags(&mut self) {
+        let key = self.rpl_flags_key();
+        let count = self.profile.rpl_flags();
+        let stored = match &mut self.save_storage {
+            Some(storage) => storage.store(&key, &self.rpl_flags[..count]),
+            None => return,
+        };
+        if let Err(e) = stored {
+            self.osd
+                .show(format!("Failed to save the RPL flags: {}", e));
+        }
+    }
+}
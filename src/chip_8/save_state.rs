@@ -0,0 +1,445 @@
+#[cfg(feature = "window")]
+use super::decode_thumbnail;
+use super::rng::Rng;
+use super::{AudioPattern, Chip8, Profile, Quirk, Quirks};
+use crate::storage::Storage;
+use std::convert::TryFrom;
+
+/// Identifies save states, followed by the version of their format
+const MAGIC: &[u8; 4] = b"C8SS";
+
+/// Version of the format written by `Chip8::save_state`. Version `1` lacks the pitch,
+/// versions before `3` the random generator, versions before `4` the quirks and versions
+/// before `5` the resolution, their display being in the low one
+const VERSION: u8 = 5;
+
+/// Reads the fields of a save state in order, failing on truncated data
+struct StateReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> StateReader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or("Truncated save state.")?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(
+            <[u8; 2]>::try_from(self.bytes(2)?).unwrap(),
+        ))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(
+            <[u8; 4]>::try_from(self.bytes(4)?).unwrap(),
+        ))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_be_bytes(
+            <[u8; 8]>::try_from(self.bytes(8)?).unwrap(),
+        ))
+    }
+}
+
+/// Packs `pixels` as bits, 8 per byte, most-significant bit first
+fn pack(pixels: &[bool]) -> Vec<u8> {
+    pixels
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (bit, &on)| byte | ((on as u8) << (7 - bit)))
+        })
+        .collect()
+}
+
+/// Unpacks the bits written by `pack` into `pixels`
+fn unpack(bytes: &[u8], pixels: &mut [bool]) {
+    for (pos, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = bytes[pos / 8] & (0x80 >> (pos % 8)) != 0;
+    }
+}
+
+impl Chip8 {
+    /// Snapshots the whole state of the VM: memory, registers, stack, timers, display
+    /// and input, so it can be restored later with `load_state`.
+    /// # Format
+    /// A versioned binary layout, big-endian: `C8SS`, the version, the `CRC32` of the ROM,
+    /// the `Profile`, the memory (size and content), `v`, `I`, `PC`, the stack (size and
+    /// addresses), the timers, the clock (phase and frames), the resolution (`1` for the
+    /// high one), both display planes in that resolution packed as bits along with the
+    /// selected planes, the pressed keys, the audio pattern, its
+    /// pitch, the state of the random generator, so `Cxkk` draws the same numbers
+    /// after a restore, and the enabled `Quirks`, one bit each in the order of `Quirk::ALL`.
+    ///
+    /// **Note:** Host integrations (peripherals, triggers, limits...) are not saved, nor
+    /// are the RPL flags, which persist on their own like on the HP-48.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = MAGIC.to_vec();
+        state.push(VERSION);
+        state.extend_from_slice(&self.rom_crc32().to_be_bytes());
+        let profile = Profile::ALL.iter().position(|&p| p == self.profile);
+        state.push(profile.unwrap_or_default() as u8);
+        state.extend_from_slice(&(self.main_memory.len() as u32).to_be_bytes());
+        state.extend_from_slice(&self.main_memory);
+        state.extend_from_slice(&self.regs.v);
+        state.extend_from_slice(&self.regs.i.to_be_bytes());
+        state.extend_from_slice(&self.regs.pc.to_be_bytes());
+        state.push(self.stack.stored.len() as u8);
+        for address in self.stack.stored.iter() {
+            state.extend_from_slice(&address.to_be_bytes());
+        }
+        state.extend_from_slice(&[self.timers.delay, self.timers.sound]);
+        state.extend_from_slice(&self.clock.phase.to_be_bytes());
+        state.extend_from_slice(&self.frames.to_be_bytes());
+        state.push(self.display.is_hires() as u8);
+        state.extend_from_slice(&pack(self.display.plane(0)));
+        state.extend_from_slice(&pack(self.display.plane(1)));
+        state.push(self.display.selected_planes);
+        state.extend_from_slice(&pack(&self.input.key_status));
+        state.extend_from_slice(&self.audio_pattern);
+        state.push(self.pitch);
+        for word in self.rng.state().iter() {
+            state.extend_from_slice(&word.to_be_bytes());
+        }
+        let quirks = Quirk::ALL
+            .iter()
+            .enumerate()
+            .filter(|&(_, &quirk)| self.quirks.is_enabled(quirk))
+            .fold(0, |byte, (bit, _)| byte | (1 << bit));
+        state.push(quirks);
+        state
+    }
+
+    /// Restores a state saved with `save_state`. The state is only applied if it's valid
+    /// as a whole, otherwise the VM is left untouched
+    /// # Errors
+    /// If the state is malformed, was saved by an unknown version, belongs to another ROM
+    /// or holds more subroutines than the stack depth
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), String> {
+        let mut reader = StateReader {
+            data: state,
+            position: 0,
+        };
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err("Not a save state.".to_string());
+        }
+        let version = reader.u8()?;
+        if version == 0 || version > VERSION {
+            return Err(format!("Unsupported save state version {}.", version));
+        }
+        if reader.u32()? != self.rom_crc32() {
+            return Err("The save state belongs to another ROM.".to_string());
+        }
+        let profile = *Profile::ALL
+            .get(reader.u8()? as usize)
+            .ok_or("Unknown profile in the save state.")?;
+        let memory_size = reader.u32()? as usize;
+        if memory_size != profile.memory_size() {
+            return Err("Invalid memory size in the save state.".to_string());
+        }
+        let memory = reader.bytes(memory_size)?;
+        let v = reader.bytes(16)?;
+        let (i, pc) = (reader.u16()?, reader.u16()?);
+        let stack_size = reader.u8()? as usize;
+        if stack_size > self.stack.depth {
+            return Err(format!(
+                "The save state holds {} subroutines, above the stack depth of {}.",
+                stack_size, self.stack.depth
+            ));
+        }
+        let stack = (0..stack_size)
+            .map(|_| reader.u16())
+            .collect::<Result<Vec<u16>, String>>()?;
+        let (delay, sound) = (reader.u8()?, reader.u8()?);
+        let (phase, frames) = (reader.u32()?, reader.u64()?);
+        let hires = match version {
+            1..=4 => false,
+            _ => reader.u8()? != 0,
+        };
+        if hires && !profile.has_schip_instructions() {
+            return Err("Invalid resolution in the save state.".to_string());
+        }
+        let plane_size = match hires {
+            true => Chip8::HIRES_WIDTH * Chip8::HIRES_HEIGHT / 8,
+            false => Chip8::VIDEO_WIDTH * Chip8::VIDEO_HEIGHT / 8,
+        };
+        let (first_plane, second_plane) = (reader.bytes(plane_size)?, reader.bytes(plane_size)?);
+        let selected_planes = reader.u8()?;
+        let keys = reader.bytes(2)?;
+        let audio_pattern = reader.bytes(16)?;
+        let pitch = match version {
+            1 => AudioPattern::DEFAULT_PITCH,
+            _ => reader.u8()?,
+        };
+        let rng = match version {
+            1 | 2 => self.rng.clone(),
+            _ => Rng::from_state([reader.u64()?, reader.u64()?, reader.u64()?, reader.u64()?]),
+        };
+        let quirks = match version {
+            1..=3 => self.quirks,
+            _ => {
+                let bits = reader.u8()?;
+                let mut quirks = Quirks::default();
+                for (bit, &quirk) in Quirk::ALL.iter().enumerate() {
+                    quirks.set(quirk, bits & (1 << bit) != 0);
+                }
+                quirks
+            }
+        };
+
+        self.set_profile(profile);
+        self.main_memory.copy_from_slice(memory);
+        self.regs.v.copy_from_slice(v);
+        self.regs.i = i;
+        self.regs.pc = pc;
+        self.stack.stored = stack;
+        self.timers.delay = delay;
+        self.timers.sound = sound;
+        self.clock.phase = phase % self.clock.rate();
+        self.frames = frames;
+        self.display.set_hires(hires);
+        unpack(first_plane, self.display.plane_mut(0));
+        unpack(second_plane, self.display.plane_mut(1));
+        self.display.selected_planes = selected_planes & 0x3;
+        unpack(keys, &mut self.input.key_status);
+        self.audio_pattern.copy_from_slice(audio_pattern);
+        self.pitch = pitch;
+        self.rng = rng;
+        self.quirks = quirks;
+        self.history.clear();
+        Ok(())
+    }
+
+    /// Returns the `Storage` key of a save state slot of the current ROM,
+    /// ex: `saves/8a3f0c2e/slot-1`
+    pub fn save_slot_key(&self, slot: u32) -> String {
+        format!("saves/{:08x}/slot-{}", self.rom_crc32(), slot)
+    }
+
+    /// Returns the `Storage` key of the thumbnail of a save state slot, next to the state,
+    /// ex: `saves/8a3f0c2e/slot-1.png`. See `Chip8::thumbnail_png`
+    pub fn save_slot_thumbnail_key(&self, slot: u32) -> String {
+        format!("{}.png", self.save_slot_key(slot))
+    }
+
+    /// Sets where the save states, the RPL flags and the battery-backed memory are kept,
+    /// and the slot used by the `save-state` and `load-state` hotkeys. The flags and the
+    /// battery-backed memory of the current ROM are restored
+    pub fn set_save_storage(&mut self, storage: Box<dyn Storage + Send>, slot: u32) {
+        self.save_storage = Some(storage);
+        self.save_slot = slot;
+        self.load_rpl_flags();
+        self.load_battery();
+    }
+
+    /// Saves the state of the VM to `slot` of the save storage, along with a thumbnail of
+    /// the display. See `save_state` and `thumbnail_png`
+    /// # Errors
+    /// If there is no save storage, or if the storage fails
+    pub fn save_to_slot(&mut self, slot: u32) -> Result<(), String> {
+        let key = self.save_slot_key(slot);
+        let thumbnail_key = self.save_slot_thumbnail_key(slot);
+        let state = self.save_state();
+        let thumbnail = self.thumbnail_png();
+        let storage = self.save_storage.as_mut().ok_or("No save storage set.")?;
+        storage
+            .store(&key, &state)
+            .and_then(|()| storage.store(&thumbnail_key, &thumbnail))
+            .map_err(|e| format!("Failed to save the state: {}", e))
+    }
+
+    /// Returns the slots of the save storage holding a state of the current ROM, sorted
+    /// # Errors
+    /// If there is no save storage, or if the storage fails
+    pub fn saved_slots(&self) -> Result<Vec<u32>, String> {
+        let prefix = format!("saves/{:08x}/slot-", self.rom_crc32());
+        let storage = self.save_storage.as_ref().ok_or("No save storage set.")?;
+        let keys = storage
+            .keys(&prefix)
+            .map_err(|e| format!("Failed to list the states: {}", e))?;
+        let mut slots: Vec<u32> = keys
+            .iter()
+            .filter_map(|key| key[prefix.len()..].parse().ok())
+            .collect();
+        slots.sort_unstable();
+        Ok(slots)
+    }
+
+    /// Returns the thumbnail saved along with the state of `slot`, if any.
+    /// See `decode_thumbnail`
+    /// # Errors
+    /// If there is no save storage, or if the storage fails
+    pub fn slot_thumbnail(&self, slot: u32) -> Result<Option<Vec<u8>>, String> {
+        let key = self.save_slot_thumbnail_key(slot);
+        let storage = self.save_storage.as_ref().ok_or("No save storage set.")?;
+        storage
+            .load(&key)
+            .map_err(|e| format!("Failed to load the thumbnail: {}", e))
+    }
+
+    /// Returns the decoded thumbnail of `slot` along with the time it's shown from, if
+    /// the slot holds one. See `slot_thumbnail`
+    #[cfg(feature = "window")]
+    pub(super) fn slot_preview(&self, slot: u32) -> Option<(Vec<usize>, std::time::Instant)> {
+        let thumbnail = self.slot_thumbnail(slot).ok().flatten()?;
+        let pixels = decode_thumbnail(&thumbnail).ok()?;
+        Some((pixels, std::time::Instant::now()))
+    }
+
+    /// Restores the state saved in `slot` of the save storage. See `load_state`
+    /// # Errors
+    /// If there is no save storage, if the slot is empty or if the state can't be loaded
+    pub fn load_from_slot(&mut self, slot: u32) -> Result<(), String> {
+        let key = self.save_slot_key(slot);
+        let storage = self.save_storage.as_ref().ok_or("No save storage set.")?;
+        let state = storage
+            .load(&key)
+            .map_err(|e| format!("Failed to load the state: {}", e))?
+            .ok_or(format!("Slot {} is empty.", slot))?;
+        self.load_state(&state)
+    }
+}
+
+#[cfg(test)]
+fn state_test_vm() -> Chip8 {
+    let mut vm = Chip8::new();
+    vm.load_rom_content(include_bytes!("../../roms/tests/ibm-logo.ch8").to_vec())
+        .unwrap();
+    vm.set_rng_seed(7);
+    while vm.frames() < 10 {
+        vm.step().unwrap();
+    }
+    vm
+}
+
+#[test]
+fn states_are_restored() {
+    let mut vm = state_test_vm();
+    vm.set_quirks(Quirks::parse("shift,wrap").unwrap());
+    vm.pitch = 100;
+    let state = vm.save_state();
+
+    let mut restored = Chip8::new();
+    restored
+        .load_rom_content(include_bytes!("../../roms/tests/ibm-logo.ch8").to_vec())
+        .unwrap();
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.quirks(), vm.quirks());
+    assert_eq!(restored.frames(), vm.frames());
+    assert_eq!(restored.display_text(), vm.display_text());
+    assert_eq!(restored.save_state(), state);
+
+    // In the high resolution of SUPER-CHIP
+    vm.set_profile(Profile::Schip);
+    vm.high();
+    vm.display.plane_mut(0)[Chip8::HIRES_WIDTH * Chip8::HIRES_HEIGHT - 1] = true;
+    let state = vm.save_state();
+    restored.load_state(&state).unwrap();
+    assert!(restored.display().is_hires());
+    assert_eq!(restored.display_text(), vm.display_text());
+    assert_eq!(restored.save_state(), state);
+}
+
+#[test]
+fn states_of_older_versions_are_restored() {
+    let mut vm = state_test_vm();
+    vm.set_quirks(Quirks::parse("jump").unwrap());
+    let state = vm.save_state();
+    // Version 4 lacks the resolution, found before the planes, selected planes, keys,
+    // audio pattern, pitch, random generator and quirks
+    let mut v4 = state.clone();
+    v4.remove(state.len() - (2 * 256 + 1 + 2 + 16 + 1 + 32 + 1) - 1);
+    // Version 3 lacks the quirks, 2 the random generator and 1 the pitch
+    let v3 = &v4[..v4.len() - 1];
+    let v2 = &v3[..v3.len() - 32];
+    let v1 = &v2[..v2.len() - 1];
+
+    for (version, old_state) in [(4, &v4[..]), (3, v3), (2, v2), (1, v1)] {
+        let mut old_state = old_state.to_vec();
+        old_state[MAGIC.len()] = version;
+        let mut restored = state_test_vm();
+        restored.set_quirks(Quirks::parse("load-store").unwrap());
+        restored.pitch = 1;
+        restored.load_state(&old_state).unwrap();
+        // Settings missing from the state are kept
+        let quirks = match version {
+            4 => vm.quirks(),
+            _ => Quirks::parse("load-store").unwrap(),
+        };
+        assert_eq!(restored.quirks(), quirks);
+        let pitch = match version {
+            1 => AudioPattern::DEFAULT_PITCH,
+            _ => vm.pitch,
+        };
+        assert_eq!(restored.pitch, pitch);
+        assert_eq!(restored.display_text(), vm.display_text());
+    }
+}
+
+#[test]
+fn invalid_states_are_rejected() {
+    let vm = state_test_vm();
+    let state = vm.save_state();
+    let mut restored = state_test_vm();
+    let before = restored.save_state();
+
+    assert_eq!(
+        restored.load_state(b"C8S"),
+        Err(String::from("Truncated save state."))
+    );
+    assert_eq!(
+        restored.load_state(&state[..state.len() - 1]),
+        Err(String::from("Truncated save state."))
+    );
+    let mut future = state.clone();
+    future[MAGIC.len()] = VERSION + 1;
+    assert!(restored.load_state(&future).is_err());
+
+    let mut other_rom = Chip8::new();
+    other_rom.load_rom_content(vec![0x12, 0x00]).unwrap();
+    assert_eq!(
+        other_rom.load_state(&state),
+        Err(String::from("The save state belongs to another ROM."))
+    );
+
+    let mut deep = state_test_vm();
+    deep.stack.stored = vec![0x200; 3];
+    let deep_state = deep.save_state();
+    restored.set_stack_depth(2);
+    assert_eq!(
+        restored.load_state(&deep_state),
+        Err(String::from(
+            "The save state holds 3 subroutines, above the stack depth of 2."
+        ))
+    );
+
+    // The VM is left untouched
+    restored.set_stack_depth(vm.stack_depth());
+    assert_eq!(restored.save_state(), before);
+}
+
+#[test]
+fn slots_are_saved_with_a_thumbnail() {
+    let mut vm = state_test_vm();
+    vm.set_save_storage(Box::new(crate::storage::MemoryStorage::new()), 1);
+    assert_eq!(vm.saved_slots().unwrap(), Vec::<u32>::new());
+    vm.save_to_slot(10).unwrap();
+    vm.save_to_slot(2).unwrap();
+
+    assert_eq!(vm.saved_slots().unwrap(), vec![2, 10]);
+    assert_eq!(vm.slot_thumbnail(2).unwrap(), Some(vm.thumbnail_png()));
+    assert_eq!(vm.slot_thumbnail(3).unwrap(), None);
+}
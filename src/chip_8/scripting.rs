@@ -0,0 +1,341 @@
+use super::memory::{Memory, MemoryBounds};
+use super::{Chip8, Instruction};
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Scope, AST};
+use std::convert::TryFrom;
+use std::fs;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Events of the VM a `Script` reacts to, by defining a function named after them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptEvent {
+    /// `on_frame(frame)`: The timers ticked, `frame` being the number of frames since
+    /// the ROM started. See `Chip8::frames`
+    Frame,
+    /// `on_instruction(pc, opcode)`: An instruction was executed, `pc` being its address
+    Instruction,
+    /// `on_draw()`: An instruction cleared or drew to the display (`00E0`, `Dxyn` and the
+    /// SUPER-CHIP scrolls and resolution switches)
+    Draw,
+    /// `on_key(key, pressed)`: A key of the keypad was pressed or released, by the player
+    /// or by a movie
+    Key,
+}
+
+impl ScriptEvent {
+    /// All available events
+    pub const ALL: [ScriptEvent; 4] = [
+        ScriptEvent::Frame,
+        ScriptEvent::Instruction,
+        ScriptEvent::Draw,
+        ScriptEvent::Key,
+    ];
+
+    /// Returns the name of the function called on the event
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScriptEvent::Frame => "on_frame",
+            ScriptEvent::Instruction => "on_instruction",
+            ScriptEvent::Draw => "on_draw",
+            ScriptEvent::Key => "on_key",
+        }
+    }
+
+    /// Returns the number of arguments of the function called on the event
+    fn arity(&self) -> usize {
+        match self {
+            ScriptEvent::Frame => 1,
+            ScriptEvent::Instruction => 2,
+            ScriptEvent::Draw => 0,
+            ScriptEvent::Key => 2,
+        }
+    }
+}
+
+/// State of the VM lent to a script while one of its functions runs
+struct Lent {
+    v: [u8; 16],
+    i: u16,
+    pc: u16,
+    delay: u8,
+    sound: u8,
+    /// Swapped with the memory of the VM, instead of copying it on every call
+    memory: Memory,
+    /// Messages printed by the script, shown on the `Osd`
+    messages: Vec<String>,
+    pause: bool,
+}
+
+type Shared = Arc<Mutex<Lent>>;
+
+/// Locks the state lent to a script. A panic in a function of the script can't leave it
+/// half updated, so a poisoned lock is used as is
+fn lock(shared: &Shared) -> MutexGuard<'_, Lent> {
+    shared
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Returns `value` as an index below `len`, or an error naming it
+fn index(value: i64, len: usize, name: &str) -> Result<usize, Box<EvalAltResult>> {
+    usize::try_from(value)
+        .ok()
+        .filter(|&index| index < len)
+        .ok_or_else(|| format!("Invalid {} {}.", name, value).into())
+}
+
+/// A [Rhai](https://rhai.rs) script reacting to the events of the VM, ex: for cheats,
+/// automated tests or reverse engineering a ROM. The script defines a function for each
+/// event it handles, see `ScriptEvent`:
+/// ```text
+/// fn on_frame(frame) {
+///     if peek(0x3E0) < 3 { poke(0x3E0, 3); }
+/// }
+/// ```
+/// Its functions can read and write the VM with:
+/// * `v(x)` and `set_v(x, value)`: The register `Vx`.
+/// * `i()`, `set_i(address)`, `pc()` and `set_pc(address)`: The `I` register and the `PC`.
+/// * `delay()`, `set_delay(value)`, `sound()` and `set_sound(value)`: The timers.
+/// * `peek(address)` and `poke(address, value)`: A byte of memory.
+/// * `pause()`: Pauses the VM. See `Chip8::set_paused`
+///
+/// `print` shows a message on the `Osd`. Values written are truncated to the size of
+/// their register, while out of range registers and addresses are errors. Functions
+/// keep their state in `this`, an object map kept between calls, ex: `this.score = 0`.
+///
+/// **Note:** Only available with the `scripting` feature.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    /// Bound to `this` in every function
+    this: Dynamic,
+    shared: Shared,
+    /// Events with a function defined, indexed like `ScriptEvent::ALL`
+    handled: [bool; 4],
+}
+
+impl Script {
+    /// Compiles `source` and runs its top level statements
+    /// # Errors
+    /// If the script doesn't compile, or its top level statements fail
+    pub fn compile(source: &str) -> Result<Script, String> {
+        let shared = Arc::new(Mutex::new(Lent {
+            v: [0; 16],
+            i: 0,
+            pc: 0,
+            delay: 0,
+            sound: 0,
+            memory: Memory::new(1, MemoryBounds::Fault),
+            messages: Vec::new(),
+            pause: false,
+        }));
+        let engine = Script::engine(&shared);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| format!("Failed to compile the script: {}", e))?;
+
+        let handled = ScriptEvent::ALL.map(|event| {
+            ast.iter_functions()
+                .any(|f| f.name == event.name() && f.params.len() == event.arity())
+        });
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|e| format!("The script failed: {}", e))?;
+
+        Ok(Script {
+            engine,
+            ast,
+            scope,
+            this: Dynamic::from_map(Map::new()),
+            shared,
+            handled,
+        })
+    }
+
+    /// Reads and compiles the script at `path`. See `Script::compile`
+    pub fn load(path: &str) -> Result<Script, String> {
+        let source =
+            fs::read_to_string(path).map_err(|e| format!("Failed to open the script: {}", e))?;
+        Script::compile(&source)
+    }
+
+    /// Returns whether the script defines the function of `event`
+    pub fn handles(&self, event: ScriptEvent) -> bool {
+        self.handled[event as usize]
+    }
+
+    /// Creates an engine whose functions access the VM lent in `shared`
+    fn engine(shared: &Shared) -> Engine {
+        let mut engine = Engine::new();
+
+        let state = shared.clone();
+        engine.on_print(move |text| lock(&state).messages.push(text.to_string()));
+
+        let state = shared.clone();
+        engine.register_fn("v", move |x: i64| -> Result<i64, Box<EvalAltResult>> {
+            let x = index(x, 16, "register")?;
+            Ok(lock(&state).v[x] as i64)
+        });
+        let state = shared.clone();
+        engine.register_fn(
+            "set_v",
+            move |x: i64, value: i64| -> Result<(), Box<EvalAltResult>> {
+                let x = index(x, 16, "register")?;
+                lock(&state).v[x] = value as u8;
+                Ok(())
+            },
+        );
+
+        let state = shared.clone();
+        engine.register_fn("i", move || lock(&state).i as i64);
+        let state = shared.clone();
+        engine.register_fn("set_i", move |address: i64| {
+            lock(&state).i = address as u16;
+        });
+        let state = shared.clone();
+        engine.register_fn("pc", move || lock(&state).pc as i64);
+        let state = shared.clone();
+        engine.register_fn("set_pc", move |address: i64| {
+            lock(&state).pc = address as u16;
+        });
+
+        let state = shared.clone();
+        engine.register_fn("delay", move || lock(&state).delay as i64);
+        let state = shared.clone();
+        engine.register_fn("set_delay", move |value: i64| {
+            lock(&state).delay = value as u8;
+        });
+        let state = shared.clone();
+        engine.register_fn("sound", move || lock(&state).sound as i64);
+        let state = shared.clone();
+        engine.register_fn("set_sound", move |value: i64| {
+            lock(&state).sound = value as u8;
+        });
+
+        let state = shared.clone();
+        engine.register_fn(
+            "peek",
+            move |address: i64| -> Result<i64, Box<EvalAltResult>> {
+                let state = lock(&state);
+                let address = index(address, state.memory.len(), "address")?;
+                Ok(state.memory[address] as i64)
+            },
+        );
+        let state = shared.clone();
+        engine.register_fn(
+            "poke",
+            move |address: i64, value: i64| -> Result<(), Box<EvalAltResult>> {
+                let mut state = lock(&state);
+                let address = index(address, state.memory.len(), "address")?;
+                state.memory[address] = value as u8;
+                Ok(())
+            },
+        );
+
+        let state = shared.clone();
+        engine.register_fn("pause", move || lock(&state).pause = true);
+        engine
+    }
+
+    /// Calls the function of `event` with `args`
+    fn call(&mut self, event: ScriptEvent, args: impl FuncArgs) -> Result<(), String> {
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.this);
+        self.engine
+            .call_fn_with_options::<Dynamic>(
+                options,
+                &mut self.scope,
+                &self.ast,
+                event.name(),
+                args,
+            )
+            .map(|_| ())
+            .map_err(|e| format!("{} failed: {}", event.name(), e))
+    }
+}
+
+impl Chip8 {
+    /// Sets the script called on the events of the VM, replacing the previous one.
+    /// See `Script`
+    ///
+    /// **Note:** Only available with the `scripting` feature.
+    pub fn set_script(&mut self, script: Script) {
+        self.script = Some(script);
+    }
+
+    /// Returns the script called on the events of the VM, if any
+    pub fn script(&self) -> Option<&Script> {
+        self.script.as_ref()
+    }
+
+    /// Runs the functions of the script for an instruction just executed
+    pub(super) fn run_script_cycle(&mut self, pc: u16, opcode: u16) {
+        self.run_script(ScriptEvent::Instruction, (pc as i64, opcode as i64));
+        if self
+            .script
+            .as_ref()
+            .is_some_and(|s| s.handles(ScriptEvent::Draw))
+            && matches!(
+                Instruction::decode(opcode),
+                Some(
+                    Instruction::Cls
+                        | Instruction::DrwVxVyN(_, _, _)
+                        | Instruction::ScrollDown(_)
+                        | Instruction::ScrollUp(_)
+                        | Instruction::ScrollRight
+                        | Instruction::ScrollLeft
+                        | Instruction::Low
+                        | Instruction::High
+                )
+            )
+        {
+            self.run_script(ScriptEvent::Draw, ());
+        }
+    }
+
+    /// Runs the function of the script for `event`, if it defines one, with the VM lent.
+    /// A failing script is removed, with the error shown on the `Osd`
+    pub(super) fn run_script(&mut self, event: ScriptEvent, args: impl FuncArgs) {
+        let mut script = match self.script.take() {
+            Some(script) if script.handles(event) => script,
+            script => {
+                self.script = script;
+                return;
+            }
+        };
+
+        {
+            let mut lent = lock(&script.shared);
+            lent.v = self.regs.v;
+            lent.i = self.regs.i;
+            lent.pc = self.regs.pc;
+            lent.delay = self.timers.delay;
+            lent.sound = self.timers.sound;
+            std::mem::swap(&mut lent.memory, &mut self.main_memory);
+        }
+        let result = script.call(event, args);
+        let messages = {
+            let mut lent = lock(&script.shared);
+            self.regs.v = lent.v;
+            self.regs.i = lent.i;
+            self.regs.pc = lent.pc;
+            self.timers.delay = lent.delay;
+            self.timers.sound = lent.sound;
+            std::mem::swap(&mut lent.memory, &mut self.main_memory);
+            if std::mem::take(&mut lent.pause) {
+                self.paused = true;
+            }
+            std::mem::take(&mut lent.messages)
+        };
+        for message in messages {
+            self.osd.show(message);
+        }
+
+        match result {
+            Ok(()) => self.script = Some(script),
+            Err(msg) => self.osd.show(format!("Script stopped: {}", msg)),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::Chip8;
+
+/// A public test ROM run by `self_test`, along with the display it ends on
+struct SelfTestRom {
+    name: &'static str,
+    rom: &'static [u8],
+    /// Bytes written to memory once the ROM is loaded, as `(address, byte)`
+    memory: &'static [(usize, u8)],
+    /// Frames run, the same as its run in `tests/snapshots/snapshots.toml` or
+    /// `tests/roms/suite.toml`
+    frames: u64,
+    /// Golden snapshot of the display after the last frame, see `Chip8::display_text`
+    expected_display: &'static str,
+}
+
+const SELF_TEST_ROMS: [SelfTestRom; 2] = [
+    // Draws the IBM logo, a quick check of `00E0`, `Annn`, `6xkk`, `7xkk` and `Dxyn`
+    SelfTestRom {
+        name: "IBM logo",
+        rom: include_bytes!("../../roms/tests/ibm-logo.ch8"),
+        memory: &[],
+        frames: 60,
+        expected_display: include_str!("../../tests/snapshots/ibm-logo.txt"),
+    },
+    // corax+ of the CHIP-8 test suite, started without its menu through `0x1FF` like in
+    // `tests/roms/suite.toml`. Checks every opcode of the arithmetic, logic, flow control
+    // and memory instructions, drawing a check mark next to each one that passed
+    SelfTestRom {
+        name: "corax+",
+        rom: include_bytes!("../../roms/tests/chip8-test-suite.ch8"),
+        memory: &[(0x1FF, 2)],
+        frames: 120,
+        expected_display: include_str!("../../tests/snapshots/corax-plus.txt"),
+    },
+];
+
+/// Outcome of a check
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelfTestStatus {
+    Passed,
+    Failed,
+    /// The check can't run in this build or environment
+    Skipped,
+}
+
+/// Result of one of the checks run by `self_test`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelfTestResult {
+    pub name: &'static str,
+    pub status: SelfTestStatus,
+    /// Details of the outcome, ex: the display that didn't match
+    pub detail: String,
+}
+
+/// Runs the public test ROMs embedded in the emulator without any window, checking that
+/// each ends on the display of its golden snapshot.
+/// A quick way to confirm that a build works before filing issues.
+pub fn self_test() -> Vec<SelfTestResult> {
+    SELF_TEST_ROMS.iter().map(run_self_test_rom).collect()
+}
+
+fn run_self_test_rom(test: &SelfTestRom) -> SelfTestResult {
+    let mut vm = Chip8::new();
+    if let Err(msg) = vm.load_rom_content(test.rom.to_vec()) {
+        return SelfTestResult {
+            name: test.name,
+            status: SelfTestStatus::Failed,
+            detail: msg.to_string(),
+        };
+    }
+    for &(address, byte) in test.memory {
+        vm.main_memory[address] = byte;
+    }
+    while vm.frames() < test.frames {
+        match vm.step() {
+            Ok(Some(_)) => {}
+            // Halted
+            Ok(None) => break,
+            Err(error) => {
+                return SelfTestResult {
+                    name: test.name,
+                    status: SelfTestStatus::Failed,
+                    detail: error.to_string(),
+                }
+            }
+        }
+    }
+
+    let display = vm.display_text();
+    let (status, detail) = if display.trim_end() == test.expected_display.trim_end() {
+        (
+            SelfTestStatus::Passed,
+            String::from("the display matches its snapshot"),
+        )
+    } else {
+        (
+            SelfTestStatus::Failed,
+            format!("the display differs from its snapshot, found:\n{}", display),
+        )
+    };
+    SelfTestResult {
+        name: test.name,
+        status,
+        detail,
+    }
+}
+
+#[test]
+fn embedded_roms_pass() {
+    for result in self_test() {
+        assert_eq!(
+            result.status,
+            SelfTestStatus::Passed,
+            "{}: {}",
+            result.name,
+            result.detail
+        );
+    }
+}
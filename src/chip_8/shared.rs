@@ -0,0 +1,113 @@
+use super::{Chip8, Chip8Error};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Display contents of the first plane, in the resolution of the display when taken:
+/// 64x32 pixels, or 128x64 in the SUPER-CHIP high resolution. See `Display`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisplaySnapshot {
+    pub width: usize,
+    pub height: usize,
+    /// `width * height` pixels, row by row
+    pub pixels: Vec<bool>,
+}
+
+/// A `Send + Sync` handle to a VM, so a frontend can drive it from several threads,
+/// ex: an emulation thread running cycles, the UI thread handling keys and rendering,
+/// and an audio callback polling the sound timer. Cloning the handle is cheap and
+/// all clones refer to the same VM.
+/// # Locking strategy
+/// The VM is guarded by a single `Mutex`, so every operation sees a consistent state:
+/// cycles are never interleaved with other accesses.
+/// * Every method locks the VM for the duration of the call only. Prefer them over
+///   `lock` and `with`, which keep the VM locked until the guard or closure ends.
+/// * Keep critical sections short: never block, sleep or render while holding the lock.
+///   Ex: take a `snapshot_display` and render the copy.
+/// * The lock is not reentrant: calling a method of the handle while holding
+///   `lock` or inside `with` deadlocks.
+/// * Faults like a Stack overflow don't poison the lock: they are returned as a
+///   `Chip8Error`, see `SharedChip8::run_cycles`. If a thread panics while holding the
+///   lock anyway, the VM is still handed out to the other threads, in the state the
+///   panic left it.
+#[derive(Clone)]
+pub struct SharedChip8 {
+    vm: Arc<Mutex<Chip8>>,
+}
+
+impl SharedChip8 {
+    /// Moves `vm` behind a shared handle
+    pub fn new(vm: Chip8) -> SharedChip8 {
+        SharedChip8 {
+            vm: Arc::new(Mutex::new(vm)),
+        }
+    }
+
+    /// Locks the VM until the returned guard is dropped
+    pub fn lock(&self) -> MutexGuard<'_, Chip8> {
+        self.vm
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Runs `f` with the VM locked
+    pub fn with<R>(&self, f: impl FnOnce(&mut Chip8) -> R) -> R {
+        f(&mut self.lock())
+    }
+
+    /// Runs `cycles` cycles at once, releasing the lock only afterwards.
+    /// Stops early if the VM halts. See `ExecutionLimits`
+    /// # Errors
+    /// If an instruction faults, stopping right away. See `Chip8Error`
+    pub fn run_cycles(&self, cycles: u32) -> Result<(), Chip8Error> {
+        let mut vm = self.lock();
+        for _ in 0..cycles {
+            if vm.step()?.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks one of the 16 keys (`0x0` to `0xF`) as pressed
+    pub fn press_key(&self, key: usize) {
+        self.lock().press_key(key);
+    }
+
+    /// Marks one of the 16 keys (`0x0` to `0xF`) as released
+    pub fn release_key(&self, key: usize) {
+        self.lock().release_key(key);
+    }
+
+    /// Returns `true` while the sound timer is active, ex: for an audio callback
+    pub fn is_sound_active(&self) -> bool {
+        self.lock().timers.sound > 0
+    }
+
+    /// Returns a copy of the display, so it can be rendered without holding the lock
+    pub fn snapshot_display(&self) -> DisplaySnapshot {
+        let vm = self.lock();
+        DisplaySnapshot {
+            width: vm.display.width(),
+            height: vm.display.height(),
+            pixels: vm.display.plane(0).to_vec(),
+        }
+    }
+}
+
+impl From<Chip8> for SharedChip8 {
+    fn from(vm: Chip8) -> SharedChip8 {
+        SharedChip8::new(vm)
+    }
+}
+
+#[test]
+fn shared_vms_can_be_sent_between_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedChip8>();
+
+    let vm = SharedChip8::new(Chip8::new());
+    let other = vm.clone();
+    std::thread::spawn(move || other.press_key(0xA))
+        .join()
+        .unwrap();
+    assert!(vm.lock().keys_pressed()[0xA]);
+}
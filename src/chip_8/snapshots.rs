@@ -0,0 +1,152 @@
+//! Regression tests of the rendering, ex: of `Dxyn`. ROMs run headless for a number of
+//! frames, and the display reached is compared against a golden snapshot committed in
+//! `tests/snapshots`, as text art with `#` for pixels on and `.` for pixels off.
+//! See `Chip8::display_text`.
+//!
+//! Each entry of `tests/snapshots/snapshots.toml` describes a run, compared against
+//! the snapshot `tests/snapshots/NAME.txt`:
+//! ```toml
+//! [[snapshot]]
+//! name = "pong"
+//! # Relative to the crate root
+//! rom = "roms/PONG"
+//! frames = 60
+//! # Optional, see `Profile`
+//! profile = "schip"
+//! # Optional, the seed of `Cxkk`, `0` by default. See `Chip8::set_rng_seed`
+//! seed = 1
+//! ```
+//! # Bless mode
+//! Once a change of the display is checked to be intended, running the tests with the
+//! `CHIP8_BLESS` environment variable set writes the displays reached as the new
+//! snapshots, creating the missing ones:
+//! ```text
+//! $ CHIP8_BLESS=1 cargo test golden_snapshots
+//! ```
+use super::fixtures::integer;
+use super::{Chip8, Profile};
+use std::env;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Description of the runs, relative to the crate root
+const SNAPSHOTS_FILE: &str = "tests/snapshots/snapshots.toml";
+
+/// Directory of the snapshots, relative to the crate root
+const SNAPSHOTS_DIRECTORY: &str = "tests/snapshots";
+
+/// Environment variable enabling the bless mode
+const BLESS_VARIABLE: &str = "CHIP8_BLESS";
+
+/// Runs the ROM of a snapshot
+/// # Returns
+/// The display reached, as text
+fn run(snapshot: &Value, root: &Path) -> Result<String, String> {
+    let rom = snapshot
+        .get("rom")
+        .and_then(Value::as_str)
+        .ok_or("Missing 'rom'")?;
+    let content = fs::read(root.join(rom)).map_err(|e| format!("Failed to open the ROM: {}", e))?;
+
+    let mut vm = Chip8::new();
+    if let Some(name) = snapshot.get("profile") {
+        let name = name.as_str().ok_or("'profile' must be a string")?;
+        vm.apply_profile(Profile::from_name(name).ok_or("Unknown profile")?);
+    }
+    let seed = match snapshot.get("seed") {
+        Some(seed) => integer(seed, "seed")? as u64,
+        None => 0,
+    };
+    vm.set_rng_seed(seed);
+    vm.load_rom_content(content)?;
+
+    let frames = integer(snapshot.get("frames").ok_or("Missing 'frames'")?, "frames")? as u64;
+    while vm.frames() < frames {
+        if vm.step().map_err(|e| e.to_string())?.is_none() {
+            break;
+        }
+    }
+    Ok(vm.display_text())
+}
+
+/// Describes the rows of `actual` that differ from `expected`
+fn diff(expected: &str, actual: &str) -> Vec<String> {
+    let mut rows = Vec::new();
+    let (expected_rows, actual_rows) = (expected.lines().count(), actual.lines().count());
+    if expected_rows != actual_rows {
+        rows.push(format!(
+            "Expected {} rows, found {}",
+            expected_rows, actual_rows
+        ));
+    }
+    for (y, (expected, actual)) in expected.lines().zip(actual.lines()).enumerate() {
+        if expected != actual {
+            rows.push(format!("Row {:2} expected {}", y, expected));
+            rows.push(format!("       found    {}", actual));
+        }
+    }
+    rows
+}
+
+#[test]
+fn golden_snapshots() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let directory = root.join(SNAPSHOTS_DIRECTORY);
+    let bless = env::var_os(BLESS_VARIABLE).is_some();
+    let content = fs::read_to_string(root.join(SNAPSHOTS_FILE)).expect("Failed to read the runs");
+    let runs: Table = content.parse().expect("Failed to parse the runs");
+
+    let mut blessed = Vec::new();
+    let mut failures = Vec::new();
+    let snapshots = runs.get("snapshot").and_then(Value::as_array);
+    for (index, snapshot) in snapshots.into_iter().flatten().enumerate() {
+        let name = match snapshot.get("name").and_then(Value::as_str) {
+            Some(name) => name.to_string(),
+            None => {
+                failures.push(format!("Snapshot #{}: Missing 'name'", index + 1));
+                continue;
+            }
+        };
+        let actual = match run(snapshot, root) {
+            Ok(display) => display + "\n",
+            Err(e) => {
+                failures.push(format!("{}: {}", name, e));
+                continue;
+            }
+        };
+
+        let path = directory.join(format!("{}.txt", name));
+        let expected = fs::read_to_string(&path).ok();
+        if expected.as_deref() == Some(actual.as_str()) {
+            continue;
+        }
+        if bless {
+            fs::write(&path, &actual).expect("Failed to write the snapshot");
+            blessed.push(name);
+            continue;
+        }
+        match expected {
+            Some(expected) => failures.push(format!(
+                "{}\n    {}",
+                name,
+                diff(&expected, &actual).join("\n    ")
+            )),
+            None => failures.push(format!(
+                "{}: no snapshot recorded, found:\n{}",
+                name, actual
+            )),
+        }
+    }
+
+    if !blessed.is_empty() {
+        eprintln!("Snapshots written: {}", blessed.join(", "));
+    }
+    assert!(
+        failures.is_empty(),
+        "{} snapshots differ, run with {}=1 to accept the changes:\n{}",
+        failures.len(),
+        BLESS_VARIABLE,
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,86 @@
+use super::audio::Voice;
+use super::{Sound, Tone};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+use std::sync::{Arc, Mutex};
+
+/// Plays the `Sound` of the VM through the default audio device: the `Tone`, or the
+/// XO-CHIP audio pattern resampled to the rate of the device.
+///
+/// The stream runs on a thread of the audio backend and only reads the sound, set by the
+/// main loop after every update with `Beeper::set_sound`, so the VM never waits on the
+/// device: a buffer written while the sound is being set keeps the previous one. The wave
+/// is silent between beeps instead of stopping the stream, which avoids clicks and the
+/// latency of restarting the device.
+///
+/// **Note:** Only available with the `sound` feature.
+pub struct Beeper {
+    /// Kept alive for as long as the beeper, dropping it closes the device
+    _stream: Stream,
+    sound: Arc<Mutex<Sound>>,
+}
+
+impl Beeper {
+    /// Opens the default output device and starts a silent stream of `tone`
+    /// # Errors
+    /// If there's no output device, or if the device rejects the stream
+    pub fn open(tone: Tone) -> Result<Beeper, String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("No audio output device found.")?;
+        let supported = device
+            .default_output_config()
+            .map_err(|e| format!("Failed to query the audio device: {}", e))?;
+        let sound = Arc::new(Mutex::new(Sound::Silent));
+        let config = supported.config();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, tone.clone(), &sound),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, tone.clone(), &sound),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, tone, &sound),
+            format => Err(format!("Unsupported audio sample format {}.", format)),
+        }?;
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start the audio stream: {}", e))?;
+        Ok(Beeper {
+            _stream: stream,
+            sound,
+        })
+    }
+
+    /// Sets what plays from the next buffer of the device, ex: `Sound::Silent` stops the beep
+    pub fn set_sound(&self, sound: Sound) {
+        if let Ok(mut current) = self.sound.lock() {
+            *current = sound;
+        }
+    }
+}
+
+/// Builds a stream writing `sound` to every channel, beeping with `tone`
+fn build_stream<T: SizedSample + FromSample<f32>>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    tone: Tone,
+    sound: &Arc<Mutex<Sound>>,
+) -> Result<Stream, String> {
+    let channels = config.channels as usize;
+    let sample_rate = config.sample_rate.0;
+    let shared = Arc::clone(sound);
+    let mut sound = Sound::Silent;
+    let mut voice = Voice::new();
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                if let Ok(current) = shared.try_lock() {
+                    sound = *current;
+                }
+                for frame in data.chunks_mut(channels) {
+                    frame.fill(T::from_sample(voice.next(&sound, &tone, sample_rate)));
+                }
+            },
+            |e| eprintln!("ERROR: Audio stream failed: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open the audio stream: {}", e))
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+/// Speed of the emulation relative to real time, changed at runtime by the `speed-up`,
+/// `slow-down` and `fast-forward` hotkeys. Frames are run more or less often, with
+/// their instructions and the ticks of the timers, so games paced by the timers
+/// speed up and slow down as well.
+///
+/// The speed goes by powers of 2, from `1/8` to `8` times the normal speed, and
+/// fast-forward runs at least at `8` times the normal speed while enabled.
+pub struct SpeedControl {
+    /// The speed is `2 ^ exponent` times the normal speed
+    exponent: i32,
+    fast_forward: bool,
+}
+
+impl SpeedControl {
+    /// Highest and lowest `exponent`
+    const MAX_EXPONENT: i32 = 3;
+    /// `exponent` while fast-forwarding
+    const FAST_FORWARD_EXPONENT: i32 = 3;
+
+    /// Creates the control at the normal speed
+    pub fn new() -> SpeedControl {
+        SpeedControl {
+            exponent: 0,
+            fast_forward: false,
+        }
+    }
+
+    /// Doubles the speed, unless already at the highest one
+    pub fn faster(&mut self) {
+        self.exponent = (self.exponent + 1).min(SpeedControl::MAX_EXPONENT);
+    }
+
+    /// Halves the speed, unless already at the lowest one
+    pub fn slower(&mut self) {
+        self.exponent = (self.exponent - 1).max(-SpeedControl::MAX_EXPONENT);
+    }
+
+    /// Enables or disables the fast-forward, ex: while its hotkey is held down
+    pub fn set_fast_forward(&mut self, enabled: bool) {
+        self.fast_forward = enabled;
+    }
+
+    fn effective_exponent(&self) -> i32 {
+        match self.fast_forward {
+            true => self.exponent.max(SpeedControl::FAST_FORWARD_EXPONENT),
+            false => self.exponent,
+        }
+    }
+
+    /// Returns whether frames run in real time
+    pub fn is_normal(&self) -> bool {
+        self.effective_exponent() == 0
+    }
+
+    /// Returns how long a frame lasting `period` at the normal speed lasts at this speed
+    pub fn frame_period(&self, period: Duration) -> Duration {
+        let exponent = self.effective_exponent();
+        match exponent >= 0 {
+            true => period / (1 << exponent),
+            false => period * (1 << -exponent),
+        }
+    }
+
+    /// Returns the speed as shown on screen, ex: `x2`, `x1/4` or `>> x8` while fast-forwarding
+    pub fn label(&self) -> String {
+        let exponent = self.effective_exponent();
+        let multiplier = match exponent >= 0 {
+            true => format!("x{}", 1 << exponent),
+            false => format!("x1/{}", 1 << -exponent),
+        };
+        match self.fast_forward {
+            true => format!(">> {}", multiplier),
+            false => multiplier,
+        }
+    }
+}
+
+impl Default for SpeedControl {
+    fn default() -> SpeedControl {
+        SpeedControl::new()
+    }
+}
@@ -0,0 +1,62 @@
+use super::{Chip8, Chip8Error, Instruction};
+
+/// What a single instruction executed by `Chip8::step` did, ex: for frontends to know
+/// when to redraw or beep without inspecting the VM after every instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepReport {
+    /// Address of the instruction executed
+    pub pc: u16,
+    pub opcode: u16,
+    /// Mnemonic of the instruction, ex: `DRW`
+    pub mnemonic: &'static str,
+    /// Whether the instruction modified the display (`00E0`, `Dxyn` and the SUPER-CHIP
+    /// scrolls and resolution switches)
+    pub display_dirty: bool,
+    /// Whether the VM is blocked on `Fx0A` until a key is pressed, or released with
+    /// `Quirks::wait_release`
+    pub waiting_for_key: bool,
+    /// Whether the sound timer is running after the instruction
+    pub sound_active: bool,
+}
+
+impl Chip8 {
+    /// Executes the next instruction, ticking the timers if a frame ends with it.
+    /// Frontends and the `Debugger` run the VM one step at a time
+    /// # Returns
+    /// What the instruction did, `None` if the VM is halted. See `Chip8::halt_report`
+    /// # Errors
+    /// If the instruction faults. It then has no effect and the `PC` is left pointing
+    /// at it. See `Chip8Error`
+    pub fn step(&mut self) -> Result<Option<StepReport>, Chip8Error> {
+        if self.halted.is_some() {
+            return Ok(None);
+        }
+        self.cycle()?;
+
+        let (pc, opcode) = self.instruction;
+        let instruction = Instruction::decode(opcode);
+        Ok(Some(StepReport {
+            pc,
+            opcode,
+            mnemonic: instruction
+                .and_then(|instruction| instruction.info().syntax.split(' ').next())
+                .unwrap_or_default(),
+            display_dirty: matches!(
+                instruction,
+                Some(
+                    Instruction::Cls
+                        | Instruction::DrwVxVyN(_, _, _)
+                        | Instruction::ScrollDown(_)
+                        | Instruction::ScrollUp(_)
+                        | Instruction::ScrollRight
+                        | Instruction::ScrollLeft
+                        | Instruction::Low
+                        | Instruction::High
+                )
+            ),
+            waiting_for_key: matches!(instruction, Some(Instruction::LdVxK(_)))
+                && self.regs.pc == pc,
+            sound_active: self.timers.sound > 0,
+        }))
+    }
+}
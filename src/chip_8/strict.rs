@@ -0,0 +1,210 @@
+use super::dump::disassemble;
+use super::{Chip8, Chip8Error, Instruction};
+
+/// Number of instructions of the `History` included in a violation report
+const REPORTED_HISTORY: usize = 16;
+
+/// Invariant checks of the strict spec mode around a single instruction.
+/// See `Chip8::set_strict`
+pub struct StrictCheck {
+    address: u16,
+    opcode: u16,
+    /// Pixels of both display planes before executing the instruction, only kept for the
+    /// ones drawing
+    display: Option<Vec<bool>>,
+}
+
+impl StrictCheck {
+    /// Checks the invariants that must hold before executing `opcode`, located at `address`
+    /// # Errors
+    /// `Chip8Error::StrictViolation` on the first violation
+    pub fn before(vm: &Chip8, address: u16, opcode: u16) -> Result<StrictCheck, Chip8Error> {
+        let check = StrictCheck {
+            address,
+            opcode,
+            display: match Instruction::decode(opcode) {
+                Some(Instruction::Cls) | Some(Instruction::DrwVxVyN(..)) => {
+                    Some(vm.planes().concat())
+                }
+                _ => None,
+            },
+        };
+        check
+            .check_before(vm)
+            .map_err(|reason| check.violation(reason))?;
+        Ok(check)
+    }
+
+    /// Checks the invariants that must hold after executing the instruction
+    /// # Errors
+    /// `Chip8Error::StrictViolation` on the first violation
+    pub fn after(self, vm: &Chip8) -> Result<(), Chip8Error> {
+        self.check_after(vm)
+            .map_err(|reason| self.violation(reason))
+    }
+
+    fn check_before(&self, vm: &Chip8) -> Result<(), String> {
+        let instruction = match Instruction::decode(self.opcode) {
+            Some(instruction) => instruction,
+            None => return Ok(()),
+        };
+        let registers: &[usize] = match instruction {
+            Instruction::SeVxByte(x, _)
+            | Instruction::SneVxByte(x, _)
+            | Instruction::LdVxValue(x, _)
+            | Instruction::AddVxByte(x, _)
+            | Instruction::RndVxByte(x, _)
+            | Instruction::SkipVx(x)
+            | Instruction::SkipNVx(x)
+            | Instruction::LdVxDt(x)
+            | Instruction::LdVxK(x)
+            | Instruction::LdDtVx(x)
+            | Instruction::LdStVx(x)
+            | Instruction::AddIVx(x)
+            | Instruction::LdFVx(x)
+            | Instruction::LdHfVx(x)
+            | Instruction::LdBVx(x)
+            | Instruction::LdIVx(x)
+            | Instruction::LdVxI(x)
+            | Instruction::Pitch(x)
+            | Instruction::LdRVx(x)
+            | Instruction::LdVxR(x) => &[x],
+            Instruction::SeVxVy(x, y)
+            | Instruction::LdVxVy(x, y)
+            | Instruction::OrVxVy(x, y)
+            | Instruction::AndVxVy(x, y)
+            | Instruction::XorVxVy(x, y)
+            | Instruction::AddVxVy(x, y)
+            | Instruction::SubVxVy(x, y)
+            | Instruction::ShrVx(x, y)
+            | Instruction::SubnVxVy(x, y)
+            | Instruction::ShlVx(x, y)
+            | Instruction::SneVxVy(x, y)
+            | Instruction::DrwVxVyN(x, y, _)
+            | Instruction::SaveVxVy(x, y)
+            | Instruction::LoadVxVy(x, y) => &[x, y],
+            _ => &[],
+        };
+        if let Some(register) = registers.iter().find(|&&register| register > 0xF) {
+            return Err(format!("register index 0x{:X} exceeds 0xF", register));
+        }
+
+        let accessed = match instruction {
+            // 16x16 sprites of SUPER-CHIP take 32 bytes
+            Instruction::DrwVxVyN(_, _, 0) if vm.profile.has_schip_instructions() => {
+                32 * vm.display.selected().count()
+            }
+            Instruction::DrwVxVyN(_, _, n) => n * vm.display.selected().count(),
+            Instruction::LdBVx(_) => 3,
+            Instruction::LdIVx(x) | Instruction::LdVxI(x) => x + 1,
+            Instruction::SaveVxVy(x, y) | Instruction::LoadVxVy(x, y) => x.abs_diff(y) + 1,
+            Instruction::Audio => 16,
+            _ => 0,
+        };
+        let i = vm.regs.i as usize;
+        if i + accessed > vm.memory().len() {
+            return Err(format!(
+                "access of {} bytes at I = 0x{:03X} exceeds memory",
+                accessed, i
+            ));
+        }
+
+        match instruction {
+            Instruction::SkipVx(x) | Instruction::SkipNVx(x) if vm.regs.v[x] > 0xF => Err(format!(
+                "key 0x{:02X} in V{:X} exceeds 0xF",
+                vm.regs.v[x], x
+            )),
+            Instruction::LdFVx(x) if vm.regs.v[x] > 0xF => Err(format!(
+                "font digit 0x{:02X} in V{:X} exceeds 0xF",
+                vm.regs.v[x], x
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_after(&self, vm: &Chip8) -> Result<(), String> {
+        let pc = vm.regs.pc as usize;
+        if !pc.is_multiple_of(2) {
+            return Err(format!("PC = 0x{:03X} is not aligned", pc));
+        }
+        if !(Chip8::INITIAL_MEMORY_ADDRESS..vm.memory().len() - 1).contains(&pc) {
+            return Err(format!("PC = 0x{:03X} is outside of the ROM area", pc));
+        }
+        if vm.stack.pointer() > vm.stack.depth {
+            return Err(format!(
+                "SP = {} exceeds the stack depth of {}",
+                vm.stack.pointer(),
+                vm.stack.depth
+            ));
+        }
+        if vm.regs.i as usize >= vm.memory().len() {
+            return Err(format!("I = 0x{:03X} is outside of memory", vm.regs.i));
+        }
+
+        let instruction = Instruction::decode(self.opcode);
+        let sets_flag = matches!(
+            instruction,
+            Some(
+                Instruction::AddVxVy(..)
+                    | Instruction::SubVxVy(..)
+                    | Instruction::ShrVx(..)
+                    | Instruction::SubnVxVy(..)
+                    | Instruction::ShlVx(..)
+                    | Instruction::DrwVxVyN(..)
+            )
+        );
+        if sets_flag && vm.regs.v[0xF] > 1 {
+            return Err(format!("flag VF = 0x{:02X} is not 0 or 1", vm.regs.v[0xF]));
+        }
+
+        if let Some(before) = &self.display {
+            let planes = vm.planes();
+            if instruction == Some(Instruction::Cls) {
+                let mut selected = vm.display.selected();
+                if selected.any(|plane| planes[plane].iter().any(|&pixel| pixel)) {
+                    return Err("display not cleared by CLS".to_string());
+                }
+            } else {
+                // A sprite collides if, and only if, it turns off a pixel
+                let collision = before
+                    .iter()
+                    .zip(planes.concat().iter())
+                    .any(|(&was_on, &is_on)| was_on && !is_on);
+                if collision != (vm.regs.v[0xF] == 1) {
+                    return Err(format!(
+                        "VF = {} doesn't match the collisions on the display",
+                        vm.regs.v[0xF]
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn violation(&self, reason: String) -> Chip8Error {
+        Chip8Error::StrictViolation {
+            pc: self.address,
+            opcode: self.opcode,
+            reason,
+        }
+    }
+}
+
+impl Chip8 {
+    /// Returns the context of a fault, ex: of a `Chip8Error::StrictViolation`: the
+    /// instruction being executed, the registers and the last instructions executed
+    pub fn fault_context(&self) -> String {
+        let history: Vec<&(u16, u16)> = self.history.iter().collect();
+        let trace: String = history[history.len().saturating_sub(REPORTED_HISTORY)..]
+            .iter()
+            .map(|&&(address, opcode)| format!("  {}\n", disassemble(address, opcode)))
+            .collect();
+        let (address, opcode) = self.instruction;
+        format!(
+            "Instruction: {}\n{}\nLast instructions:\n{}",
+            disassemble(address, opcode),
+            self.register_dump(),
+            trace
+        )
+    }
+}
@@ -0,0 +1,122 @@
+//! Integration tests running the community test ROMs of the
+//! [CHIP-8 test suite](https://github.com/Timendus/chip8-test-suite) headless, comparing
+//! the display after a number of frames against a known-good hash.
+//!
+//! The ROMs are vendored in `roms/tests`. Each entry of `tests/roms/suite.toml`
+//! describes a run:
+//! ```toml
+//! [[rom]]
+//! name = "corax+"
+//! # Relative to the crate root
+//! rom = "roms/tests/chip8-test-suite.ch8"
+//! frames = 120
+//! # Optional, see `Profile`
+//! profile = "cosmac-vip"
+//! # Optional, the SHA-1 of the ROM, to catch other versions of the suite
+//! sha1 = "..."
+//! # Optional, bytes written after loading the ROM, ex: the test the suite starts
+//! memory = { "0x1FF" = [2] }
+//! # SHA-1 of the display after `frames`, as a PBM image. See `Chip8::display_pbm`
+//! display = "..."
+//! ```
+//! Missing ROMs fail, and so do runs without a `display` hash, printing the display
+//! reached and its hash: once the display is checked to show every test passing, the
+//! hash can be recorded.
+use super::fixtures::{hex_table, integer, integers};
+use super::rom_database::rom_sha1;
+use super::{Chip8, Profile};
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Description of the runs, relative to the crate root
+const SUITE_FILE: &str = "tests/roms/suite.toml";
+
+/// Runs a single ROM of the suite
+/// # Returns
+/// A description of every mismatch, empty if the display matches.
+fn run(rom: &Value, root: &Path) -> Result<Vec<String>, String> {
+    let path = rom
+        .get("rom")
+        .and_then(Value::as_str)
+        .ok_or("Missing 'rom'")?;
+    let content =
+        fs::read(root.join(path)).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    if let Some(expected) = rom.get("sha1") {
+        let expected = expected.as_str().ok_or("'sha1' must be a string")?;
+        let actual = rom_sha1(&content);
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Ok(vec![format!(
+                "ROM: expected SHA-1 {}, found {}, another version of the suite?",
+                expected, actual
+            )]);
+        }
+    }
+
+    let mut vm = Chip8::new();
+    if let Some(name) = rom.get("profile") {
+        let name = name.as_str().ok_or("'profile' must be a string")?;
+        vm.apply_profile(Profile::from_name(name).ok_or("Unknown profile")?);
+    }
+    vm.load_rom_content(content)?;
+    if let Some(memory) = rom.get("memory") {
+        for (address, bytes) in hex_table(memory, "memory")? {
+            for (offset, byte) in integers(&bytes, "memory")?.into_iter().enumerate() {
+                *vm.main_memory
+                    .get_mut(address + offset)
+                    .ok_or("Invalid memory address")? = byte as u8;
+            }
+        }
+    }
+
+    let frames = integer(rom.get("frames").ok_or("Missing 'frames'")?, "frames")? as u64;
+    while vm.frames() < frames {
+        if vm.step().map_err(|e| e.to_string())?.is_none() {
+            break;
+        }
+    }
+
+    let actual = rom_sha1(vm.display_pbm().as_bytes());
+    let mismatch = match rom.get("display") {
+        Some(expected) => {
+            let expected = expected.as_str().ok_or("'display' must be a string")?;
+            if actual.eq_ignore_ascii_case(expected) {
+                return Ok(Vec::new());
+            }
+            format!("Display: expected SHA-1 {}, found {}", expected, actual)
+        }
+        None => format!("Display: no hash recorded, found {}", actual),
+    };
+    Ok(vec![
+        mismatch,
+        format!("Display after {} frames:\n{}", frames, vm.display_text()),
+    ])
+}
+
+#[test]
+fn test_roms() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let content = fs::read_to_string(root.join(SUITE_FILE)).expect("Failed to read the suite");
+    let suite: Table = content.parse().expect("Failed to parse the suite");
+
+    let mut failures = Vec::new();
+    let roms = suite.get("rom").and_then(Value::as_array);
+    for (index, rom) in roms.into_iter().flatten().enumerate() {
+        let name = match rom.get("name").and_then(Value::as_str) {
+            Some(name) => name.to_string(),
+            None => format!("ROM #{}", index + 1),
+        };
+        match run(rom, root) {
+            Ok(mismatches) if mismatches.is_empty() => {}
+            Ok(mismatches) => failures.push(format!("{}\n    {}", name, mismatches.join("\n    "))),
+            Err(e) => failures.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} test ROMs failed:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,134 @@
+use super::Chip8;
+
+/// 2-bit gray level of each color of the `Palette`: pixels lit on the first plane are
+/// white, then light gray on the second plane and gray on both, so thumbnails of ROMs
+/// drawing on a single plane look the same as before XO-CHIP
+const THUMBNAIL_LEVELS: [u8; 4] = [0, 3, 2, 1];
+
+impl Chip8 {
+    /// Encodes the display as a tiny PNG image: 64x32 pixels, 2-bit grayscale, with both
+    /// planes composited. See `THUMBNAIL_LEVELS`. Meant as a preview of the state of a VM,
+    /// ex: to tell save-state slots apart.
+    ///
+    /// In the high resolution every pixel of the thumbnail stands for a square of 2x2
+    /// pixels, lit on the planes any of them is lit on
+    pub fn thumbnail_png(&self) -> Vec<u8> {
+        let width = self.display.width();
+        let scale = width / Chip8::VIDEO_WIDTH;
+        // Each row is packed as 16 bytes, 4 pixels per byte, most-significant bits first
+        let mut data = vec![0u8; Chip8::VIDEO_WIDTH * Chip8::VIDEO_HEIGHT / 4];
+        for pos in 0..Chip8::VIDEO_WIDTH * Chip8::VIDEO_HEIGHT {
+            let (x, y) = (
+                pos % Chip8::VIDEO_WIDTH * scale,
+                pos / Chip8::VIDEO_WIDTH * scale,
+            );
+            let color = (0..scale * scale).fold(0, |color, offset| {
+                color
+                    | self
+                        .display
+                        .color((y + offset / scale) * width + x + offset % scale)
+            });
+            let level = THUMBNAIL_LEVELS[color];
+            data[pos / 4] |= level << (6 - 2 * (pos % 4));
+        }
+
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(
+            &mut png,
+            Chip8::VIDEO_WIDTH as u32,
+            Chip8::VIDEO_HEIGHT as u32,
+        );
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Two);
+
+        // Encoding into memory can't fail: the header and data sizes always match
+        let mut writer = encoder
+            .write_header()
+            .expect("Failed to write the thumbnail header");
+        writer
+            .write_image_data(&data)
+            .expect("Failed to write the thumbnail data");
+        drop(writer);
+
+        png
+    }
+}
+
+/// Decodes a thumbnail written by `Chip8::thumbnail_png`.
+/// # Returns
+/// The index of the color of every pixel in the `Palette`, row by row, like
+/// `Chip8::composite_text`.
+/// # Errors
+/// If `png` isn't a valid thumbnail
+pub fn decode_thumbnail(png: &[u8]) -> Result<Vec<usize>, String> {
+    let invalid = |e: png::DecodingError| format!("Invalid thumbnail: {}", e);
+    let mut decoder = png::Decoder::new(png);
+    // Expands the pixels to a byte each, gray levels scaled to 0x00, 0x55, 0xAA and 0xFF
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(invalid)?;
+    let mut pixels = vec![0u8; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut pixels).map_err(invalid)?;
+    let size = (Chip8::VIDEO_WIDTH as u32, Chip8::VIDEO_HEIGHT as u32);
+    if (frame.width, frame.height) != size || frame.color_type != png::ColorType::Grayscale {
+        return Err(String::from(
+            "Invalid thumbnail: expected a 64x32 grayscale image",
+        ));
+    }
+
+    Ok(pixels[..frame.buffer_size()]
+        .iter()
+        .map(|&gray| {
+            let level = gray / 0x55;
+            THUMBNAIL_LEVELS
+                .iter()
+                .position(|&l| l == level)
+                .unwrap_or_default()
+        })
+        .collect())
+}
+
+/// Draws the pixels of a thumbnail decoded by `decode_thumbnail` as text, two rows of
+/// pixels per line with half blocks, ex: to list save states in a terminal
+pub fn thumbnail_text(pixels: &[usize]) -> String {
+    let lit = |row: usize, col: usize| {
+        pixels
+            .get(row * Chip8::VIDEO_WIDTH + col)
+            .is_some_and(|&color| color != 0)
+    };
+    let mut text = String::new();
+    for row in (0..Chip8::VIDEO_HEIGHT).step_by(2) {
+        for col in 0..Chip8::VIDEO_WIDTH {
+            text.push(match (lit(row, col), lit(row + 1, col)) {
+                (false, false) => ' ',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (true, true) => '█',
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[test]
+fn thumbnails_composite_both_planes() {
+    let mut vm = Chip8::new();
+    vm.display.buffer[0] = true;
+    vm.display.second_plane[1] = true;
+    vm.display.buffer[2] = true;
+    vm.display.second_plane[2] = true;
+    vm.display.buffer[Chip8::VIDEO_WIDTH + 3] = true;
+
+    let pixels = decode_thumbnail(&vm.thumbnail_png()).unwrap();
+    assert_eq!(pixels.len(), Chip8::VIDEO_WIDTH * Chip8::VIDEO_HEIGHT);
+    assert_eq!(&pixels[..4], &[1, 2, 3, 0]);
+    assert_eq!(pixels[Chip8::VIDEO_WIDTH + 3], 1);
+    assert!(thumbnail_text(&pixels).starts_with("▀▀▀▄ "));
+    assert!(decode_thumbnail(b"not a png").is_err());
+
+    vm.display.set_hires(true);
+    vm.display.buffer[Chip8::HIRES_WIDTH + 1] = true;
+    vm.display.second_plane[2] = true;
+    let pixels = decode_thumbnail(&vm.thumbnail_png()).unwrap();
+    assert_eq!(&pixels[..3], &[1, 2, 0]);
+}
@@ -0,0 +1,55 @@
+/// Machine cycles of the `CDP1802` per second: its clock of 1.76 MHz takes 8 ticks
+/// per machine cycle
+const MACHINE_CYCLES_PER_SECOND: u32 = 1_760_640 / 8;
+
+/// Machine cycles taken every second by the display interrupt: the CPU is busy for the
+/// 14 machine cycles of each of the 128 lines of every frame, refreshed through DMA
+const DISPLAY_CYCLES_PER_SECOND: u32 = 14 * 128 * 60;
+
+/// Machine cycles left to the interpreter every second, about half of them
+pub const VIP_CYCLE_RATE: u32 = MACHINE_CYCLES_PER_SECOND - DISPLAY_CYCLES_PER_SECOND;
+
+/// Machine cycles spent by the interpreter fetching and decoding every instruction
+const FETCH_CYCLES: u32 = 68;
+
+/// Extra machine cycles of the conditional instructions when they skip
+const SKIP_CYCLES: u32 = 4;
+
+/// Returns the machine cycles spent by the interpreter of the COSMAC VIP executing
+/// `opcode`, fetch included. `skipped` tells if a conditional instruction skipped the
+/// next one. Opcodes the VIP didn't have cost as much as the simplest instructions.
+///
+/// The costs are approximations drawn from analyses of the VIP interpreter, which
+/// don't account for the wait for the display interrupt before drawing.
+/// See `Chip8::set_vip_timing`
+pub fn vip_cycles(opcode: u16, skipped: bool) -> u32 {
+    let x = ((opcode & 0x0F00) >> 8) as u32;
+    let n = (opcode & 0x000F) as u32;
+    let skip = if skipped { SKIP_CYCLES } else { 0 };
+    let execution = match (opcode & 0xF000) >> 12 {
+        0x0 if opcode == 0x00E0 => 3078,
+        0x0 if opcode == 0x00EE => 10,
+        0x1 => 12,
+        0x2 => 26,
+        0x3 | 0x4 => 10 + skip,
+        0x5 | 0x9 => 14 + skip,
+        0x6 => 6,
+        0x7 => 10,
+        0x8 if n == 0x0 => 12,
+        0x8 => 44,
+        0xA => 12,
+        0xB => 22,
+        0xC => 36,
+        // Each row is shifted into place and XORed with the two bytes it covers
+        0xD => 26 + 46 * n,
+        0xE => 14 + skip,
+        0xF => match opcode & 0x00FF {
+            0x1E | 0x29 => 16,
+            0x33 => 88,
+            0x55 | 0x65 => 14 + 14 * (x + 1),
+            _ => 10,
+        },
+        _ => 10,
+    };
+    FETCH_CYCLES + execution
+}
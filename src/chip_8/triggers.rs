@@ -0,0 +1,200 @@
+use super::Chip8;
+use std::convert::TryFrom;
+use std::fs;
+
+/// Change of a byte of memory that fires a `Trigger`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryChange {
+    /// The byte is written with a different value
+    Changes,
+    /// The byte is written with a greater value
+    Increases,
+    /// The byte is written with a lesser value
+    Decreases,
+    /// The byte is written with this value, coming from any other
+    Becomes(u8),
+}
+
+/// State of the game that fires a `Trigger`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    /// A byte of memory changes, ex: a score
+    Memory { address: u16, change: MemoryChange },
+    /// The `PC` reaches an address, ex: the start of a level
+    Pc(u16),
+}
+
+/// What a `Trigger` does when fired
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TriggerAction {
+    /// Shows a message on the `Osd`
+    Log(String),
+    /// Saves the display to a file: a PBM image for `.pbm` paths, a PNG image otherwise.
+    /// `{n}` in the path is replaced by the number of times the trigger fired
+    Screenshot(String),
+    /// Pauses the VM. See `Chip8::set_paused`
+    Pause,
+    /// Rings the bell of the terminal
+    Sound,
+}
+
+/// An action fired when the game reaches a given state
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trigger {
+    pub condition: Condition,
+    pub action: TriggerAction,
+    /// Times the trigger fired
+    fired: u32,
+    /// Value of the watched byte at the last check, `None` before the first one
+    previous: Option<u8>,
+}
+
+/// Triggers firing actions on game events, ex: to build overlays for streams or
+/// checkpoints for lessons. They are checked after every cycle.
+/// # Triggers format
+/// A text file with one trigger per line, written as `CONDITION => ACTION`.
+/// Empty lines and lines starting with `#` are ignored. Numbers are decimal,
+/// or hexadecimal with a `0x` prefix.
+/// * Conditions: `mem[ADDRESS] changes`, `mem[ADDRESS] increases`,
+///   `mem[ADDRESS] decreases`, `mem[ADDRESS] == VALUE` and `pc == ADDRESS`.
+/// * Actions: `log MESSAGE`, `screenshot FILE`, `pause` and `sound`.
+///
+/// Ex: `mem[0x3E0] increases => log score +1`
+pub struct Triggers {
+    triggers: Vec<Trigger>,
+}
+
+/// Parses a number written in decimal, or in hexadecimal with a `0x` prefix
+pub(super) fn parse_number(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse::<u16>().ok(),
+    }
+}
+
+fn parse_condition(text: &str) -> Option<Condition> {
+    let fields: Vec<&str> = text.split_whitespace().collect();
+    match fields.as_slice() {
+        ["pc", "==", address] => Some(Condition::Pc(parse_number(address)?)),
+        [target, change @ ..] => {
+            let address = target.strip_prefix("mem[")?.strip_suffix(']')?;
+            let address =
+                parse_number(address).filter(|&a| (a as usize) < Chip8::MAX_MEMORY_ADDRESS)?;
+            let change = match change {
+                ["changes"] => MemoryChange::Changes,
+                ["increases"] => MemoryChange::Increases,
+                ["decreases"] => MemoryChange::Decreases,
+                ["==", value] => MemoryChange::Becomes(u8::try_from(parse_number(value)?).ok()?),
+                _ => return None,
+            };
+            Some(Condition::Memory { address, change })
+        }
+        _ => None,
+    }
+}
+
+fn parse_action(text: &str) -> Option<TriggerAction> {
+    let (name, argument) = match text.split_once(' ') {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (text, ""),
+    };
+    match (name, argument) {
+        ("log", message) if !message.is_empty() => Some(TriggerAction::Log(message.to_string())),
+        ("screenshot", path) if !path.is_empty() => {
+            Some(TriggerAction::Screenshot(path.to_string()))
+        }
+        ("pause", "") => Some(TriggerAction::Pause),
+        ("sound", "") => Some(TriggerAction::Sound),
+        _ => None,
+    }
+}
+
+impl Trigger {
+    /// Returns the number of times the trigger fired
+    pub fn fired(&self) -> u32 {
+        self.fired
+    }
+}
+
+impl Triggers {
+    pub fn new() -> Triggers {
+        Triggers {
+            triggers: Vec::new(),
+        }
+    }
+
+    /// Parses the triggers in `text`. See `Triggers` for the format
+    pub fn parse(text: &str) -> Result<Triggers, String> {
+        let mut triggers = Triggers::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || format!("Malformed trigger at line {}: '{}'", number + 1, line);
+            let (condition, action) = line.split_once("=>").ok_or_else(invalid)?;
+            let condition = parse_condition(condition).ok_or_else(invalid)?;
+            let action = parse_action(action.trim()).ok_or_else(invalid)?;
+            triggers.add(condition, action);
+        }
+        Ok(triggers)
+    }
+
+    /// Reads the triggers file at `path`. See `Triggers` for the format
+    pub fn load(path: &str) -> Result<Triggers, String> {
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("Failed to open the triggers: {}", e))?;
+        Triggers::parse(&text)
+    }
+
+    /// Adds a trigger firing `action` whenever `condition` is met
+    pub fn add(&mut self, condition: Condition, action: TriggerAction) {
+        self.triggers.push(Trigger {
+            condition,
+            action,
+            fired: 0,
+            previous: None,
+        });
+    }
+
+    /// Returns all triggers, in the order they were added
+    pub fn triggers(&self) -> &[Trigger] {
+        &self.triggers
+    }
+
+    /// Checks every trigger against the state of a VM after a cycle
+    /// # Returns
+    /// The actions fired, along with the number of times their trigger fired so far.
+    pub fn check(&mut self, memory: &[u8], pc: u16) -> Vec<(TriggerAction, u32)> {
+        let mut fired = Vec::new();
+        for trigger in self.triggers.iter_mut() {
+            let met = match trigger.condition {
+                Condition::Pc(address) => pc == address,
+                Condition::Memory { address, change } => {
+                    let value = memory[address as usize];
+                    let previous = trigger.previous.replace(value);
+                    match (previous, change) {
+                        (None, _) => false,
+                        (Some(previous), _) if previous == value => false,
+                        (Some(_), MemoryChange::Changes) => true,
+                        (Some(previous), MemoryChange::Increases) => value > previous,
+                        (Some(previous), MemoryChange::Decreases) => value < previous,
+                        (Some(_), MemoryChange::Becomes(expected)) => value == expected,
+                    }
+                }
+            };
+            if met {
+                trigger.fired += 1;
+                fired.push((trigger.action.clone(), trigger.fired));
+            }
+        }
+        fired
+    }
+}
+
+impl Default for Triggers {
+    fn default() -> Triggers {
+        Triggers::new()
+    }
+}
@@ -0,0 +1,277 @@
+use super::{pulse, Chip8, Chip8Error, Clock, DebugHook, Palette, Rgb};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+/// Characters of the terminal mapped to the 16 keys of the keypad, indexed by keypad key.
+/// Same layout as the `KeyMap` of the window: `1234`, `QWER`, `ASDF` and `ZXCV`
+const KEYS: [char; 16] = [
+    'x', '1', '2', '3', 'q', 'w', 'e', 'a', 's', 'd', 'z', 'c', '4', 'r', 'f', 'v',
+];
+
+/// Keys of the emulator, shown on the status line
+const STATUS: &str = "Esc: quit  P: pause  F12: reset";
+
+/// Most terminals only report presses, repeated while the key is held down.
+/// Without release events a key is released once it isn't repeated for this long,
+/// which has to cover the delay before the first repeat
+const KEY_HOLD: Duration = Duration::from_millis(500);
+
+/// Prepares the terminal for the frontend, and restores it when dropped,
+/// even if the VM panics
+struct TerminalGuard {
+    stdout: Stdout,
+    /// The terminal reports releases, see `KEY_HOLD`
+    releases: bool,
+}
+
+impl TerminalGuard {
+    fn enter() -> io::Result<TerminalGuard> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        let releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if releases {
+            execute!(
+                stdout,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+        }
+        Ok(TerminalGuard { stdout, releases })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if self.releases {
+            let _ = execute!(self.stdout, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(self.stdout, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn to_color([r, g, b]: Rgb) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+impl Chip8 {
+    /// Starts the execution of the VM in the terminal, ex: over SSH or on machines
+    /// without OpenGL. Returns when `Esc` or `Ctrl+C` is pressed, or when the VM halts.
+    ///
+    /// Every character cell shows two pixels of a column with the `▀` half block, so the
+    /// display takes 64x16 cells plus a status line. Keys are read from the terminal with
+    /// the conventional layout of `KeyMap`, while `P` pauses and `F12` restarts the ROM.
+    /// The loop is paced by frames like `start`, and the terminal bell rings when a beep
+    /// starts. With `audio_reactive`, the foreground pulses with the sound like in `start`.
+    ///
+    /// When an instruction faults the VM is paused, with the error on the status line.
+    /// Like `start`, a `debug_hook` can take over the execution, ex: `RomLoading`. It also
+    /// stops the loop when it should exit, and handles the faults itself.
+    ///
+    /// **Note:** Only available with the `tui` feature.
+    /// # Returns
+    /// The fault that paused the VM, if it wasn't restarted since and there's no hook
+    /// # Errors
+    /// If the terminal can't be set up or written to
+    pub fn start_tui(
+        &mut self,
+        palette: Palette,
+        high_contrast: bool,
+        audio_reactive: bool,
+        debug_hook: Option<&mut dyn DebugHook>,
+    ) -> Result<Option<Chip8Error>, String> {
+        let mut guard =
+            TerminalGuard::enter().map_err(|e| format!("Failed to set up the terminal: {}", e))?;
+        let colors = palette.colors(high_contrast);
+        self.run_tui(&mut guard, colors, audio_reactive, debug_hook)
+            .map_err(|e| format!("Failed to write to the terminal: {}", e))
+    }
+
+    fn run_tui(
+        &mut self,
+        guard: &mut TerminalGuard,
+        colors: [Rgb; 4],
+        audio_reactive: bool,
+        mut debug_hook: Option<&mut dyn DebugHook>,
+    ) -> io::Result<Option<Chip8Error>> {
+        let frame_period = Duration::from_secs(1) / Chip8::FRAME_RATE;
+        let mut next_frame_time = Instant::now();
+        let mut cycle_budget: i64 = 0;
+        // Last time each key was seen pressed, while held down
+        let mut held: [Option<Instant>; 16] = [None; 16];
+        let mut status = String::from(STATUS);
+        // Set to draw the next frame even if the display didn't change, ex: for the status
+        let mut redraw = true;
+        let mut beeping = false;
+        // Colors drawn last, pulsing with the sound when `audio_reactive`
+        let mut shown_colors = colors;
+        // Resolution drawn last, the status line moves with it
+        let mut hires = self.display.is_hires();
+        let mut fault = None;
+
+        execute!(guard.stdout, terminal::Clear(terminal::ClearType::All))?;
+        loop {
+            // Input, until the next frame is due
+            let timeout = next_frame_time.saturating_duration_since(Instant::now());
+            if event::poll(timeout)? {
+                if let Event::Key(KeyEvent {
+                    code,
+                    modifiers,
+                    kind,
+                    ..
+                }) = event::read()?
+                {
+                    match code {
+                        KeyCode::Esc => break,
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                        KeyCode::Char('p') | KeyCode::F(12) if kind == KeyEventKind::Press => {
+                            if code == KeyCode::F(12) {
+                                self.reset();
+                            } else {
+                                self.paused = !self.paused;
+                            }
+                            fault = None;
+                            status = String::from(STATUS);
+                            if self.paused {
+                                status.insert_str(0, "Paused - ");
+                            }
+                            redraw = true;
+                        }
+                        KeyCode::Char(c) => {
+                            let c = c.to_ascii_lowercase();
+                            if let Some(key) = KEYS.iter().position(|&k| k == c) {
+                                let key = self.swapped_key(key);
+                                if kind == KeyEventKind::Release {
+                                    held[key] = None;
+                                    self.release_key(key);
+                                } else {
+                                    if held[key].is_none() {
+                                        self.press_key(key);
+                                    }
+                                    held[key] = Some(Instant::now());
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+
+            let now = Instant::now();
+            if !guard.releases {
+                for (key, pressed_at) in held.iter_mut().enumerate() {
+                    if matches!(pressed_at, Some(time) if now - *time > KEY_HOLD) {
+                        *pressed_at = None;
+                        self.release_key(key);
+                    }
+                }
+            }
+
+            if !self.paused {
+                cycle_budget += self.clock.rate() as i64;
+                while cycle_budget >= Chip8::FRAME_RATE as i64 {
+                    if self.halted.is_some() || self.paused {
+                        break;
+                    }
+                    let elapsed = self.clock.elapsed;
+                    match debug_hook.as_deref_mut() {
+                        Some(hook) if hook.should_exit() => break,
+                        Some(hook) => hook.update(self),
+                        None => {
+                            // Paused, so the last frame stays on screen along with the fault
+                            if let Err(error) = self.step() {
+                                self.paused = true;
+                                status = format!("Paused: {} - {}", error, STATUS);
+                                fault = Some(error);
+                                redraw = true;
+                                break;
+                            }
+                        }
+                    }
+                    cycle_budget -= self.clock.spent_since(elapsed) * Chip8::FRAME_RATE as i64;
+                }
+                cycle_budget = Clock::carried_budget(cycle_budget);
+            }
+            if self.halted.is_some() || debug_hook.as_ref().is_some_and(|h| h.should_exit()) {
+                break;
+            }
+            if let Some(message) = self.osd.take_log().pop() {
+                status = message;
+                redraw = true;
+            }
+
+            let sound = self.timers.sound > 0 && !self.paused;
+            if sound && !beeping {
+                queue!(guard.stdout, Print('\x07'))?;
+            }
+            beeping = sound;
+            if audio_reactive {
+                let pulsed = pulse(colors, self.sound_level());
+                redraw |= pulsed != shown_colors;
+                shown_colors = pulsed;
+            }
+
+            if self.display.take_dirty() || redraw {
+                if hires != self.display.is_hires() {
+                    hires = self.display.is_hires();
+                    queue!(guard.stdout, terminal::Clear(terminal::ClearType::All))?;
+                }
+                let pixels: Vec<usize> = (0..self.display.size())
+                    .map(|pos| self.display.color(pos))
+                    .collect();
+                let width = self.display.width();
+                draw(&mut guard.stdout, &pixels, width, &shown_colors, &status)?;
+                redraw = false;
+            }
+            guard.stdout.flush()?;
+
+            // Late frames are dropped instead of run at once, the terminal can't keep up
+            next_frame_time = (next_frame_time + frame_period).max(now);
+        }
+        Ok(fault)
+    }
+}
+
+/// Draws `pixels`, rows of `width` pixels, with two rows per line, followed by the
+/// `status` line
+fn draw(
+    stdout: &mut Stdout,
+    pixels: &[usize],
+    width: usize,
+    colors: &[Rgb; 4],
+    status: &str,
+) -> io::Result<()> {
+    let lines = pixels.len() / width / 2;
+    // Colors are only sent when they change, most cells share them with the previous one
+    let mut current = None;
+    for row in 0..lines {
+        queue!(stdout, cursor::MoveTo(0, row as u16))?;
+        for col in 0..width {
+            let top = pixels[row * 2 * width + col];
+            let bottom = pixels[(row * 2 + 1) * width + col];
+            if current != Some((top, bottom)) {
+                queue!(
+                    stdout,
+                    SetForegroundColor(to_color(colors[top])),
+                    SetBackgroundColor(to_color(colors[bottom]))
+                )?;
+                current = Some((top, bottom));
+            }
+            queue!(stdout, Print('▀'))?;
+        }
+    }
+    queue!(
+        stdout,
+        ResetColor,
+        cursor::MoveTo(0, lines as u16),
+        terminal::Clear(terminal::ClearType::CurrentLine),
+        Print(status)
+    )
+}
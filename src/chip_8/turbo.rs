@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+/// Auto-fire for keypad keys: while a turbo key is held down, the VM sees it
+/// repeatedly pressed and released at a fixed rate instead of permanently pressed,
+/// so instructions like `Ex9E` observe synthetic press/release cycles.
+///
+/// Turbo can be toggled at runtime: while disabled, turbo keys behave as regular keys.
+pub struct Turbo {
+    /// Keys designated as turbo
+    keys: [bool; 16],
+    /// Duration of a full press/release cycle
+    period: Duration,
+    enabled: bool,
+    /// Host timestamps of the turbo keys currently held down
+    held: [Option<Instant>; 16],
+}
+
+impl Turbo {
+    /// Default repeat rate, in presses per second
+    pub const DEFAULT_RATE: u32 = 10;
+
+    /// Creates an enabled `Turbo` without any turbo key, repeating `rate` times per second
+    /// # Panics
+    /// If `rate` is `0`
+    pub fn new(rate: u32) -> Turbo {
+        assert!(
+            rate > 0,
+            "The turbo rate must be at least 1 press per second."
+        );
+
+        Turbo {
+            keys: [false; 16],
+            period: Duration::from_secs(1) / rate,
+            enabled: true,
+            held: [None; 16],
+        }
+    }
+
+    /// Designates one of the 16 keys (`0x0` to `0xF`) as turbo
+    pub fn add_key(&mut self, key: usize) {
+        self.keys[key] = true;
+    }
+
+    /// Returns `true` if `key` is designated as turbo
+    pub fn is_turbo_key(&self, key: usize) -> bool {
+        self.keys[key]
+    }
+
+    /// Returns `true` if turbo keys currently auto-fire
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables the auto-fire
+    /// # Returns
+    /// `true` if the auto-fire is now enabled
+    pub fn toggle(&mut self) -> bool {
+        self.enabled = !self.enabled;
+        self.enabled
+    }
+
+    /// Records that the turbo `key` was pressed on the host
+    pub fn hold(&mut self, key: usize) {
+        if self.held[key].is_none() {
+            self.held[key] = Some(Instant::now());
+        }
+    }
+
+    /// Records that the turbo `key` was released on the host
+    pub fn release(&mut self, key: usize) {
+        self.held[key] = None;
+    }
+
+    /// Returns the status of a held turbo `key` as seen by the VM at `now`:
+    /// pressed during the first half of each cycle and released during the second one.
+    /// # Returns
+    /// `None` if the key is not held down
+    pub fn status(&self, key: usize, now: Instant) -> Option<bool> {
+        let held_since = self.held[key]?;
+        if !self.enabled {
+            return Some(true);
+        }
+
+        let half_period = self.period.as_nanos() / 2;
+        let elapsed = now.duration_since(held_since).as_nanos();
+        Some((elapsed / half_period.max(1)).is_multiple_of(2))
+    }
+}
+
+impl Default for Turbo {
+    fn default() -> Turbo {
+        Turbo::new(Turbo::DEFAULT_RATE)
+    }
+}
@@ -2,23 +2,180 @@ use glutin_window::GlutinWindow as Window;
 use graphics::types::Color;
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::window::WindowSettings;
+use std::panic;
 
-pub const BLACK: Color = [0.0, 0.0, 0.0, 1.0];
-pub const WHITE: Color = [1.0, 1.0, 1.0, 1.0];
+use super::{Rgb, VideoSettings};
+
+/// Color of the messages of the `Osd`
+pub const OSD_TEXT: Color = [1.0, 1.0, 1.0, 1.0];
+
+/// Color drawn behind the messages of the `Osd`, so they stay readable over any frame
+pub const OSD_BACKDROP: Color = [0.0, 0.0, 0.0, 0.75];
+
+/// Color of the scanlines drawn over the lower third of every row with `Filter::Crt`
+pub const SCANLINE: Color = [0.0, 0.0, 0.0, 0.45];
+
+/// Opacity of the foreground drawn on unlit cells with `Filter::Lcd`
+pub const LCD_UNLIT_ALPHA: f32 = 0.06;
+
+/// Color drawn behind the keys of the on-screen keypad held down
+pub const KEYPAD_PRESSED: Color = [1.0, 1.0, 1.0, 0.35];
+
+/// Width of the glyphs used to render text, in pixels
+pub const GLYPH_WIDTH: usize = 3;
+
+/// Height of the glyphs used to render text, in pixels
+pub const GLYPH_HEIGHT: usize = 5;
 
 /// OpenGL version used
 pub const OPENGL: OpenGL = OpenGL::V3_2;
 
 /// Build a Window for displaying the VM
-pub fn build_window() -> Window {
-    WindowSettings::new("Chip 8", [1280, 640])
+pub fn build_window(video_settings: VideoSettings) -> Window {
+    let window: Window = WindowSettings::new("Chip 8", video_settings.window_size())
         .graphics_api(OPENGL)
-        .exit_on_esc(true)
+        .fullscreen(video_settings.kiosk)
+        .vsync(video_settings.vsync)
+        // Closing the window is a `HostAction`, so it can be remapped
+        .exit_on_esc(false)
         .build()
-        .unwrap()
+        .unwrap();
+    if video_settings.kiosk {
+        window.window.set_cursor_visible(false);
+    }
+    window
+}
+
+/// Switches `window` between borderless fullscreen, on its current monitor, and windowed
+pub fn toggle_fullscreen(window: &Window) {
+    use winit::window::Fullscreen;
+
+    let fullscreen = match window.window.fullscreen() {
+        Some(_) => None,
+        None => Some(Fullscreen::Borderless(window.window.current_monitor())),
+    };
+    window.window.set_fullscreen(fullscreen);
+}
+
+/// Checks that the video backend works, by opening a small window and closing it right away
+/// # Errors
+/// If the window can't be created, ex: when no display is available
+pub fn check_video_backend() -> Result<(), String> {
+    // Some backends panic instead of returning an error, ex: when no display server runs
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(|| {
+        WindowSettings::new("Chip 8", [64, 32])
+            .graphics_api(OPENGL)
+            .build::<Window>()
+            .map(drop)
+            .map_err(|e| e.to_string())
+    });
+    panic::set_hook(previous_hook);
+
+    result.unwrap_or_else(|panic| {
+        let msg = panic
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| panic.downcast_ref::<&str>().map(|msg| msg.to_string()))
+            .unwrap_or_else(|| String::from("The window backend panicked"));
+        Err(msg)
+    })
 }
 
 /// Build a GLGraphics instance, needed to render on screen
 pub fn build_graphics() -> GlGraphics {
     GlGraphics::new(OPENGL)
 }
+
+/// Converts a color of a `Palette` to the color type used for rendering
+pub fn to_color(rgb: Rgb) -> Color {
+    [
+        rgb[0] as f32 / 255.0,
+        rgb[1] as f32 / 255.0,
+        rgb[2] as f32 / 255.0,
+        1.0,
+    ]
+}
+
+/// Returns the rows of the glyph drawing `c` in a tiny uppercase font,
+/// where the 3 lowest bits of each row are its pixels from left to right.
+/// Characters without a glyph are drawn as `?`
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+/// Returns the width and height of `text` rendered with `text_pixels`, in pixels
+pub fn text_size(text: &str) -> [usize; 2] {
+    let glyphs = text.chars().count();
+    [(glyphs * (GLYPH_WIDTH + 1)).saturating_sub(1), GLYPH_HEIGHT]
+}
+
+/// Returns the coordinates of the pixels on when rendering `text`,
+/// with a blank column between glyphs
+pub fn text_pixels(text: &str) -> Vec<[usize; 2]> {
+    let mut pixels = Vec::new();
+    for (index, c) in text.chars().enumerate() {
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (0b100 >> col) != 0 {
+                    pixels.push([index * (GLYPH_WIDTH + 1) + col, row]);
+                }
+            }
+        }
+    }
+    pixels
+}
@@ -0,0 +1,120 @@
+use super::{AntiFlicker, Chip8, Filter, Palette};
+
+/// Settings of the window displaying the VM
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VideoSettings {
+    /// Makes the foreground pulse with the sound: it's dimmed while silent and
+    /// brightens while the sound timer is active, fading out as the timer runs out
+    pub audio_reactive: bool,
+    /// Opens the window fullscreen, hides the cursor and ignores the `Esc` key.
+    /// See `Kiosk`
+    pub kiosk: bool,
+    /// Waits for the vertical blank of the monitor to present frames, so they never tear.
+    /// The speed of the emulation doesn't depend on the refresh rate either way
+    pub vsync: bool,
+    /// Renders only 1 of every N frames, while the VM keeps running at full speed.
+    /// When `None`, frames are skipped automatically if frame deadlines are missed repeatedly
+    pub frame_skip: Option<u32>,
+    /// Colors used to render the display
+    pub palette: Palette,
+    /// Uses the high contrast variant of the palette. See `Palette`
+    pub high_contrast: bool,
+    /// Size of each pixel of the display in the initial window, in host pixels.
+    /// When `None`, `VideoSettings::DEFAULT_SCALE` is used.
+    /// Resizing the window rescales the display to fit it, see `scaling`
+    pub scale: Option<u32>,
+    /// How the display is scaled to the size of the window
+    pub scaling: Scaling,
+    /// Visual filter drawn over the display
+    pub filter: Filter,
+    /// Blends the last frames to hide flicker. See `FrameBlend`
+    pub anti_flicker: AntiFlicker,
+    /// Frames blended with `anti_flicker`, from `2` to `FrameBlend::MAX_FRAMES`.
+    /// When `None`, `FrameBlend::DEFAULT_FRAMES` are blended
+    pub anti_flicker_frames: Option<usize>,
+    /// Pauses the VM while the window doesn't have the focus, ex: while alt-tabbed.
+    /// See `Chip8::set_paused`
+    pub pause_on_focus_loss: bool,
+    /// Shows the on-screen keypad from the start. See `HostAction::ToggleKeypad`
+    pub show_keypad: bool,
+}
+
+impl VideoSettings {
+    /// Default pixel scale, for a 1280x640 window
+    pub const DEFAULT_SCALE: u32 = 20;
+
+    /// Returns the initial size of the window, as `[width, height]`
+    pub fn window_size(&self) -> [u32; 2] {
+        let scale = self.scale.unwrap_or(VideoSettings::DEFAULT_SCALE);
+        [
+            Chip8::VIDEO_WIDTH as u32 * scale,
+            Chip8::VIDEO_HEIGHT as u32 * scale,
+        ]
+    }
+}
+
+/// How the display is scaled to fit the window, recomputed from its size on every frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scaling {
+    /// Pixels are squares of a whole amount of host pixels, as large as fit, so they
+    /// all have the same size. The rest of the window is left as borders around them
+    #[default]
+    Integer,
+    /// The display fills as much of the window as it can, keeping its 2:1 aspect ratio,
+    /// with borders on the sides or above and below (letterboxing)
+    Fit,
+    /// The display fills the whole window, and pixels stretch to its aspect ratio
+    Stretch,
+}
+
+/// Where the display is drawn in the window, in host pixels. See `Scaling::geometry`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Geometry {
+    /// Top left corner of the display, as `[x, y]`
+    pub offset: [f64; 2],
+    /// Size of each pixel of the display, as `[width, height]`
+    pub pixel: [f64; 2],
+}
+
+impl Scaling {
+    /// All available modes, in the order they are presented to users
+    pub const ALL: [Scaling; 3] = [Scaling::Integer, Scaling::Fit, Scaling::Stretch];
+
+    /// Returns the name used to select the mode, ex: from the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scaling::Integer => "integer",
+            Scaling::Fit => "fit",
+            Scaling::Stretch => "stretch",
+        }
+    }
+
+    /// Finds a mode by its name
+    pub fn from_name(name: &str) -> Option<Scaling> {
+        Scaling::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == name)
+    }
+
+    /// Returns where a display of `[columns, rows]` pixels, ex: `[64, 32]`, is drawn in a
+    /// window of `[width, height]` host pixels. The display is centered, and
+    /// `Scaling::Integer` never shrinks pixels below one host pixel, even if the window is
+    /// smaller than the display
+    pub fn geometry(&self, [width, height]: [f64; 2], [columns, rows]: [usize; 2]) -> Geometry {
+        let fit_x = width / columns as f64;
+        let fit_y = height / rows as f64;
+        let pixel = match self {
+            Scaling::Integer => [fit_x.min(fit_y).floor().max(1.0); 2],
+            Scaling::Fit => [fit_x.min(fit_y); 2],
+            Scaling::Stretch => [fit_x, fit_y],
+        };
+        Geometry {
+            offset: [
+                (width - pixel[0] * columns as f64) / 2.0,
+                (height - pixel[1] * rows as f64) / 2.0,
+            ],
+            pixel,
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use super::Chip8;
+use std::ops::Range;
+
+/// Accesses to memory watched by a `Watchpoint`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    /// All available accesses, in the order they are presented to users
+    pub const ALL: [Access; 3] = [Access::Read, Access::Write, Access::ReadWrite];
+
+    /// Returns the name used to select the access, as in the Debug Adapter Protocol
+    pub fn name(&self) -> &'static str {
+        match self {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::ReadWrite => "readWrite",
+        }
+    }
+
+    /// Finds an access by its name
+    pub fn from_name(name: &str) -> Option<Access> {
+        Access::ALL
+            .iter()
+            .copied()
+            .find(|access| access.name() == name)
+    }
+
+    fn includes(&self, write: bool) -> bool {
+        match self {
+            Access::Read => !write,
+            Access::Write => write,
+            Access::ReadWrite => true,
+        }
+    }
+}
+
+/// A data breakpoint: suspends the execution once an instruction reads or writes
+/// a range of memory. See `Chip8::set_watchpoints`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: Range<usize>,
+    pub access: Access,
+}
+
+impl Watchpoint {
+    /// Watches `access`es to the byte at `address`
+    pub fn new(address: usize, access: Access) -> Watchpoint {
+        Watchpoint {
+            range: address..address + 1,
+            access,
+        }
+    }
+
+    /// Watches `access`es to the `len` bytes `I` currently points to, ex: the sprite of a
+    /// `Dxyn` or the registers stored by `Fx55`. The range stays where it is when `I` changes
+    pub fn at_index(vm: &Chip8, len: usize, access: Access) -> Watchpoint {
+        let start = vm.regs.i as usize;
+        Watchpoint {
+            range: start..start + len,
+            access,
+        }
+    }
+
+    fn watches(&self, address: usize, write: bool) -> bool {
+        self.access.includes(write) && self.range.contains(&address)
+    }
+}
+
+/// An access that hit a `Watchpoint`, along with the instruction responsible for it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchHit {
+    /// Address accessed, after wrapping around with `MemoryBounds::Wrap`
+    pub address: usize,
+    pub write: bool,
+    /// Address of the instruction
+    pub pc: u16,
+    pub opcode: u16,
+}
+
+impl Chip8 {
+    /// Replaces the watchpoints. The accesses of every instruction are checked against
+    /// them, and the first one hitting a watchpoint is kept until taken with `take_watch_hit`.
+    /// Fetching instructions is not a read of data, so it never hits a watchpoint.
+    ///
+    /// **Note:** Accesses are checked while the instruction executes, so a `Debugger`
+    /// suspends the execution right after the instruction responsible.
+    pub fn set_watchpoints(&mut self, watchpoints: Vec<Watchpoint>) {
+        self.watchpoints = watchpoints;
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Returns the first access of the last instruction that hit a watchpoint, if any
+    pub fn take_watch_hit(&mut self) -> Option<WatchHit> {
+        self.watch_hit.take()
+    }
+
+    /// Records an access to `address` if it hits a watchpoint and no access of the current
+    /// instruction did yet
+    pub(super) fn check_watchpoints(&mut self, address: usize, write: bool) {
+        if self.watch_hit.is_some() {
+            return;
+        }
+        if self.watchpoints.iter().any(|w| w.watches(address, write)) {
+            let (pc, opcode) = self.instruction;
+            self.watch_hit = Some(WatchHit {
+                address,
+                write,
+                pc,
+                opcode,
+            });
+        }
+    }
+}
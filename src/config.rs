@@ -0,0 +1,545 @@
+//! Settings of the emulator read from a TOML file, `chip8.toml` by default, so they
+//! don't have to be repeated on every run. Every section is optional:
+//! ```toml
+//! [video]
+//! scale = 12
+//! scaling = "fit"            # Or "integer", "stretch"
+//! vsync = true
+//! filter = "crt"             # Or "lcd", "none"
+//! anti_flicker = "max"       # Or "average", "off"
+//! anti_flicker_frames = 2
+//! palette = "amber"          # Or custom colors with `fg` and `bg`, ex: "#00FF00"
+//! high_contrast = false
+//! frame_skip = 1
+//! show_keypad = false
+//! pause_on_focus_loss = true
+//!
+//! [input]
+//! keys = { 5 = "Space" }     # Host keys of the keypad keys, in hexadecimal
+//! hotkeys = { quit = "End" }
+//!
+//! [emulation]
+//! profile = "standard"
+//! quirks = ["shift", "load-store"]
+//! instructions_per_frame = 15
+//! stack_depth = 16
+//! memory_bounds = "fault"    # Or "wrap" and "open-bus"
+//! rewind = 10
+//! rom_database = true
+//!
+//! [audio]
+//! tone = 440
+//! volume = 25
+//! waveform = "square"        # Or "triangle" and "sine"
+//! sample = "beep.wav"        # Played instead of the wave
+//!
+//! [roms.6f6509f38220e057a7e32ebb22dd353c1078e3e7]  # SHA-1 of the ROM
+//! profile = "schip"
+//! instructions_per_frame = 20
+//! ```
+//! Values are validated like the command line options they stand for, which take
+//! precedence over them. Unknown sections and settings are rejected, so typos don't
+//! go unnoticed.
+//!
+//! Keys remapped from the window are written back with `save_keys`, and the settings
+//! adjusted in the ROM browser with `save_rom`, keeping the rest of the file as is.
+//! Like save states, configs are read and written through a `Storage`, under the key of
+//! their file name. See `file_storage`
+use crate::storage::{FileStorage, Storage};
+use std::convert::TryFrom;
+use std::path::Path;
+use toml::{Table, Value};
+use toml_edit::{DocumentMut, InlineTable};
+
+/// `[video]` section of a `Config`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VideoConfig {
+    pub scale: Option<u32>,
+    pub scaling: Option<String>,
+    pub vsync: Option<bool>,
+    pub filter: Option<String>,
+    pub anti_flicker: Option<String>,
+    pub anti_flicker_frames: Option<u32>,
+    pub palette: Option<String>,
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub high_contrast: Option<bool>,
+    pub frame_skip: Option<u32>,
+    pub show_keypad: Option<bool>,
+    pub pause_on_focus_loss: Option<bool>,
+}
+
+/// `[input]` section of a `Config`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InputConfig {
+    /// Host key names by keypad key
+    pub keys: Vec<(usize, String)>,
+    /// Host key names by action name
+    pub hotkeys: Vec<(String, String)>,
+}
+
+/// `[emulation]` section of a `Config`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EmulationConfig {
+    pub profile: Option<String>,
+    /// Quirk names, as accepted by `Quirks::parse`
+    pub quirks: Option<Vec<String>>,
+    pub instructions_per_frame: Option<u32>,
+    pub stack_depth: Option<u32>,
+    /// Name of the `MemoryBounds`
+    pub memory_bounds: Option<String>,
+    /// Seconds of rewind, `0` disables it
+    pub rewind: Option<u32>,
+    /// Whether to apply the settings of known ROMs. See `RomDatabase`
+    pub rom_database: Option<bool>,
+}
+
+/// `[audio]` section of a `Config`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AudioConfig {
+    /// Frequency of the beep, in Hz
+    pub tone: Option<u32>,
+    /// Volume of the beep, from `0` to `100`
+    pub volume: Option<u32>,
+    /// Name of the wave of the beep, as accepted by `Waveform::from_name`
+    pub waveform: Option<String>,
+    /// Path of a WAV file played as the beep. See `WavSample`
+    pub sample: Option<String>,
+}
+
+/// `[roms.<SHA-1>]` section of a `Config`, the settings a user picked for a ROM
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RomConfig {
+    pub profile: Option<String>,
+    pub instructions_per_frame: Option<u32>,
+}
+
+/// Settings read from a config file. See the `config` module for the format
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    pub video: VideoConfig,
+    pub input: InputConfig,
+    pub emulation: EmulationConfig,
+    pub audio: AudioConfig,
+    /// Settings by `SHA-1` of the ROM, in lowercase hexadecimal
+    pub roms: Vec<(String, RomConfig)>,
+}
+
+/// Name of the config file looked up in the current directory
+pub const DEFAULT_CONFIG_PATH: &str = "chip8.toml";
+
+fn string(value: &Value, field: &str) -> Result<String, String> {
+    value
+        .as_str()
+        .map(str::to_string)
+        .ok_or(format!("'{}' must be a string", field))
+}
+
+fn number(value: &Value, field: &str) -> Result<u32, String> {
+    value
+        .as_integer()
+        .and_then(|n| u32::try_from(n).ok())
+        .ok_or(format!("'{}' must be a non-negative integer", field))
+}
+
+fn positive(value: &Value, field: &str) -> Result<u32, String> {
+    value
+        .as_integer()
+        .and_then(|n| u32::try_from(n).ok())
+        .filter(|&n| n > 0)
+        .ok_or(format!("'{}' must be a positive integer", field))
+}
+
+fn boolean(value: &Value, field: &str) -> Result<bool, String> {
+    value
+        .as_bool()
+        .ok_or(format!("'{}' must be true or false", field))
+}
+
+/// Parses a table of strings, ex: `{ quit = "End" }`
+fn string_table(value: &Value, field: &str) -> Result<Vec<(String, String)>, String> {
+    value
+        .as_table()
+        .ok_or(format!("'{}' must be a table", field))?
+        .iter()
+        .map(|(key, value)| Ok((key.clone(), string(value, field)?)))
+        .collect()
+}
+
+/// Calls `apply` with every setting of the `section` table, as `section.setting`
+fn for_each_setting<F>(table: &Table, section: &str, mut apply: F) -> Result<(), String>
+where
+    F: FnMut(&str, &Value, &str) -> Result<bool, String>,
+{
+    let settings = match table.get(section) {
+        Some(settings) => settings
+            .as_table()
+            .ok_or(format!("'{}' must be a section", section))?,
+        None => return Ok(()),
+    };
+    for (name, value) in settings {
+        let field = format!("{}.{}", section, name);
+        if !apply(name, value, &field)? {
+            return Err(format!("Unknown setting '{}'", field));
+        }
+    }
+    Ok(())
+}
+
+impl Config {
+    /// Parses the config in `text`. See the `config` module for the format
+    /// # Errors
+    /// If the TOML is malformed, or if any section or setting is unknown or has the wrong type
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let table = text.parse::<Table>().map_err(|e| e.to_string())?;
+        if let Some(section) = table
+            .keys()
+            .find(|&key| !["video", "input", "emulation", "audio", "roms"].contains(&key.as_str()))
+        {
+            return Err(format!("Unknown section '{}'", section));
+        }
+
+        let mut config = Config::default();
+        let video = &mut config.video;
+        for_each_setting(&table, "video", |name, value, field| {
+            match name {
+                "scale" => video.scale = Some(number(value, field)?),
+                "scaling" => video.scaling = Some(string(value, field)?),
+                "vsync" => video.vsync = Some(boolean(value, field)?),
+                "filter" => video.filter = Some(string(value, field)?),
+                "anti_flicker" => video.anti_flicker = Some(string(value, field)?),
+                "anti_flicker_frames" => video.anti_flicker_frames = Some(number(value, field)?),
+                "palette" => video.palette = Some(string(value, field)?),
+                "fg" => video.fg = Some(string(value, field)?),
+                "bg" => video.bg = Some(string(value, field)?),
+                "high_contrast" => video.high_contrast = Some(boolean(value, field)?),
+                "frame_skip" => video.frame_skip = Some(number(value, field)?),
+                "show_keypad" => video.show_keypad = Some(boolean(value, field)?),
+                "pause_on_focus_loss" => video.pause_on_focus_loss = Some(boolean(value, field)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+
+        let input = &mut config.input;
+        for_each_setting(&table, "input", |name, value, field| {
+            match name {
+                "keys" => {
+                    for (key, host_key) in string_table(value, field)? {
+                        let keypad_key =
+                            usize::from_str_radix(&key, 16)
+                                .ok()
+                                .filter(|&k| k < 16)
+                                .ok_or(format!("Invalid keypad key '{}' in '{}'", key, field))?;
+                        input.keys.push((keypad_key, host_key));
+                    }
+                }
+                "hotkeys" => input.hotkeys = string_table(value, field)?,
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+
+        let emulation = &mut config.emulation;
+        for_each_setting(&table, "emulation", |name, value, field| {
+            match name {
+                "profile" => emulation.profile = Some(string(value, field)?),
+                "quirks" => {
+                    let quirks = match value.as_array() {
+                        Some(quirks) => quirks
+                            .iter()
+                            .map(|quirk| string(quirk, field))
+                            .collect::<Result<Vec<String>, String>>()?,
+                        None => vec![string(value, field)?],
+                    };
+                    emulation.quirks = Some(quirks);
+                }
+                "instructions_per_frame" => {
+                    emulation.instructions_per_frame = Some(number(value, field)?)
+                }
+                "stack_depth" => emulation.stack_depth = Some(positive(value, field)?),
+                "memory_bounds" => emulation.memory_bounds = Some(string(value, field)?),
+                "rewind" => emulation.rewind = Some(number(value, field)?),
+                "rom_database" => emulation.rom_database = Some(boolean(value, field)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+
+        let audio = &mut config.audio;
+        for_each_setting(&table, "audio", |name, value, field| {
+            match name {
+                "tone" => audio.tone = Some(number(value, field)?),
+                "volume" => audio.volume = Some(number(value, field)?),
+                "waveform" => audio.waveform = Some(string(value, field)?),
+                "sample" => audio.sample = Some(string(value, field)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+
+        if let Some(roms) = table.get("roms") {
+            let roms = roms.as_table().ok_or("'roms' must be a section")?;
+            for (sha1, settings) in roms {
+                if sha1.len() != 40 || !sha1.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(format!("Invalid SHA-1 '{}' in 'roms'", sha1));
+                }
+                let section = format!("roms.{}", sha1);
+                let settings = settings
+                    .as_table()
+                    .ok_or(format!("'{}' must be a section", section))?;
+                let mut rom = RomConfig::default();
+                for (name, value) in settings {
+                    let field = format!("{}.{}", section, name);
+                    match name.as_str() {
+                        "profile" => rom.profile = Some(string(value, &field)?),
+                        "instructions_per_frame" => {
+                            rom.instructions_per_frame = Some(number(value, &field)?)
+                        }
+                        _ => return Err(format!("Unknown setting '{}'", field)),
+                    }
+                }
+                config.roms.push((sha1.to_ascii_lowercase(), rom));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Returns the settings picked for the ROM with the `SHA-1` `sha1`, if any
+    pub fn rom(&self, sha1: &str) -> Option<&RomConfig> {
+        self.roms
+            .iter()
+            .find(|(key, _)| key == sha1)
+            .map(|(_, rom)| rom)
+    }
+
+    /// Reads the config file at `path`. See the `config` module for the format
+    pub fn load(path: &str) -> Result<Config, String> {
+        let (storage, key) = file_storage(path)?;
+        Config::load_from(&storage, &key)
+    }
+
+    /// Reads the config stored under `key` in `storage`
+    /// # Errors
+    /// If the config is missing, can't be read or is invalid
+    pub fn load_from(storage: &dyn Storage, key: &str) -> Result<Config, String> {
+        let text = read_text(storage, key)?
+            .ok_or(format!("Failed to open the config {}: not found", key))?;
+        Config::parse(&text).map_err(|e| format!("Invalid config {}: {}", key, e))
+    }
+}
+
+/// Returns a `FileStorage` of the directory of the config file at `path`, along with the
+/// key of the file in it, ex: `chip8.toml`
+/// # Errors
+/// If `path` doesn't end with a file name
+pub fn file_storage(path: &str) -> Result<(FileStorage, String), String> {
+    let path = Path::new(path);
+    let key = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or(format!("Invalid config path {}", path.display()))?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    Ok((FileStorage::new(directory), key))
+}
+
+/// Reads the text of the config stored under `key`, `None` if missing
+fn read_text(storage: &dyn Storage, key: &str) -> Result<Option<String>, String> {
+    let data = storage
+        .load(key)
+        .map_err(|e| format!("Failed to open the config {}: {}", key, e))?;
+    data.map(|data| {
+        String::from_utf8(data).map_err(|e| format!("Failed to open the config {}: {}", key, e))
+    })
+    .transpose()
+}
+
+/// Reads the config stored under `key` for editing, empty if missing
+fn open_document(storage: &dyn Storage, key: &str) -> Result<DocumentMut, String> {
+    read_text(storage, key)?
+        .unwrap_or_default()
+        .parse::<DocumentMut>()
+        .map_err(|e| format!("Invalid config {}: {}", key, e))
+}
+
+fn save_document(
+    storage: &mut dyn Storage,
+    key: &str,
+    document: &DocumentMut,
+) -> Result<(), String> {
+    storage
+        .store(key, document.to_string().as_bytes())
+        .map_err(|e| format!("Failed to save the config {}: {}", key, e))
+}
+
+/// Replaces the `keys` of the `[input]` section of the config stored under `key` with
+/// `keys`, host key names by keypad key. The config is created if missing, and its other
+/// settings and comments are kept
+/// # Errors
+/// If the config can't be read or written, or if it isn't valid TOML
+pub fn save_keys(
+    storage: &mut dyn Storage,
+    key: &str,
+    keys: &[(usize, String)],
+) -> Result<(), String> {
+    let mut document = open_document(storage, key)?;
+
+    let mut table = InlineTable::new();
+    for (keypad_key, key) in keys {
+        table.insert(format!("{:X}", keypad_key), key.as_str().into());
+    }
+    let input = document
+        .entry("input")
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .ok_or(format!("Invalid config {}: 'input' must be a section", key))?;
+    input.insert("keys", toml_edit::value(table));
+
+    save_document(storage, key, &document)
+}
+
+/// Writes the settings of `rom` set to the `[roms.<SHA-1>]` section of the config stored
+/// under `key`, `sha1` being the one of the ROM. Settings left as `None` are kept. The
+/// config is created if missing, and its other settings and comments are kept
+/// # Errors
+/// If the config can't be read or written, or if it isn't valid TOML
+pub fn save_rom(
+    storage: &mut dyn Storage,
+    key: &str,
+    sha1: &str,
+    rom: &RomConfig,
+) -> Result<(), String> {
+    let mut document = open_document(storage, key)?;
+
+    let invalid =
+        |section: &str| format!("Invalid config {}: '{}' must be a section", key, section);
+    let roms = document
+        .entry("roms")
+        .or_insert(toml_edit::table())
+        .as_table_mut()
+        .ok_or_else(|| invalid("roms"))?;
+    // Only the sections of the ROMs are written, not an empty `[roms]`
+    roms.set_implicit(true);
+    let settings = roms
+        .entry(sha1)
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .ok_or_else(|| invalid(&format!("roms.{}", sha1)))?;
+    if let Some(profile) = &rom.profile {
+        settings.insert("profile", toml_edit::value(profile.as_str()));
+    }
+    if let Some(instructions) = rom.instructions_per_frame {
+        settings.insert(
+            "instructions_per_frame",
+            toml_edit::value(i64::from(instructions)),
+        );
+    }
+
+    save_document(storage, key, &document)
+}
+
+#[test]
+fn configs_are_parsed() {
+    let config = Config::parse(
+        r#"
+        [video]
+        scale = 12
+        high_contrast = false
+        [input]
+        keys = { 5 = "Space", a = "Z" }
+        [emulation]
+        quirks = "shift"
+        instructions_per_frame = 15
+        rom_database = false
+        [roms.6F6509F38220E057A7E32EBB22DD353C1078E3E7]
+        profile = "schip"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.video.scale, Some(12));
+    assert_eq!(config.video.high_contrast, Some(false));
+    assert_eq!(config.video.show_keypad, None);
+    assert_eq!(
+        config.input.keys,
+        vec![(0x5, String::from("Space")), (0xA, String::from("Z"))]
+    );
+    assert_eq!(config.emulation.quirks, Some(vec![String::from("shift")]));
+    assert_eq!(config.emulation.instructions_per_frame, Some(15));
+    assert_eq!(config.emulation.rom_database, Some(false));
+    assert_eq!(config.audio, AudioConfig::default());
+    let rom = RomConfig {
+        profile: Some(String::from("schip")),
+        instructions_per_frame: None,
+    };
+    assert_eq!(
+        config.rom("6f6509f38220e057a7e32ebb22dd353c1078e3e7"),
+        Some(&rom)
+    );
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    let error = |text: &str| Config::parse(text).unwrap_err();
+    assert_eq!(error("[colors]"), "Unknown section 'colors'");
+    assert_eq!(error("[video]\nscal = 2"), "Unknown setting 'video.scal'");
+    assert_eq!(
+        error("[video]\nhigh_contrast = 1"),
+        "'video.high_contrast' must be true or false"
+    );
+    assert_eq!(
+        error("[emulation]\nstack_depth = -1"),
+        "'emulation.stack_depth' must be a positive integer"
+    );
+    assert_eq!(
+        error("[emulation]\nstack_depth = 0"),
+        "'emulation.stack_depth' must be a positive integer"
+    );
+    assert_eq!(
+        error("[input]\nkeys = { 10 = \"A\" }"),
+        "Invalid keypad key '10' in 'input.keys'"
+    );
+    assert_eq!(error("video = 1"), "'video' must be a section");
+    assert_eq!(error("[roms.blitz]"), "Invalid SHA-1 'blitz' in 'roms'");
+    assert_eq!(
+        error("[roms.6f6509f38220e057a7e32ebb22dd353c1078e3e7]\nspeed = 2"),
+        "Unknown setting 'roms.6f6509f38220e057a7e32ebb22dd353c1078e3e7.speed'"
+    );
+}
+
+#[test]
+fn rom_settings_are_saved() {
+    use crate::storage::MemoryStorage;
+
+    let mut storage = MemoryStorage::new();
+    let sha1 = "6f6509f38220e057a7e32ebb22dd353c1078e3e7";
+    storage
+        .store("chip8.toml", b"# Mine\n[video]\nscale = 12\n")
+        .unwrap();
+    let rom = |profile: Option<&str>, instructions| RomConfig {
+        profile: profile.map(str::to_string),
+        instructions_per_frame: instructions,
+    };
+    save_rom(&mut storage, "chip8.toml", sha1, &rom(Some("schip"), None)).unwrap();
+    save_rom(&mut storage, "chip8.toml", sha1, &rom(None, Some(20))).unwrap();
+
+    let text = String::from_utf8(storage.load("chip8.toml").unwrap().unwrap()).unwrap();
+    assert!(text.starts_with("# Mine\n[video]\nscale = 12\n"));
+    assert!(!text.contains("[roms]"));
+    let config = Config::load_from(&storage, "chip8.toml").unwrap();
+    assert_eq!(config.video.scale, Some(12));
+    assert_eq!(config.rom(sha1), Some(&rom(Some("schip"), Some(20))));
+}
+
+#[test]
+fn keys_are_saved() {
+    use crate::storage::MemoryStorage;
+
+    let mut storage = MemoryStorage::new();
+    let keys = [(0x5, String::from("Space")), (0xA, String::from("Z"))];
+    save_keys(&mut storage, "chip8.toml", &keys).unwrap();
+    let config = Config::load_from(&storage, "chip8.toml").unwrap();
+    assert_eq!(config.input.keys, keys);
+
+    assert!(Config::load_from(&storage, "missing.toml").is_err());
+}
@@ -0,0 +1,966 @@
+//! Debug Adapter Protocol (DAP) server, allowing the emulator to be debugged
+//! from any DAP client such as VS Code.
+//!
+//! The server speaks over `stdin`/`stdout` (the client launches the emulator as
+//! its debug adapter) or over a TCP connection (the client uses `debugServer`).
+//!
+//! # Source mapping
+//! ROMs have no source code, so a ROM is shown as a listing where line `n`
+//! holds the instruction at address `0x200 + 2 * (n - 1)`. Breakpoints can also
+//! be set directly on addresses through instruction breakpoints.
+//!
+//! # Data breakpoints
+//! Rows of the memory scope, the `I` register, and ranges written like `3E0+20` in the
+//! debug console can be watched with data breakpoints. See `Watchpoint`
+use chip8::chip_8::{
+    self, Access, Chip8, DebugHook, Debugger, FrameHasher, Instruction, MemoryBounds, Profile,
+    Quirks, RamSearch, SearchFilter, StopReason, VideoSettings, Watchpoint,
+};
+use chip8::rom;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// The only thread exposed to clients: the `CHIP-8` has a single execution unit
+const THREAD_ID: u64 = 1;
+
+const REGISTERS_REFERENCE: u64 = 1;
+const TIMERS_REFERENCE: u64 = 2;
+const MEMORY_REFERENCE: u64 = 3;
+
+/// Amount of bytes shown on each row of the memory scope
+const MEMORY_ROW_SIZE: usize = 16;
+
+/// Amount of bytes watched from the address in `I`: the largest sprite of `Dxyn`, and
+/// all the registers stored by `Fx55`
+const INDEX_WATCH_SIZE: usize = 16;
+
+/// Commands supported by the debug console
+const CONSOLE_USAGE: &str =
+    "Usage: explain [opcode] | disassembly | registers | display | planes | backtrace \
+     | search (start | FILTER) | watch [ADDRESS] | freeze ADDRESS [VALUE] | unfreeze ADDRESS \
+     | dump RANGE";
+
+/// Address of the instruction shown on the first line of a ROM listing
+const LISTING_BASE_ADDRESS: u16 = 0x200;
+
+/// ROM and settings requested by the client with a `launch` or `attach` request
+pub struct Target {
+    pub program: Option<String>,
+    /// File to load when the program is a ZIP archive holding several ROMs
+    pub entry: Option<String>,
+    pub stop_on_entry: bool,
+    pub instructions_per_frame: Option<u32>,
+    /// Settings of the VM overriding the ones given on the command line
+    pub profile: Option<Profile>,
+    pub quirks: Option<Quirks>,
+    pub stack_depth: Option<usize>,
+    pub memory_bounds: Option<MemoryBounds>,
+    pub seed: Option<u64>,
+}
+
+impl Target {
+    /// Reads the target from the `arguments` of a `launch` or `attach` request
+    /// # Errors
+    /// If a setting of the VM is invalid, ex: an unknown profile or a stack depth of `0`.
+    fn parse(arguments: &Value) -> Result<Target, String> {
+        let profile = match arguments["profile"].as_str() {
+            None => None,
+            Some(name) => Some(
+                Profile::from_name(name).ok_or_else(|| format!("Unknown profile '{}'.", name))?,
+            ),
+        };
+        let quirks = match arguments["quirks"].as_str() {
+            None => None,
+            Some(list) => Some(Quirks::parse(list)?),
+        };
+        let stack_depth = match arguments["stackDepth"].as_u64() {
+            None => None,
+            Some(0) => return Err(String::from("The stack depth must be positive.")),
+            Some(depth) => Some(depth.min(usize::MAX as u64) as usize),
+        };
+        let memory_bounds = match arguments["memoryBounds"].as_str() {
+            None => None,
+            Some(name) => Some(
+                MemoryBounds::from_name(name)
+                    .ok_or_else(|| format!("Unknown memory bounds '{}'.", name))?,
+            ),
+        };
+
+        Ok(Target {
+            program: arguments["program"].as_str().map(String::from),
+            entry: arguments["entry"].as_str().map(String::from),
+            stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+            instructions_per_frame: arguments["instructionsPerFrame"]
+                .as_u64()
+                .filter(|&instructions| instructions > 0)
+                .map(|instructions| instructions.min(u32::MAX as u64) as u32),
+            profile,
+            quirks,
+            stack_depth,
+            memory_bounds,
+            seed: arguments["seed"].as_u64(),
+        })
+    }
+
+    /// Applies the settings of the VM requested by the client to `chip_8_vm`, before the
+    /// ROM is loaded
+    fn apply(&self, chip_8_vm: &mut Chip8) {
+        if let Some(profile) = self.profile {
+            chip_8_vm.apply_profile(profile);
+        }
+        if let Some(quirks) = self.quirks {
+            chip_8_vm.set_quirks(quirks);
+        }
+        if let Some(depth) = self.stack_depth {
+            chip_8_vm.set_stack_depth(depth);
+        }
+        if let Some(bounds) = self.memory_bounds {
+            chip_8_vm.set_memory_bounds(bounds);
+        }
+        if let Some(seed) = self.seed {
+            chip_8_vm.set_rng_seed(seed);
+        }
+        if let Some(instructions) = self.instructions_per_frame {
+            chip_8_vm.set_instructions_per_frame(instructions);
+        }
+    }
+}
+
+/// A debugging session with a single DAP client
+pub struct Session {
+    requests: Receiver<Value>,
+    writer: Box<dyn Write>,
+    seq: u64,
+    debugger: Debugger,
+    program: Option<String>,
+    stop_on_entry: bool,
+    line_breakpoints: HashSet<u16>,
+    instruction_breakpoints: HashSet<u16>,
+    /// RAM search started from the debug console, if any
+    ram_search: Option<RamSearch>,
+    /// Addresses listed by the `watch` command of the debug console
+    watches: Vec<u16>,
+    /// Size of the memory of the VM, the last time a request could see it. Breakpoints
+    /// beyond it are rejected
+    memory_size: usize,
+    /// Rolling hash of the frames run, answered to `frameHash` requests. See `FrameHasher`
+    hasher: FrameHasher,
+    exit: bool,
+}
+
+/// Starts a session over `stdin`/`stdout`
+pub fn connect_stdio() -> Session {
+    Session::new(io::stdin(), Box::new(io::stdout()))
+}
+
+/// Waits for a single client to connect on `port` and starts a session with it
+pub fn listen(port: u16) -> io::Result<Session> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("Waiting for a DAP client on port {} ...", port);
+    let (stream, _) = listener.accept()?;
+    let reader = stream.try_clone()?;
+
+    Ok(Session::new(reader, Box::new(stream)))
+}
+
+/// Reads a single DAP message: a set of headers followed by a JSON body.
+/// # Returns
+/// `None` when the input was closed.
+fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut content_length: Option<usize> = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse().ok();
+        }
+    }
+
+    let content_length = content_length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Missing Content-Length"))?;
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Maps a line of a ROM listing to the address of its instruction, if it's part of a
+/// memory of `memory_size` bytes
+fn line_to_address(line: u64, memory_size: usize) -> Option<u16> {
+    let offset = line.checked_sub(1)?.checked_mul(2)?;
+    let address = (LISTING_BASE_ADDRESS as u64).checked_add(offset)?;
+    if address < memory_size as u64 {
+        Some(address as u16)
+    } else {
+        None
+    }
+}
+
+/// Maps an address to its line in a ROM listing, if it's part of it
+fn address_to_line(address: u16) -> Option<u64> {
+    if address < LISTING_BASE_ADDRESS || !address.is_multiple_of(2) {
+        return None;
+    }
+
+    Some(((address - LISTING_BASE_ADDRESS) / 2) as u64 + 1)
+}
+
+/// Returns the part of `range` within the memory of `vm`, `None` if it's entirely beyond it
+fn within_memory(range: Range<usize>, vm: &Chip8) -> Option<Range<usize>> {
+    Some(range.start..range.end.min(vm.memory().len())).filter(|range| !range.is_empty())
+}
+
+/// Parses an address in decimal or `0x` prefixed hexadecimal notation
+fn parse_address(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+fn stop_reason_name(reason: &StopReason) -> &'static str {
+    match reason {
+        StopReason::Entry => "entry",
+        StopReason::Breakpoint => "breakpoint",
+        StopReason::Step => "step",
+        StopReason::Pause => "pause",
+        StopReason::Fault(_) => "exception",
+        StopReason::Watchpoint(_) => "data breakpoint",
+    }
+}
+
+fn variable(name: String, value: String) -> Value {
+    json!({ "name": name, "value": value, "variablesReference": 0 })
+}
+
+impl Session {
+    fn new<R: Read + Send + 'static>(input: R, writer: Box<dyn Write>) -> Session {
+        let (sender, requests) = mpsc::channel();
+
+        // Requests are read on a separate thread so the main loop never blocks on the client
+        thread::spawn(move || {
+            let mut reader = BufReader::new(input);
+            while let Ok(Some(message)) = read_message(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Session {
+            requests,
+            writer,
+            seq: 1,
+            debugger: Debugger::new(),
+            program: None,
+            stop_on_entry: false,
+            line_breakpoints: HashSet::new(),
+            instruction_breakpoints: HashSet::new(),
+            ram_search: None,
+            watches: Vec::new(),
+            memory_size: Profile::default().memory_size(),
+            hasher: FrameHasher::new(),
+            exit: false,
+        }
+    }
+
+    fn send(&mut self, mut message: Value) {
+        message["seq"] = json!(self.seq);
+        self.seq += 1;
+
+        let body = message.to_string();
+        let result = write!(
+            self.writer,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .and_then(|_| self.writer.flush());
+        if result.is_err() {
+            // The client is gone: there is nobody left to debug for
+            self.exit = true;
+        }
+    }
+
+    fn respond(&mut self, request: &Value, body: Value) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    fn respond_error(&mut self, request: &Value, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }));
+    }
+
+    fn event(&mut self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    /// Sends a message to the debug console of the client
+    pub fn output(&mut self, text: &str) {
+        self.event(
+            "output",
+            json!({ "category": "console", "output": format!("{}\n", text) }),
+        );
+    }
+
+    fn stopped(&mut self, reason: StopReason) {
+        let mut body = json!({
+            "reason": stop_reason_name(&reason),
+            "threadId": THREAD_ID,
+            "allThreadsStopped": true,
+        });
+        match reason {
+            StopReason::Watchpoint(hit) => {
+                let access = if hit.write { "Write to" } else { "Read of" };
+                body["description"] = json!(format!("{} 0x{:03X}", access, hit.address));
+                body["text"] = json!(chip_8::disassemble(hit.pc, hit.opcode));
+            }
+            StopReason::Fault(error) => {
+                body["description"] = json!("Fault");
+                body["text"] = json!(error.to_string());
+            }
+            _ => {}
+        }
+        self.event("stopped", body);
+    }
+
+    /// Blocks until the client sends a `launch` or `attach` request.
+    /// # Returns
+    /// The requested target, or why its arguments are invalid, or `None` if the client
+    /// disconnected first. The pending request must then be answered with
+    /// `Session::launched` or `Session::launch_failed`.
+    pub fn wait_for_target(&mut self) -> Option<(Value, Result<Target, String>)> {
+        while let Ok(request) = self.requests.recv() {
+            match request["command"].as_str() {
+                Some("launch") | Some("attach") => {
+                    let target = Target::parse(&request["arguments"]);
+                    return Some((request, target));
+                }
+                Some("disconnect") | Some("terminate") => {
+                    self.respond(&request, json!({}));
+                    return None;
+                }
+                _ => self.handle_request(&request, None),
+            }
+        }
+
+        None
+    }
+
+    /// Answers the `launch`/`attach` request once the ROM at `program` was loaded
+    pub fn launched(&mut self, request: &Value, program: &str, stop_on_entry: bool) {
+        self.program = Some(program.to_string());
+        self.stop_on_entry = stop_on_entry;
+        self.respond(request, json!({}));
+    }
+
+    /// Reports a failed `launch`/`attach` request to the client
+    pub fn launch_failed(&mut self, request: &Value, message: &str) {
+        self.respond_error(request, message);
+    }
+
+    fn source(&self) -> Value {
+        match &self.program {
+            Some(program) => json!({ "name": program, "path": program }),
+            None => Value::Null,
+        }
+    }
+
+    fn update_breakpoints(&mut self) {
+        let addresses = self
+            .line_breakpoints
+            .union(&self.instruction_breakpoints)
+            .copied()
+            .collect::<Vec<u16>>();
+        self.debugger.set_breakpoints(addresses);
+    }
+
+    fn set_breakpoints(&mut self, request: &Value) {
+        let mut results = Vec::new();
+        self.line_breakpoints.clear();
+
+        if let Some(breakpoints) = request["arguments"]["breakpoints"].as_array() {
+            for breakpoint in breakpoints {
+                let line = breakpoint["line"].as_u64().unwrap_or(0);
+                match line_to_address(line, self.memory_size) {
+                    Some(address) => {
+                        self.line_breakpoints.insert(address);
+                        results.push(json!({
+                            "verified": true,
+                            "line": line,
+                            "instructionReference": format!("0x{:03X}", address),
+                        }));
+                    }
+                    None => results.push(json!({
+                        "verified": false,
+                        "line": line,
+                        "message": "Line is outside the ROM listing",
+                    })),
+                }
+            }
+        }
+
+        self.update_breakpoints();
+        self.respond(request, json!({ "breakpoints": results }));
+    }
+
+    /// Tells whether a variable can be watched: rows of the memory scope, `I` and ranges
+    /// of memory within the memory of the VM. Data ids are memory ranges, as parsed by
+    /// `parse_memory_range`
+    fn data_breakpoint_info(&mut self, request: &Value, vm: &Chip8) {
+        let arguments = &request["arguments"];
+        let name = arguments["name"].as_str().unwrap_or_default();
+        let range = match arguments["variablesReference"].as_u64() {
+            Some(MEMORY_REFERENCE) => {
+                parse_address(name).map(|start| start as usize..start as usize + MEMORY_ROW_SIZE)
+            }
+            Some(REGISTERS_REFERENCE) if name == "I" => {
+                Some(Watchpoint::at_index(vm, INDEX_WATCH_SIZE, Access::ReadWrite).range)
+            }
+            Some(_) => None,
+            None => chip_8::parse_memory_range(name).ok(),
+        };
+
+        let body = match range.and_then(|range| within_memory(range, vm)) {
+            Some(range) => json!({
+                "dataId": format!("{:X}-{:X}", range.start, range.end - 1),
+                "description": format!("0x{:03X}-0x{:03X}", range.start, range.end - 1),
+                "accessTypes": Access::ALL.iter().map(Access::name).collect::<Vec<_>>(),
+                "canPersist": true,
+            }),
+            None => json!({
+                "dataId": null,
+                "description": "Only memory can be watched",
+            }),
+        };
+        self.respond(request, body);
+    }
+
+    fn set_data_breakpoints(&mut self, request: &Value, vm: &mut Chip8) {
+        let mut results = Vec::new();
+        let mut watchpoints = Vec::new();
+
+        if let Some(breakpoints) = request["arguments"]["dataBreakpoints"].as_array() {
+            for breakpoint in breakpoints {
+                let range = breakpoint["dataId"]
+                    .as_str()
+                    .and_then(|id| chip_8::parse_memory_range(id).ok())
+                    .and_then(|range| within_memory(range, vm));
+                let access = match breakpoint["accessType"].as_str() {
+                    Some(name) => Access::from_name(name),
+                    None => Some(Access::Write),
+                };
+
+                match (range, access) {
+                    (Some(range), Some(access)) => {
+                        watchpoints.push(Watchpoint { range, access });
+                        results.push(json!({ "verified": true }));
+                    }
+                    _ => results.push(json!({
+                        "verified": false,
+                        "message": "Invalid data breakpoint",
+                    })),
+                }
+            }
+        }
+
+        vm.set_watchpoints(watchpoints);
+        self.respond(request, json!({ "breakpoints": results }));
+    }
+
+    fn set_instruction_breakpoints(&mut self, request: &Value) {
+        let mut results = Vec::new();
+        self.instruction_breakpoints.clear();
+
+        if let Some(breakpoints) = request["arguments"]["breakpoints"].as_array() {
+            for breakpoint in breakpoints {
+                let address = breakpoint["instructionReference"]
+                    .as_str()
+                    .and_then(parse_address)
+                    .and_then(|address| {
+                        (address as i64).checked_add(breakpoint["offset"].as_i64().unwrap_or(0))
+                    });
+
+                match address {
+                    Some(address) if (0..self.memory_size as i64).contains(&address) => {
+                        self.instruction_breakpoints.insert(address as u16);
+                        results.push(json!({
+                            "verified": true,
+                            "instructionReference": format!("0x{:03X}", address),
+                        }));
+                    }
+                    _ => results.push(json!({
+                        "verified": false,
+                        "message": "Invalid instruction address",
+                    })),
+                }
+            }
+        }
+
+        self.update_breakpoints();
+        self.respond(request, json!({ "breakpoints": results }));
+    }
+
+    fn stack_frame(&self, id: usize, address: u16, vm: &Chip8) -> Value {
+        let memory = vm.memory();
+        let name = match (
+            memory.get(address as usize),
+            memory.get(address as usize + 1),
+        ) {
+            (Some(&high), Some(&low)) => {
+                let opcode = ((high as u16) << 8) | low as u16;
+                match Instruction::decode(opcode) {
+                    Some(instruction) => format!("{:04X} {}", opcode, instruction),
+                    None => format!("{:04X}", opcode),
+                }
+            }
+            _ => String::from("????"),
+        };
+
+        let mut frame = json!({
+            "id": id,
+            "name": format!("0x{:03X}: {}", address, name),
+            "line": 0,
+            "column": 0,
+            "instructionPointerReference": format!("0x{:03X}", address),
+        });
+        if let Some(line) = address_to_line(address) {
+            frame["line"] = json!(line);
+            frame["column"] = json!(1);
+            frame["source"] = self.source();
+        }
+
+        frame
+    }
+
+    fn stack_trace(&mut self, request: &Value, vm: &Chip8) {
+        // The current instruction first, then every `CALL` site from the innermost one
+        let mut frames = vec![self.stack_frame(0, vm.pc(), vm)];
+        for (id, &return_address) in vm.stack().iter().rev().enumerate() {
+            let call_site = return_address.wrapping_sub(2);
+            frames.push(self.stack_frame(id + 1, call_site, vm));
+        }
+
+        let total = frames.len();
+        self.respond(
+            request,
+            json!({ "stackFrames": frames, "totalFrames": total }),
+        );
+    }
+
+    fn variables(&mut self, request: &Value, vm: &Chip8) {
+        let reference = request["arguments"]["variablesReference"]
+            .as_u64()
+            .unwrap_or(0);
+
+        let variables = match reference {
+            REGISTERS_REFERENCE => {
+                let mut variables = vm
+                    .v()
+                    .iter()
+                    .enumerate()
+                    .map(|(x, value)| variable(format!("V{:X}", x), format!("0x{:02X}", value)))
+                    .collect::<Vec<Value>>();
+                variables.push(variable("I".into(), format!("0x{:03X}", vm.index())));
+                variables.push(variable("PC".into(), format!("0x{:03X}", vm.pc())));
+                variables.push(variable("SP".into(), vm.stack().len().to_string()));
+                variables
+            }
+            TIMERS_REFERENCE => {
+                let (delay, sound) = vm.timers();
+                vec![
+                    variable("delay".into(), delay.to_string()),
+                    variable("sound".into(), sound.to_string()),
+                ]
+            }
+            MEMORY_REFERENCE => vm
+                .memory()
+                .chunks(MEMORY_ROW_SIZE)
+                .enumerate()
+                .map(|(row, bytes)| {
+                    let hex = bytes
+                        .iter()
+                        .map(|byte| format!("{:02X}", byte))
+                        .collect::<Vec<String>>()
+                        .join(" ");
+                    variable(format!("0x{:03X}", row * MEMORY_ROW_SIZE), hex)
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        self.respond(request, json!({ "variables": variables }));
+    }
+
+    /// Runs the memory commands of the debug console against `vm`:
+    /// * `search start`: Starts a `RamSearch` on the current memory.
+    /// * `search FILTER`: Filters the candidates, see `SearchFilter`. Ex: `search increased`.
+    /// * `search`: Lists the candidates.
+    /// * `watch ADDRESS` and `watch`: Adds an address to the watch list, and lists it.
+    /// * `freeze ADDRESS [VALUE]` and `unfreeze ADDRESS`: See `Chip8::freeze`.
+    /// * `dump RANGE`: Prints a hex dump of the memory, ex: `dump 3E0+20`. See `Chip8::hex_dump`.
+    ///
+    /// # Returns
+    /// `None` if `expression` isn't a memory command.
+    fn memory_command(
+        &mut self,
+        expression: &str,
+        vm: &mut Chip8,
+    ) -> Option<Result<String, String>> {
+        let mut words = expression.splitn(2, ' ');
+        let command = words.next().unwrap_or_default();
+        let argument = words.next().unwrap_or_default().trim();
+        let address = |text: &str| {
+            text.split_whitespace()
+                .next()
+                .and_then(|address| u16::from_str_radix(address.trim_start_matches("0x"), 16).ok())
+                .filter(|&address| (address as usize) < vm.memory().len())
+                .ok_or(format!("Invalid address '{}'.", text))
+        };
+
+        let result = match (command, argument) {
+            ("search", "start") => {
+                let search = RamSearch::new(vm);
+                let report = format!("{} candidates", search.candidates().len());
+                self.ram_search = Some(search);
+                Ok(report)
+            }
+            ("search", filter) => match &mut self.ram_search {
+                None => Err(String::from(
+                    "No search running, start one with `search start`.",
+                )),
+                Some(search) if filter.is_empty() => Ok(search.report(vm)),
+                Some(search) => match SearchFilter::from_name(filter) {
+                    Some(filter) => {
+                        search.filter(vm, filter);
+                        Ok(search.report(vm))
+                    }
+                    None => {
+                        let names: Vec<&str> = SearchFilter::ALL.iter().map(|f| f.name()).collect();
+                        Err(format!(
+                            "Unknown filter '{}'. Available filters: {}, == VALUE",
+                            filter,
+                            names.join(", ")
+                        ))
+                    }
+                },
+            },
+            ("watch", "") => Ok(self
+                .watches
+                .iter()
+                .map(|&address| format!("0x{:03X}: {:02X}", address, vm.memory()[address as usize]))
+                .collect::<Vec<String>>()
+                .join("\n")),
+            ("watch", text) => address(text).map(|address| {
+                if !self.watches.contains(&address) {
+                    self.watches.push(address);
+                }
+                format!("Watching 0x{:03X}", address)
+            }),
+            ("freeze", text) => address(text).and_then(|address| {
+                let value = match text.split_whitespace().nth(1) {
+                    Some(value) => u8::from_str_radix(value.trim_start_matches("0x"), 16)
+                        .map_err(|_| format!("Invalid value '{}'.", value))?,
+                    None => vm.memory()[address as usize],
+                };
+                vm.freeze(address, value)?;
+                Ok(format!("0x{:03X} frozen to {:02X}", address, value))
+            }),
+            ("dump", text) => chip_8::parse_memory_range(text).and_then(|range| vm.hex_dump(range)),
+            ("unfreeze", text) => address(text).map(|address| match vm.unfreeze(address) {
+                true => format!("0x{:03X} unfrozen", address),
+                false => format!("0x{:03X} wasn't frozen", address),
+            }),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// Evaluates an expression typed in the debug console of the client.
+    /// Supported commands: `explain (opcode)`, under the quirks of the VM once running, and
+    /// while a VM is running `explain` for the current instruction, `disassembly`,
+    /// `registers`, `backtrace`, `display` and `planes` to print the state as text, along
+    /// with the memory commands of `Session::memory_command`.
+    fn evaluate(&mut self, request: &Value, vm: Option<&mut Chip8>) {
+        let expression = request["arguments"]["expression"]
+            .as_str()
+            .unwrap_or_default();
+        let result = match (expression.trim(), vm) {
+            ("explain", Some(vm)) => vm.explain_current(),
+            ("disassembly", Some(vm)) => Ok(vm.disassembly_line()),
+            ("registers", Some(vm)) => Ok(vm.register_dump()),
+            ("backtrace", Some(vm)) => Ok(vm.backtrace()),
+            ("display", Some(vm)) => Ok(vm.display_text()),
+            ("planes", Some(vm)) => Ok(vm.planes_text()),
+            (expression, vm) => match (expression.strip_prefix("explain "), vm) {
+                (Some(opcode), vm) => {
+                    chip_8::explain(opcode, vm.map_or_else(Quirks::default, |vm| vm.quirks()))
+                }
+                (None, Some(vm)) => match self.memory_command(expression, vm) {
+                    Some(result) => result,
+                    None => Err(String::from(CONSOLE_USAGE)),
+                },
+                (None, None) => Err(String::from(CONSOLE_USAGE)),
+            },
+        };
+
+        match result {
+            Ok(explanation) => self.respond(
+                request,
+                json!({ "result": explanation, "variablesReference": 0 }),
+            ),
+            Err(msg) => self.respond_error(request, &msg),
+        }
+    }
+
+    /// Handles a single request. Requests that need the VM are rejected while
+    /// no VM is available (before the `launch`/`attach` request completes).
+    fn handle_request(&mut self, request: &Value, vm: Option<&mut Chip8>) {
+        let command = request["command"].as_str().unwrap_or_default();
+        if let Some(vm) = &vm {
+            self.memory_size = vm.memory().len();
+        }
+
+        match (command, vm) {
+            ("initialize", _) => {
+                self.respond(
+                    request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsInstructionBreakpoints": true,
+                        "supportsDataBreakpoints": true,
+                        "supportsTerminateRequest": true,
+                    }),
+                );
+                self.event("initialized", json!({}));
+            }
+            ("setBreakpoints", _) => self.set_breakpoints(request),
+            ("setInstructionBreakpoints", _) => self.set_instruction_breakpoints(request),
+            ("dataBreakpointInfo", Some(vm)) => self.data_breakpoint_info(request, vm),
+            ("setDataBreakpoints", Some(vm)) => self.set_data_breakpoints(request, vm),
+            ("setExceptionBreakpoints", _) => {
+                self.respond(request, json!({ "breakpoints": [] }))
+            }
+            ("threads", _) => self.respond(
+                request,
+                json!({ "threads": [{ "id": THREAD_ID, "name": "CHIP-8" }] }),
+            ),
+            ("configurationDone", _) => {
+                self.respond(request, json!({}));
+                if self.stop_on_entry {
+                    self.stopped(StopReason::Entry);
+                } else {
+                    self.debugger.resume();
+                }
+            }
+            ("disconnect", _) | ("terminate", _) => {
+                self.respond(request, json!({}));
+                self.event("terminated", json!({}));
+                self.exit = true;
+            }
+            ("evaluate", vm) => self.evaluate(request, vm),
+            ("stackTrace", Some(vm)) => self.stack_trace(request, vm),
+            ("scopes", Some(_)) => self.respond(
+                request,
+                json!({ "scopes": [
+                    { "name": "Registers", "variablesReference": REGISTERS_REFERENCE, "expensive": false },
+                    { "name": "Timers", "variablesReference": TIMERS_REFERENCE, "expensive": false },
+                    { "name": "Memory", "variablesReference": MEMORY_REFERENCE, "expensive": true },
+                ]}),
+            ),
+            ("variables", Some(vm)) => self.variables(request, vm),
+            ("continue", Some(_)) => {
+                self.debugger.resume();
+                self.respond(request, json!({ "allThreadsContinued": true }));
+            }
+            ("next", Some(vm)) => {
+                self.debugger.step_over(vm);
+                self.respond(request, json!({}));
+            }
+            ("stepIn", Some(_)) => {
+                self.debugger.step_in();
+                self.respond(request, json!({}));
+            }
+            ("stepOut", Some(vm)) => {
+                self.debugger.step_out(vm);
+                self.respond(request, json!({}));
+            }
+            ("frameHash", Some(_)) => {
+                let body = match self.hasher.last() {
+                    Some(frame_hash) => json!({
+                        "frame": frame_hash.frame,
+                        "hash": format!("{:016x}", frame_hash.hash),
+                    }),
+                    None => json!({ "frame": Value::Null, "hash": Value::Null }),
+                };
+                self.respond(request, body);
+            }
+            ("pause", Some(_)) => {
+                self.respond(request, json!({}));
+                if self.debugger.pause() {
+                    self.stopped(StopReason::Pause);
+                }
+            }
+            (_, None) => self.respond_error(request, "No ROM is being debugged"),
+            (command, _) => {
+                self.respond_error(request, &format!("Unsupported request: {}", command))
+            }
+        }
+    }
+}
+
+impl Session {
+    /// Notifies the client that the debugging session is over, if it doesn't know yet
+    pub fn finish(&mut self) {
+        if !self.exit {
+            self.event("terminated", json!({}));
+            self.exit = true;
+        }
+    }
+}
+
+impl DebugHook for Session {
+    fn update(&mut self, vm: &mut Chip8) {
+        loop {
+            match self.requests.try_recv() {
+                Ok(request) => self.handle_request(&request, Some(vm)),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.exit = true;
+                    break;
+                }
+            }
+        }
+
+        let reason = self.debugger.cycle(vm);
+        self.hasher.update(vm);
+        if let Some(reason) = reason {
+            self.stopped(reason);
+        }
+    }
+
+    fn should_exit(&self) -> bool {
+        self.exit
+    }
+}
+
+/// Runs the emulator as a debug adapter. Without a `port` the session runs over
+/// `stdin`/`stdout`. The ROM can also be provided by the client in the `program`
+/// argument of its `launch` request, which takes precedence over `rom_path`. ROMs are
+/// loaded like `rom::load`: from archives, with the `entry` argument to pick one, or URLs.
+///
+/// `chip_8_vm` comes with the settings given on the command line, overridden by the ones
+/// of the `launch` request: `profile`, `quirks`, `stackDepth`, `memoryBounds`, `seed` and
+/// `instructionsPerFrame`. `on_loaded` is called with the path of the ROM once it's loaded,
+/// ex: to apply its cheats.
+pub fn run<F>(
+    port: Option<u16>,
+    rom_path: Option<String>,
+    mut chip_8_vm: Chip8,
+    on_loaded: F,
+) -> Result<(), String>
+where
+    F: FnOnce(&mut Chip8, &str),
+{
+    let mut session = match port {
+        None => connect_stdio(),
+        Some(port) => listen(port).map_err(|e| format!("Failed to accept a client: {}", e))?,
+    };
+
+    let (request, target) = match session.wait_for_target() {
+        None => return Ok(()),
+        Some((request, Ok(target))) => (request, target),
+        Some((request, Err(msg))) => {
+            session.launch_failed(&request, &msg);
+            return Err(msg);
+        }
+    };
+
+    let path = match target.program.clone().or(rom_path) {
+        None => {
+            session.launch_failed(&request, "No ROM provided.");
+            return Err(String::from("No ROM provided."));
+        }
+        Some(path) => path,
+    };
+
+    target.apply(&mut chip_8_vm);
+    let loaded = rom::load(&path, target.entry.as_deref())
+        .and_then(|content| chip_8_vm.load_rom_content(content).map_err(String::from));
+    match loaded {
+        Err(msg) => {
+            session.launch_failed(&request, &msg);
+            return Err(msg);
+        }
+        Ok(total_read) => {
+            on_loaded(&mut chip_8_vm, &path);
+            session.launched(&request, &path, target.stop_on_entry);
+            session.output(&format!(
+                "ROM {} loaded successfully. {} bytes were read.",
+                path, total_read
+            ));
+        }
+    }
+
+    chip_8_vm.start(VideoSettings::default(), Some(&mut session));
+    session.finish();
+
+    Ok(())
+}
+
+#[test]
+fn lines_map_to_addresses_within_memory() {
+    assert_eq!(line_to_address(1, 0x1000), Some(0x200));
+    assert_eq!(line_to_address(0x700, 0x1000), Some(0xFFE));
+    assert_eq!(line_to_address(0x701, 0x1000), None);
+    assert_eq!(line_to_address(0x701, 0x10000), Some(0x1000));
+    assert_eq!(line_to_address(0, 0x10000), None);
+    assert_eq!(line_to_address(u64::MAX, 0x10000), None);
+}
+
+#[test]
+fn launch_arguments_set_the_vm() {
+    let target = Target::parse(&json!({
+        "program": "roms/tests/ibm-logo.ch8",
+        "profile": "xo-chip",
+        "stackDepth": 64,
+        "memoryBounds": "wrap",
+        "seed": 7,
+    }))
+    .unwrap();
+    let mut chip_8_vm = Chip8::new();
+    target.apply(&mut chip_8_vm);
+    assert_eq!(chip_8_vm.memory().len(), Profile::XoChip.memory_size());
+    assert_eq!(chip_8_vm.quirks(), Profile::XoChip.quirks());
+    assert_eq!(chip_8_vm.memory_bounds(), MemoryBounds::Wrap);
+
+    assert!(Target::parse(&json!({ "profile": "missing" })).is_err());
+    assert!(Target::parse(&json!({ "stackDepth": 0 })).is_err());
+    assert!(Target::parse(&json!({ "quirks": "missing" })).is_err());
+}
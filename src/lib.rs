@@ -0,0 +1,19 @@
+//! Simple but extensively documented `CHIP-8` emulator.
+//!
+//! The `chip_8` module holds the VM, which can be embedded in other frontends.
+//! The `rom` module loads ROM files, also from archives, and holds utilities to work with them.
+//! The `zip` module reads and writes ZIP archives, ex: ROM packs and bug report bundles.
+//! The `pbm` module compares frames saved as PBM images.
+//! The `report` module generates HTML reports of ROM collections.
+//! The `storage` module persists save states, flags and configuration.
+//! The `config` module reads the settings of the emulator from a TOML file.
+//! The `library` module downloads ROM packs from a remote index, with the `network` feature.
+pub mod chip_8;
+pub mod config;
+#[cfg(feature = "network")]
+pub mod library;
+pub mod pbm;
+pub mod report;
+pub mod rom;
+pub mod storage;
+pub mod zip;
@@ -0,0 +1,212 @@
+//! Synchronization of a local ROM library with a remote index of community ROM packs.
+//! Only available with the `network` feature.
+//! # Index format
+//! A JSON document served over HTTP(S), listing packs of ROMs:
+//! ```json
+//! {
+//!   "packs": [
+//!     {
+//!       "name": "demos",
+//!       "description": "Classic demos",
+//!       "roms": [
+//!         { "name": "Maze.ch8", "url": "demos/maze.ch8", "sha256": "5c3f..." }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//! ROM URLs are either absolute or relative to the index. The `SHA-256` of every
+//! download is checked against the index, so a corrupted or tampered ROM is never
+//! written to the library.
+use crate::chip_8::Chip8;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// A ROM listed by a remote index
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteRom {
+    /// File name of the ROM inside its pack directory
+    pub name: String,
+    /// Absolute URL of the ROM
+    pub url: String,
+    /// Expected `SHA-256` of the ROM, as lowercase hexadecimal
+    pub sha256: String,
+}
+
+/// A pack of ROMs listed by a remote index
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RomPack {
+    /// Name of the pack, also used as its directory inside the library
+    pub name: String,
+    pub description: String,
+    pub roms: Vec<RemoteRom>,
+}
+
+/// Outcome of the synchronization of a `RomPack`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// ROMs downloaded and written to the library
+    pub downloaded: Vec<String>,
+    /// ROMs already present in the library with the expected hash
+    pub up_to_date: Vec<String>,
+}
+
+/// Returns `true` if `name` can be used as a file or directory name on every platform,
+/// so an index can't write outside of the library
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(|c: char| c == '/' || c == '\\' || c == ':' || c.is_control())
+}
+
+/// Returns the `SHA-256` of `content` as lowercase hexadecimal
+fn sha256_hex(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Resolves `url` relative to the URL of the index, unless already absolute
+fn resolve_url(index_url: &str, url: &str) -> String {
+    if url.contains("://") {
+        return url.to_string();
+    }
+    let base = index_url
+        .rsplit_once('/')
+        .map_or(index_url, |(base, _)| base);
+    format!("{}/{}", base, url.trim_start_matches('/'))
+}
+
+/// Downloads the resource at `url`, failing if it's bigger than `max_size` bytes
+pub(crate) fn download(url: &str, max_size: usize) -> Result<Vec<u8>, String> {
+    download_with_progress(url, max_size, &mut |_, _| {})
+}
+
+/// Downloads the resource at `url` like `download`, calling `progress` with the bytes
+/// received so far and the size announced by the server, if any, as they arrive
+pub(crate) fn download_with_progress(
+    url: &str,
+    max_size: usize,
+    progress: &mut dyn FnMut(usize, Option<usize>),
+) -> Result<Vec<u8>, String> {
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    let size = response
+        .header("Content-Length")
+        .and_then(|length| length.parse::<usize>().ok());
+
+    let mut content = Vec::new();
+    let mut reader = response.into_reader().take(max_size as u64 + 1);
+    let mut chunk = [0; 0x4000];
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Failed to download {}: {}", url, e)),
+        };
+        content.extend_from_slice(&chunk[..read]);
+        progress(content.len(), size);
+    }
+    if content.len() > max_size {
+        return Err(format!("{} exceeds {} bytes.", url, max_size));
+    }
+    Ok(content)
+}
+
+/// Parses a remote index. See the module documentation for the format
+/// # Errors
+/// If the index is not valid JSON, misses a field or holds unsafe names
+pub fn parse_index(index_url: &str, content: &[u8]) -> Result<Vec<RomPack>, String> {
+    let index: Value =
+        serde_json::from_slice(content).map_err(|e| format!("Invalid ROM index: {}", e))?;
+    let text = |value: &Value, field: &str| -> Result<String, String> {
+        value[field]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(format!("Invalid ROM index: missing '{}'.", field))
+    };
+    let safe_name = |value: &Value| -> Result<String, String> {
+        let name = text(value, "name")?;
+        if is_safe_name(&name) {
+            Ok(name)
+        } else {
+            Err(format!("Invalid ROM index: unsafe name '{}'.", name))
+        }
+    };
+
+    let packs = index["packs"]
+        .as_array()
+        .ok_or("Invalid ROM index: missing 'packs'.")?;
+    packs
+        .iter()
+        .map(|pack| {
+            let roms = pack["roms"]
+                .as_array()
+                .ok_or("Invalid ROM index: missing 'roms'.")?
+                .iter()
+                .map(|rom| {
+                    Ok(RemoteRom {
+                        name: safe_name(rom)?,
+                        url: resolve_url(index_url, &text(rom, "url")?),
+                        sha256: text(rom, "sha256")?.to_lowercase(),
+                    })
+                })
+                .collect::<Result<Vec<RemoteRom>, String>>()?;
+
+            Ok(RomPack {
+                name: safe_name(pack)?,
+                description: pack["description"].as_str().unwrap_or_default().to_string(),
+                roms,
+            })
+        })
+        .collect()
+}
+
+/// Downloads and parses the remote index at `index_url`
+pub fn fetch_index(index_url: &str) -> Result<Vec<RomPack>, String> {
+    // Generous limit: an index only holds names, URLs and hashes
+    const MAX_INDEX_SIZE: usize = 16 * 1024 * 1024;
+
+    let content = download(index_url, MAX_INDEX_SIZE)?;
+    parse_index(index_url, &content)
+}
+
+/// Downloads the ROMs of `pack` into the `(library)/(pack name)` directory.
+/// ROMs already present with the expected hash are not downloaded again.
+/// # Errors
+/// If a download fails, or its hash doesn't match the index. ROMs are verified
+/// before being written, so the library never holds an unverified ROM.
+pub fn sync_pack(pack: &RomPack, library: &Path) -> Result<SyncReport, String> {
+    let directory: PathBuf = library.join(&pack.name);
+    fs::create_dir_all(&directory)
+        .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+
+    let mut report = SyncReport::default();
+    for rom in pack.roms.iter() {
+        let path = directory.join(&rom.name);
+        if fs::read(&path).is_ok_and(|content| sha256_hex(&content) == rom.sha256) {
+            report.up_to_date.push(rom.name.clone());
+            continue;
+        }
+
+        let content = download(&rom.url, Chip8::MAX_ROM_SIZE)?;
+        let hash = sha256_hex(&content);
+        if hash != rom.sha256 {
+            return Err(format!(
+                "Hash mismatch for {}: expected {}, found {}.",
+                rom.name, rom.sha256, hash
+            ));
+        }
+        fs::write(&path, content)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        report.downloaded.push(rom.name.clone());
+    }
+    Ok(report)
+}
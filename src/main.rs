@@ -1,55 +1,2227 @@
-mod chip_8;
+#[cfg(feature = "window")]
+mod dap;
 
-use chip_8::Chip8;
+#[cfg(feature = "sound")]
+use chip8::chip_8::Beeper;
+#[cfg(feature = "gamepad")]
+use chip8::chip_8::GamepadMap;
+#[cfg(any(feature = "window", feature = "tui"))]
+use chip8::chip_8::RomLoading;
+#[cfg(any(feature = "window", feature = "tui", feature = "framebuffer"))]
+use chip8::chip_8::RomSettings;
+#[cfg(feature = "scripting")]
+use chip8::chip_8::Script;
+#[cfg(all(feature = "framebuffer", not(feature = "window")))]
+use chip8::chip_8::VideoSettings;
+use chip8::chip_8::{
+    self, parse_rgb, AntiFlicker, Cheats, Chip8, Chip8Error, ExecutionLimits, Filter, Limit,
+    MemoryBounds, Movie, Palette, Profile, Quirk, Quirks, Rgb, RomDatabase, Scaling,
+    SelfTestResult, SelfTestStatus, SpeedClass, Tone, Triggers, WavSample, Waveform,
+    WriteProtection,
+};
+#[cfg(feature = "window")]
+use chip8::chip_8::{
+    DebugHook, DemoPicker, FrameHashTrace, HostAction, KeyMap, Kiosk, Playlist, PlaylistEntry,
+    Rewind, Turbo, VideoSettings, DEMOS,
+};
+use chip8::config::{self, Config, RomConfig, DEFAULT_CONFIG_PATH};
+#[cfg(feature = "network")]
+use chip8::library;
+use chip8::pbm::Frame;
+use chip8::report::{self, Platform, RomAnalysis};
+use chip8::rom;
+#[cfg(any(feature = "window", feature = "tui"))]
+use chip8::rom::RomLoader;
+use chip8::storage::{FileStorage, Storage};
+use clap::{Args, Parser, Subcommand};
 use std::env;
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::sync::Arc;
+use std::time::Duration;
 
-const DEFAULT_CYCLE_DELAY: u64 = 10;
+/// Size of the squares drawn for each pixel of a visual diff
+const DIFF_SCALE: usize = 8;
+
+/// Length of the longest bar of the instruction histogram printed by `info`
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Simple but extensively documented CHIP-8 emulator.
+///
+/// Runs the ROM in a window when no command is given, same as `run`.
+/// Without any ROM, offers a menu of embedded demos.
+#[derive(Parser)]
+#[command(
+    name = "chip8",
+    version,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    args_override_self = true
+)]
+#[cfg_attr(not(feature = "window"), command(arg_required_else_help = true))]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs a ROM in a window
+    #[command(args_override_self = true)]
+    Run(Box<RunArgs>),
+    /// Runs a ROM in the terminal, ex: over SSH. Needs the `tui` feature
+    #[command(args_override_self = true)]
+    Tui(TuiArgs),
+    /// Runs a ROM without any window until a limit halts it, then writes the final frame
+    Headless(HeadlessArgs),
+    /// Runs the emulator as a Debug Adapter Protocol server, ex: for VS Code
+    Dap(DapArgs),
+    /// Prints the disassembly of a ROM
+    Disasm {
+        /// Path or URL of the ROM
+        rom: String,
+        /// File to load when the ROM is a ZIP archive holding several ROMs
+        #[arg(long, value_name = "NAME")]
+        entry: Option<String>,
+    },
+    /// Prints the size, hashes, platform, instruction histogram and known settings of a ROM
+    Info {
+        /// Path or URL of the ROM
+        rom: String,
+        /// File to load when the ROM is a ZIP archive holding several ROMs
+        #[arg(long, value_name = "NAME")]
+        entry: Option<String>,
+    },
+    /// Prints a hex dump of the memory, optionally after running a ROM for a while
+    Memory(MemoryArgs),
+    /// Explains OP Codes, ex: `D123` or `8xy6`
+    Explain {
+        #[arg(required = true, value_name = "OPCODE")]
+        opcodes: Vec<String>,
+        /// Compatibility profile whose quirks are explained, ex: `cosmac-vip`
+        #[arg(long, value_name = "NAME", value_parser = parse_profile)]
+        profile: Option<Profile>,
+        /// Comma separated list of quirks explained, ex: `shift,load-store`. Overrides the
+        /// ones of the profile
+        #[arg(long, value_name = "LIST", value_parser = parse_quirks)]
+        quirks: Option<Quirks>,
+    },
+    /// Trims or pads ROM files
+    #[command(subcommand)]
+    Rom(RomCommand),
+    /// Assembles a source file into a ROM
+    Assemble {
+        /// Path of the source, ex: `box.asm`
+        input: String,
+        /// Path of the ROM written, ex: `box.ch8`
+        output: String,
+    },
+    /// Writes an HTML report of a ROM or a directory of ROMs
+    Report {
+        /// A ROM or a directory of ROMs
+        roms: String,
+        /// Directory the report is written to
+        output: String,
+    },
+    /// Compares two frames saved as PBM images. Exits with `2` if they differ
+    CompareFrames {
+        a: String,
+        b: String,
+        /// Writes a visual diff of the frames to this PNG image
+        #[arg(long, value_name = "FILE")]
+        diff: Option<String>,
+    },
+    /// Lists a directory of ROMs with their recommended profile and speed, and runs the ones
+    /// picked, remembering the settings adjusted
+    Browse(BrowseArgs),
+    /// Downloads ROM packs from a remote index. Needs the `network` feature
+    #[command(subcommand)]
+    Library(LibraryCommand),
+    /// Runs embedded ROMs and checks the backends, ex: before filing an issue
+    Selftest,
+}
+
+#[derive(Subcommand)]
+enum RomCommand {
+    /// Removes the trailing `0x00` bytes of a ROM
+    Trim { input: String, output: String },
+    /// Pads a ROM with `0x00` bytes up to a size
+    Pad {
+        input: String,
+        output: String,
+        /// Size in bytes, in decimal or hexadecimal with a `0x` prefix
+        #[arg(value_parser = parse_size)]
+        size: usize,
+        /// Profile whose memory must hold the ROM, ex: `xo-chip` for ROMs up to 65024 bytes
+        #[arg(long, value_name = "NAME", value_parser = parse_profile)]
+        profile: Option<Profile>,
+    },
+}
+
+#[derive(Subcommand)]
+enum LibraryCommand {
+    /// Lists the ROM packs of an index
+    List { index_url: String },
+    /// Downloads the missing or changed ROMs of a pack
+    Sync {
+        index_url: String,
+        pack: String,
+        /// Directory the pack is downloaded to
+        #[arg(long, value_name = "DIR", default_value = "roms")]
+        dir: String,
+    },
+}
+
+/// Arguments of the `memory` command
+#[derive(Args)]
+struct MemoryArgs {
+    /// Path or URL of the ROM
+    rom: String,
+    /// File to load when the ROM is a ZIP archive holding several ROMs
+    #[arg(long, value_name = "NAME")]
+    entry: Option<String>,
+    /// Range of memory dumped, ex: `200-2FF` or `3E0+20`
+    #[arg(value_name = "RANGE", value_parser = chip_8::parse_memory_range)]
+    range: Range<usize>,
+    /// Cycles executed before the dump
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    cycles: u64,
+    #[command(flatten)]
+    vm: VmArgs,
+}
+
+/// Settings of the VM shared by every frontend
+#[derive(Args)]
+struct VmArgs {
+    /// Compatibility profile, ex: `schip`. Sets the quirks and speed of the interpreter
+    /// it stands for, unless given
+    #[arg(long, value_name = "NAME", value_parser = parse_profile)]
+    profile: Option<Profile>,
+    /// Comma separated list of quirks, ex: `shift,load-store`
+    #[arg(long, value_name = "LIST", value_parser = parse_quirks)]
+    quirks: Option<Quirks>,
+    /// Actions fired when the game reaches a given state
+    #[arg(long, value_name = "FILE")]
+    triggers: Option<String>,
+    /// Cheats patching the memory or the registers on every frame. By default, the ones of
+    /// the `.cht` file next to the ROM if there's one, ex: `roms/BRIX.cht`
+    #[arg(long, value_name = "FILE")]
+    cheats: Option<String>,
+    /// Ignores the `.cht` file next to the ROM
+    #[arg(long, conflicts_with = "cheats")]
+    no_cheats: bool,
+    /// Rhai script called on the events of the VM, with access to its registers and memory
+    #[cfg(feature = "scripting")]
+    #[arg(long, value_name = "FILE")]
+    script: Option<String>,
+    /// Logs every instruction executed and the registers it changed to the standard error
+    #[arg(long)]
+    trace: bool,
+    /// Makes every instruction last the machine cycles it took on the COSMAC VIP,
+    /// instead of running a fixed number of instructions per frame
+    #[arg(long)]
+    vip_timing: bool,
+    /// Maximum number of nested subroutines, 16 by default, ex: 32 or 64 for ROMs
+    /// recursing deeper
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    stack_depth: Option<u32>,
+    /// How accesses beyond the end of memory are handled: `fault` (default), `wrap` or
+    /// `open-bus`
+    #[arg(long, value_name = "MODE", value_parser = parse_memory_bounds)]
+    memory_bounds: Option<MemoryBounds>,
+    /// Seed of the random numbers of `Cxkk`, so runs with the same inputs are identical.
+    /// Random by default
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+}
+
+impl VmArgs {
+    /// Returns the quirks given, or the ones of the profile
+    #[cfg(any(feature = "window", feature = "tui", feature = "framebuffer"))]
+    fn quirks(&self) -> Option<Quirks> {
+        self.quirks
+            .or_else(|| self.profile.map(|profile| profile.quirks()))
+    }
+
+    /// Returns the instructions per frame of the profile, or the standard ones
+    #[cfg(any(feature = "window", feature = "tui", feature = "framebuffer"))]
+    fn instructions_per_frame(&self) -> u32 {
+        self.profile.unwrap_or_default().instructions_per_frame()
+    }
+
+    /// Applies the cheats of the ROM at `rom` to `chip_8_vm`: the ones given, otherwise the
+    /// ones next to the ROM if any. Exits if the cheats can't be read.
+    fn apply_cheats(&self, chip_8_vm: &mut Chip8, rom: &str) {
+        let path = match &self.cheats {
+            Some(path) => PathBuf::from(path),
+            None if self.no_cheats => return,
+            None => match Cheats::path_for(rom) {
+                path if path.is_file() => path,
+                _ => return,
+            },
+        };
+        match Cheats::load(&path) {
+            Ok(cheats) => {
+                chip_8_vm.osd_mut().show(format!(
+                    "{} cheats loaded from {}",
+                    cheats.cheats().len(),
+                    path.display()
+                ));
+                chip_8_vm.set_cheats(cheats);
+            }
+            Err(msg) => {
+                println!("ERROR: {}", msg);
+                exit(1);
+            }
+        }
+    }
+
+    /// Applies the settings to `chip_8_vm`. Exits if the triggers or the script can't be read.
+    fn apply(&self, chip_8_vm: &mut Chip8) {
+        if let Some(profile) = self.profile {
+            chip_8_vm.apply_profile(profile);
+        }
+        if let Some(quirks) = self.quirks {
+            chip_8_vm.set_quirks(quirks);
+        }
+        chip_8_vm.set_vip_timing(self.vip_timing);
+        if let Some(depth) = self.stack_depth {
+            chip_8_vm.set_stack_depth(depth as usize);
+        }
+        if let Some(bounds) = self.memory_bounds {
+            chip_8_vm.set_memory_bounds(bounds);
+        }
+        if let Some(seed) = self.seed {
+            chip_8_vm.set_rng_seed(seed);
+        }
+        if let Some(path) = &self.triggers {
+            match Triggers::load(path) {
+                Ok(triggers) => chip_8_vm.set_triggers(triggers),
+                Err(msg) => {
+                    println!("ERROR: {}", msg);
+                    exit(1);
+                }
+            }
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(path) = &self.script {
+            match Script::load(path) {
+                Ok(script) => chip_8_vm.set_script(script),
+                Err(msg) => {
+                    println!("ERROR: {}", msg);
+                    exit(1);
+                }
+            }
+        }
+    }
+}
+
+/// Colors of the display
+#[derive(Args)]
+struct PaletteArgs {
+    /// Color palette, ex: `amber`
+    #[arg(long, value_name = "NAME", value_parser = parse_palette)]
+    palette: Option<Palette>,
+    /// Color of lit pixels, ex: `#00FF00`. Overrides the one of the palette
+    #[arg(long, value_name = "COLOR", value_parser = parse_rgb)]
+    fg: Option<Rgb>,
+    /// Color of the background. Overrides the one of the palette
+    #[arg(long, value_name = "COLOR", value_parser = parse_rgb)]
+    bg: Option<Rgb>,
+    /// Uses the high contrast variant of the palette
+    #[arg(long, overrides_with = "no_high_contrast")]
+    high_contrast: bool,
+    /// Uses the regular variant of the palette, ex: over `high_contrast` in the config file
+    #[arg(long, overrides_with = "high_contrast")]
+    no_high_contrast: bool,
+    /// Pulses the foreground color with the sound, dimmed while silent
+    #[arg(long)]
+    audio_reactive: bool,
+}
+
+impl PaletteArgs {
+    /// Returns the palette, classic by default, with the colors given overriding its own
+    #[cfg(any(feature = "window", feature = "tui", feature = "framebuffer"))]
+    fn palette(&self) -> Palette {
+        let palette = self.palette.unwrap_or_default();
+        if self.fg.is_none() && self.bg.is_none() {
+            return palette;
+        }
+        let colors = palette.colors(false);
+        Palette::Custom {
+            foreground: self.fg.unwrap_or(colors[1]),
+            background: self.bg.unwrap_or(colors[0]),
+        }
+    }
+}
+
+/// Tone of the beep
+#[derive(Args)]
+struct ToneArgs {
+    /// Frequency of the beep
+    #[arg(long, value_name = "HZ", value_parser = clap::value_parser!(u32).range(1..))]
+    tone: Option<u32>,
+    /// Volume of the beep, from 0 to 100
+    #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(..=100))]
+    volume: Option<u8>,
+    /// Wave of the beep: `square`, `triangle` or `sine`
+    #[arg(long, value_name = "NAME", value_parser = parse_waveform)]
+    waveform: Option<Waveform>,
+    /// WAV file played as the beep instead of the wave, looped while the beep lasts
+    #[arg(long, value_name = "FILE")]
+    beep_sample: Option<String>,
+}
+
+impl ToneArgs {
+    /// Returns the resulting `Tone`, if any option was given. Exits if the sample
+    /// can't be read.
+    fn tone(&self) -> Option<Tone> {
+        let waveform = match &self.beep_sample {
+            Some(path) => match WavSample::load(path) {
+                Ok(sample) => Some(Waveform::Sample(Arc::new(sample))),
+                Err(msg) => {
+                    println!("ERROR: {}", msg);
+                    exit(1);
+                }
+            },
+            None => self.waveform.clone(),
+        };
+        if self.tone.is_none() && self.volume.is_none() && waveform.is_none() {
+            return None;
+        }
+        Some(Tone {
+            frequency: self.tone.unwrap_or(Tone::DEFAULT.frequency),
+            volume: self.volume.unwrap_or(Tone::DEFAULT.volume),
+            waveform: waveform.unwrap_or(Tone::DEFAULT.waveform),
+        })
+    }
+}
+
+/// Limits that halt the execution. See `ExecutionLimits`
+#[derive(Args)]
+struct LimitArgs {
+    /// Halts after N instructions
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_cycles: Option<u64>,
+    /// Halts after SECS seconds
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    max_time: Option<u64>,
+    /// Halts when a frame draws more than N sprites
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    max_draws_per_frame: Option<u32>,
+}
+
+impl LimitArgs {
+    fn limits(&self) -> ExecutionLimits {
+        ExecutionLimits {
+            max_cycles: self.max_cycles,
+            max_wall_time: self.max_time.map(Duration::from_secs),
+            max_draws_per_frame: self.max_draws_per_frame,
+        }
+    }
+}
+
+/// Options of the `run` command, also used when no command is given
+#[derive(Args)]
+struct RunArgs {
+    /// Path or URL of the ROM, or of a directory of ROMs with `--kiosk`.
+    /// Offers a menu of embedded demos when not given
+    #[arg(conflicts_with = "playlist")]
+    rom: Option<String>,
+    /// File to load when the ROM is a ZIP archive holding several ROMs
+    #[arg(long, value_name = "NAME")]
+    entry: Option<String>,
+    /// Delay between instructions in milliseconds, predates `--instructions-per-frame`
+    #[arg(hide = true, value_name = "DELAY")]
+    delay: Option<u64>,
+    #[command(flatten)]
+    vm: VmArgs,
+    /// Instructions executed per frame, 11 by default
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    instructions_per_frame: Option<u32>,
+    /// Instructions executed per second, ex: 700. Alternative to `--instructions-per-frame`
+    #[arg(
+        long,
+        value_name = "HZ",
+        value_parser = clap::value_parser!(u32).range(1..),
+        overrides_with = "instructions_per_frame"
+    )]
+    speed: Option<u32>,
+    /// Doesn't apply the settings of known ROMs
+    #[arg(long, overrides_with = "rom_database")]
+    no_rom_database: bool,
+    /// Applies the settings of known ROMs, the default, ex: over `rom_database = false` in
+    /// the config file
+    #[arg(long, overrides_with = "no_rom_database")]
+    rom_database: bool,
+    /// Memory kept between runs of the ROM, like battery-backed RAM, ex: `3E0+20` for a
+    /// score table. Overrides the one of known ROMs
+    #[arg(long, value_name = "RANGE", value_parser = chip_8::parse_memory_range, conflicts_with = "no_rom_database")]
+    battery: Option<Range<usize>>,
+    /// How writes below `0x200` are handled
+    #[arg(long, value_name = "MODE", value_parser = parse_write_protection)]
+    write_protection: Option<WriteProtection>,
+    /// Binds a hotkey, ex: `quit=End`
+    #[arg(long, value_name = "ACTION=KEY")]
+    hotkey: Vec<String>,
+    /// Maps a keypad key to a host key, ex: `5=Space`
+    #[arg(long, value_name = "KEYPAD=KEY")]
+    key: Vec<String>,
+    /// Maps gamepad buttons to the keypad. Needs the `gamepad` feature
+    #[arg(long, value_name = "FILE")]
+    gamepad_map: Option<String>,
+    /// Comma separated keypad keys repeated while held down, ex: `5,6`
+    #[arg(long, value_name = "KEYS")]
+    turbo: Option<String>,
+    /// Presses per second of turbo keys
+    #[arg(
+        long,
+        value_name = "HZ",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "turbo"
+    )]
+    turbo_rate: Option<u32>,
+    /// Runs the ROMs listed in a playlist file
+    #[arg(long, value_name = "FILE")]
+    playlist: Option<String>,
+    /// Rotates the ROMs of a directory, or of the playlist, for unattended screens
+    #[arg(long)]
+    kiosk: bool,
+    /// Seconds without input before the next ROM of the kiosk
+    #[arg(
+        long,
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "kiosk"
+    )]
+    kiosk_idle: Option<u64>,
+    #[command(flatten)]
+    limits: LimitArgs,
+    /// Host pixels per pixel, 20 by default
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    scale: Option<u32>,
+    /// How the display is scaled to the window, `integer` by default
+    #[arg(long, value_name = "MODE", value_parser = parse_scaling)]
+    scaling: Option<Scaling>,
+    /// Visual filter imitating old screens: `crt`, `lcd` or `none` (by default)
+    #[arg(long, value_name = "NAME", value_parser = parse_filter)]
+    filter: Option<Filter>,
+    /// Blends the last frames to hide flicker: `max`, `average` or `off` (by default)
+    #[arg(long, value_name = "MODE", value_parser = parse_anti_flicker)]
+    anti_flicker: Option<AntiFlicker>,
+    /// Frames blended by `--anti-flicker`, 2 by default
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(2..=3))]
+    anti_flicker_frames: Option<u32>,
+    /// Waits for the vertical blank of the monitor to present frames, `off` by default
+    #[arg(long, value_name = "on|off", value_parser = parse_switch)]
+    vsync: Option<bool>,
+    /// Renders 1 of every N frames, adjusted automatically by default
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    frame_skip: Option<u32>,
+    #[command(flatten)]
+    palette: PaletteArgs,
+    /// Pauses while the window isn't focused
+    #[arg(long, overrides_with = "no_pause_on_focus_loss")]
+    pause_on_focus_loss: bool,
+    /// Keeps running while the window isn't focused, the default, ex: over
+    /// `pause_on_focus_loss` in the config file
+    #[arg(long, overrides_with = "pause_on_focus_loss")]
+    no_pause_on_focus_loss: bool,
+    /// Shows the on-screen keypad
+    #[arg(long, overrides_with = "no_show_keypad")]
+    show_keypad: bool,
+    /// Hides the on-screen keypad, the default, ex: over `show_keypad` in the config file
+    #[arg(long, overrides_with = "show_keypad")]
+    no_show_keypad: bool,
+    /// Measures the latency between key presses and the frames they change
+    #[arg(long)]
+    measure_latency: bool,
+    /// Measures the time spent executing each opcode family, and counts the executions
+    /// of each address. The report is printed on exit
+    #[arg(long)]
+    profile_instructions: bool,
+    /// Checks the invariants of the VM around every instruction
+    #[arg(long)]
+    strict: bool,
+    #[command(flatten)]
+    tone: ToneArgs,
+    /// Slot used by the save state hotkeys, 1 by default
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    save_slot: Option<u32>,
+    /// Restores the state of a slot after loading the ROM
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    load_slot: Option<u32>,
+    /// Lists the save slots of the ROM with a thumbnail of each, instead of running it
+    #[arg(long, requires = "rom")]
+    list_states: bool,
+    /// Seconds of frames kept for rewinding, 0 disables it
+    #[arg(long, value_name = "SECS")]
+    rewind: Option<u32>,
+    /// Records the hash of every frame to a file
+    #[arg(long, value_name = "FILE", conflicts_with = "verify_hashes")]
+    record_hashes: Option<String>,
+    /// Checks the hash of every frame against a file
+    #[arg(long, value_name = "FILE")]
+    verify_hashes: Option<String>,
+    /// Records the keys of every frame to a movie file, saved on exit
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["playback", "load_slot"])]
+    record_movie: Option<String>,
+    /// Replays the inputs of a movie recorded with `--record-movie`
+    #[arg(long, value_name = "FILE", conflicts_with = "load_slot")]
+    playback: Option<String>,
+    /// Config file, `chip8.toml` by default
+    #[arg(long, value_name = "FILE")]
+    config: Option<String>,
+}
+
+/// Options of the `tui` command
+#[derive(Args)]
+struct TuiArgs {
+    /// Path or URL of the ROM
+    rom: String,
+    /// File to load when the ROM is a ZIP archive holding several ROMs
+    #[arg(long, value_name = "NAME")]
+    entry: Option<String>,
+    #[command(flatten)]
+    vm: VmArgs,
+    /// Instructions executed per frame, 11 by default
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    instructions_per_frame: Option<u32>,
+    /// Instructions executed per second, ex: 700. Alternative to `--instructions-per-frame`
+    #[arg(
+        long,
+        value_name = "HZ",
+        value_parser = clap::value_parser!(u32).range(1..),
+        overrides_with = "instructions_per_frame"
+    )]
+    speed: Option<u32>,
+    /// Doesn't apply the settings of known ROMs
+    #[arg(long, overrides_with = "rom_database")]
+    no_rom_database: bool,
+    /// Applies the settings of known ROMs, the default, ex: over `rom_database = false` in
+    /// the config file
+    #[arg(long, overrides_with = "no_rom_database")]
+    rom_database: bool,
+    /// Memory kept between runs of the ROM, like battery-backed RAM, ex: `3E0+20` for a
+    /// score table. Overrides the one of known ROMs
+    #[arg(long, value_name = "RANGE", value_parser = chip_8::parse_memory_range, conflicts_with = "no_rom_database")]
+    battery: Option<Range<usize>>,
+    #[command(flatten)]
+    palette: PaletteArgs,
+    /// Config file, `chip8.toml` by default
+    #[arg(long, value_name = "FILE")]
+    config: Option<String>,
+}
+
+/// Options of the `headless` command
+#[derive(Args)]
+struct HeadlessArgs {
+    /// Path or URL of the ROM
+    rom: String,
+    /// File to load when the ROM is a ZIP archive holding several ROMs
+    #[arg(long, value_name = "NAME")]
+    entry: Option<String>,
+    #[command(flatten)]
+    limits: LimitArgs,
+    #[command(flatten)]
+    vm: VmArgs,
+    /// Instructions executed per second
+    #[arg(
+        long,
+        visible_alias = "cycle-rate",
+        value_name = "HZ",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    speed: Option<u32>,
+    /// How writes below `0x200` are handled
+    #[arg(long, value_name = "MODE", value_parser = parse_write_protection)]
+    write_protection: Option<WriteProtection>,
+    /// Measures the time spent executing each opcode family, and counts the executions
+    /// of each address. The report is printed on exit
+    #[arg(long)]
+    profile_instructions: bool,
+    /// Checks the invariants of the VM around every instruction
+    #[arg(long)]
+    strict: bool,
+    /// Writes the final frame to a PBM image instead of the standard output
+    #[arg(long, value_name = "FILE")]
+    frame: Option<String>,
+    /// Writes each plane of the final frame to PREFIX-1.pbm and PREFIX-2.pbm, and their
+    /// composite to PREFIX-composite.pgm, ex: to see which XO-CHIP plane a sprite landed on
+    #[arg(long, value_name = "PREFIX")]
+    planes: Option<String>,
+    /// Writes the sound to a WAV file
+    #[arg(long, value_name = "FILE")]
+    audio: Option<String>,
+    #[command(flatten)]
+    tone: ToneArgs,
+    /// Restores the state of a slot before running
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    load_slot: Option<u32>,
+    /// Saves the state to a slot once halted
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    save_slot: Option<u32>,
+    /// Replays the inputs of a movie recorded with `--record-movie`
+    #[arg(long, value_name = "FILE", conflicts_with = "load_slot")]
+    playback: Option<String>,
+}
+
+/// Options of the `browse` command
+#[derive(Args)]
+struct BrowseArgs {
+    /// Directory of the ROMs
+    #[arg(default_value = "roms")]
+    dir: String,
+    /// Config file the adjusted settings are saved to, `chip8.toml` by default.
+    /// Created if missing
+    #[arg(long, value_name = "FILE")]
+    config: Option<String>,
+}
+
+/// Options of the `dap` command
+#[derive(Args)]
+struct DapArgs {
+    /// Waits for a client on this TCP port instead of using stdin/stdout
+    #[arg(long)]
+    port: Option<u16>,
+    /// Path of the ROM, if not given by the client
+    rom: Option<String>,
+    #[command(flatten)]
+    vm: VmArgs,
+}
+
+/// Finds a `Profile` by name, listing the available ones otherwise
+fn parse_profile(name: &str) -> Result<Profile, String> {
+    Profile::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Profile::ALL.iter().map(|p| p.name()).collect();
+        format!("available profiles: {}", names.join(", "))
+    })
+}
+
+/// Finds a synthesized `Waveform` by name, listing the available ones otherwise
+fn parse_waveform(name: &str) -> Result<Waveform, String> {
+    Waveform::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Waveform::SYNTHESIZED.iter().map(|w| w.name()).collect();
+        format!("available waveforms: {}", names.join(", "))
+    })
+}
+
+/// Parses a list of quirks, listing the available ones if any is unknown
+fn parse_quirks(list: &str) -> Result<Quirks, String> {
+    Quirks::parse(list).map_err(|msg| {
+        let names: Vec<&str> = Quirk::ALL.iter().map(|q| q.name()).collect();
+        format!("{} Available quirks: {}", msg, names.join(", "))
+    })
+}
+
+/// Finds a `Palette` by name, listing the available ones otherwise
+fn parse_palette(name: &str) -> Result<Palette, String> {
+    Palette::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Palette::ALL.iter().map(|p| p.name()).collect();
+        format!("available palettes: {}", names.join(", "))
+    })
+}
+
+/// Finds a `WriteProtection` by name, listing the available ones otherwise
+fn parse_write_protection(name: &str) -> Result<WriteProtection, String> {
+    WriteProtection::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = WriteProtection::ALL.iter().map(|m| m.name()).collect();
+        format!("available modes: {}", names.join(", "))
+    })
+}
+
+/// Finds a `MemoryBounds` by name, listing the available ones otherwise
+fn parse_memory_bounds(name: &str) -> Result<MemoryBounds, String> {
+    MemoryBounds::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = MemoryBounds::ALL.iter().map(|m| m.name()).collect();
+        format!("available modes: {}", names.join(", "))
+    })
+}
+
+/// Parses the name of a `Scaling` mode
+fn parse_scaling(name: &str) -> Result<Scaling, String> {
+    Scaling::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Scaling::ALL.iter().map(|m| m.name()).collect();
+        format!("available modes: {}", names.join(", "))
+    })
+}
+
+/// Parses the name of an `AntiFlicker` mode
+fn parse_anti_flicker(name: &str) -> Result<AntiFlicker, String> {
+    AntiFlicker::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = AntiFlicker::ALL.iter().map(|m| m.name()).collect();
+        format!("available modes: {}", names.join(", "))
+    })
+}
+
+/// Parses the name of a `Filter`
+fn parse_filter(name: &str) -> Result<Filter, String> {
+    Filter::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Filter::ALL.iter().map(|f| f.name()).collect();
+        format!("available filters: {}", names.join(", "))
+    })
+}
+
+/// Parses `on` or `off`
+fn parse_switch(text: &str) -> Result<bool, String> {
+    match text {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(String::from("expected on or off")),
+    }
+}
+
+/// Parses a size in decimal or `0x` prefixed hexadecimal notation
+fn parse_size(text: &str) -> Result<usize, String> {
+    let parsed = match text.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| format!("Invalid size '{}'.", text))
+}
+
+/// Parses the command line `args`. Prints the help or the version and exits if requested,
+/// or exits with `1` if the arguments are invalid.
+fn parse_cli(args: &[String]) -> Cli {
+    Cli::try_parse_from(args).unwrap_or_else(|error| {
+        let _ = error.print();
+        exit(if error.use_stderr() { 1 } else { 0 });
+    })
+}
+
+/// Returns the settings of the config file at `path`, or of `chip8.toml` if present, as
+/// command line options. See `config_options`.
+/// Exits if the config can't be read.
+fn config_args(path: Option<&str>, window: bool) -> Vec<String> {
+    if path.is_none() && !Path::new(DEFAULT_CONFIG_PATH).is_file() {
+        return Vec::new();
+    }
+    match Config::load(path.unwrap_or(DEFAULT_CONFIG_PATH)) {
+        Ok(config) => config_options(&config, window),
+        Err(msg) => {
+            println!("ERROR: {}", msg);
+            exit(1);
+        }
+    }
+}
+
+/// Returns the settings of `config` as command line options, placed before the ones of
+/// the command line so these take precedence. Switches are written as their option or
+/// its `--no-` counterpart, so either can be overridden. Settings of the window are
+/// skipped unless `window`
+fn config_options(config: &Config, window: bool) -> Vec<String> {
+    let (video, emulation, audio) = (&config.video, &config.emulation, &config.audio);
+    let flag = |enabled: Option<bool>, name: &'static str| {
+        enabled.map(|enabled| match enabled {
+            true => format!("--{}", name),
+            false => format!("--no-{}", name),
+        })
+    };
+    let value = |value: Option<u32>| value.map(|value| Some(value.to_string()));
+    let mut options: Vec<(&str, Option<Option<String>>)> = vec![
+        ("--profile", emulation.profile.clone().map(Some)),
+        (
+            "--quirks",
+            emulation.quirks.as_ref().map(|q| Some(q.join(","))),
+        ),
+        (
+            "--instructions-per-frame",
+            value(emulation.instructions_per_frame),
+        ),
+        ("--stack-depth", value(emulation.stack_depth)),
+        ("--memory-bounds", emulation.memory_bounds.clone().map(Some)),
+        ("--palette", video.palette.clone().map(Some)),
+        ("--fg", video.fg.clone().map(Some)),
+        ("--bg", video.bg.clone().map(Some)),
+    ];
+    let mut switches = vec![
+        flag(emulation.rom_database, "rom-database"),
+        flag(video.high_contrast, "high-contrast"),
+    ];
+    if window {
+        options.extend(vec![
+            ("--rewind", value(emulation.rewind)),
+            ("--scale", value(video.scale)),
+            ("--scaling", video.scaling.clone().map(Some)),
+            ("--filter", video.filter.clone().map(Some)),
+            ("--anti-flicker", video.anti_flicker.clone().map(Some)),
+            ("--anti-flicker-frames", value(video.anti_flicker_frames)),
+            (
+                "--vsync",
+                video
+                    .vsync
+                    .map(|on| Some(String::from(if on { "on" } else { "off" }))),
+            ),
+            ("--frame-skip", value(video.frame_skip)),
+            ("--tone", value(audio.tone)),
+            ("--volume", value(audio.volume)),
+            ("--waveform", audio.waveform.clone().map(Some)),
+            ("--beep-sample", audio.sample.clone().map(Some)),
+        ]);
+        switches.extend(vec![
+            flag(video.show_keypad, "show-keypad"),
+            flag(video.pause_on_focus_loss, "pause-on-focus-loss"),
+        ]);
+    }
+
+    let mut args = Vec::new();
+    for (name, setting) in options {
+        if let Some(value) = setting {
+            args.push(name.to_string());
+            args.extend(value);
+        }
+    }
+    args.extend(switches.into_iter().flatten());
+    if window {
+        for (keypad_key, key) in &config.input.keys {
+            args.push(String::from("--key"));
+            args.push(format!("{:X}={}", keypad_key, key));
+        }
+        for (action, key) in &config.input.hotkeys {
+            args.push(String::from("--hotkey"));
+            args.push(format!("{}={}", action, key));
+        }
+    }
+    args
+}
+
+/// Runs the `rom` command
+/// # Returns
+/// A message describing the result.
+fn run_rom_command(command: RomCommand) -> Result<String, String> {
+    let (input, output) = match &command {
+        RomCommand::Trim { input, output } | RomCommand::Pad { input, output, .. } => {
+            (input, output)
+        }
+    };
+    let content = fs::read(input).map_err(|e| format!("Failed to open the ROM: {}", e))?;
+
+    let result = match command {
+        RomCommand::Trim { .. } => rom::trim(&content).to_vec(),
+        RomCommand::Pad { size, profile, .. } => {
+            rom::pad(&content, size, profile.unwrap_or_default())?
+        }
+    };
+
+    fs::write(output, &result).map_err(|e| format!("Failed to write the ROM: {}", e))?;
+    Ok(format!(
+        "{} bytes were read, {} bytes were written to {}.",
+        content.len(),
+        result.len(),
+        output
+    ))
+}
+
+/// Reads the ROM at `path`. `path` can also be a directory,
+/// in which case all files inside it are read, sorted by name.
+/// # Returns
+/// The path and content of every ROM read.
+fn read_roms(path: &str) -> io::Result<Vec<(String, Vec<u8>)>> {
+    if !Path::new(path).is_dir() {
+        return Ok(vec![(path.to_string(), fs::read(path)?)]);
+    }
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    paths.retain(|path| path.is_file());
+    paths.sort();
+    if paths.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "The directory contains no ROMs",
+        ));
+    }
+
+    paths
+        .into_iter()
+        .map(|path| Ok((path.display().to_string(), fs::read(&path)?)))
+        .collect()
+}
+
+/// Runs the `assemble` command, assembling the source at `input` into the ROM `output`.
+/// See `chip_8::asm`
+/// # Returns
+/// The size of the ROM, in bytes.
+fn run_assemble(input: &str, output: &str) -> Result<usize, String> {
+    let source =
+        fs::read_to_string(input).map_err(|e| format!("Failed to open the source: {}", e))?;
+    let rom = chip_8::asm::assemble(&source)?;
+    fs::write(output, &rom).map_err(|e| format!("Failed to write the ROM: {}", e))?;
+    Ok(rom.len())
+}
+
+/// Runs the `report` command, writing the HTML report of the ROMs at `path`
+/// (a ROM or a directory of ROMs) to the `output` directory
+/// # Returns
+/// The number of ROMs in the report.
+fn run_report(path: &str, output: &str) -> Result<usize, String> {
+    let roms = read_roms(path).map_err(|e| format!("Failed to open the ROMs: {}", e))?;
+    let roms: Vec<(String, Vec<u8>)> = roms
+        .into_iter()
+        .map(|(path, content)| {
+            let name = Path::new(&path)
+                .file_name()
+                .map_or(path.clone(), |name| name.to_string_lossy().to_string());
+            (name, content)
+        })
+        .collect();
+    report::write_report(&roms, Path::new(output))
+}
+
+/// Runs the `disasm` command, disassembling the ROM at `path` linearly from the load address
+/// into source the `assemble` command turns back into the ROM. Jump and call targets are
+/// preceded by an empty line
+fn run_disasm(path: &str, entry: Option<&str>) -> Result<(), String> {
+    let content = rom::load(path, entry)?;
+    let analysis = RomAnalysis::new(path, &content);
+    for (address, line) in chip_8::disassemble_rom(&content) {
+        if analysis.labels.contains(&address) {
+            println!();
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Runs the `memory` command, returning the hex dump
+fn run_memory(args: MemoryArgs) -> Result<String, String> {
+    let content = rom::load(&args.rom, args.entry.as_deref())?;
+    let mut chip_8_vm = Chip8::new();
+    args.vm.apply(&mut chip_8_vm);
+    chip_8_vm
+        .load_rom_content(content)
+        .map_err(|e| e.to_string())?;
+    args.vm.apply_cheats(&mut chip_8_vm, &args.rom);
+    if args.cycles > 0 {
+        let limits = ExecutionLimits {
+            max_cycles: Some(args.cycles),
+            ..ExecutionLimits::default()
+        };
+        chip_8_vm
+            .run_until_halted(limits)
+            .map_err(|e| e.to_string())?;
+    }
+    chip_8_vm.hex_dump(args.range)
+}
+
+/// Runs the `info` command, printing the size, hashes, platform, instruction histogram,
+/// unknown OP Codes and settings known for the ROM at `path`
+fn run_info(path: &str, entry: Option<&str>) -> Result<(), String> {
+    let content = rom::load(path, entry)?;
+    let analysis = RomAnalysis::new(path, &content);
+    let settings = RomDatabase::embedded().lookup(&content).cloned();
+    let title = settings
+        .as_ref()
+        .and_then(|settings| settings.title.clone());
+
+    println!("Title: {}", title.as_deref().unwrap_or("Unknown"));
+    println!("Size: {} bytes", analysis.size);
+    println!("SHA-1: {}", chip_8::rom_sha1(&content));
+    println!("CRC-32: {:08x}", analysis.crc32);
+    let platform = analysis.platform;
+    println!(
+        "Platform: {} (--profile {})",
+        platform.name(),
+        platform.profile().name()
+    );
+    println!(
+        "Instructions: {} of {} OP Codes",
+        analysis.instructions(),
+        analysis.listing.len()
+    );
+    if let Some(quirks) = settings.as_ref().and_then(|settings| settings.quirks) {
+        let names: Vec<&str> = Quirk::ALL
+            .iter()
+            .filter(|&&quirk| quirks.is_enabled(quirk))
+            .map(|quirk| quirk.name())
+            .collect();
+        println!("Quirks: {}", names.join(", "));
+    }
+    if let Some(instructions) = settings.and_then(|settings| settings.instructions_per_frame) {
+        println!("Instructions per frame: {}", instructions);
+    }
+
+    let mut families: Vec<(&&str, &usize)> = analysis.families.iter().collect();
+    families.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    let most = families.first().map_or(1, |(_, &count)| count);
+    println!("\nInstruction histogram:");
+    for (family, &count) in families {
+        let bar = (count * HISTOGRAM_WIDTH).div_ceil(most);
+        println!("  {:<5} {:>5} {}", family, count, "#".repeat(bar));
+    }
+
+    if !analysis.unknown.is_empty() {
+        println!("\nUnknown OP Codes:");
+    }
+    for &(address, opcode) in analysis.unknown.iter() {
+        match Platform::of(opcode) {
+            Platform::Chip8 => println!("  {}", chip_8::disassemble(address, opcode)),
+            platform => println!(
+                "  {}  ({})",
+                chip_8::disassemble(address, opcode),
+                platform.name()
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Prints the save slots of the ROM of `args`, with the thumbnail of the state each holds
+fn list_states(args: &RunArgs) -> Result<(), String> {
+    let path = args.rom.as_deref().unwrap_or_default();
+    let mut chip_8_vm = Chip8::new();
+    if let Some(profile) = args.vm.profile {
+        chip_8_vm.apply_profile(profile);
+    }
+    chip_8_vm
+        .load_rom_content(rom::load(path, args.entry.as_deref())?)
+        .map_err(|e| e.to_string())?;
+    chip_8_vm.set_save_storage(Box::new(FileStorage::new(".")), 1);
+
+    let slots = chip_8_vm.saved_slots()?;
+    if slots.is_empty() {
+        println!("No save states of {}.", path);
+    }
+    for slot in slots {
+        println!("Slot {} ({}):", slot, chip_8_vm.save_slot_key(slot));
+        match chip_8_vm.slot_thumbnail(slot)? {
+            Some(png) => print!(
+                "{}",
+                chip_8::thumbnail_text(&chip_8::decode_thumbnail(&png)?)
+            ),
+            None => println!("No thumbnail, saved before thumbnails were added."),
+        }
+    }
+    Ok(())
+}
+
+/// A ROM listed by the `browse` command, with the settings it runs with
+struct BrowserEntry {
+    path: String,
+    sha1: String,
+    title: String,
+    profile: Profile,
+    instructions_per_frame: u32,
+    /// Whether the settings were adjusted by the user, instead of recommended
+    adjusted: bool,
+}
+
+impl BrowserEntry {
+    /// Returns the settings of the ROM at `path`: the ones adjusted by the user in `config`,
+    /// or else the profile of its platform, at the speed known by the `database` or
+    /// expected by the profile
+    fn new(path: &str, rom: &[u8], database: &RomDatabase, config: &Config) -> BrowserEntry {
+        let sha1 = chip_8::rom_sha1(rom);
+        let known = database.lookup(rom);
+        let adjusted = config.rom(&sha1).cloned().unwrap_or_default();
+        let profile = adjusted
+            .profile
+            .as_deref()
+            .and_then(Profile::from_name)
+            .unwrap_or_else(|| RomAnalysis::new(path, rom).platform.profile());
+        let instructions_per_frame = adjusted
+            .instructions_per_frame
+            .or(known.and_then(|settings| settings.instructions_per_frame))
+            .unwrap_or(profile.instructions_per_frame());
+        let title = known
+            .and_then(|settings| settings.title.clone())
+            .or(Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string()))
+            .unwrap_or(path.to_string());
+        BrowserEntry {
+            path: path.to_string(),
+            sha1,
+            title,
+            profile,
+            instructions_per_frame,
+            adjusted: adjusted != RomConfig::default(),
+        }
+    }
+}
+
+/// ROM picked in the `browse` command, ex: `3 speed=fast profile=schip`, with the settings
+/// overridden for this run and the next ones
+#[derive(Debug, PartialEq, Eq)]
+struct BrowserChoice {
+    /// Number of the ROM in the list, from `1`
+    number: usize,
+    profile: Option<Profile>,
+    instructions_per_frame: Option<u32>,
+}
+
+/// Parses the ROM picked in the `browse` command: its number followed by any of
+/// `profile=NAME`, `speed=CLASS` or `ipf=N`
+fn parse_browser_choice(line: &str) -> Result<BrowserChoice, String> {
+    let mut words = line.split_whitespace();
+    let number = words
+        .next()
+        .and_then(|number| number.parse::<usize>().ok())
+        .ok_or(format!("Invalid ROM number in '{}'.", line.trim()))?;
+    let mut choice = BrowserChoice {
+        number,
+        profile: None,
+        instructions_per_frame: None,
+    };
+    for word in words {
+        match word.split_once('=') {
+            Some(("profile", name)) => choice.profile = Some(parse_profile(name)?),
+            Some(("speed", name)) => {
+                let class = SpeedClass::from_name(name).ok_or_else(|| {
+                    let names: Vec<&str> = SpeedClass::ALL.iter().map(|c| c.name()).collect();
+                    format!("available speeds: {}", names.join(", "))
+                })?;
+                choice.instructions_per_frame = Some(class.instructions_per_frame());
+            }
+            Some(("ipf", instructions)) => {
+                choice.instructions_per_frame = Some(
+                    instructions
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|&instructions| instructions > 0)
+                        .ok_or(format!(
+                            "Invalid instructions per frame '{}'.",
+                            instructions
+                        ))?,
+                )
+            }
+            _ => return Err(format!("Unknown setting '{}'.", word)),
+        }
+    }
+    Ok(choice)
+}
+
+/// Runs the `browse` command: lists the ROMs of a directory with the profile and speed
+/// class they run with, and runs the ones picked until an empty line is entered.
+/// Settings adjusted when picking a ROM are saved to the config, see `config::save_rom`
+fn run_browser(args: BrowseArgs) -> Result<(), String> {
+    let config_path = args
+        .config
+        .clone()
+        .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+    let (mut config_storage, config_key) = config::file_storage(&config_path)?;
+    // The config is created along with the first settings adjusted
+    let load_config = |storage: &FileStorage| match storage.load(&config_key) {
+        Ok(None) => Ok(Config::default()),
+        _ => Config::load_from(storage, &config_key),
+    };
+    let roms = read_roms(&args.dir).map_err(|e| format!("Failed to open the ROMs: {}", e))?;
+    let database = RomDatabase::embedded();
+
+    loop {
+        let config = load_config(&config_storage)?;
+        let entries: Vec<BrowserEntry> = roms
+            .iter()
+            .map(|(path, rom)| BrowserEntry::new(path, rom, &database, &config))
+            .collect();
+        for (number, entry) in entries.iter().enumerate() {
+            println!(
+                "{:>3}. {:<32} {:<12} {:<7} ({} ipf){}",
+                number + 1,
+                entry.title,
+                entry.profile.name(),
+                SpeedClass::of(entry.instructions_per_frame).name(),
+                entry.instructions_per_frame,
+                if entry.adjusted { " *" } else { "" }
+            );
+        }
+        println!("* Adjusted, saved in {}", config_path);
+        print!("ROM to run, ex: `1 speed=fast profile=schip`, or nothing to quit: ");
+        io::stdout().flush().map_err(|e| e.to_string())?;
+
+        let mut line = String::new();
+        io::stdin()
+            .read_line(&mut line)
+            .map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        let choice = match parse_browser_choice(&line) {
+            Ok(choice) => choice,
+            Err(msg) => {
+                println!("ERROR: {}", msg);
+                continue;
+            }
+        };
+        let entry = match choice.number.checked_sub(1).and_then(|i| entries.get(i)) {
+            Some(entry) => entry,
+            None => {
+                println!("ERROR: There's no ROM {}.", choice.number);
+                continue;
+            }
+        };
+
+        if choice.profile.is_some() || choice.instructions_per_frame.is_some() {
+            let adjusted = RomConfig {
+                profile: choice.profile.map(|profile| profile.name().to_string()),
+                instructions_per_frame: choice.instructions_per_frame,
+            };
+            config::save_rom(&mut config_storage, &config_key, &entry.sha1, &adjusted)?;
+        }
+        let profile = choice.profile.unwrap_or(entry.profile);
+        let instructions = choice
+            .instructions_per_frame
+            .unwrap_or(entry.instructions_per_frame);
+        let mut command_line = vec![
+            String::from("chip8"),
+            String::from("run"),
+            entry.path.clone(),
+            String::from("--profile"),
+            profile.name().to_string(),
+            String::from("--instructions-per-frame"),
+            instructions.to_string(),
+            String::from("--config"),
+            config_path.clone(),
+        ];
+        command_line.splice(2..2, config_options(&load_config(&config_storage)?, true));
+        let run = Cli::try_parse_from(command_line).map_err(|e| e.to_string())?;
+        if let Some(Command::Run(run)) = run.command {
+            run_window(*run);
+        }
+    }
+}
+
+/// Runs the `compare-frames` command, printing every differing pixel
+/// and writing a visual diff to `diff_path`, if any
+/// # Returns
+/// `true` if both frames are identical.
+fn compare_frames(a_path: &str, b_path: &str, diff_path: Option<&str>) -> Result<bool, String> {
+    let read = |path: &str| {
+        fs::read(path)
+            .map_err(|e| format!("Failed to open {}: {}", path, e))
+            .and_then(|content| Frame::parse_pbm(&content))
+    };
+    let (a, b) = (read(a_path)?, read(b_path)?);
+
+    let diff = a.diff(&b)?;
+    for pixel in &diff {
+        println!("{}", pixel);
+    }
+    if !diff.is_empty() {
+        println!("{} pixels differ.", diff.len());
+    }
+
+    if let Some(diff_path) = diff_path {
+        fs::write(diff_path, a.diff_png(&b, DIFF_SCALE)?)
+            .map_err(|e| format!("Failed to write the diff image: {}", e))?;
+    }
+    Ok(diff.is_empty())
+}
+
+/// Prints the context of a strict spec violation, on top of the message of the fault.
+/// See `Chip8::fault_context`
+fn print_fault_context(chip_8_vm: &Chip8, error: &Chip8Error) {
+    if let Chip8Error::StrictViolation { .. } = error {
+        eprintln!("{}", chip_8_vm.fault_context());
+    }
+}
+
+/// Stores the battery-backed memory of the ROM running when a frontend exits
+#[cfg(any(feature = "window", feature = "tui", feature = "framebuffer"))]
+fn store_battery(chip_8_vm: &mut Chip8) {
+    if let Err(msg) = chip_8_vm.store_battery() {
+        println!("ERROR: {}", msg);
+    }
+}
+
+/// Prints the error of a command and exits with `1`
+fn exit_on_error<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|msg| {
+        println!("ERROR: {}", msg);
+        exit(1);
+    })
+}
 
 fn main() {
-    let executable_name = env::args().nth(0).unwrap();
+    let mut args: Vec<String> = env::args().collect();
+    let mut cli = parse_cli(&args);
+
+    // Settings of the config file go right before the ones of the command line,
+    // which override them
+    let config = match &cli.command {
+        None => Some((cli.run.config.clone(), 1, true)),
+        Some(Command::Run(run)) => Some((run.config.clone(), 2, true)),
+        Some(Command::Tui(tui)) => Some((tui.config.clone(), 2, false)),
+        Some(_) => None,
+    };
+    if let Some((path, position, window)) = config {
+        let options = config_args(path.as_deref(), window);
+        if !options.is_empty() {
+            args.splice(position..position, options);
+            cli = parse_cli(&args);
+        }
+    }
+
+    let trace = match &cli.command {
+        None => cli.run.vm.trace,
+        Some(Command::Run(run)) => run.vm.trace,
+        Some(Command::Tui(tui)) => tui.vm.trace,
+        Some(Command::Headless(headless)) => headless.vm.trace,
+        Some(Command::Memory(memory)) => memory.vm.trace,
+        Some(_) => false,
+    };
+    init_logger(trace);
+
+    match cli.command {
+        None if cli.run.list_states => exit_on_error(list_states(&cli.run)),
+        None => run_window(cli.run),
+        Some(Command::Run(run)) if run.list_states => exit_on_error(list_states(&run)),
+        Some(Command::Run(run)) => run_window(*run),
+        Some(Command::Tui(tui)) => run_tui(tui),
+        Some(Command::Headless(headless)) => run_headless(headless),
+        Some(Command::Dap(dap)) => run_dap(dap),
+        Some(Command::Disasm { rom, entry }) => exit_on_error(run_disasm(&rom, entry.as_deref())),
+        Some(Command::Info { rom, entry }) => exit_on_error(run_info(&rom, entry.as_deref())),
+        Some(Command::Memory(memory)) => println!("{}", exit_on_error(run_memory(memory))),
+        Some(Command::Explain {
+            opcodes,
+            profile,
+            quirks,
+        }) => {
+            let quirks = quirks.unwrap_or_else(|| profile.unwrap_or_default().quirks());
+            for opcode in opcodes {
+                println!("{}", exit_on_error(chip_8::explain(&opcode, quirks)));
+            }
+        }
+        Some(Command::Rom(command)) => println!("{}", exit_on_error(run_rom_command(command))),
+        Some(Command::Assemble { input, output }) => {
+            let size = exit_on_error(run_assemble(&input, &output));
+            println!("Assembled {} bytes to {}", size, output);
+        }
+        Some(Command::Report { roms, output }) => {
+            let count = exit_on_error(run_report(&roms, &output));
+            println!("Report of {} ROMs written to {}", count, output);
+        }
+        Some(Command::CompareFrames { a, b, diff }) => {
+            match exit_on_error(compare_frames(&a, &b, diff.as_deref())) {
+                true => println!("Frames are identical."),
+                false => exit(2),
+            }
+        }
+        Some(Command::Browse(browse)) => exit_on_error(run_browser(browse)),
+        Some(Command::Library(command)) => exit_on_error(run_library_command(command)),
+        Some(Command::Selftest) => run_self_test(),
+    }
+}
+
+/// Logs the messages enabled by `RUST_LOG` to the standard error, ex: `RUST_LOG=trace`.
+/// `trace` enables the trace of the instructions executed as well
+fn init_logger(trace: bool) {
+    let mut builder = env_logger::Builder::from_default_env();
+    if trace {
+        builder.filter_module("chip8", log::LevelFilter::Trace);
+    }
+    builder.init();
+}
+
+/// Runs the `library` command
+#[cfg(feature = "network")]
+fn run_library_command(command: LibraryCommand) -> Result<(), String> {
+    match command {
+        LibraryCommand::List { index_url } => {
+            for pack in library::fetch_index(&index_url)? {
+                println!(
+                    "{} ({} ROMs): {}",
+                    pack.name,
+                    pack.roms.len(),
+                    pack.description
+                );
+            }
+        }
+        LibraryCommand::Sync {
+            index_url,
+            pack: name,
+            dir,
+        } => {
+            let packs = library::fetch_index(&index_url)?;
+            let pack = packs
+                .iter()
+                .find(|pack| pack.name == name)
+                .ok_or(format!("Unknown ROM pack '{}'.", name))?;
+            let report = library::sync_pack(pack, Path::new(&dir))?;
+            for rom in report.downloaded.iter() {
+                println!("Downloaded {}", rom);
+            }
+            println!(
+                "{} ROMs downloaded, {} already up to date in {}/{}.",
+                report.downloaded.len(),
+                report.up_to_date.len(),
+                dir,
+                pack.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Runs the `library` command. Only available with the `network` feature
+#[cfg(not(feature = "network"))]
+fn run_library_command(_command: LibraryCommand) -> Result<(), String> {
+    Err(String::from("This build has no network support."))
+}
+
+/// Runs the emulator as a debug adapter
+#[cfg(feature = "window")]
+fn run_dap(args: DapArgs) {
     let mut chip_8_vm = Chip8::new();
+    chip_8_vm.set_instructions_per_frame(args.vm.instructions_per_frame());
+    args.vm.apply(&mut chip_8_vm);
+    let on_loaded = |chip_8_vm: &mut Chip8, path: &str| args.vm.apply_cheats(chip_8_vm, path);
+    if let Err(msg) = dap::run(args.port, args.rom.clone(), chip_8_vm, on_loaded) {
+        eprintln!("ERROR: {}", msg);
+        exit(1);
+    }
+}
 
-    let path = match env::args().nth(1) {
-        None => {
-            println!("ERROR: No ROM provided.");
-            println!("Usage: {} (path-to-your-rom)", executable_name);
+/// Runs the emulator as a debug adapter. Only available with the `window` feature
+#[cfg(not(feature = "window"))]
+fn run_dap(_args: DapArgs) {
+    println!("ERROR: This build has no window support.");
+    exit(1);
+}
+
+/// Checks that the video backend can be initialized. Skipped without the `window` feature
+fn check_video_backend() -> SelfTestResult {
+    #[cfg(feature = "window")]
+    let (status, detail) = match chip_8::check_video_backend() {
+        Ok(()) => (SelfTestStatus::Passed, String::from("a window was created")),
+        Err(msg) => (SelfTestStatus::Failed, msg),
+    };
+    #[cfg(not(feature = "window"))]
+    let (status, detail) = (
+        SelfTestStatus::Skipped,
+        String::from("this build has no window support"),
+    );
+
+    SelfTestResult {
+        name: "Video backend",
+        status,
+        detail,
+    }
+}
+
+/// Checks that the audio backend can be initialized, by opening the default output device.
+/// Skipped without the `sound` feature
+fn check_audio_backend() -> SelfTestResult {
+    #[cfg(feature = "sound")]
+    let (status, detail) = match Beeper::open(Tone::DEFAULT) {
+        Ok(_) => (
+            SelfTestStatus::Passed,
+            String::from("the output device was opened"),
+        ),
+        Err(msg) => (SelfTestStatus::Failed, msg),
+    };
+    #[cfg(not(feature = "sound"))]
+    let (status, detail) = (
+        SelfTestStatus::Skipped,
+        String::from("this build has no audio output"),
+    );
+
+    SelfTestResult {
+        name: "Audio backend",
+        status,
+        detail,
+    }
+}
+
+/// Runs the embedded ROMs and checks the backends, printing a summary.
+/// Exits with `1` if any check failed.
+fn run_self_test() {
+    println!("Chip 8 {} self-test", env!("CARGO_PKG_VERSION"));
+
+    let mut results = chip_8::self_test();
+    results.push(check_video_backend());
+    results.push(check_audio_backend());
+
+    for result in results.iter() {
+        let status = match result.status {
+            SelfTestStatus::Passed => "PASS",
+            SelfTestStatus::Failed => "FAIL",
+            SelfTestStatus::Skipped => "SKIP",
+        };
+        println!("{}  {}: {}", status, result.name, result.detail);
+    }
+
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    let failed = count(SelfTestStatus::Failed);
+    println!(
+        "{} passed, {} failed, {} skipped",
+        count(SelfTestStatus::Passed),
+        failed,
+        count(SelfTestStatus::Skipped)
+    );
+    if failed > 0 {
+        exit(1);
+    }
+}
+
+/// Runs a ROM without any window until an `ExecutionLimits` halts it,
+/// ex: for grading services or sandboxes. The final frame is written as a PBM image.
+/// # Exit codes
+/// * `0`: The maximum amount of cycles was reached, or the ROM exited with `00FD`.
+/// * `1`: Invalid arguments or ROM.
+/// * `2`: The maximum wall time was reached.
+/// * `3`: The maximum draws per frame were exceeded.
+/// * `4`: An instruction faulted. See `Chip8Error`
+fn run_headless(args: HeadlessArgs) {
+    let limits = args.limits.limits();
+    if limits.max_cycles.is_none() && limits.max_wall_time.is_none() {
+        println!("ERROR: Headless runs need --max-cycles or --max-time.");
+        exit(1);
+    }
+
+    let mut chip_8_vm = Chip8::new();
+    args.vm.apply(&mut chip_8_vm);
+    if let Some(mode) = args.write_protection {
+        chip_8_vm.set_write_protection(mode);
+    }
+    if args.profile_instructions {
+        chip_8_vm.enable_profiler();
+    }
+    chip_8_vm.set_strict(args.strict);
+    if let Some(cycle_rate) = args.speed {
+        chip_8_vm.set_cycle_rate(cycle_rate);
+    }
+    if let Some(tone) = args.tone.tone() {
+        chip_8_vm.set_tone(tone);
+    }
+    if args.audio.is_some() {
+        chip_8_vm.enable_audio_track();
+    }
+    chip_8_vm.set_save_storage(Box::new(FileStorage::new(".")), args.save_slot.unwrap_or(1));
+
+    let loaded = rom::load(&args.rom, args.entry.as_deref()).and_then(|content| {
+        chip_8_vm
+            .load_rom_content(content)
+            .map_err(|e| e.to_string())
+    });
+    if let Err(msg) = loaded {
+        println!("ERROR: {}", msg);
+        exit(1);
+    }
+    args.vm.apply_cheats(&mut chip_8_vm, &args.rom);
+    if let Some(slot) = args.load_slot {
+        if let Err(msg) = chip_8_vm.load_from_slot(slot) {
+            println!("ERROR: {}", msg);
+            exit(1);
+        }
+    }
+    if let Some(path) = &args.playback {
+        if let Err(msg) = Movie::load(path).and_then(|movie| chip_8_vm.play_movie(movie)) {
+            println!("ERROR: {}", msg);
+            exit(1);
+        }
+    }
+
+    let halted = chip_8_vm.run_until_halted(limits);
+    if let Some(slot) = args.save_slot {
+        if let Err(msg) = chip_8_vm.save_to_slot(slot) {
+            println!("ERROR: {}", msg);
+            exit(1);
+        }
+    }
+    for message in chip_8_vm.osd_mut().take_log() {
+        eprintln!("{}", message);
+    }
+    match &halted {
+        Ok(report) => eprintln!("{}", report),
+        Err(error) => {
+            eprintln!("ERROR: {}", error);
+            print_fault_context(&chip_8_vm, error);
+        }
+    }
+    if let Some(profiler) = chip_8_vm.profiler() {
+        eprintln!("{}", profiler.report());
+    }
+
+    if let (Some(audio_path), Some(track)) = (args.audio, chip_8_vm.audio_track()) {
+        let written = File::create(&audio_path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            track.write_wav(&mut writer)?;
+            writer.flush()
+        });
+        if let Err(msg) = written {
+            println!("ERROR: Failed to write the audio: {}", msg);
+            exit(1);
+        }
+    }
+
+    if let Some(prefix) = &args.planes {
+        let images = [
+            (format!("{}-1.pbm", prefix), chip_8_vm.plane_pbm(0)),
+            (format!("{}-2.pbm", prefix), chip_8_vm.plane_pbm(1)),
+            (
+                format!("{}-composite.pgm", prefix),
+                chip_8_vm.composite_pgm(),
+            ),
+        ];
+        for (path, image) in images.iter() {
+            if let Err(msg) = fs::write(path, image) {
+                println!("ERROR: Failed to write the planes: {}", msg);
+                exit(1);
+            }
+        }
+    }
+
+    let frame = chip_8_vm.display_pbm();
+    match args.frame {
+        Some(frame_path) => {
+            if let Err(msg) = fs::write(&frame_path, frame) {
+                println!("ERROR: Failed to write the frame: {}", msg);
+                exit(1);
+            }
+        }
+        None => print!("{}", frame),
+    }
+
+    exit(match halted.map(|report| report.limit) {
+        Ok(Limit::Cycles) | Ok(Limit::Exit) => 0,
+        Ok(Limit::WallTime) => 2,
+        Ok(Limit::DrawsPerFrame) => 3,
+        Err(_) => 4,
+    });
+}
+
+/// Runs a ROM in the terminal. Only available with the `tui` feature
+#[cfg(not(feature = "tui"))]
+fn run_tui(_args: TuiArgs) {
+    println!("ERROR: This build has no terminal support.");
+    exit(1);
+}
+
+/// Runs a ROM in the terminal, ex: over SSH. Exits with `4` if an instruction faulted
+#[cfg(feature = "tui")]
+fn run_tui(args: TuiArgs) {
+    let mut chip_8_vm = Chip8::new();
+    args.vm.apply(&mut chip_8_vm);
+    match (args.instructions_per_frame, args.speed) {
+        (_, Some(cycle_rate)) => chip_8_vm.set_cycle_rate(cycle_rate),
+        (instructions, None) => chip_8_vm
+            .set_instructions_per_frame(instructions.unwrap_or(args.vm.instructions_per_frame())),
+    }
+    if !args.no_rom_database {
+        let overrides = RomSettings {
+            quirks: args.vm.quirks(),
+            instructions_per_frame: args.instructions_per_frame.or(args
+                .vm
+                .profile
+                .map(|profile| profile.instructions_per_frame())),
+            battery: args.battery.clone(),
+            ..RomSettings::default()
+        };
+        chip_8_vm.set_rom_database(RomDatabase::embedded(), overrides);
+    }
+    // Keeps the RPL flags between runs
+    chip_8_vm.set_save_storage(Box::new(FileStorage::new(".")), 1);
+
+    // Applied before the ROM is loaded, so their errors are reported before the terminal
+    // is taken over
+    args.vm.apply_cheats(&mut chip_8_vm, &args.rom);
+
+    // Loaded in the background, so the status line shows the progress of downloads and
+    // extractions
+    let loader = RomLoader::start(&args.rom, args.entry.as_deref());
+    let mut loading = RomLoading::new(loader, |_: &mut Chip8| Ok(()), None);
+    let stopped = chip_8_vm.start_tui(
+        args.palette.palette(),
+        args.palette.high_contrast,
+        args.palette.audio_reactive,
+        Some(&mut loading),
+    );
+    if let Some(msg) = loading.error() {
+        println!("ERROR: {}", msg);
+        exit(1);
+    }
+    store_battery(&mut chip_8_vm);
+    match stopped.map(|_| loading.fault()) {
+        Ok(None) => {}
+        Ok(Some(error)) => {
+            println!("ERROR: {}", error);
+            print_fault_context(&chip_8_vm, error);
+            exit(4);
+        }
+        Err(msg) => {
+            println!("ERROR: {}", msg);
+            exit(1);
+        }
+    }
+}
+
+/// Runs a ROM in a window. Only available with the `window` or `framebuffer` features
+#[cfg(not(any(feature = "window", feature = "framebuffer")))]
+fn run_window(_args: RunArgs) {
+    println!("ERROR: This build has no window support.");
+    println!("Run ROMs with the headless command instead, see --help.");
+    exit(1);
+}
+
+/// Runs a ROM in a window drawn by the CPU, for builds with the `framebuffer` feature but
+/// not the `window` one. Only the options of the VM, the speed, the scale and the palette
+/// are supported. Exits with `4` if an instruction faulted
+#[cfg(all(feature = "framebuffer", not(feature = "window")))]
+fn run_window(args: RunArgs) {
+    let rom = match &args.rom {
+        Some(rom) if args.playlist.is_none() && !args.kiosk => rom,
+        _ => {
+            println!(
+                "ERROR: This build needs a ROM, playlists and kiosk mode need the window feature."
+            );
             exit(1);
         }
-        Some(path) => path,
     };
+    let mut chip_8_vm = Chip8::new();
+    args.vm.apply(&mut chip_8_vm);
+    if let Some(mode) = args.write_protection {
+        chip_8_vm.set_write_protection(mode);
+    }
+    match (args.instructions_per_frame, args.speed) {
+        (_, Some(cycle_rate)) => chip_8_vm.set_cycle_rate(cycle_rate),
+        (instructions, None) => chip_8_vm
+            .set_instructions_per_frame(instructions.unwrap_or(args.vm.instructions_per_frame())),
+    }
+    if !args.no_rom_database {
+        let overrides = RomSettings {
+            quirks: args.vm.quirks(),
+            instructions_per_frame: args.instructions_per_frame.or(args
+                .vm
+                .profile
+                .map(|profile| profile.instructions_per_frame())),
+            battery: args.battery.clone(),
+            ..RomSettings::default()
+        };
+        chip_8_vm.set_rom_database(RomDatabase::embedded(), overrides);
+    }
+    // Keeps the RPL flags between runs
+    chip_8_vm.set_save_storage(Box::new(FileStorage::new(".")), 1);
+
+    let loaded = rom::load(rom, args.entry.as_deref()).and_then(|content| {
+        chip_8_vm
+            .load_rom_content(content)
+            .map_err(|e| e.to_string())
+    });
+    if let Err(msg) = loaded {
+        println!("ERROR: {}", msg);
+        exit(1);
+    }
+    args.vm.apply_cheats(&mut chip_8_vm, rom);
+
+    let scale = args.scale.unwrap_or(VideoSettings::DEFAULT_SCALE);
+    let stopped = chip_8_vm.start_framebuffer(
+        scale,
+        args.palette.palette(),
+        args.palette.high_contrast,
+        args.palette.audio_reactive,
+    );
+    store_battery(&mut chip_8_vm);
+    match stopped {
+        Ok(None) => {}
+        Ok(Some(error)) => {
+            println!("ERROR: {}", error);
+            print_fault_context(&chip_8_vm, &error);
+            exit(4);
+        }
+        Err(msg) => {
+            println!("ERROR: {}", msg);
+            exit(1);
+        }
+    }
+}
+
+/// Runs a ROM in a window, as configured by the command line `args`
+#[cfg(feature = "window")]
+fn run_window(args: RunArgs) {
+    let mut chip_8_vm = Chip8::new();
+    args.vm.apply(&mut chip_8_vm);
+    if let Some(mode) = args.write_protection {
+        chip_8_vm.set_write_protection(mode);
+    }
+
+    #[cfg(feature = "gamepad")]
+    if let Some(path) = &args.gamepad_map {
+        match GamepadMap::load(path) {
+            Ok(map) => chip_8_vm.set_gamepad_map(map),
+            Err(msg) => {
+                println!("ERROR: {}", msg);
+                exit(1);
+            }
+        }
+    }
+    #[cfg(not(feature = "gamepad"))]
+    if args.gamepad_map.is_some() {
+        println!("ERROR: This build has no gamepad support.");
+        exit(1);
+    }
 
-    let rom_binary_content = match fs::read(&path) {
+    for binding in args.hotkey.iter() {
+        if let Err(msg) = chip_8_vm.hotkeys_mut().bind_str(binding) {
+            let names: Vec<&str> = HostAction::ALL.iter().map(|a| a.name()).collect();
+            println!("ERROR: {}", msg);
+            println!("Available actions: {}", names.join(", "));
+            exit(1);
+        }
+    }
+    let mut keymap = KeyMap::new();
+    for binding in args.key.iter() {
+        if let Err(msg) = keymap.bind_str(binding) {
+            println!("ERROR: {}", msg);
+            exit(1);
+        }
+    }
+    if let Err(msg) = chip_8_vm.set_keymap(keymap) {
+        println!("ERROR: {}", msg);
+        exit(1);
+    }
+    let config_path = args
+        .config
+        .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+    match config::file_storage(&config_path) {
+        Ok((storage, key)) => chip_8_vm.set_config_storage(Box::new(storage), key),
         Err(msg) => {
-            println!("ERROR: Failed to open the ROM.");
+            println!("ERROR: {}", msg);
+            exit(1);
+        }
+    }
+
+    if let Some(keys) = &args.turbo {
+        let mut turbo = Turbo::new(args.turbo_rate.unwrap_or(Turbo::DEFAULT_RATE));
+        for key in keys.split(',') {
+            match usize::from_str_radix(key.trim(), 16) {
+                Ok(key) if key < 16 => turbo.add_key(key),
+                _ => {
+                    println!("ERROR: Invalid turbo key '{}'.", key);
+                    exit(1);
+                }
+            }
+        }
+        chip_8_vm.set_turbo(turbo);
+    }
+
+    let limits = args.limits.limits();
+    if limits != ExecutionLimits::default() {
+        chip_8_vm.set_limits(limits);
+    }
+    let video_settings = VideoSettings {
+        audio_reactive: args.palette.audio_reactive,
+        kiosk: args.kiosk,
+        frame_skip: args.frame_skip,
+        palette: args.palette.palette(),
+        high_contrast: args.palette.high_contrast,
+        scale: args.scale,
+        scaling: args.scaling.unwrap_or_default(),
+        vsync: args.vsync.unwrap_or(false),
+        filter: args.filter.unwrap_or_default(),
+        anti_flicker: args.anti_flicker.unwrap_or_default(),
+        anti_flicker_frames: args.anti_flicker_frames.map(|frames| frames as usize),
+        pause_on_focus_loss: args.pause_on_focus_loss,
+        show_keypad: args.show_keypad,
+    };
+    let idle_timeout = args
+        .kiosk_idle
+        .map_or(Kiosk::DEFAULT_IDLE_TIMEOUT, Duration::from_secs);
+
+    if args.measure_latency {
+        chip_8_vm.enable_latency_probe();
+    }
+    if args.profile_instructions {
+        chip_8_vm.enable_profiler();
+    }
+    chip_8_vm.set_strict(args.strict);
+    if let Some(tone) = args.tone.tone() {
+        chip_8_vm.set_tone(tone);
+    }
+    chip_8_vm.set_save_storage(Box::new(FileStorage::new(".")), args.save_slot.unwrap_or(1));
+    chip_8_vm.enable_rewind(args.rewind.unwrap_or(Rewind::DEFAULT_SECONDS));
+
+    let frame_hash_trace = match (&args.record_hashes, &args.verify_hashes) {
+        (Some(path), _) => Some(FrameHashTrace::record(path)),
+        (None, Some(path)) => Some(FrameHashTrace::verify(path)),
+        (None, None) => None,
+    };
+    let mut frame_hash_trace = match frame_hash_trace.transpose() {
+        Ok(trace) => trace,
+        Err(msg) => {
+            println!("ERROR: Failed to open the frame hashes trace.");
             println!("Rust provided the next error message:\n>> {}", msg);
             exit(1);
         }
-        Ok(content) => content,
     };
 
-    let cycle_delay: u64 = match env::args().nth(2) {
-        None => DEFAULT_CYCLE_DELAY,
-        Some(delay) => match delay.parse::<u64>() {
-            Ok(delay) => delay,
+    match (args.instructions_per_frame, args.speed, args.delay) {
+        (Some(instructions), _, _) => chip_8_vm.set_instructions_per_frame(instructions),
+        (None, Some(cycle_rate), _) => chip_8_vm.set_cycle_rate(cycle_rate),
+        (None, None, Some(delay)) if delay > 0 => {
+            chip_8_vm.set_cycle_rate((1000 / delay).max(1) as u32);
+        }
+        _ => chip_8_vm.set_instructions_per_frame(args.vm.instructions_per_frame()),
+    }
+    if !args.no_rom_database {
+        let overrides = RomSettings {
+            quirks: args.vm.quirks(),
+            instructions_per_frame: args.instructions_per_frame.or(args
+                .vm
+                .profile
+                .map(|profile| profile.instructions_per_frame())),
+            battery: args.battery.clone(),
+            ..RomSettings::default()
+        };
+        chip_8_vm.set_rom_database(RomDatabase::embedded(), overrides);
+    }
+
+    let playlist = match (&args.playlist, &args.rom) {
+        (Some(playlist_path), _) => match Playlist::load(playlist_path) {
+            Ok(playlist) => Some(playlist),
             Err(msg) => {
                 println!("ERROR: {}", msg);
                 exit(1);
             }
         },
+        (None, Some(path)) if args.kiosk => match read_roms(path) {
+            Ok(roms) => Some(Playlist::new(
+                roms.into_iter()
+                    .map(|(name, rom)| PlaylistEntry::new(name, rom))
+                    .collect(),
+            )),
+            Err(msg) => {
+                println!("ERROR: Failed to open the ROM.");
+                println!("Rust provided the next error message:\n>> {}", msg);
+                exit(1);
+            }
+        },
+        _ => None,
     };
 
-    println!("Loading ROM {} ...", &path);
-    match chip_8_vm.load_rom_content(rom_binary_content) {
-        Err(msg) => {
-            println!("ERROR: {}", msg);
-            exit(1);
+    match playlist {
+        Some(mut playlist) => {
+            if frame_hash_trace.is_some() {
+                println!("ERROR: Frame hashes can't be used with playlists or in kiosk mode.");
+                exit(1);
+            }
+            if args.load_slot.is_some() {
+                println!("ERROR: Save states can't be loaded with playlists or in kiosk mode.");
+                exit(1);
+            }
+            if args.record_movie.is_some() || args.playback.is_some() {
+                println!("ERROR: Movies can't be used with playlists or in kiosk mode.");
+                exit(1);
+            }
+            println!("Using the {} profile.", chip_8_vm.profile().name());
+            playlist.start(&mut chip_8_vm);
+
+            if video_settings.kiosk {
+                println!("Kiosk mode: {} ROMs in rotation.", playlist.entries().len());
+                let mut kiosk = Kiosk::new(playlist, idle_timeout);
+                chip_8_vm.start(video_settings, Some(&mut kiosk));
+            } else {
+                chip_8_vm.start(video_settings, Some(&mut playlist));
+            }
         }
-        Ok(total_read) => {
-            println!("ROM loaded successfully. {} bytes were read.", total_read);
+        None if args.rom.is_none() => {
+            if video_settings.kiosk {
+                println!("ERROR: Kiosk mode needs a directory of ROMs or a playlist.");
+                exit(1);
+            }
+            if frame_hash_trace.is_some()
+                || args.load_slot.is_some()
+                || args.record_movie.is_some()
+                || args.playback.is_some()
+            {
+                println!("ERROR: Frame hashes, save states and movies need a ROM.");
+                exit(1);
+            }
+            println!("No ROM given, press a key to choose one of the demos:");
+            for (index, demo) in DEMOS.iter().enumerate() {
+                println!("{}: {}", index + 1, demo.name);
+            }
+            let mut picker = DemoPicker::new();
+            picker.start(&mut chip_8_vm);
+            chip_8_vm.start(video_settings, Some(&mut picker));
         }
+        None => {
+            let path = args.rom.unwrap_or_default();
+            // Applied before the ROM is loaded, so their errors are reported before the
+            // window opens
+            args.vm.apply_cheats(&mut chip_8_vm, &path);
+
+            // Loaded in the background, so the window shows the progress of downloads and
+            // extractions instead of freezing
+            println!("Loading ROM {} ...", path);
+            let loader = RomLoader::start(&path, args.entry.as_deref());
+            let (load_slot, playback, record_movie) =
+                (args.load_slot, &args.playback, args.record_movie.is_some());
+            let on_loaded = |chip_8_vm: &mut Chip8| {
+                println!(
+                    "ROM loaded successfully. {} bytes were read.",
+                    chip_8_vm.rom().len()
+                );
+                if let Some(slot) = load_slot {
+                    chip_8_vm.load_from_slot(slot)?;
+                    println!("State loaded from slot {}.", slot);
+                }
+                if let Some(path) = playback {
+                    chip_8_vm.play_movie(Movie::load(path)?)?;
+                    println!("Replaying the movie {}.", path);
+                }
+                if record_movie {
+                    chip_8_vm.record_movie();
+                }
+                Ok(())
+            };
+            println!("Using the {} profile.", chip_8_vm.profile().name());
+
+            let trace = frame_hash_trace
+                .as_mut()
+                .map(|trace| trace as &mut dyn DebugHook);
+            let mut loading = RomLoading::new(loader, on_loaded, trace);
+            chip_8_vm.start(video_settings, Some(&mut loading));
+            if let Some(msg) = loading.error() {
+                println!("ERROR: {}", msg);
+                exit(1);
+            }
+            drop(loading);
+            if let Some(trace) = &mut frame_hash_trace {
+                if let Some(msg) = trace.message() {
+                    println!("{}", msg);
+                }
+                if let Err(e) = trace.finish() {
+                    println!("ERROR: Failed to record frame hashes: {}", e);
+                }
+            }
+
+            if let Some(path) = &args.record_movie {
+                let movie = chip_8_vm.stop_movie().unwrap_or_else(|| {
+                    Movie::new(
+                        chip_8_vm.rom_crc32(),
+                        chip_8_vm.rng_seed().unwrap_or_default(),
+                    )
+                });
+                match movie.save(path) {
+                    Ok(()) => println!("Movie of {} frames saved to {}.", movie.len(), path),
+                    Err(msg) => println!("ERROR: {}", msg),
+                }
+            }
+        }
+    }
+
+    store_battery(&mut chip_8_vm);
+    if let Some(probe) = chip_8_vm.latency_probe() {
+        println!("{}", probe.report());
+    }
+    if let Some(profiler) = chip_8_vm.profiler() {
+        println!("{}", profiler.report());
     }
+}
+
+/// Parses `command_line` with the settings of `config` placed before its options, like `main`
+#[cfg(test)]
+fn run_args_with_config(config: &str, command_line: &[&str]) -> RunArgs {
+    let config = Config::parse(config).unwrap();
+    let mut args: Vec<String> = command_line.iter().map(|arg| arg.to_string()).collect();
+    args.splice(2..2, config_options(&config, true));
+    match Cli::try_parse_from(args).unwrap().command {
+        Some(Command::Run(run)) => *run,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn browser_choices_are_parsed() {
+    let choice = |number, profile, instructions_per_frame| BrowserChoice {
+        number,
+        profile,
+        instructions_per_frame,
+    };
+    assert_eq!(parse_browser_choice("3\n"), Ok(choice(3, None, None)));
+    assert_eq!(
+        parse_browser_choice("1 speed=fast profile=xo-chip"),
+        Ok(choice(1, Some(Profile::XoChip), Some(30)))
+    );
+    assert_eq!(
+        parse_browser_choice("2 ipf=20"),
+        Ok(choice(2, None, Some(20)))
+    );
+    assert!(parse_browser_choice("blitz").is_err());
+    assert!(parse_browser_choice("1 speed=warp").is_err());
+    assert!(parse_browser_choice("1 ipf=0").is_err());
+}
+
+#[test]
+fn browser_entries_recommend_the_platform_unless_adjusted() {
+    // `00FF` switches to the high resolution of SUPER-CHIP
+    let rom = [0x00, 0xFF, 0x12, 0x02];
+    let database = RomDatabase::embedded();
+    let entry = BrowserEntry::new("roms/hires.ch8", &rom, &database, &Config::default());
+    assert_eq!(entry.title, "hires.ch8");
+    assert_eq!(entry.profile, Profile::Schip);
+    assert_eq!(entry.instructions_per_frame, 30);
+    assert!(!entry.adjusted);
+
+    let config = format!(
+        "[roms.{}]\ninstructions_per_frame = 8",
+        chip_8::rom_sha1(&rom)
+    );
+    let config = Config::parse(&config).unwrap();
+    let entry = BrowserEntry::new("roms/hires.ch8", &rom, &database, &config);
+    assert_eq!(entry.profile, Profile::Schip);
+    assert_eq!(
+        SpeedClass::of(entry.instructions_per_frame),
+        SpeedClass::Slow
+    );
+    assert!(entry.adjusted);
+}
+
+#[test]
+fn config_settings_apply_without_options() {
+    let config = "[video]\nhigh_contrast = true\nshow_keypad = true\nscale = 8\n\
+                  [emulation]\ninstructions_per_frame = 15\nrom_database = false";
+    let run = run_args_with_config(config, &["chip8", "run", "rom.ch8"]);
+    assert!(run.palette.high_contrast);
+    assert!(run.show_keypad);
+    assert_eq!(run.instructions_per_frame, Some(15));
+    assert!(run.no_rom_database);
+}
+
+#[test]
+fn options_take_precedence_over_the_config() {
+    let config = "[video]\nhigh_contrast = true\nshow_keypad = true\npause_on_focus_loss = true\n\
+                  [emulation]\ninstructions_per_frame = 15\nrom_database = false";
+    let run = run_args_with_config(
+        config,
+        &[
+            "chip8",
+            "run",
+            "rom.ch8",
+            "--no-high-contrast",
+            "--no-show-keypad",
+            "--no-pause-on-focus-loss",
+            "--rom-database",
+            "--instructions-per-frame",
+            "20",
+        ],
+    );
+    assert!(!run.palette.high_contrast);
+    assert!(!run.show_keypad);
+    assert!(!run.pause_on_focus_loss);
+    assert!(!run.no_rom_database);
+    assert_eq!(run.instructions_per_frame, Some(20));
 
-    chip_8_vm.start(cycle_delay);
+    // Switches turned off in the config can be turned on
+    let config = "[video]\nhigh_contrast = false\nshow_keypad = false";
+    let run = run_args_with_config(
+        config,
+        &[
+            "chip8",
+            "run",
+            "rom.ch8",
+            "--high-contrast",
+            "--show-keypad",
+        ],
+    );
+    assert!(run.palette.high_contrast);
+    assert!(run.show_keypad);
 }
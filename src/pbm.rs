@@ -0,0 +1,295 @@
+//! Utilities to compare frames saved as PBM images, ex: by the `headless` subcommand.
+use std::fmt;
+
+/// A 1-bit image, where `true` pixels are on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    /// Pixels row by row, from the top left corner
+    pub pixels: Vec<bool>,
+}
+
+/// A pixel that differs between two frames
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PixelDiff {
+    pub x: usize,
+    pub y: usize,
+    /// Value of the pixel in the first frame
+    pub before: bool,
+    /// Value of the pixel in the second frame
+    pub after: bool,
+}
+
+impl fmt::Display for PixelDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "({}, {}): {} -> {}",
+            self.x, self.y, self.before as u8, self.after as u8
+        )
+    }
+}
+
+/// Largest width or height of a frame, far beyond any display, so a corrupt header can't
+/// allocate gigabytes
+pub const MAX_DIMENSION: usize = 4096;
+
+/// Color of the pixels on in both frames
+const SAME_ON: [u8; 3] = [0x80, 0x80, 0x80];
+/// Color of the pixels only on in the first frame
+const REMOVED: [u8; 3] = [0xFF, 0x30, 0x30];
+/// Color of the pixels only on in the second frame
+const ADDED: [u8; 3] = [0x30, 0xFF, 0x30];
+
+/// Removes the `#` comments of a PBM header, which run until the end of the line
+fn strip_comments(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+impl Frame {
+    /// Parses a PBM image, either plain (`P1`) or raw (`P4`)
+    pub fn parse_pbm(content: &[u8]) -> Result<Frame, String> {
+        let magic = content.get(..2).ok_or("Not a PBM image.")?;
+        match magic {
+            b"P1" => Frame::parse_plain(&String::from_utf8_lossy(&content[2..])),
+            b"P4" => Frame::parse_raw(&content[2..]),
+            _ => Err(String::from("Not a PBM image.")),
+        }
+    }
+
+    /// Parses the body of a plain PBM image: dimensions, then one `0` or `1` per pixel
+    fn parse_plain(body: &str) -> Result<Frame, String> {
+        let body = strip_comments(body);
+        let mut tokens = body.split_whitespace();
+        let mut dimension = || {
+            tokens
+                .next()
+                .and_then(|token| token.parse::<usize>().ok())
+                .ok_or("Invalid PBM dimensions.")
+        };
+        let (width, height) = (dimension()?, dimension()?);
+
+        // Pixels don't need to be separated by whitespace
+        let pixels = tokens
+            .flat_map(|token| token.chars())
+            .map(|pixel| match pixel {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                _ => Err(format!("Invalid PBM pixel '{}'.", pixel)),
+            })
+            .collect::<Result<Vec<bool>, String>>()?;
+
+        Frame::new(width, height, pixels)
+    }
+
+    /// Parses the body of a raw PBM image: dimensions, a single whitespace,
+    /// then each row packed in bytes, most-significant bit first
+    fn parse_raw(body: &[u8]) -> Result<Frame, String> {
+        // The header ends after the whitespace following the height
+        let mut numbers = Vec::new();
+        let mut position = 0;
+        while numbers.len() < 2 {
+            let byte = *body.get(position).ok_or("Invalid PBM dimensions.")?;
+            if byte == b'#' {
+                while body.get(position).is_some_and(|&byte| byte != b'\n') {
+                    position += 1;
+                }
+            } else if byte.is_ascii_digit() {
+                let start = position;
+                while body.get(position).is_some_and(u8::is_ascii_digit) {
+                    position += 1;
+                }
+                let number = String::from_utf8_lossy(&body[start..position]);
+                numbers.push(number.parse::<usize>().map_err(|e| e.to_string())?);
+                continue;
+            } else if !byte.is_ascii_whitespace() {
+                return Err(String::from("Invalid PBM dimensions."));
+            }
+            position += 1;
+        }
+        let (width, height) = (numbers[0], numbers[1]);
+        Frame::check_dimensions(width, height)?;
+        let data = body.get(position + 1..).unwrap_or_default();
+
+        let row_bytes = width.div_ceil(8);
+        if data.len() < row_bytes * height {
+            return Err(String::from("Truncated PBM image."));
+        }
+        let pixels = (0..width * height)
+            .map(|pos| {
+                let (x, y) = (pos % width, pos / width);
+                data[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0
+            })
+            .collect();
+
+        Frame::new(width, height, pixels)
+    }
+
+    /// Checks that a `width` x `height` frame has pixels and at most `MAX_DIMENSION` of
+    /// them per side
+    fn check_dimensions(width: usize, height: usize) -> Result<(), String> {
+        if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+            return Err(format!(
+                "Invalid PBM dimensions {}x{}, each must be between 1 and {}.",
+                width, height, MAX_DIMENSION
+            ));
+        }
+        Ok(())
+    }
+
+    fn new(width: usize, height: usize, pixels: Vec<bool>) -> Result<Frame, String> {
+        Frame::check_dimensions(width, height)?;
+        if pixels.len() != width * height {
+            return Err(format!(
+                "Expected {} pixels for a {}x{} image, found {}.",
+                width * height,
+                width,
+                height,
+                pixels.len()
+            ));
+        }
+
+        Ok(Frame {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Returns the pixels that differ from `other`, row by row
+    /// # Errors
+    /// If both frames don't have the same dimensions
+    pub fn diff(&self, other: &Frame) -> Result<Vec<PixelDiff>, String> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(format!(
+                "Frames have different sizes: {}x{} and {}x{}.",
+                self.width, self.height, other.width, other.height
+            ));
+        }
+
+        Ok(self
+            .pixels
+            .iter()
+            .zip(other.pixels.iter())
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(pos, (&before, &after))| PixelDiff {
+                x: pos % self.width,
+                y: pos / self.width,
+                before,
+                after,
+            })
+            .collect())
+    }
+
+    /// Renders a visual diff against `other` as a PNG image: pixels on in both frames
+    /// are gray, pixels only on in this frame are red and pixels only on in `other` are green.
+    /// Each pixel is drawn as a `scale` x `scale` square.
+    /// # Errors
+    /// If both frames don't have the same dimensions, if `scale` is `0` or makes the image
+    /// too big for a PNG, or if it can't be encoded.
+    pub fn diff_png(&self, other: &Frame, scale: usize) -> Result<Vec<u8>, String> {
+        self.diff(other)?;
+
+        let scaled = |size: usize| {
+            size.checked_mul(scale)
+                .filter(|&size| size > 0 && size <= u32::MAX as usize)
+                .ok_or(format!("Invalid scale {} for the diff image.", scale))
+        };
+        let (width, height) = (scaled(self.width)?, scaled(self.height)?);
+        let size = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or(format!("Invalid scale {} for the diff image.", scale))?;
+        let mut data = Vec::with_capacity(size);
+        for y in 0..height {
+            for x in 0..width {
+                let pos = (y / scale) * self.width + x / scale;
+                let color = match (self.pixels[pos], other.pixels[pos]) {
+                    (true, true) => SAME_ON,
+                    (true, false) => REMOVED,
+                    (false, true) => ADDED,
+                    (false, false) => [0, 0, 0],
+                };
+                data.extend_from_slice(&color);
+            }
+        }
+
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| format!("Failed to encode the diff image: {}", e))?;
+        writer
+            .write_image_data(&data)
+            .map_err(|e| format!("Failed to encode the diff image: {}", e))?;
+        drop(writer);
+
+        Ok(png)
+    }
+}
+
+#[test]
+fn pbm_images_are_parsed() {
+    let plain = Frame::parse_pbm(b"P1\n# A comment\n3 2\n1 0 1\n010").unwrap();
+    assert_eq!((plain.width, plain.height), (3, 2));
+    assert_eq!(plain.pixels, [true, false, true, false, true, false]);
+
+    let raw = Frame::parse_pbm(b"P4\n3 2\n\xA0\x40").unwrap();
+    assert_eq!(raw, plain);
+}
+
+#[test]
+fn invalid_pbm_images_are_rejected() {
+    let error = |content: &[u8]| Frame::parse_pbm(content).unwrap_err();
+    assert_eq!(error(b"P5\n1 1\n0"), "Not a PBM image.");
+    assert_eq!(error(b"P1\n2 x\n"), "Invalid PBM dimensions.");
+    assert_eq!(error(b"P1\n2 1\n02"), "Invalid PBM pixel '2'.");
+    assert_eq!(
+        error(b"P1\n2 2\n010"),
+        "Expected 4 pixels for a 2x2 image, found 3."
+    );
+    assert_eq!(error(b"P4\n16 2\n\xFF\xFF"), "Truncated PBM image.");
+    assert!(error(b"P1\n0 0\n").starts_with("Invalid PBM dimensions 0x0"));
+    assert!(error(b"P4\n0 5\n").starts_with("Invalid PBM dimensions 0x5"));
+    // Would overflow the size of the data otherwise
+    assert!(
+        error(b"P4\n99999999999999999 99999999999999999\n").starts_with("Invalid PBM dimensions")
+    );
+}
+
+#[test]
+fn frames_are_diffed() {
+    let a = Frame::parse_pbm(b"P1 2 1 10").unwrap();
+    let b = Frame::parse_pbm(b"P1 2 1 01").unwrap();
+    assert_eq!(
+        a.diff(&b).unwrap(),
+        [
+            PixelDiff {
+                x: 0,
+                y: 0,
+                before: true,
+                after: false
+            },
+            PixelDiff {
+                x: 1,
+                y: 0,
+                before: false,
+                after: true
+            },
+        ]
+    );
+    assert!(a.diff(&Frame::parse_pbm(b"P1 1 2 10").unwrap()).is_err());
+
+    let png = a.diff_png(&b, 4).unwrap();
+    assert_eq!(&png[1..4], b"PNG");
+    assert!(a.diff_png(&b, 0).is_err());
+    assert!(a.diff_png(&b, usize::MAX).is_err());
+}
@@ -0,0 +1,432 @@
+//! HTML reports of ROM collections, for archival and study: every ROM gets a page with
+//! its disassembly, the sprites it draws and statistics about its instructions,
+//! all linked from an index page.
+use crate::chip_8::{Chip8, Instruction, Profile};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// Address where ROMs are loaded
+const ROM_ADDRESS: u16 = 0x200;
+
+/// Size of each pixel of the sprite previews, in CSS pixels
+const SPRITE_SCALE: usize = 4;
+
+/// Style shared by all pages
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;background:#fafafa;color:#222}\
+pre,td.code{font-family:monospace}\
+table{border-collapse:collapse}td,th{padding:2px 8px;text-align:left}\
+tr:target{background:#ffe9a8}.data{color:#999}.label{color:#06c;font-weight:bold}\
+.sprite{display:inline-block;margin:0 1em 1em 0;vertical-align:top}\
+.sprite svg{background:#000;display:block}";
+
+/// A sprite drawn by a ROM: `Dxyn` reading `n` bytes at an address set by `Annn`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sprite {
+    pub address: u16,
+    /// Bytes of the sprite, one per row, most-significant bit on the left
+    pub rows: Vec<u8>,
+}
+
+/// Platforms a ROM is written for, from the original CHIP-8 to its extensions,
+/// each one a superset of the previous
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Platform {
+    Chip8,
+    Schip,
+    XoChip,
+}
+
+impl Platform {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Chip8 => "CHIP-8",
+            Platform::Schip => "SCHIP",
+            Platform::XoChip => "XO-CHIP",
+        }
+    }
+
+    /// Returns the profile running the ROMs of the platform
+    pub fn profile(&self) -> Profile {
+        match self {
+            Platform::Chip8 => Profile::Standard,
+            Platform::Schip => Profile::Schip,
+            Platform::XoChip => Profile::XoChip,
+        }
+    }
+
+    /// Returns the platform that introduced `opcode`, ex: `SCHIP` for `00FF`.
+    /// OP Codes that don't belong to any extension are `CHIP-8`, even unknown ones
+    pub fn of(opcode: u16) -> Platform {
+        match opcode {
+            0x00D0..=0x00DF | 0xF000 | 0xF002 => Platform::XoChip,
+            _ if matches!(opcode & 0xF00F, 0x5002 | 0x5003) => Platform::XoChip,
+            _ if matches!(opcode & 0xF0FF, 0xF001 | 0xF03A) => Platform::XoChip,
+            0x00C0..=0x00CF | 0x00FB..=0x00FF => Platform::Schip,
+            _ if matches!(opcode & 0xF0FF, 0xF030 | 0xF075 | 0xF085) => Platform::Schip,
+            _ if opcode & 0xF00F == 0xD000 => Platform::Schip,
+            _ => Platform::Chip8,
+        }
+    }
+}
+
+/// Static analysis of a ROM, without running it
+#[derive(Clone, Debug)]
+pub struct RomAnalysis {
+    pub name: String,
+    pub size: usize,
+    pub crc32: u32,
+    /// Every 2 bytes of the ROM decoded as an instruction, along with their address and OP Code
+    pub listing: Vec<(u16, u16, Option<Instruction>)>,
+    /// Addresses targeted by `JP`, `CALL` and `LD I`, shown as labels of the listing
+    pub labels: BTreeSet<u16>,
+    /// Addresses called as subroutines
+    pub subroutines: BTreeSet<u16>,
+    /// Sprites drawn, in the order they appear in the ROM, without duplicates
+    pub sprites: Vec<Sprite>,
+    /// Number of instructions of each opcode family, ex: `Dxyn`
+    pub families: BTreeMap<&'static str, usize>,
+    /// Platform of the instructions reachable from the load address, see `reachable`
+    pub platform: Platform,
+    /// Address and OP Code of the instructions reachable from the load address that
+    /// don't decode, in address order
+    pub unknown: Vec<(u16, u16)>,
+}
+
+impl RomAnalysis {
+    /// Disassembles `content` linearly from the load address, following the `Annn`
+    /// preceding each `Dxyn` to find the sprites drawn
+    pub fn new(name: &str, content: &[u8]) -> RomAnalysis {
+        let mut crc = flate2::Crc::new();
+        crc.update(content);
+
+        let mut analysis = RomAnalysis {
+            name: name.to_string(),
+            size: content.len(),
+            crc32: crc.sum(),
+            listing: Vec::new(),
+            labels: BTreeSet::new(),
+            subroutines: BTreeSet::new(),
+            sprites: Vec::new(),
+            families: BTreeMap::new(),
+            platform: Platform::Chip8,
+            unknown: Vec::new(),
+        };
+
+        let mut index = None;
+        for (offset, bytes) in content.chunks(2).enumerate() {
+            let address = ROM_ADDRESS + 2 * offset as u16;
+            let opcode = ((bytes[0] as u16) << 8) | *bytes.get(1).unwrap_or(&0) as u16;
+            let instruction = Instruction::decode(opcode);
+            if let Some(instruction) = instruction {
+                *analysis
+                    .families
+                    .entry(instruction.info().pattern)
+                    .or_default() += 1;
+            }
+
+            match instruction {
+                Some(Instruction::Jp(nnn)) => {
+                    analysis.labels.insert(nnn);
+                }
+                Some(Instruction::Call(nnn)) => {
+                    analysis.labels.insert(nnn);
+                    analysis.subroutines.insert(nnn);
+                }
+                Some(Instruction::LdIAddr(nnn)) => {
+                    analysis.labels.insert(nnn);
+                    index = Some(nnn);
+                }
+                Some(Instruction::DrwVxVyN(_, _, n)) => {
+                    if let Some(sprite) = index.and_then(|address| sprite_at(content, address, n)) {
+                        if !analysis.sprites.contains(&sprite) {
+                            analysis.sprites.push(sprite);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            analysis.listing.push((address, opcode, instruction));
+        }
+        for (&address, &opcode) in reachable(content).iter() {
+            analysis.platform = analysis.platform.max(Platform::of(opcode));
+            if Instruction::decode(opcode).is_none() {
+                analysis.unknown.push((address, opcode));
+            }
+        }
+        analysis
+    }
+
+    /// Returns the number of OP Codes that decode to an instruction
+    pub fn instructions(&self) -> usize {
+        self.families.values().sum()
+    }
+
+    /// Returns the name of the page of the ROM in the report
+    pub fn page_name(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("{}.html", name)
+    }
+
+    /// Returns the sprite whose bytes include `address`, if any
+    fn sprite_containing(&self, address: u16) -> Option<usize> {
+        self.sprites.iter().position(|sprite| {
+            (sprite.address..sprite.address + sprite.rows.len() as u16).contains(&address)
+        })
+    }
+}
+
+/// Walks the code of the ROM `content` from the load address, following jumps, calls and
+/// both outcomes of skips, so the data isn't taken for instructions. Walking stops at
+/// returns, at `Bnnn` whose target is only known while running, and at OP Codes that
+/// don't belong to any platform.
+/// # Returns
+/// The address and OP Code of every instruction reached
+fn reachable(content: &[u8]) -> BTreeMap<u16, u16> {
+    let mut reached = BTreeMap::new();
+    let mut pending = vec![ROM_ADDRESS];
+    while let Some(address) = pending.pop() {
+        if reached.contains_key(&address) {
+            continue;
+        }
+        let start = match address.checked_sub(ROM_ADDRESS) {
+            Some(start) => start as usize,
+            None => continue,
+        };
+        let opcode = match content.get(start..start + 2) {
+            Some(bytes) => ((bytes[0] as u16) << 8) | bytes[1] as u16,
+            None => continue,
+        };
+        reached.insert(address, opcode);
+
+        let next = address.wrapping_add(2);
+        match Instruction::decode(opcode) {
+            Some(Instruction::Jp(nnn)) => pending.push(nnn),
+            Some(Instruction::Call(nnn)) => pending.extend([nnn, next]),
+            Some(Instruction::Ret) | Some(Instruction::JpV0Addr(_)) => {}
+            Some(Instruction::SeVxByte(_, _))
+            | Some(Instruction::SneVxByte(_, _))
+            | Some(Instruction::SeVxVy(_, _))
+            | Some(Instruction::SneVxVy(_, _))
+            | Some(Instruction::SkipVx(_))
+            | Some(Instruction::SkipNVx(_)) => pending.extend([next, next.wrapping_add(2)]),
+            // Followed by the 16 bits address
+            Some(Instruction::LdILong) => pending.push(next.wrapping_add(2)),
+            Some(_) => pending.push(next),
+            // `00FD` exits the interpreter
+            None if Platform::of(opcode) != Platform::Chip8 && opcode != 0x00FD => {
+                pending.push(next)
+            }
+            None => {}
+        }
+    }
+    reached
+}
+
+/// Returns the `n` bytes long sprite at `address`, if fully inside the ROM `content`
+fn sprite_at(content: &[u8], address: u16, n: usize) -> Option<Sprite> {
+    let start = (address.checked_sub(ROM_ADDRESS)?) as usize;
+    let rows = content.get(start..start + n)?;
+    if rows.is_empty() {
+        return None;
+    }
+    Some(Sprite {
+        address,
+        rows: rows.to_vec(),
+    })
+}
+
+/// Escapes the characters of `text` that have a meaning in HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Wraps `body` in a complete HTML page titled `title`
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+/// Returns a preview of `sprite` as an inline SVG image
+fn sprite_svg(sprite: &Sprite) -> String {
+    let mut svg = format!(
+        "<svg width=\"{}\" height=\"{}\">",
+        8 * SPRITE_SCALE,
+        sprite.rows.len() * SPRITE_SCALE
+    );
+    for (y, row) in sprite.rows.iter().enumerate() {
+        for x in (0..8).filter(|x| row & (0x80 >> x) != 0) {
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" fill=\"#fff\"/>",
+                x * SPRITE_SCALE,
+                y * SPRITE_SCALE,
+                s = SPRITE_SCALE
+            ));
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Returns the assembly of `instruction`, linking its target address to the listing
+fn linked_instruction(analysis: &RomAnalysis, instruction: Instruction) -> String {
+    let target = match instruction {
+        Instruction::Jp(nnn) | Instruction::Call(nnn) | Instruction::LdIAddr(nnn) => nnn,
+        _ => return escape(&instruction.to_string()),
+    };
+    let text = escape(&instruction.to_string());
+    let in_rom = target >= ROM_ADDRESS && ((target - ROM_ADDRESS) as usize) < analysis.size;
+    if !in_rom {
+        return text;
+    }
+    let operand = format!("0x{:03X}", target);
+    text.replace(
+        &operand,
+        &format!("<a href=\"#L{:03X}\">{}</a>", target & !1, operand),
+    )
+}
+
+/// Returns the page of a ROM: statistics, sprites and the annotated listing
+pub fn rom_page(analysis: &RomAnalysis) -> String {
+    let mut body = format!(
+        "<p><a href=\"index.html\">&larr; All ROMs</a></p>\n<h1>{}</h1>\n",
+        escape(&analysis.name)
+    );
+
+    body.push_str("<h2>Statistics</h2>\n<table>\n");
+    let stats = [
+        ("Size", format!("{} bytes", analysis.size)),
+        ("CRC32", format!("{:08X}", analysis.crc32)),
+        ("Platform", analysis.platform.name().to_string()),
+        ("Instructions", analysis.instructions().to_string()),
+        (
+            "Unknown OP Codes",
+            (analysis.listing.len() - analysis.instructions()).to_string(),
+        ),
+        ("Subroutines", analysis.subroutines.len().to_string()),
+        ("Sprites", analysis.sprites.len().to_string()),
+    ];
+    for (name, value) in stats.iter() {
+        body.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", name, value));
+    }
+    body.push_str("</table>\n<h3>Opcode families</h3>\n<table>\n");
+    let mut families: Vec<(&&str, &usize)> = analysis.families.iter().collect();
+    families.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    for (family, count) in families {
+        body.push_str(&format!(
+            "<tr><td class=\"code\">{}</td><td>{}</td></tr>\n",
+            family, count
+        ));
+    }
+    body.push_str("</table>\n");
+
+    body.push_str("<h2>Sprites</h2>\n");
+    for (index, sprite) in analysis.sprites.iter().enumerate() {
+        body.push_str(&format!(
+            "<div class=\"sprite\" id=\"S{}\"><a href=\"#L{:03X}\">0x{:03X}</a> ({} rows){}</div>\n",
+            index,
+            sprite.address & !1,
+            sprite.address,
+            sprite.rows.len(),
+            sprite_svg(sprite)
+        ));
+    }
+
+    body.push_str("<h2>Listing</h2>\n<table>\n");
+    for &(address, opcode, instruction) in analysis.listing.iter() {
+        let label = if analysis.subroutines.contains(&address) {
+            format!("<span class=\"label\">sub_{:03X}:</span>", address)
+        } else if analysis.labels.contains(&address) {
+            format!("<span class=\"label\">L{:03X}:</span>", address)
+        } else {
+            String::new()
+        };
+        let sprite = analysis
+            .sprite_containing(address)
+            .or_else(|| analysis.sprite_containing(address + 1));
+        let (class, text) = match (sprite, instruction) {
+            (Some(index), _) => (
+                " class=\"data\"",
+                format!("<a href=\"#S{}\">sprite data</a>", index),
+            ),
+            (None, Some(instruction)) => ("", linked_instruction(analysis, instruction)),
+            (None, None) => (" class=\"data\"", String::from("data")),
+        };
+        body.push_str(&format!(
+            "<tr id=\"L{a:03X}\"{}><td>{}</td><td class=\"code\">0x{a:03X}</td>\
+             <td class=\"code\">{:04X}</td><td class=\"code\">{}</td></tr>\n",
+            class,
+            label,
+            opcode,
+            text,
+            a = address
+        ));
+    }
+    body.push_str("</table>\n");
+
+    page(&analysis.name, &body)
+}
+
+/// Returns the index page, listing every ROM with a link to its page
+pub fn index_page(analyses: &[RomAnalysis]) -> String {
+    let mut body = format!(
+        "<h1>ROM collection</h1>\n<p>{} ROMs</p>\n<table>\n<tr><th>ROM</th><th>Size</th>\
+         <th>CRC32</th><th>Instructions</th><th>Subroutines</th><th>Sprites</th></tr>\n",
+        analyses.len()
+    );
+    for analysis in analyses {
+        body.push_str(&format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td class=\"code\">{:08X}</td>\
+             <td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            analysis.page_name(),
+            escape(&analysis.name),
+            analysis.size,
+            analysis.crc32,
+            analysis.instructions(),
+            analysis.subroutines.len(),
+            analysis.sprites.len()
+        ));
+    }
+    body.push_str("</table>\n");
+    page("ROM collection", &body)
+}
+
+/// Writes the report of `roms`, given as name and content, to the `directory`:
+/// an `index.html` page plus one page per ROM
+/// # Returns
+/// The number of ROMs in the report.
+/// # Errors
+/// If a ROM is too big to be loaded, or if any page can't be written
+pub fn write_report(roms: &[(String, Vec<u8>)], directory: &Path) -> Result<usize, String> {
+    let write_error = |e: std::io::Error| format!("Failed to write the report: {}", e);
+    fs::create_dir_all(directory).map_err(write_error)?;
+
+    let mut analyses = Vec::new();
+    for (name, content) in roms {
+        if content.len() > Chip8::MAX_ROM_SIZE {
+            return Err(format!(
+                "{} exceeds the maximum ROM size of {} bytes.",
+                name,
+                Chip8::MAX_ROM_SIZE
+            ));
+        }
+        let analysis = RomAnalysis::new(name, content);
+        fs::write(directory.join(analysis.page_name()), rom_page(&analysis))
+            .map_err(write_error)?;
+        analyses.push(analysis);
+    }
+    fs::write(directory.join("index.html"), index_page(&analyses)).map_err(write_error)?;
+    Ok(analyses.len())
+}
@@ -0,0 +1,299 @@
+//! Loading of ROM files, also from ZIP or gzip archives, and utilities to prepare
+//! them, ex: for distribution or flash cartridges.
+use crate::chip_8::{Chip8, Profile};
+use crate::zip::{ZipArchive, ZipEntry};
+use flate2::read::GzDecoder;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// Extensions of the files loaded from ZIP archives, compared ignoring case
+pub const ROM_EXTENSIONS: [&str; 4] = ["ch8", "c8", "sc8", "xo8"];
+/// Largest ROM extracted from an archive: the whole memory of XO-CHIP
+const MAX_EXTRACTED_SIZE: usize = 0x10000;
+/// Largest file downloaded when a ROM is loaded from a URL, archives of ROM packs included
+#[cfg(feature = "network")]
+const MAX_DOWNLOAD_SIZE: usize = 0x40_0000;
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// Returns `true` if `path` is an HTTP or HTTPS URL rather than a file
+pub fn is_url(path: &str) -> bool {
+    ["http://", "https://"].iter().any(|scheme| {
+        path.get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+    })
+}
+
+/// Step of the loading of a ROM reported by `load_with_progress`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadProgress {
+    /// Bytes received so far, and the size announced by the server, if any
+    Downloading {
+        received: usize,
+        size: Option<usize>,
+    },
+    /// The ROM is being extracted from its archive
+    Extracting,
+}
+
+impl fmt::Display for LoadProgress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadProgress::Downloading {
+                received,
+                size: Some(size),
+            } if *size > 0 => write!(
+                f,
+                "Downloading... {} of {} KB ({}%)",
+                received / 1024,
+                size / 1024,
+                received * 100 / size
+            ),
+            LoadProgress::Downloading { received, .. } => {
+                write!(f, "Downloading... {} KB", received / 1024)
+            }
+            LoadProgress::Extracting => write!(f, "Extracting..."),
+        }
+    }
+}
+
+/// Reads the file at `path`, or downloads it if `path` is a URL
+fn read(path: &str, progress: &mut dyn FnMut(LoadProgress)) -> Result<Vec<u8>, String> {
+    if is_url(path) {
+        #[cfg(feature = "network")]
+        return crate::library::download_with_progress(
+            path,
+            MAX_DOWNLOAD_SIZE,
+            &mut |received, size| progress(LoadProgress::Downloading { received, size }),
+        );
+        #[cfg(not(feature = "network"))]
+        {
+            // Nothing to report without downloads
+            let _ = progress;
+            return Err(String::from(
+                "This build has no network support, ROMs can't be loaded from URLs.",
+            ));
+        }
+    }
+    fs::read(path).map_err(|e| format!("Failed to open the ROM: {}", e))
+}
+
+/// Reads the ROM at `path`, which can also be an HTTP(S) URL with the `network` feature.
+/// ZIP and gzip archives are detected by their content and extracted on the fly:
+/// a ZIP archive must hold a single ROM, or `entry` must name one of them, either
+/// by its full path in the archive or by its file name.
+/// # Errors
+/// If the file can't be read or downloaded, or if the archive is damaged, holds no
+/// ROM or several ROMs and no `entry`.
+pub fn load(path: &str, entry: Option<&str>) -> Result<Vec<u8>, String> {
+    load_with_progress(path, entry, &mut |_| {})
+}
+
+/// Reads the ROM at `path` like `load`, calling `progress` as the ROM is downloaded and
+/// extracted, ex: to show it while loading big ROMs
+/// # Errors
+/// The same as `load`
+pub fn load_with_progress(
+    path: &str,
+    entry: Option<&str>,
+    progress: &mut dyn FnMut(LoadProgress),
+) -> Result<Vec<u8>, String> {
+    let content = read(path, progress)?;
+    if content.starts_with(&GZIP_MAGIC) {
+        progress(LoadProgress::Extracting);
+        let mut rom = Vec::new();
+        GzDecoder::new(content.as_slice())
+            .take(MAX_EXTRACTED_SIZE as u64 + 1)
+            .read_to_end(&mut rom)
+            .map_err(|e| format!("Failed to extract the gzip archive: {}", e))?;
+        if rom.len() > MAX_EXTRACTED_SIZE {
+            return Err(too_big(path));
+        }
+        return Ok(rom);
+    }
+    if !ZipArchive::is_zip(&content) {
+        return Ok(content);
+    }
+
+    let archive = ZipArchive::new(&content)?;
+    let roms: Vec<&ZipEntry> = archive.entries().iter().filter(|e| is_rom(e)).collect();
+    let names = || {
+        roms.iter()
+            .map(|rom| rom.name.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    };
+    let rom = match (entry, roms.as_slice()) {
+        (Some(entry), _) => archive
+            .entries()
+            .iter()
+            .find(|rom| rom.name == entry || file_name(&rom.name) == entry)
+            .ok_or_else(|| {
+                format!(
+                    "No entry '{}' in the archive, its ROMs are: {}",
+                    entry,
+                    names()
+                )
+            })?,
+        (None, [rom]) => rom,
+        (None, []) => return Err(format!("The archive {} holds no ROM.", path)),
+        (None, _) => {
+            return Err(format!(
+                "The archive {} holds several ROMs, choose one with --entry: {}",
+                path,
+                names()
+            ))
+        }
+    };
+    if rom.size as usize > MAX_EXTRACTED_SIZE {
+        return Err(too_big(path));
+    }
+    progress(LoadProgress::Extracting);
+    archive.read(rom)
+}
+
+/// Message sent by the thread of a `RomLoader`
+enum LoaderEvent {
+    Progress(LoadProgress),
+    Loaded(Result<Vec<u8>, String>),
+}
+
+/// A ROM loaded with `load_with_progress` on another thread, so frontends keep responding
+/// while it's downloaded or extracted. See `RomLoader::poll`
+pub struct RomLoader {
+    events: Receiver<LoaderEvent>,
+    progress: Option<LoadProgress>,
+}
+
+impl RomLoader {
+    /// Starts loading the ROM at `path`, the same as `load`
+    pub fn start(path: &str, entry: Option<&str>) -> RomLoader {
+        let (sender, events) = mpsc::channel();
+        let (path, entry) = (path.to_string(), entry.map(str::to_string));
+        thread::spawn(move || {
+            let mut progress = |progress| {
+                let _ = sender.send(LoaderEvent::Progress(progress));
+            };
+            let loaded = load_with_progress(&path, entry.as_deref(), &mut progress);
+            let _ = sender.send(LoaderEvent::Loaded(loaded));
+        });
+        RomLoader {
+            events,
+            progress: None,
+        }
+    }
+
+    /// Returns the ROM once loaded, `None` while it's still loading. Only returns it once
+    /// # Errors
+    /// The same as `load`
+    pub fn poll(&mut self) -> Option<Result<Vec<u8>, String>> {
+        loop {
+            match self.events.try_recv() {
+                Ok(LoaderEvent::Progress(progress)) => self.progress = Some(progress),
+                Ok(LoaderEvent::Loaded(loaded)) => return Some(loaded),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    return Some(Err(String::from("The ROM is no longer loading.")))
+                }
+            }
+        }
+    }
+
+    /// Returns the last step reported while loading, `None` until one is reported, ex:
+    /// for files read from the disk
+    pub fn progress(&self) -> Option<LoadProgress> {
+        self.progress
+    }
+}
+
+/// Returns `true` if `entry` is a file with one of the `ROM_EXTENSIONS`
+fn is_rom(entry: &ZipEntry) -> bool {
+    Path::new(&entry.name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            ROM_EXTENSIONS
+                .iter()
+                .any(|rom_extension| extension.eq_ignore_ascii_case(rom_extension))
+        })
+}
+
+/// Returns the part of `name` after the last `/`, ZIP archives use it as separator
+fn file_name(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+/// Returns the error for an archive at `path` holding a ROM too big for any memory
+fn too_big(path: &str) -> String {
+    format!(
+        "The ROM in the archive {} is bigger than {} bytes.",
+        path, MAX_EXTRACTED_SIZE
+    )
+}
+
+/// Removes the trailing `0x00` bytes of a ROM.
+/// These bytes are never needed: memory is already zeroed when a ROM is loaded.
+pub fn trim(content: &[u8]) -> &[u8] {
+    let length = content
+        .iter()
+        .rposition(|&byte| byte != 0)
+        .map_or(0, |last| last + 1);
+    &content[..length]
+}
+
+/// Pads a ROM with `0x00` bytes until it's exactly `size` bytes long.
+/// # Errors
+/// If the ROM is already bigger than `size`, or if `size` exceeds the space
+/// available for ROMs in the memory of `profile`, ex: 3584 bytes for `chip-8` but
+/// 65024 bytes for `xo-chip`.
+pub fn pad(content: &[u8], size: usize, profile: Profile) -> Result<Vec<u8>, String> {
+    let max_size = profile.memory_size() - Chip8::INITIAL_MEMORY_ADDRESS;
+    if size > max_size {
+        return Err(format!(
+            "Size {} exceeds the maximum ROM size of {} bytes for the {} profile.",
+            size,
+            max_size,
+            profile.name()
+        ));
+    }
+    if content.len() > size {
+        return Err(format!(
+            "ROM is already {} bytes long, can't pad it to {} bytes.",
+            content.len(),
+            size
+        ));
+    }
+
+    let mut padded = content.to_vec();
+    padded.resize(size, 0);
+    Ok(padded)
+}
+
+#[test]
+fn roms_are_trimmed() {
+    assert_eq!(trim(&[0x12, 0x00, 0x34, 0x00, 0x00]), &[0x12, 0x00, 0x34]);
+    assert_eq!(trim(&[0x00, 0x00]), &[] as &[u8]);
+    assert_eq!(trim(&[]), &[] as &[u8]);
+}
+
+#[test]
+fn roms_are_padded_within_the_memory_of_the_profile() {
+    assert_eq!(
+        pad(&[0x12, 0x34], 4, Profile::Standard),
+        Ok(vec![0x12, 0x34, 0, 0])
+    );
+    assert_eq!(
+        pad(&[0x12, 0x34], 0xE00, Profile::Standard).map(|rom| rom.len()),
+        Ok(0xE00)
+    );
+    assert!(pad(&[0x12, 0x34], 0xE01, Profile::Standard).is_err());
+    assert_eq!(
+        pad(&[0x12, 0x34], 0xFE00, Profile::XoChip).map(|rom| rom.len()),
+        Ok(0xFE00)
+    );
+    assert!(pad(&[0x12, 0x34], 0xFE01, Profile::XoChip).is_err());
+    assert!(pad(&[0x12, 0x34, 0x56], 2, Profile::Standard).is_err());
+}
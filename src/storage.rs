@@ -0,0 +1,215 @@
+//! Persistence of save states, RPL flags and configuration behind the `Storage` trait,
+//! so each frontend can persist data through its own mechanism: files on desktop,
+//! `localStorage` in a browser or flash memory on embedded devices.
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// A key-value store of binary data.
+/// # Keys
+/// Keys are `/` separated paths of plain names, ex: `saves/8a3f0c2e/slot-1`.
+/// Components can't be empty, `.` or `..`, so every backend can map them safely.
+/// By convention, data tied to a ROM is stored under the `CRC32` of the ROM in
+/// hexadecimal, so it survives renaming the ROM file.
+pub trait Storage {
+    /// Returns the data stored under `key`, or `None` if there is none
+    fn load(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Stores `data` under `key`, replacing any previous data
+    fn store(&mut self, key: &str, data: &[u8]) -> io::Result<()>;
+
+    /// Removes the data stored under `key`. Removing a missing key is not an error
+    fn remove(&mut self, key: &str) -> io::Result<()>;
+
+    /// Returns all keys starting with `prefix`, sorted
+    fn keys(&self, prefix: &str) -> io::Result<Vec<String>>;
+}
+
+/// Checks that `key` follows the rules described in `Storage`
+fn validate_key(key: &str) -> io::Result<()> {
+    let valid = key
+        .split('/')
+        .all(|name| !name.is_empty() && name != "." && name != ".." && !name.contains('\\'));
+    if valid {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid storage key '{}'", key),
+        ))
+    }
+}
+
+/// A `Storage` keeping everything in memory, ex: for tests or frontends that
+/// synchronize the data by other means (ex: a browser's `localStorage`).
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    entries: BTreeMap<String, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        validate_key(key)?;
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn store(&mut self, key: &str, data: &[u8]) -> io::Result<()> {
+        validate_key(key)?;
+        self.entries.insert(key.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> io::Result<()> {
+        validate_key(key)?;
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    fn keys(&self, prefix: &str) -> io::Result<Vec<String>> {
+        Ok(self
+            .entries
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+}
+
+/// A `Storage` mapping every key to a file inside a root directory,
+/// ex: `saves/8a3f0c2e/slot-1` is stored in `(root)/saves/8a3f0c2e/slot-1`.
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    /// Creates a `FileStorage` inside `root`. Directories are created as needed
+    pub fn new<P: Into<PathBuf>>(root: P) -> FileStorage {
+        FileStorage { root: root.into() }
+    }
+
+    /// Returns the path of the file holding `key`
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+
+    /// Collects the keys of all files inside `directory`, recursively
+    fn collect_keys(&self, directory: &Path, keys: &mut Vec<String>) -> io::Result<()> {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.collect_keys(&path, keys)?;
+            } else if let Ok(relative) = path.strip_prefix(&self.root) {
+                let names: Vec<String> = relative
+                    .components()
+                    .filter_map(|component| match component {
+                        Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                        _ => None,
+                    })
+                    .collect();
+                keys.push(names.join("/"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)?) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn store(&mut self, key: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, data)
+    }
+
+    fn remove(&mut self, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(key)?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn keys(&self, prefix: &str) -> io::Result<Vec<String>> {
+        // Only the directory holding the keys with the prefix is walked, ex: `saves/8a3f0c2e`
+        let directory = match prefix.rfind('/') {
+            Some(end) if validate_key(&prefix[..end]).is_ok() => self.root.join(&prefix[..end]),
+            _ => self.root.clone(),
+        };
+        let mut keys = Vec::new();
+        if directory.is_dir() {
+            self.collect_keys(&directory, &mut keys)?;
+        }
+        keys.retain(|key| key.starts_with(prefix));
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+#[test]
+fn keys_are_validated() {
+    for key in [
+        "slot-1",
+        "saves/8a3f0c2e/slot-1",
+        "flags/8a3f0c2e",
+        "chip8.toml",
+    ] {
+        assert!(validate_key(key).is_ok(), "{}", key);
+    }
+    for key in [
+        "",
+        "/saves",
+        "saves/",
+        "saves//slot-1",
+        ".",
+        "saves/../x",
+        "a\\b",
+    ] {
+        assert!(validate_key(key).is_err(), "{}", key);
+    }
+}
+
+#[test]
+fn files_are_stored_inside_the_root() {
+    let root = std::env::temp_dir().join(format!("chip8-storage-{}", std::process::id()));
+    let mut storage = FileStorage::new(root.join("data"));
+    storage.store("saves/8a3f0c2e/slot-1", b"state").unwrap();
+    storage.store("saves/8a3f0c2e/slot-2", b"other").unwrap();
+    storage.store("flags/8a3f0c2e", b"flags").unwrap();
+    assert!(root.join("data/saves/8a3f0c2e/slot-1").is_file());
+    assert_eq!(
+        storage.load("saves/8a3f0c2e/slot-1").unwrap(),
+        Some(b"state".to_vec())
+    );
+    assert_eq!(storage.load("saves/8a3f0c2e/slot-3").unwrap(), None);
+
+    assert_eq!(
+        storage.keys("saves/").unwrap(),
+        ["saves/8a3f0c2e/slot-1", "saves/8a3f0c2e/slot-2"]
+    );
+    assert_eq!(storage.keys("").unwrap().len(), 3);
+    storage.remove("saves/8a3f0c2e/slot-2").unwrap();
+    storage.remove("missing").unwrap();
+    assert_eq!(storage.keys("saves/").unwrap(), ["saves/8a3f0c2e/slot-1"]);
+
+    // Keys can't reach files outside of the root
+    assert!(storage.store("../escaped", b"data").is_err());
+    assert!(storage.load("saves/../../escaped").is_err());
+    assert!(!root.join("escaped").exists());
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,269 @@
+//! Minimal reader and writer of ZIP archives, ex: to load ROM packs or to bundle
+//! files attached to bug reports. Files are compressed with Deflate when written.
+//! Timestamps are not stored: all files are dated 1980-01-01, the earliest date
+//! ZIP archives can hold. Only stored and Deflate files can be read.
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::io::{self, Read, Write};
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x0403_4B50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0201_4B50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4B50;
+/// Version 2.0: the minimum supporting Deflate
+const VERSION: u16 = 20;
+const STORED_METHOD: u16 = 0;
+const DEFLATE_METHOD: u16 = 8;
+/// Size of the end of central directory record, without the comment
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+/// 1980-01-01 in MS-DOS format: `(year - 1980) << 9 | month << 5 | day`
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Metadata of a file already written, needed by the central directory
+struct Entry {
+    name: String,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Writes a ZIP archive to `W`, one file at a time
+pub struct ZipWriter<W: Write> {
+    writer: W,
+    entries: Vec<Entry>,
+    offset: u32,
+}
+
+impl<W: Write> ZipWriter<W> {
+    pub fn new(writer: W) -> ZipWriter<W> {
+        ZipWriter {
+            writer,
+            entries: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Writes `bytes` to the archive, keeping track of the current offset
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)?;
+        self.offset += bytes.len() as u32;
+        Ok(())
+    }
+
+    /// Adds a file called `name` holding `content`
+    pub fn add_file(&mut self, name: &str, content: &[u8]) -> io::Result<()> {
+        let mut crc = Crc::new();
+        crc.update(content);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content)?;
+        let compressed = encoder.finish()?;
+
+        let entry = Entry {
+            name: name.to_string(),
+            crc: crc.sum(),
+            compressed_size: compressed.len() as u32,
+            size: content.len() as u32,
+            offset: self.offset,
+        };
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // Flags
+        header.extend_from_slice(&DEFLATE_METHOD.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // Time
+        header.extend_from_slice(&DOS_DATE.to_le_bytes());
+        header.extend_from_slice(&entry.crc.to_le_bytes());
+        header.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        header.extend_from_slice(&entry.size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // Extra field length
+        header.extend_from_slice(name.as_bytes());
+
+        self.write(&header)?;
+        self.write(&compressed)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Writes the central directory, completing the archive
+    /// # Returns
+    /// The underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let directory_offset = self.offset;
+        let entries = std::mem::take(&mut self.entries);
+
+        for entry in &entries {
+            let mut header = Vec::new();
+            header.extend_from_slice(&CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+            header.extend_from_slice(&VERSION.to_le_bytes()); // Version made by
+            header.extend_from_slice(&VERSION.to_le_bytes()); // Version needed
+            header.extend_from_slice(&0u16.to_le_bytes()); // Flags
+            header.extend_from_slice(&DEFLATE_METHOD.to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes()); // Time
+            header.extend_from_slice(&DOS_DATE.to_le_bytes());
+            header.extend_from_slice(&entry.crc.to_le_bytes());
+            header.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            header.extend_from_slice(&entry.size.to_le_bytes());
+            header.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes()); // Extra field length
+            header.extend_from_slice(&0u16.to_le_bytes()); // Comment length
+            header.extend_from_slice(&0u16.to_le_bytes()); // Disk number
+            header.extend_from_slice(&0u16.to_le_bytes()); // Internal attributes
+            header.extend_from_slice(&0u32.to_le_bytes()); // External attributes
+            header.extend_from_slice(&entry.offset.to_le_bytes());
+            header.extend_from_slice(entry.name.as_bytes());
+            self.write(&header)?;
+        }
+
+        let directory_size = self.offset - directory_offset;
+        let mut end = Vec::new();
+        end.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes()); // Disk number
+        end.extend_from_slice(&0u16.to_le_bytes()); // Disk with the central directory
+        end.extend_from_slice(&(entries.len() as u16).to_le_bytes()); // Entries on this disk
+        end.extend_from_slice(&(entries.len() as u16).to_le_bytes()); // Total entries
+        end.extend_from_slice(&directory_size.to_le_bytes());
+        end.extend_from_slice(&directory_offset.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes()); // Comment length
+        self.write(&end)?;
+
+        Ok(self.writer)
+    }
+}
+
+/// File listed in the central directory of an archive read by `ZipArchive`
+pub struct ZipEntry {
+    pub name: String,
+    /// Size of the file once extracted, as declared by the archive
+    pub size: u32,
+    method: u16,
+    flags: u16,
+    crc: u32,
+    compressed_size: u32,
+    offset: u32,
+}
+
+/// ZIP archive held in memory, read from its central directory
+pub struct ZipArchive<'a> {
+    bytes: &'a [u8],
+    entries: Vec<ZipEntry>,
+}
+
+/// Reads the little endian `u16` at `offset`
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let field = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([field[0], field[1]]))
+}
+
+/// Reads the little endian `u32` at `offset`
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let field = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+}
+
+impl<'a> ZipArchive<'a> {
+    /// Returns `true` if `bytes` start like a ZIP archive
+    pub fn is_zip(bytes: &[u8]) -> bool {
+        read_u32(bytes, 0) == Some(LOCAL_FILE_HEADER_SIGNATURE)
+    }
+
+    /// Reads the central directory of the archive held in `bytes`
+    /// # Errors
+    /// If `bytes` isn't a ZIP archive, or if it's truncated.
+    pub fn new(bytes: &'a [u8]) -> Result<ZipArchive<'a>, String> {
+        let invalid = || String::from("Invalid ZIP archive: the central directory is damaged.");
+        // The end of central directory is followed by a comment of up to 64KB
+        let end = (0..=bytes.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_SIZE))
+            .rev()
+            .take(u16::MAX as usize + 1)
+            .find(|&offset| read_u32(bytes, offset) == Some(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+            .ok_or("Invalid ZIP archive: the central directory is missing.")?;
+        let count = read_u16(bytes, end + 10).ok_or_else(invalid)?;
+        let mut offset = read_u32(bytes, end + 16).ok_or_else(invalid)? as usize;
+
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            if read_u32(bytes, offset) != Some(CENTRAL_DIRECTORY_SIGNATURE) {
+                return Err(invalid());
+            }
+            let field = |position: usize| read_u32(bytes, offset + position).ok_or_else(invalid);
+            let short_field =
+                |position: usize| read_u16(bytes, offset + position).ok_or_else(invalid);
+            let name_length = short_field(28)? as usize;
+            let extra_length = short_field(30)? as usize;
+            let comment_length = short_field(32)? as usize;
+            let name = bytes
+                .get(offset + 46..offset + 46 + name_length)
+                .ok_or_else(invalid)?;
+            entries.push(ZipEntry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                size: field(24)?,
+                method: short_field(10)?,
+                flags: short_field(8)?,
+                crc: field(16)?,
+                compressed_size: field(20)?,
+                offset: field(42)?,
+            });
+            offset += 46 + name_length + extra_length + comment_length;
+        }
+        Ok(ZipArchive { bytes, entries })
+    }
+
+    /// Returns the files of the archive, in the order they are stored
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    /// Extracts `entry`, checking its CRC
+    /// # Errors
+    /// If the file is encrypted, compressed with a method other than Deflate,
+    /// or damaged.
+    pub fn read(&self, entry: &ZipEntry) -> Result<Vec<u8>, String> {
+        let damaged = || format!("The file '{}' of the ZIP archive is damaged.", entry.name);
+        if entry.flags & 1 != 0 {
+            return Err(format!(
+                "The file '{}' of the ZIP archive is encrypted.",
+                entry.name
+            ));
+        }
+
+        let header = entry.offset as usize;
+        if read_u32(self.bytes, header) != Some(LOCAL_FILE_HEADER_SIGNATURE) {
+            return Err(damaged());
+        }
+        let name_length = read_u16(self.bytes, header + 26).ok_or_else(damaged)? as usize;
+        let extra_length = read_u16(self.bytes, header + 28).ok_or_else(damaged)? as usize;
+        let start = header + 30 + name_length + extra_length;
+        let data = self
+            .bytes
+            .get(start..start + entry.compressed_size as usize)
+            .ok_or_else(damaged)?;
+
+        let content = match entry.method {
+            STORED_METHOD => data.to_vec(),
+            DEFLATE_METHOD => {
+                let mut content = Vec::with_capacity(entry.size as usize);
+                DeflateDecoder::new(data)
+                    .take(entry.size as u64)
+                    .read_to_end(&mut content)
+                    .map_err(|_| damaged())?;
+                content
+            }
+            method => {
+                return Err(format!(
+                    "The file '{}' of the ZIP archive uses the unsupported compression method {}.",
+                    entry.name, method
+                ))
+            }
+        };
+
+        let mut crc = Crc::new();
+        crc.update(&content);
+        if content.len() != entry.size as usize || crc.sum() != entry.crc {
+            return Err(damaged());
+        }
+        Ok(content)
+    }
+}